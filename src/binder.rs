@@ -0,0 +1,624 @@
+//! Implementation of the binder building the logical plan of a query, type
+//! checking its expressions against the schemas of the tables it reads and
+//! resolving their column references to the columns of these schemas.
+use std::error::Error;
+use std::fmt;
+
+use crate::catalog::Catalog;
+use crate::functions::FunctionRegistry;
+use crate::logical::LogicalPlan;
+use crate::schema::{Field, Schema};
+use crate::sql::{Alias, ColumnRef, Expr, Query, TableRef};
+use crate::typecheck::{TypeChecker, TypeError};
+use crate::types::DataType;
+
+/// Errors reported when a query does not bind to the tables it reads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindError {
+    UnknownTable(String),
+    UnknownTableFunction(String),
+    /// Alias of a `FROM` item renaming more columns than the item has.
+    TooManyColumnAliases {
+        item: String,
+        columns: usize,
+        aliases: usize,
+    },
+    /// Column evaluated on aggregated rows which is neither a group key nor
+    /// an argument of an aggregate function.
+    UngroupedColumn(String),
+    Type(TypeError),
+}
+
+impl fmt::Display for BindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindError::UnknownTable(table) => write!(f, "Unknown table {table}"),
+            BindError::UnknownTableFunction(name) => write!(f, "Unknown table function {name}"),
+            BindError::TooManyColumnAliases {
+                item,
+                columns,
+                aliases,
+            } => write!(
+                f,
+                "{item} has {columns} columns but {aliases} column aliases were given"
+            ),
+            BindError::UngroupedColumn(column) => write!(
+                f,
+                "Column {column} must appear in the GROUP BY clause or be used in an aggregate function"
+            ),
+            BindError::Type(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl Error for BindError {}
+
+impl From<TypeError> for BindError {
+    fn from(error: TypeError) -> Self {
+        BindError::Type(error)
+    }
+}
+
+pub struct Binder<'a> {
+    catalog: &'a Catalog,
+    functions: &'a FunctionRegistry,
+}
+
+impl<'a> Binder<'a> {
+    pub fn new(catalog: &'a Catalog, functions: &'a FunctionRegistry) -> Self {
+        Self { catalog, functions }
+    }
+
+    /// Build the logical plan of a query, columns of its output are named
+    /// after the expressions of the select list.
+    pub fn bind_query(&self, query: Query) -> Result<LogicalPlan, BindError> {
+        let (columns, from, filter, group_by, order_by, limit) = match query {
+            Query::Select {
+                columns,
+                from,
+                filter,
+                group_by,
+                order_by,
+                limit,
+            } => (columns, from, filter, group_by, order_by, limit),
+            Query::Values(rows) => {
+                let (rows, schema) = self.bind_values(rows)?;
+                return Ok(LogicalPlan::Values { rows, schema });
+            }
+        };
+
+        // Start with the rows of the FROM clause
+        let mut plan = self.bind_from(*from)?;
+        let mut schema = plan.schema();
+
+        // Bind the expressions evaluated on the rows of the FROM clause,
+        // which makes implicit coercions explicit.
+        let filter = filter
+            .map(|expr| self.bind_predicate(expr, &schema))
+            .transpose()?;
+        let names = columns.iter().map(Self::output_name).collect::<Vec<_>>();
+        let mut columns = columns
+            .into_iter()
+            .map(|expr| self.bind_expr(expr, &schema))
+            .collect::<Result<Vec<_>, _>>()?;
+        let group_by = group_by
+            .into_iter()
+            .map(|expr| self.bind_expr(expr, &schema))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut order_by = order_by
+            .map(|expr| self.bind_expr(expr, &schema))
+            .transpose()?;
+
+        if let Some(predicate) = filter {
+            plan = LogicalPlan::Filter {
+                input: Box::new(plan),
+                predicate,
+            };
+        }
+
+        // Rows are aggregated when grouped or when aggregate functions are
+        // called, the expressions evaluated after the aggregation are
+        // rewritten to refer to its output columns.
+        let mut aggregates = vec![];
+        for expr in columns.iter().chain(order_by.iter()) {
+            Self::collect_aggregates(expr, self.functions, &mut aggregates);
+        }
+        if !group_by.is_empty() || !aggregates.is_empty() {
+            let aggregate_schema = self.aggregate_schema(&schema, &group_by, &aggregates)?;
+            columns = columns
+                .into_iter()
+                .map(|expr| Self::rewrite(expr, &aggregate_schema))
+                .collect::<Result<_, _>>()?;
+            order_by = order_by
+                .map(|expr| Self::rewrite(expr, &aggregate_schema))
+                .transpose()?;
+            plan = LogicalPlan::Aggregate {
+                input: Box::new(plan),
+                group_by,
+                aggregates,
+                schema: aggregate_schema.clone(),
+            };
+            schema = aggregate_schema;
+        }
+
+        if let Some(key) = order_by {
+            plan = LogicalPlan::Sort {
+                input: Box::new(plan),
+                key,
+            };
+        }
+        if let Some(count) = limit {
+            plan = LogicalPlan::Limit {
+                input: Box::new(plan),
+                count: count as usize,
+            };
+        }
+        Ok(LogicalPlan::Project {
+            input: Box::new(plan),
+            schema: self.output_schema(&names, &columns, &schema)?,
+            exprs: columns,
+        })
+    }
+
+    /// Build the logical plan producing the rows of a `FROM` item.
+    pub fn bind_from(&self, item: TableRef) -> Result<LogicalPlan, BindError> {
+        Ok(match item {
+            TableRef::Table { name, alias } => {
+                let schema = match self.catalog.table(&name) {
+                    Some(table) => table.schema(),
+                    None => return Err(BindError::UnknownTable(name)),
+                };
+                LogicalPlan::Scan {
+                    schema: Self::alias(schema, &name, alias)?,
+                    table: name,
+                    projection: None,
+                }
+            }
+            // Table functions which are not joined to preceding items can
+            // only be called with constant arguments.
+            TableRef::Function {
+                name, args, alias, ..
+            } => {
+                let (args, schema) =
+                    self.bind_table_function(&name, args, &Schema::default(), alias)?;
+                LogicalPlan::TableFunction { name, args, schema }
+            }
+            TableRef::Values { rows, alias } => {
+                let (rows, schema) = self.bind_values(rows)?;
+                let name = alias.name.clone();
+                LogicalPlan::Values {
+                    rows,
+                    schema: Self::alias(schema, &name, Some(alias))?,
+                }
+            }
+            TableRef::Join { left, right, on } => {
+                let left = self.bind_from(*left)?;
+                let left_schema = left.schema();
+                match *right {
+                    TableRef::Function {
+                        name,
+                        args,
+                        lateral: true,
+                        alias,
+                    } => {
+                        let (args, right_schema) =
+                            self.bind_table_function(&name, args, &left_schema, alias)?;
+                        let schema = left_schema.join(&right_schema);
+                        LogicalPlan::LateralJoin {
+                            input: Box::new(left),
+                            function: name,
+                            args,
+                            condition: on
+                                .map(|expr| self.bind_predicate(expr, &schema))
+                                .transpose()?,
+                            schema,
+                        }
+                    }
+                    right => {
+                        let right = self.bind_from(right)?;
+                        let schema = left_schema.join(&right.schema());
+                        LogicalPlan::Join {
+                            left: Box::new(left),
+                            right: Box::new(right),
+                            condition: on
+                                .map(|expr| self.bind_predicate(expr, &schema))
+                                .transpose()?,
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    // Type checks the rows of a `VALUES` list, columns are named `column1`,
+    // `column2`... and typed after all the values of the column.
+    fn bind_values(&self, rows: Vec<Vec<Expr>>) -> Result<(Vec<Vec<Expr>>, Schema), BindError> {
+        let (rows, types) =
+            TypeChecker::new(&Schema::default(), self.functions).check_values(rows)?;
+        let fields = types
+            .into_iter()
+            .enumerate()
+            .map(|(index, data_type)| Field::new(&format!("column{}", index + 1), data_type))
+            .collect();
+        Ok((rows, Schema::new(fields)))
+    }
+
+    // Binds the arguments of a table function evaluated on rows of the given
+    // schema, returns them along with the schema of the rows it produces.
+    fn bind_table_function(
+        &self,
+        name: &str,
+        args: Vec<Expr>,
+        schema: &Schema,
+        alias: Option<Alias>,
+    ) -> Result<(Vec<Expr>, Schema), BindError> {
+        let function = self
+            .functions
+            .table_function(name)
+            .ok_or_else(|| BindError::UnknownTableFunction(name.to_string()))?;
+        let args = args
+            .into_iter()
+            .map(|arg| self.bind_expr(arg, schema))
+            .collect::<Result<Vec<_>, _>>()?;
+        let arg_types = args
+            .iter()
+            .map(|arg| self.type_of(arg, schema))
+            .collect::<Result<Vec<_>, _>>()?;
+        let schema = function
+            .schema(&arg_types)
+            .ok_or_else(|| TypeError::InvalidArguments {
+                function: name.to_string(),
+                arg_types,
+            })?;
+        let schema = Self::alias(schema, name, alias)?;
+        Ok((args, schema))
+    }
+
+    /// Returns the name of the column produced by an expression as written
+    /// in the query, before implicit coercions are made explicit. Columns
+    /// are named after the expression unless it is a column reference.
+    pub fn output_name(expr: &Expr) -> String {
+        match expr {
+            Expr::Column(name) | Expr::QualifiedColumn(_, name) => name.clone(),
+            Expr::Bound(column) => column.name.clone(),
+            expr => expr.to_string(),
+        }
+    }
+
+    /// Returns the schema of the rows produced by a list of bound
+    /// expressions, columns are given the names of the expressions as
+    /// written.
+    pub fn output_schema(
+        &self,
+        names: &[String],
+        exprs: &[Expr],
+        schema: &Schema,
+    ) -> Result<Schema, BindError> {
+        let fields = names
+            .iter()
+            .zip(exprs)
+            .map(|(name, expr)| Ok(Field::new(name, self.type_of(expr, schema)?)))
+            .collect::<Result<_, BindError>>()?;
+        Ok(Schema::new(fields))
+    }
+
+    // Returns the schema of the rows produced by the aggregation, columns are
+    // named after the group keys and aggregate calls they hold.
+    fn aggregate_schema(
+        &self,
+        schema: &Schema,
+        group_by: &[Expr],
+        aggregates: &[Expr],
+    ) -> Result<Schema, BindError> {
+        let fields = group_by
+            .iter()
+            .chain(aggregates)
+            .map(|expr| Ok(Field::new(&expr.to_string(), self.type_of(expr, schema)?)))
+            .collect::<Result<_, BindError>>()?;
+        Ok(Schema::new(fields))
+    }
+
+    /// Qualify the fields of a `FROM` item with its alias or name, the alias
+    /// can also rename its columns.
+    fn alias(schema: Schema, name: &str, alias: Option<Alias>) -> Result<Schema, BindError> {
+        let Some(alias) = alias else {
+            return Ok(schema.qualify(name));
+        };
+        if alias.columns.len() > schema.len() {
+            return Err(BindError::TooManyColumnAliases {
+                item: name.to_string(),
+                columns: schema.len(),
+                aliases: alias.columns.len(),
+            });
+        }
+        let mut schema = schema.qualify(&alias.name);
+        for (field, column) in schema.fields.iter_mut().zip(alias.columns) {
+            field.name = column;
+        }
+        Ok(schema)
+    }
+
+    /// Collect the distinct aggregate calls made by an expression.
+    fn collect_aggregates(expr: &Expr, functions: &FunctionRegistry, aggregates: &mut Vec<Expr>) {
+        match expr {
+            Expr::Function(name, _) if functions.is_aggregate(name) => {
+                if !aggregates.contains(expr) {
+                    aggregates.push(expr.clone());
+                }
+            }
+            Expr::Function(_, args) => {
+                for arg in args {
+                    Self::collect_aggregates(arg, functions, aggregates);
+                }
+            }
+            Expr::Comparison(left, _, right)
+            | Expr::Arithmetic(left, _, right)
+            | Expr::And(left, right)
+            | Expr::Or(left, right) => {
+                Self::collect_aggregates(left, functions, aggregates);
+                Self::collect_aggregates(right, functions, aggregates);
+            }
+            Expr::Not(expr)
+            | Expr::IsNull(expr, _)
+            | Expr::Negate(expr)
+            | Expr::Cast(expr, _)
+            | Expr::Extract(_, expr) => Self::collect_aggregates(expr, functions, aggregates),
+            Expr::Array(elements) => {
+                for element in elements {
+                    Self::collect_aggregates(element, functions, aggregates);
+                }
+            }
+            Expr::Column(_)
+            | Expr::QualifiedColumn(_, _)
+            | Expr::Bound(_)
+            | Expr::Null
+            | Expr::Boolean(_)
+            | Expr::Value(_)
+            | Expr::Numeric(_)
+            | Expr::Varchar(_)
+            | Expr::Constant(_) => {}
+        }
+    }
+
+    /// Rewrite an expression evaluated on the output of an aggregation so
+    /// group keys and aggregate calls refer to its columns.
+    fn rewrite(expr: Expr, schema: &Schema) -> Result<Expr, BindError> {
+        let name = expr.to_string();
+        if let Some(index) = schema.index_of(&name) {
+            return Ok(Expr::Bound(Box::new(ColumnRef {
+                table: None,
+                name,
+                index,
+                data_type: schema.field(index).data_type.clone(),
+                qualified: false,
+            })));
+        }
+        let boxed = |expr: Box<Expr>| Self::rewrite(*expr, schema).map(Box::new);
+        Ok(match expr {
+            Expr::Column(_) | Expr::QualifiedColumn(_, _) | Expr::Bound(_) => {
+                return Err(BindError::UngroupedColumn(name))
+            }
+            Expr::Comparison(left, op, right) => Expr::Comparison(boxed(left)?, op, boxed(right)?),
+            Expr::Arithmetic(left, op, right) => Expr::Arithmetic(boxed(left)?, op, boxed(right)?),
+            Expr::Negate(expr) => Expr::Negate(boxed(expr)?),
+            Expr::And(left, right) => Expr::And(boxed(left)?, boxed(right)?),
+            Expr::Or(left, right) => Expr::Or(boxed(left)?, boxed(right)?),
+            Expr::Not(expr) => Expr::Not(boxed(expr)?),
+            Expr::IsNull(expr, negated) => Expr::IsNull(boxed(expr)?, negated),
+            Expr::Cast(expr, data_type) => Expr::Cast(boxed(expr)?, data_type),
+            Expr::Extract(field, expr) => Expr::Extract(field, boxed(expr)?),
+            Expr::Function(name, args) => Expr::Function(
+                name,
+                args.into_iter()
+                    .map(|arg| Self::rewrite(arg, schema))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Expr::Array(elements) => Expr::Array(
+                elements
+                    .into_iter()
+                    .map(|element| Self::rewrite(element, schema))
+                    .collect::<Result<_, _>>()?,
+            ),
+            expr => expr,
+        })
+    }
+
+    /// Bind an expression evaluated on rows of the given schema, returns the
+    /// expression with implicit coercions made explicit and column references
+    /// resolved.
+    pub fn bind_expr(&self, expr: Expr, schema: &Schema) -> Result<Expr, BindError> {
+        let (expr, _) = TypeChecker::new(schema, self.functions).check(expr)?;
+        Ok(Self::resolve_columns(expr, schema))
+    }
+
+    /// Bind a predicate evaluated on rows of the given schema.
+    pub fn bind_predicate(&self, expr: Expr, schema: &Schema) -> Result<Expr, BindError> {
+        let expr = TypeChecker::new(schema, self.functions).check_predicate(expr)?;
+        Ok(Self::resolve_columns(expr, schema))
+    }
+
+    /// Resolve the column references of a type checked expression to the
+    /// columns of the schema it was checked against.
+    pub fn resolve_columns(expr: Expr, schema: &Schema) -> Expr {
+        expr.transform(&mut |expr| {
+            let (table, name, qualified) = match expr {
+                Expr::Column(name) => (None, name, false),
+                Expr::QualifiedColumn(table, name) => (Some(table), name, true),
+                expr => return expr,
+            };
+            let index = schema
+                .resolve(table.as_deref(), &name)
+                .unwrap_or_else(|| unreachable!("expected column {name} to be type checked"));
+            let field = schema.field(index);
+            Expr::Bound(Box::new(ColumnRef {
+                table: field.table.clone(),
+                name,
+                index,
+                data_type: field.data_type.clone(),
+                qualified,
+            }))
+        })
+    }
+
+    // Returns the type of the values an expression evaluates to.
+    fn type_of(&self, expr: &Expr, schema: &Schema) -> Result<DataType, BindError> {
+        Ok(TypeChecker::new(schema, self.functions).type_of(expr)?)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::{Column, Table};
+    use crate::sql::{Parser, Tokenizer};
+
+    fn catalog() -> Catalog {
+        let mut catalog = Catalog::new();
+        let table = Table::new(
+            "accounts",
+            vec![
+                Column::new("id", DataType::Integer),
+                Column::new("owner", DataType::Varchar),
+            ],
+        )
+        .unwrap();
+        catalog.create_table(table).unwrap();
+        catalog
+    }
+
+    fn bind(sql: &str) -> Result<LogicalPlan, BindError> {
+        let query = Parser::new(Tokenizer::new(sql)).unwrap().parse().unwrap();
+        Binder::new(&catalog(), &FunctionRegistry::default()).bind_query(query)
+    }
+
+    fn error(sql: &str) -> String {
+        bind(sql).unwrap_err().to_string()
+    }
+
+    fn column(table: &str, name: &str, index: usize, data_type: DataType) -> Expr {
+        Expr::Bound(Box::new(ColumnRef {
+            table: Some(table.to_string()),
+            name: name.to_string(),
+            index,
+            data_type,
+            qualified: false,
+        }))
+    }
+
+    #[test]
+    fn can_bind_queries() {
+        let plan = bind("SELECT owner FROM accounts WHERE id > 1 ORDER BY owner LIMIT 2").unwrap();
+        let scan = LogicalPlan::Scan {
+            table: "accounts".to_string(),
+            schema: catalog()
+                .table("accounts")
+                .unwrap()
+                .schema()
+                .qualify("accounts"),
+            projection: None,
+        };
+        let filter = LogicalPlan::Filter {
+            input: Box::new(scan),
+            predicate: Expr::Comparison(
+                Box::new(column("accounts", "id", 0, DataType::Integer)),
+                ">".to_string(),
+                Box::new(Expr::Value(1)),
+            ),
+        };
+        let sort = LogicalPlan::Sort {
+            input: Box::new(filter),
+            key: column("accounts", "owner", 1, DataType::Varchar),
+        };
+        assert_eq!(
+            plan,
+            LogicalPlan::Project {
+                input: Box::new(LogicalPlan::Limit {
+                    input: Box::new(sort),
+                    count: 2,
+                }),
+                exprs: vec![column("accounts", "owner", 1, DataType::Varchar)],
+                schema: Schema::new(vec![Field::new("owner", DataType::Varchar)]),
+            }
+        );
+    }
+
+    #[test]
+    fn columns_are_resolved_to_their_index() {
+        let plan = bind("SELECT b.owner, a.id + 1 FROM accounts a JOIN accounts b ON a.id = b.id WHERE a.id > 1");
+        let LogicalPlan::Project { exprs, .. } = plan.unwrap() else {
+            panic!("expected a projection");
+        };
+        let Expr::Bound(owner) = &exprs[0] else {
+            panic!("expected a column got {}", exprs[0]);
+        };
+        assert_eq!((owner.table.as_deref(), owner.index), (Some("b"), 3));
+        assert_eq!(exprs[0].to_string(), "b.owner");
+        assert_eq!(
+            error("SELECT id FROM accounts a JOIN accounts b ON a.id = b.id"),
+            "Column reference id is ambiguous, it could refer to a.id or b.id"
+        );
+    }
+
+    #[test]
+    fn unknown_references_are_rejected() {
+        assert_eq!(error("SELECT id FROM employees"), "Unknown table employees");
+        assert_eq!(
+            error("SELECT balance FROM accounts"),
+            "Unknown column balance"
+        );
+        assert_eq!(
+            error("SELECT a.id FROM accounts"),
+            "Column a.id references a which is not in the FROM clause"
+        );
+        assert_eq!(
+            error("SELECT owner FROM accounts AS a(id, owner, balance)"),
+            "accounts has 2 columns but 3 column aliases were given"
+        );
+        assert_eq!(
+            bind("SELECT x FROM series(1, 2) AS s(x)"),
+            Err(BindError::UnknownTableFunction("series".to_string()))
+        );
+    }
+
+    #[test]
+    fn output_columns_are_named_as_written() {
+        let plan = bind("SELECT id * 1.5, sum(id) + 1 FROM accounts GROUP BY id").unwrap();
+        let LogicalPlan::Project { exprs, schema, .. } = plan else {
+            panic!("expected a projection");
+        };
+        assert!(exprs[1].to_string().contains("CAST(1 AS BIGINT)"));
+        assert_eq!(
+            schema
+                .fields
+                .iter()
+                .map(|field| field.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["id * 1.5", "sum(id) + 1"]
+        );
+    }
+
+    #[test]
+    fn aggregates_are_bound_below_projections() {
+        let plan = bind("SELECT owner, count(*) FROM accounts GROUP BY owner").unwrap();
+        let LogicalPlan::Project { input, exprs, .. } = plan else {
+            panic!("expected a projection");
+        };
+        assert_eq!(
+            exprs
+                .iter()
+                .map(|expr| expr.to_string())
+                .collect::<Vec<_>>(),
+            vec!["owner", "\"count(*)\""]
+        );
+        assert_eq!(
+            input.schema(),
+            Schema::new(vec![
+                Field::new("owner", DataType::Varchar),
+                Field::new("count(*)", DataType::BigInt),
+            ])
+        );
+        assert_eq!(
+            error("SELECT id, count(*) FROM accounts GROUP BY owner"),
+            "Column id must appear in the GROUP BY clause or be used in an aggregate function"
+        );
+    }
+}
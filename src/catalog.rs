@@ -0,0 +1,1577 @@
+//! Implementation of the catalog of the tables created by DDL statements,
+//! along with the rows they hold and the constraints they enforce.
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+use crate::operators::ExecutionError;
+use crate::row::Row;
+use crate::schema::{Field, Schema};
+use crate::sql::{ConstraintKind, Expr, ReferentialAction};
+use crate::statistics::TableStatistics;
+use crate::types::{DataType, Value};
+
+/// Name of the system table holding one row per column of each table
+/// analyzed by `ANALYZE`, with the statistics collected on the column.
+pub const STATISTICS_TABLE: &str = "eocene_stats";
+
+/// Errors reported when a statement does not apply to the catalog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CatalogError {
+    DuplicateTable(String),
+    UnknownTable(String),
+    /// System table, which statements cannot create, alter or modify.
+    SystemTable(String),
+    DuplicateColumn {
+        table: String,
+        column: String,
+    },
+    UnknownColumn {
+        table: String,
+        column: String,
+    },
+    DuplicateConstraint {
+        table: String,
+        constraint: String,
+    },
+    MultiplePrimaryKeys(String),
+    /// Foreign key referencing the primary key of a table without one.
+    NoPrimaryKey(String),
+    /// Foreign key referencing columns which are not a primary key or
+    /// unique constraint of their table.
+    NoUniqueKey {
+        table: String,
+        columns: Vec<String>,
+    },
+    /// Foreign key column whose type differs from the referenced column.
+    IncompatibleForeignKey {
+        column: String,
+        data_type: DataType,
+        referenced: String,
+        referenced_type: DataType,
+    },
+    /// Table or column referenced by a foreign key of another table.
+    Referenced {
+        table: String,
+        constraint: String,
+        referencing: String,
+    },
+    /// `NULL` value in a `NOT NULL` column.
+    NotNullViolation {
+        table: String,
+        column: String,
+    },
+    UniqueViolation {
+        table: String,
+        constraint: String,
+        key: String,
+    },
+    CheckViolation {
+        table: String,
+        constraint: String,
+    },
+    /// Key which is not present in the referenced table.
+    ForeignKeyViolation {
+        table: String,
+        constraint: String,
+        key: String,
+        referenced: String,
+    },
+    /// Deleted or updated key still referenced by the rows of a table
+    /// whose foreign key does not cascade.
+    RestrictViolation {
+        table: String,
+        constraint: String,
+        key: String,
+        referencing: String,
+    },
+    /// Predicate of a check constraint whose evaluation on a row failed.
+    Execution(ExecutionError),
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogError::DuplicateTable(table) => write!(f, "Table {table} already exists"),
+            CatalogError::UnknownTable(table) => write!(f, "Unknown table {table}"),
+            CatalogError::SystemTable(table) => {
+                write!(f, "Table {table} is a system table and cannot be modified")
+            }
+            CatalogError::DuplicateColumn { table, column } => {
+                write!(f, "Column {column} of table {table} already exists")
+            }
+            CatalogError::UnknownColumn { table, column } => {
+                write!(f, "Unknown column {column} of table {table}")
+            }
+            CatalogError::DuplicateConstraint { table, constraint } => {
+                write!(f, "Constraint {constraint} of table {table} already exists")
+            }
+            CatalogError::MultiplePrimaryKeys(table) => {
+                write!(f, "Table {table} cannot have multiple primary keys")
+            }
+            CatalogError::NoPrimaryKey(table) => {
+                write!(f, "Table {table} has no primary key to reference")
+            }
+            CatalogError::NoUniqueKey { table, columns } => write!(
+                f,
+                "There is no unique constraint matching columns ({}) of table {table}",
+                columns.join(", ")
+            ),
+            CatalogError::IncompatibleForeignKey {
+                column,
+                data_type,
+                referenced,
+                referenced_type,
+            } => write!(
+                f,
+                "Foreign key column {column} of type {data_type} cannot reference column {referenced} of type {referenced_type}"
+            ),
+            CatalogError::Referenced {
+                table,
+                constraint,
+                referencing,
+            } => write!(
+                f,
+                "Table {table} is referenced by constraint {constraint} of table {referencing}"
+            ),
+            CatalogError::NotNullViolation { table, column } => {
+                write!(f, "Column {column} of table {table} cannot be NULL")
+            }
+            CatalogError::UniqueViolation {
+                table,
+                constraint,
+                key,
+            } => write!(
+                f,
+                "Key {key} already exists in table {table}, violating constraint {constraint}"
+            ),
+            CatalogError::CheckViolation { table, constraint } => write!(
+                f,
+                "Row of table {table} violates check constraint {constraint}"
+            ),
+            CatalogError::ForeignKeyViolation {
+                table,
+                constraint,
+                key,
+                referenced,
+            } => write!(
+                f,
+                "Key {key} of table {table} is not present in table {referenced}, violating constraint {constraint}"
+            ),
+            CatalogError::RestrictViolation {
+                table,
+                constraint,
+                key,
+                referencing,
+            } => write!(
+                f,
+                "Key {key} of table {table} is still referenced from table {referencing}, violating constraint {constraint}"
+            ),
+            CatalogError::Execution(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl Error for CatalogError {}
+
+impl From<ExecutionError> for CatalogError {
+    fn from(error: ExecutionError) -> Self {
+        CatalogError::Execution(error)
+    }
+}
+
+/// Column of a table, its default is type checked against the column type
+/// and evaluated for rows which are not given a value for the column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    pub name: String,
+    pub data_type: DataType,
+    pub not_null: bool,
+    pub default: Option<Expr>,
+}
+
+impl Column {
+    /// Create a new nullable column without a default.
+    pub fn new(name: &str, data_type: DataType) -> Self {
+        Self {
+            name: name.to_string(),
+            data_type,
+            not_null: false,
+            default: None,
+        }
+    }
+}
+
+/// Named constraint of a table, foreign keys list their referenced columns
+/// in the order of the referenced primary key or unique constraint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    pub name: String,
+    pub kind: ConstraintKind,
+}
+
+impl Constraint {
+    /// Returns the columns whose values are unique for primary keys and
+    /// unique constraints.
+    pub fn unique_columns(&self) -> Option<&[String]> {
+        match &self.kind {
+            ConstraintKind::PrimaryKey(columns) | ConstraintKind::Unique(columns) => Some(columns),
+            _ => None,
+        }
+    }
+
+    // Returns whether the constraint depends on a column of its table.
+    fn involves(&self, column: &str) -> bool {
+        match &self.kind {
+            ConstraintKind::PrimaryKey(columns)
+            | ConstraintKind::Unique(columns)
+            | ConstraintKind::ForeignKey { columns, .. } => {
+                columns.iter().any(|name| name == column)
+            }
+            ConstraintKind::Check(expr) => expr.columns().contains(&column),
+        }
+    }
+}
+
+/// Evaluates the bound predicate of a check constraint on a row, returns
+/// `false` when the row violates it.
+pub type RowCheck = dyn Fn(&Row) -> Result<bool, ExecutionError>;
+
+/// Binds the predicate of a check constraint to the columns of its table,
+/// once per statement, into the function evaluating it on each row.
+pub type CheckFn = dyn Fn(&Table, &Expr) -> Box<RowCheck>;
+
+/// Changes applied to the rows of a table by a single statement, positions
+/// are the ones of the rows before any change is applied.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Changes {
+    pub inserted: Vec<Row>,
+    pub updated: Vec<(usize, Row)>,
+    pub deleted: Vec<usize>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.inserted.is_empty() && self.updated.is_empty() && self.deleted.is_empty()
+    }
+}
+
+// Position of the row holding each key of a unique constraint.
+type Index = HashMap<Vec<Value>, usize>;
+
+/// Table of the catalog, every row holds one item per column. Rows are
+/// only modified through [`Catalog::apply`] which enforces the constraints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub name: String,
+    pub columns: Vec<Column>,
+    pub constraints: Vec<Constraint>,
+    rows: Vec<Row>,
+    // Index of each primary key and unique constraint by constraint name,
+    // keys holding a `NULL` value are not indexed as they never conflict.
+    indexes: HashMap<String, Index>,
+}
+
+impl Table {
+    /// Create an empty table without constraints, column names must be
+    /// distinct.
+    pub fn new(name: &str, columns: Vec<Column>) -> Result<Self, CatalogError> {
+        let mut table = Self {
+            name: name.to_string(),
+            columns: Vec::with_capacity(columns.len()),
+            constraints: vec![],
+            rows: vec![],
+            indexes: HashMap::new(),
+        };
+        for column in columns {
+            table.check_new_column(&column.name)?;
+            table.columns.push(column);
+        }
+        Ok(table)
+    }
+
+    /// Returns the schema of the rows of the table.
+    pub fn schema(&self) -> Schema {
+        Schema::new(
+            self.columns
+                .iter()
+                .map(|column| Field::new(&column.name, column.data_type.clone()))
+                .collect(),
+        )
+    }
+
+    /// Returns the rows of the table in insertion order.
+    pub fn rows(&self) -> &[Row] {
+        &self.rows
+    }
+
+    /// Returns the index of the column with the given name.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column.name == name)
+    }
+
+    /// Returns the primary key and unique constraints of the table along
+    /// with the indexes of their columns.
+    pub fn unique_keys(&self) -> Vec<(&str, Vec<usize>)> {
+        self.constraints
+            .iter()
+            .filter_map(|constraint| {
+                let columns = constraint.unique_columns()?;
+                Some((constraint.name.as_str(), self.column_indexes(columns)))
+            })
+            .collect()
+    }
+
+    /// Returns the position of the row holding a key of a primary key or
+    /// unique constraint.
+    pub fn find(&self, constraint: &str, key: &[Value]) -> Option<usize> {
+        self.indexes.get(constraint)?.get(key).copied()
+    }
+
+    // Returns the name of the primary key or unique constraint on exactly
+    // the given columns.
+    fn unique_constraint(&self, columns: &[String]) -> Option<&str> {
+        self.constraints
+            .iter()
+            .find(|constraint| constraint.unique_columns() == Some(columns))
+            .map(|constraint| constraint.name.as_str())
+    }
+
+    // Returns the indexes of columns known to exist.
+    fn column_indexes(&self, columns: &[String]) -> Vec<usize> {
+        columns
+            .iter()
+            .map(|column| self.column_index(column).unwrap())
+            .collect()
+    }
+
+    // Build the index of a unique constraint from the rows of the table.
+    fn index(&self, constraint: &str, columns: &[usize]) -> Result<Index, CatalogError> {
+        let mut index = Index::new();
+        for (position, row) in self.rows.iter().enumerate() {
+            if let Some(key) = key(row, columns) {
+                if index.insert(key, position).is_some() {
+                    return Err(self.unique_violation(constraint, columns, row));
+                }
+            }
+        }
+        Ok(index)
+    }
+
+    // Returns the rows and indexes of the table once changes are applied,
+    // ensuring that no key of a unique constraint is duplicated.
+    fn changed(
+        &self,
+        changes: &Changes,
+    ) -> Result<(Vec<Row>, HashMap<String, Index>), CatalogError> {
+        let unique_keys = self.unique_keys();
+        let mut rows = self.rows.clone();
+        let mut indexes = self.indexes.clone();
+        let removed = changes
+            .deleted
+            .iter()
+            .chain(changes.updated.iter().map(|(position, _)| position));
+        for &position in removed {
+            for (constraint, columns) in &unique_keys {
+                if let Some(key) = key(&self.rows[position], columns) {
+                    indexes.get_mut(*constraint).unwrap().remove(&key);
+                }
+            }
+        }
+        let inserted = changes
+            .inserted
+            .iter()
+            .enumerate()
+            .map(|(offset, row)| (self.rows.len() + offset, row));
+        let added = changes
+            .updated
+            .iter()
+            .map(|(position, row)| (*position, row))
+            .chain(inserted);
+        for (position, row) in added {
+            for (constraint, columns) in &unique_keys {
+                if let Some(key) = key(row, columns) {
+                    let index = indexes.get_mut(*constraint).unwrap();
+                    if index.insert(key, position).is_some() {
+                        return Err(self.unique_violation(constraint, columns, row));
+                    }
+                }
+            }
+            match rows.get_mut(position) {
+                Some(existing) => *existing = row.clone(),
+                None => rows.push(row.clone()),
+            }
+        }
+        if !changes.deleted.is_empty() {
+            let deleted = changes.deleted.iter().collect::<HashSet<_>>();
+            // Position of every row once the deleted rows are removed.
+            let mut shifted = Vec::with_capacity(rows.len());
+            let mut removed = 0;
+            for position in 0..rows.len() {
+                shifted.push(position - removed);
+                if deleted.contains(&position) {
+                    removed += 1;
+                }
+            }
+            rows = rows
+                .into_iter()
+                .enumerate()
+                .filter(|(position, _)| !deleted.contains(position))
+                .map(|(_, row)| row)
+                .collect();
+            for position in indexes.values_mut().flat_map(|index| index.values_mut()) {
+                *position = shifted[*position];
+            }
+        }
+        Ok((rows, indexes))
+    }
+
+    // Binds the predicates of the check constraints of the table, along
+    // with the names of their constraints.
+    fn checks(&self, check: &CheckFn) -> Vec<(&str, Box<RowCheck>)> {
+        self.constraints
+            .iter()
+            .filter_map(|constraint| match &constraint.kind {
+                ConstraintKind::Check(expr) => Some((constraint.name.as_str(), check(self, expr))),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Ensure a row holds no `NULL` value in a `NOT NULL` column and
+    // satisfies the bound check constraints.
+    fn check_row(&self, row: &Row, checks: &[(&str, Box<RowCheck>)]) -> Result<(), CatalogError> {
+        let violation = self
+            .columns
+            .iter()
+            .zip(&row.items)
+            .find(|(column, value)| column.not_null && value.is_null());
+        if let Some((column, _)) = violation {
+            return Err(CatalogError::NotNullViolation {
+                table: self.name.clone(),
+                column: column.name.clone(),
+            });
+        }
+        for (constraint, check) in checks {
+            if !check(row)? {
+                return Err(CatalogError::CheckViolation {
+                    table: self.name.clone(),
+                    constraint: constraint.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn unique_violation(&self, constraint: &str, columns: &[usize], row: &Row) -> CatalogError {
+        let key = columns
+            .iter()
+            .map(|&index| row.items[index].clone())
+            .collect::<Vec<_>>();
+        CatalogError::UniqueViolation {
+            table: self.name.clone(),
+            constraint: constraint.to_string(),
+            key: self.describe_key(columns, &key),
+        }
+    }
+
+    // Format the key of the given columns as `(column, ...)=(value, ...)`.
+    fn describe_key(&self, columns: &[usize], key: &[Value]) -> String {
+        let names = columns
+            .iter()
+            .map(|&index| self.columns[index].name.as_str())
+            .collect::<Vec<_>>();
+        let values = key.iter().map(Value::to_string).collect::<Vec<_>>();
+        format!("({})=({})", names.join(", "), values.join(", "))
+    }
+
+    // Returns a constraint name derived from the table name and the columns
+    // of the constraint, suffixed by a number when it is already taken.
+    fn constraint_name(&self, kind: &ConstraintKind) -> String {
+        let name = match kind {
+            ConstraintKind::PrimaryKey(_) => format!("{}_pkey", self.name),
+            ConstraintKind::Unique(columns) => format!("{}_{}_key", self.name, columns.join("_")),
+            ConstraintKind::Check(expr) => match expr.columns().first() {
+                Some(column) => format!("{}_{column}_check", self.name),
+                None => format!("{}_check", self.name),
+            },
+            ConstraintKind::ForeignKey { columns, .. } => {
+                format!("{}_{}_fkey", self.name, columns.join("_"))
+            }
+        };
+        let taken = |name: &str| self.constraints.iter().any(|c| c.name == name);
+        if !taken(&name) {
+            return name;
+        }
+        (1..)
+            .map(|suffix| format!("{name}{suffix}"))
+            .find(|name| !taken(name))
+            .unwrap()
+    }
+
+    fn existing_column(&self, name: &str) -> Result<usize, CatalogError> {
+        self.column_index(name)
+            .ok_or_else(|| CatalogError::UnknownColumn {
+                table: self.name.clone(),
+                column: name.to_string(),
+            })
+    }
+
+    fn check_new_column(&self, name: &str) -> Result<(), CatalogError> {
+        match self.column_index(name) {
+            Some(_) => Err(CatalogError::DuplicateColumn {
+                table: self.name.clone(),
+                column: name.to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+// Returns the values of the given columns of a row, `None` when one of them
+// is `NULL` as such a key neither conflicts with nor references another.
+fn key(row: &Row, columns: &[usize]) -> Option<Vec<Value>> {
+    columns
+        .iter()
+        .map(|&index| Some(row.items[index].clone()).filter(|value| !value.is_null()))
+        .collect()
+}
+
+/// Tables created by DDL statements indexed by name, along with the
+/// statistics of the analyzed ones.
+#[derive(Debug, Clone)]
+pub struct Catalog {
+    tables: BTreeMap<String, Table>,
+    statistics: BTreeMap<String, TableStatistics>,
+    // System table exposing the statistics, rebuilt when they change.
+    statistics_table: Table,
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        let varchar = |name| Column::new(name, DataType::Varchar);
+        let bigint = |name| Column::new(name, DataType::BigInt);
+        let columns = vec![
+            varchar("table_name"),
+            varchar("column_name"),
+            bigint("row_count"),
+            bigint("distinct_count"),
+            Column::new("null_fraction", DataType::Float),
+            varchar("min_value"),
+            varchar("max_value"),
+            Column::new(
+                "histogram_bounds",
+                DataType::Array(Box::new(DataType::Varchar)),
+            ),
+        ];
+        Self {
+            tables: BTreeMap::new(),
+            statistics: BTreeMap::new(),
+            statistics_table: Table::new(STATISTICS_TABLE, columns).unwrap(),
+        }
+    }
+}
+
+impl Catalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the table with the given name, system tables included.
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        match name {
+            STATISTICS_TABLE => Some(&self.statistics_table),
+            name => self.tables.get(name),
+        }
+    }
+
+    /// Returns the statistics collected on a table when it was last
+    /// analyzed, none when it was not or was altered since.
+    pub fn statistics(&self, name: &str) -> Option<&TableStatistics> {
+        self.statistics.get(name)
+    }
+
+    /// Collect the statistics of the rows of a table, replacing the previous
+    /// ones.
+    pub fn analyze(&mut self, name: &str) -> Result<(), CatalogError> {
+        let table = self.existing_table(name)?;
+        let statistics = TableStatistics::collect(table.rows(), table.columns.len());
+        self.statistics.insert(name.to_string(), statistics);
+        self.refresh_statistics();
+        Ok(())
+    }
+
+    /// Returns the tables of the catalog ordered by name.
+    pub fn tables(&self) -> impl Iterator<Item = &Table> {
+        self.tables.values()
+    }
+
+    /// Add a table to the catalog, its name must not be taken.
+    pub fn create_table(&mut self, table: Table) -> Result<(), CatalogError> {
+        if table.name == STATISTICS_TABLE {
+            return Err(CatalogError::SystemTable(table.name));
+        }
+        if self.tables.contains_key(&table.name) {
+            return Err(CatalogError::DuplicateTable(table.name));
+        }
+        self.tables.insert(table.name.clone(), table);
+        Ok(())
+    }
+
+    /// Remove a table from the catalog, returns it along with its rows. A
+    /// table referenced by the foreign key of another table is kept.
+    pub fn drop_table(&mut self, name: &str) -> Result<Table, CatalogError> {
+        let table = self.existing_table(name)?;
+        if let Some((referencing, constraint)) = self
+            .references(name)
+            .find(|(referencing, _)| referencing.name != table.name)
+        {
+            return Err(CatalogError::Referenced {
+                table: name.to_string(),
+                constraint: constraint.name.clone(),
+                referencing: referencing.name.clone(),
+            });
+        }
+        self.invalidate_statistics(name);
+        Ok(self.tables.remove(name).unwrap())
+    }
+
+    /// Append a column to a table, existing rows take the given value.
+    pub fn add_column(
+        &mut self,
+        name: &str,
+        column: Column,
+        value: Value,
+    ) -> Result<(), CatalogError> {
+        let table = self.existing_table(name)?;
+        table.check_new_column(&column.name)?;
+        if column.not_null && value.is_null() && !table.rows.is_empty() {
+            return Err(CatalogError::NotNullViolation {
+                table: name.to_string(),
+                column: column.name,
+            });
+        }
+        let table = self.tables.get_mut(name).unwrap();
+        for row in &mut table.rows {
+            row.items.push(value.clone());
+        }
+        table.columns.push(column);
+        self.invalidate_statistics(name);
+        Ok(())
+    }
+
+    /// Remove a column along with its items from every row, and the
+    /// constraints of the table depending on it. A column referenced by the
+    /// foreign key of another table is kept.
+    pub fn drop_column(&mut self, name: &str, column: &str) -> Result<(), CatalogError> {
+        let index = self.existing_table(name)?.existing_column(column)?;
+        let referenced = self.references(name).find(|(referencing, constraint)| {
+            let ConstraintKind::ForeignKey { referenced, .. } = &constraint.kind else {
+                unreachable!()
+            };
+            referencing.name != name && referenced.iter().any(|c| c == column)
+        });
+        if let Some((referencing, constraint)) = referenced {
+            return Err(CatalogError::Referenced {
+                table: name.to_string(),
+                constraint: constraint.name.clone(),
+                referencing: referencing.name.clone(),
+            });
+        }
+        let table = self.tables.get_mut(name).unwrap();
+        let dropped = table
+            .constraints
+            .iter()
+            .filter(|constraint| {
+                constraint.involves(column)
+                    || matches!(
+                        &constraint.kind,
+                        ConstraintKind::ForeignKey { table: parent, referenced, .. }
+                            if parent == name && referenced.iter().any(|c| c == column)
+                    )
+            })
+            .map(|constraint| constraint.name.clone())
+            .collect::<HashSet<_>>();
+        table
+            .constraints
+            .retain(|constraint| !dropped.contains(&constraint.name));
+        table
+            .indexes
+            .retain(|constraint, _| !dropped.contains(constraint));
+        table.columns.remove(index);
+        for row in &mut table.rows {
+            row.items.remove(index);
+        }
+        self.invalidate_statistics(name);
+        Ok(())
+    }
+
+    /// Rename a column, the new name must not be taken by another column.
+    /// Constraints and the foreign keys referencing the column follow it.
+    pub fn rename_column(&mut self, name: &str, from: &str, to: &str) -> Result<(), CatalogError> {
+        let table = self.existing_table(name)?;
+        let index = table.existing_column(from)?;
+        table.check_new_column(to)?;
+        let rename = |column: &mut String| {
+            if column == from {
+                *column = to.to_string();
+            }
+        };
+        for table in self.tables.values_mut() {
+            let is_renamed = table.name == name;
+            for constraint in &mut table.constraints {
+                match &mut constraint.kind {
+                    ConstraintKind::PrimaryKey(columns) | ConstraintKind::Unique(columns)
+                        if is_renamed =>
+                    {
+                        columns.iter_mut().for_each(rename)
+                    }
+                    ConstraintKind::Check(expr) if is_renamed => {
+                        *expr =
+                            std::mem::replace(expr, Expr::Null).transform(&mut |expr| match expr {
+                                Expr::Column(column) if column == from => {
+                                    Expr::Column(to.to_string())
+                                }
+                                Expr::QualifiedColumn(table, column)
+                                    if table == name && column == from =>
+                                {
+                                    Expr::QualifiedColumn(table, to.to_string())
+                                }
+                                expr => expr,
+                            })
+                    }
+                    ConstraintKind::ForeignKey {
+                        columns,
+                        table: parent,
+                        referenced,
+                        ..
+                    } => {
+                        if is_renamed {
+                            columns.iter_mut().for_each(rename);
+                        }
+                        if parent == name {
+                            referenced.iter_mut().for_each(rename);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.tables.get_mut(name).unwrap().columns[index].name = to.to_string();
+        self.refresh_statistics();
+        Ok(())
+    }
+
+    /// Add a constraint to a table, its name is derived from the table and
+    /// its columns unless given. Existing rows must satisfy the constraint.
+    pub fn add_constraint(
+        &mut self,
+        name: &str,
+        constraint: Option<String>,
+        kind: ConstraintKind,
+        check: &CheckFn,
+    ) -> Result<(), CatalogError> {
+        let table = self.existing_table(name)?;
+        let kind = self.resolve_constraint(table, kind)?;
+        let constraint = constraint.unwrap_or_else(|| table.constraint_name(&kind));
+        if table.constraints.iter().any(|c| c.name == constraint) {
+            return Err(CatalogError::DuplicateConstraint {
+                table: name.to_string(),
+                constraint,
+            });
+        }
+        let mut index = None;
+        match &kind {
+            ConstraintKind::PrimaryKey(columns) | ConstraintKind::Unique(columns) => {
+                let is_primary_key = matches!(kind, ConstraintKind::PrimaryKey(_));
+                if is_primary_key
+                    && (table.constraints.iter())
+                        .any(|c| matches!(c.kind, ConstraintKind::PrimaryKey(_)))
+                {
+                    return Err(CatalogError::MultiplePrimaryKeys(name.to_string()));
+                }
+                let indexes = table.column_indexes(columns);
+                if is_primary_key {
+                    for (column, &index) in columns.iter().zip(&indexes) {
+                        if table.rows.iter().any(|row| row.items[index].is_null()) {
+                            return Err(CatalogError::NotNullViolation {
+                                table: name.to_string(),
+                                column: column.clone(),
+                            });
+                        }
+                    }
+                }
+                index = Some(table.index(&constraint, &indexes)?);
+            }
+            ConstraintKind::Check(expr) => {
+                let check = check(table, expr);
+                for row in &table.rows {
+                    if !check(row)? {
+                        return Err(CatalogError::CheckViolation {
+                            table: name.to_string(),
+                            constraint,
+                        });
+                    }
+                }
+            }
+            ConstraintKind::ForeignKey { .. } => {
+                let constraint = Constraint {
+                    name: constraint.clone(),
+                    kind: kind.clone(),
+                };
+                for row in &table.rows {
+                    self.check_reference(table, &constraint, row, None)?;
+                }
+            }
+        }
+        let table = self.tables.get_mut(name).unwrap();
+        if let ConstraintKind::PrimaryKey(columns) = &kind {
+            for column in columns {
+                let index = table.column_index(column).unwrap();
+                table.columns[index].not_null = true;
+            }
+        }
+        if let Some(index) = index {
+            table.indexes.insert(constraint.clone(), index);
+        }
+        table.constraints.push(Constraint {
+            name: constraint,
+            kind,
+        });
+        Ok(())
+    }
+
+    /// Apply the changes of a statement to the rows of a table, nothing is
+    /// changed unless every constraint is satisfied once they are applied.
+    /// Rows referencing deleted or updated keys are deleted, updated or
+    /// set to `NULL` according to the actions of their foreign keys.
+    pub fn apply(
+        &mut self,
+        name: &str,
+        changes: Changes,
+        check: &CheckFn,
+    ) -> Result<(), CatalogError> {
+        self.existing_table(name)?;
+        // Any failure of a referential action, including a failing check, may
+        // come after some table was changed, in which case every table is
+        // restored. Without references a table is only changed once all
+        // of its rows are checked.
+        let snapshot = self
+            .references(name)
+            .next()
+            .is_some()
+            .then(|| self.tables.clone());
+        let result = self.apply_changes(name, changes, check);
+        if let (Err(_), Some(tables)) = (&result, snapshot) {
+            self.tables = tables;
+        }
+        result
+    }
+
+    fn apply_changes(
+        &mut self,
+        name: &str,
+        changes: Changes,
+        check: &CheckFn,
+    ) -> Result<(), CatalogError> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+        let table = &self.tables[name];
+        let (rows, indexes) = table.changed(&changes)?;
+        let changed = changes
+            .updated
+            .iter()
+            .map(|(_, row)| row)
+            .chain(&changes.inserted);
+        let checks = table.checks(check);
+        for row in changed {
+            table.check_row(row, &checks)?;
+            for constraint in &table.constraints {
+                self.check_reference(table, constraint, row, Some(&indexes))?;
+            }
+        }
+        let table = self.tables.get_mut(name).unwrap();
+        let previous = std::mem::replace(&mut table.rows, rows);
+        table.indexes = indexes;
+        let referencing = self
+            .references(name)
+            .map(|(table, constraint)| (table.name.clone(), constraint.clone()))
+            .collect::<Vec<_>>();
+        for (referencing, constraint) in referencing {
+            let changes =
+                self.referential_actions(name, &previous, &changes, &referencing, &constraint)?;
+            self.apply_changes(&referencing, changes, check)?;
+        }
+        Ok(())
+    }
+
+    // Returns the changes to the rows of a table referencing the keys
+    // deleted or updated by the changes to the referenced table.
+    fn referential_actions(
+        &self,
+        name: &str,
+        previous: &[Row],
+        changes: &Changes,
+        referencing: &str,
+        constraint: &Constraint,
+    ) -> Result<Changes, CatalogError> {
+        let ConstraintKind::ForeignKey {
+            columns,
+            referenced,
+            on_delete,
+            on_update,
+            ..
+        } = &constraint.kind
+        else {
+            unreachable!()
+        };
+        let table = &self.tables[name];
+        let unique = table.unique_constraint(referenced).unwrap();
+        let referenced = table.column_indexes(referenced);
+        // Keys which are no longer present, along with the values they were
+        // updated to.
+        let mut removed = HashMap::new();
+        for &position in &changes.deleted {
+            if let Some(key) = key(&previous[position], &referenced) {
+                removed.insert(key, None);
+            }
+        }
+        for (position, row) in &changes.updated {
+            if let Some(key) = key(&previous[*position], &referenced) {
+                let values = referenced
+                    .iter()
+                    .map(|&index| row.items[index].clone())
+                    .collect::<Vec<_>>();
+                if key != values {
+                    removed.insert(key, Some(values));
+                }
+            }
+        }
+        removed.retain(|key, _| table.find(unique, key).is_none());
+        let mut actions = Changes::default();
+        if removed.is_empty() {
+            return Ok(actions);
+        }
+        let referencing = &self.tables[referencing];
+        let columns = referencing.column_indexes(columns);
+        for (position, row) in referencing.rows.iter().enumerate() {
+            let Some(key) = key(row, &columns) else {
+                continue;
+            };
+            let Some(values) = removed.get(&key) else {
+                continue;
+            };
+            let action = match values {
+                Some(_) => on_update,
+                None => on_delete,
+            };
+            let mut row = row.clone();
+            match (action, values) {
+                (ReferentialAction::NoAction | ReferentialAction::Restrict, _) => {
+                    return Err(CatalogError::RestrictViolation {
+                        table: name.to_string(),
+                        constraint: constraint.name.clone(),
+                        key: table.describe_key(&referenced, &key),
+                        referencing: referencing.name.clone(),
+                    });
+                }
+                (ReferentialAction::Cascade, None) => {
+                    actions.deleted.push(position);
+                    continue;
+                }
+                (ReferentialAction::Cascade, Some(values)) => {
+                    for (&index, value) in columns.iter().zip(values) {
+                        row.items[index] = value.clone();
+                    }
+                }
+                (ReferentialAction::SetNull, _) => {
+                    for &index in &columns {
+                        row.items[index] = Value::Null;
+                    }
+                }
+            }
+            actions.updated.push((position, row));
+        }
+        Ok(actions)
+    }
+
+    // Ensure the key of a row is present in the table referenced by a
+    // foreign key, using the given indexes when the table references itself.
+    fn check_reference(
+        &self,
+        table: &Table,
+        constraint: &Constraint,
+        row: &Row,
+        indexes: Option<&HashMap<String, Index>>,
+    ) -> Result<(), CatalogError> {
+        let ConstraintKind::ForeignKey {
+            columns,
+            table: parent,
+            referenced,
+            ..
+        } = &constraint.kind
+        else {
+            return Ok(());
+        };
+        let columns = table.column_indexes(columns);
+        let Some(key) = key(row, &columns) else {
+            return Ok(());
+        };
+        let parent_table = &self.tables[parent];
+        let unique = parent_table.unique_constraint(referenced).unwrap();
+        let found = match indexes {
+            Some(indexes) if *parent == table.name => indexes[unique].contains_key(&key),
+            _ => parent_table.find(unique, &key).is_some(),
+        };
+        match found {
+            true => Ok(()),
+            false => Err(CatalogError::ForeignKeyViolation {
+                table: table.name.clone(),
+                constraint: constraint.name.clone(),
+                key: table.describe_key(&columns, &key),
+                referenced: parent.clone(),
+            }),
+        }
+    }
+
+    // Ensure the columns of a constraint exist, and that a foreign key
+    // references a primary key or unique constraint of the same types. The
+    // columns of a foreign key are reordered after its referenced key.
+    fn resolve_constraint(
+        &self,
+        table: &Table,
+        kind: ConstraintKind,
+    ) -> Result<ConstraintKind, CatalogError> {
+        let ConstraintKind::ForeignKey {
+            columns,
+            table: parent,
+            referenced,
+            on_delete,
+            on_update,
+        } = kind
+        else {
+            if let ConstraintKind::PrimaryKey(columns) | ConstraintKind::Unique(columns) = &kind {
+                for column in columns {
+                    table.existing_column(column)?;
+                }
+            }
+            return Ok(kind);
+        };
+        for column in &columns {
+            table.existing_column(column)?;
+        }
+        // A table referencing itself is not yet part of the catalog while it
+        // is created.
+        let parent_table = match parent == table.name {
+            true => table,
+            false => self.existing_table(&parent)?,
+        };
+        for column in &referenced {
+            parent_table.existing_column(column)?;
+        }
+        let referenced = match referenced.is_empty() {
+            true => parent_table
+                .constraints
+                .iter()
+                .find_map(|constraint| match &constraint.kind {
+                    ConstraintKind::PrimaryKey(columns) => Some(columns.clone()),
+                    _ => None,
+                })
+                .ok_or_else(|| CatalogError::NoPrimaryKey(parent.clone()))?,
+            false => referenced,
+        };
+        let no_unique_key = || CatalogError::NoUniqueKey {
+            table: parent.clone(),
+            columns: referenced.clone(),
+        };
+        if columns.len() != referenced.len() {
+            return Err(no_unique_key());
+        }
+        let key = parent_table
+            .constraints
+            .iter()
+            .filter_map(Constraint::unique_columns)
+            .find(|key| {
+                key.len() == referenced.len()
+                    && key.iter().all(|column| referenced.contains(column))
+            })
+            .ok_or_else(no_unique_key)?;
+        let columns = key
+            .iter()
+            .map(|column| {
+                let position = referenced.iter().position(|c| c == column).unwrap();
+                columns[position].clone()
+            })
+            .collect::<Vec<_>>();
+        for (column, referenced) in columns.iter().zip(key) {
+            let data_type = &table.columns[table.column_index(column).unwrap()].data_type;
+            let referenced_type =
+                &parent_table.columns[parent_table.column_index(referenced).unwrap()].data_type;
+            if data_type != referenced_type {
+                return Err(CatalogError::IncompatibleForeignKey {
+                    column: column.clone(),
+                    data_type: data_type.clone(),
+                    referenced: format!("{parent}.{referenced}"),
+                    referenced_type: referenced_type.clone(),
+                });
+            }
+        }
+        Ok(ConstraintKind::ForeignKey {
+            columns,
+            table: parent,
+            referenced: key.to_vec(),
+            on_delete,
+            on_update,
+        })
+    }
+
+    // Returns the foreign keys referencing a table along with their table.
+    fn references<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = (&'a Table, &'a Constraint)> {
+        self.tables.values().flat_map(move |table| {
+            table
+                .constraints
+                .iter()
+                .filter(move |constraint| {
+                    matches!(&constraint.kind, ConstraintKind::ForeignKey { table, .. } if table == name)
+                })
+                .map(move |constraint| (table, constraint))
+        })
+    }
+
+    // Drops the statistics of a table whose columns changed.
+    fn invalidate_statistics(&mut self, name: &str) {
+        if self.statistics.remove(name).is_some() {
+            self.refresh_statistics();
+        }
+    }
+
+    // Rebuilds the rows of the statistics table, values are written as text
+    // since the columns of the analyzed tables have different types.
+    fn refresh_statistics(&mut self) {
+        let text = |value: &Option<Value>| match value {
+            Some(value) => Value::Varchar(value.to_string()),
+            None => Value::Null,
+        };
+        let rows = self
+            .statistics
+            .iter()
+            .flat_map(|(name, statistics)| {
+                let columns = &self.tables[name].columns;
+                columns
+                    .iter()
+                    .zip(&statistics.columns)
+                    .map(|(column, stats)| {
+                        Row::new(&[
+                            Value::from(name.as_str()),
+                            Value::from(column.name.as_str()),
+                            Value::BigInt(statistics.row_count as i64),
+                            Value::BigInt(stats.distinct_count as i64),
+                            Value::Float(stats.null_fraction),
+                            text(&stats.min),
+                            text(&stats.max),
+                            Value::Array(
+                                stats
+                                    .histogram
+                                    .bounds
+                                    .iter()
+                                    .map(|bound| Value::Varchar(bound.to_string()))
+                                    .collect(),
+                            ),
+                        ])
+                    })
+            })
+            .collect();
+        self.statistics_table.rows = rows;
+    }
+
+    fn existing_table(&self, name: &str) -> Result<&Table, CatalogError> {
+        if name == STATISTICS_TABLE {
+            return Err(CatalogError::SystemTable(name.to_string()));
+        }
+        self.tables
+            .get(name)
+            .ok_or_else(|| CatalogError::UnknownTable(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Evaluates `balance >= 0`, the only check constraint of these tests.
+    fn check(_: &Table, _: &Expr) -> Box<RowCheck> {
+        Box::new(|row: &Row| Ok(!matches!(row.items[2], Value::Integer(balance) if balance < 0)))
+    }
+
+    fn account(id: i32, owner: &str, balance: i32) -> Row {
+        Row::new(&[
+            Value::Integer(id),
+            Value::from(owner),
+            Value::Integer(balance),
+        ])
+    }
+
+    fn inserted(rows: Vec<Row>) -> Changes {
+        Changes {
+            inserted: rows,
+            ..Changes::default()
+        }
+    }
+
+    fn deleted(positions: Vec<usize>) -> Changes {
+        Changes {
+            deleted: positions,
+            ..Changes::default()
+        }
+    }
+
+    fn catalog() -> Catalog {
+        let mut catalog = Catalog::new();
+        let mut owner = Column::new("owner", DataType::Varchar);
+        owner.not_null = true;
+        let table = Table::new(
+            "accounts",
+            vec![
+                Column::new("id", DataType::Integer),
+                owner,
+                Column::new("balance", DataType::Integer),
+            ],
+        )
+        .unwrap();
+        catalog.create_table(table).unwrap();
+        let rows = vec![account(1, "Alice", 10), account(2, "Bob", 20)];
+        catalog.apply("accounts", inserted(rows), &check).unwrap();
+        catalog
+    }
+
+    fn transfers(on_delete: ReferentialAction) -> Catalog {
+        let mut catalog = catalog();
+        let table = Table::new(
+            "transfers",
+            vec![
+                Column::new("account", DataType::Integer),
+                Column::new("amount", DataType::Integer),
+            ],
+        )
+        .unwrap();
+        catalog.create_table(table).unwrap();
+        let primary_key = ConstraintKind::PrimaryKey(vec!["id".to_string()]);
+        catalog
+            .add_constraint("accounts", None, primary_key, &check)
+            .unwrap();
+        let foreign_key = ConstraintKind::ForeignKey {
+            columns: vec!["account".to_string()],
+            table: "accounts".to_string(),
+            referenced: vec![],
+            on_delete,
+            on_update: ReferentialAction::Cascade,
+        };
+        catalog
+            .add_constraint("transfers", None, foreign_key, &check)
+            .unwrap();
+        let transfer =
+            |account, amount| Row::new(&[Value::Integer(account), Value::Integer(amount)]);
+        let rows = vec![transfer(1, 5), transfer(2, 7), transfer(1, 3)];
+        catalog.apply("transfers", inserted(rows), &check).unwrap();
+        catalog
+    }
+
+    fn rows(catalog: &Catalog, table: &str) -> Vec<Vec<Value>> {
+        let table = catalog.table(table).unwrap();
+        table.rows().iter().map(|row| row.items.clone()).collect()
+    }
+
+    #[test]
+    fn can_create_and_drop_tables() {
+        let mut catalog = catalog();
+        assert_eq!(
+            catalog.create_table(Table::new("accounts", vec![]).unwrap()),
+            Err(CatalogError::DuplicateTable("accounts".to_string()))
+        );
+        assert_eq!(catalog.table("accounts").unwrap().rows().len(), 2);
+        assert_eq!(catalog.drop_table("accounts").unwrap().name, "accounts");
+        assert!(catalog.table("accounts").is_none());
+        assert_eq!(
+            catalog.drop_table("accounts").unwrap_err().to_string(),
+            "Unknown table accounts"
+        );
+    }
+
+    #[test]
+    fn duplicate_columns_are_rejected() {
+        let columns = vec![
+            Column::new("id", DataType::Integer),
+            Column::new("id", DataType::Varchar),
+        ];
+        assert_eq!(
+            Table::new("accounts", columns),
+            Err(CatalogError::DuplicateColumn {
+                table: "accounts".to_string(),
+                column: "id".to_string(),
+            })
+        );
+        assert!(catalog().rename_column("accounts", "owner", "id").is_err());
+    }
+
+    #[test]
+    fn can_alter_columns() {
+        let mut catalog = catalog();
+        let mut opened = Column::new("opened", DataType::Integer);
+        opened.not_null = true;
+        assert_eq!(
+            catalog
+                .add_column("accounts", opened.clone(), Value::Null)
+                .unwrap_err()
+                .to_string(),
+            "Column opened of table accounts cannot be NULL"
+        );
+        catalog
+            .add_column("accounts", opened, Value::Integer(2024))
+            .unwrap();
+        catalog
+            .rename_column("accounts", "owner", "holder")
+            .unwrap();
+        catalog.drop_column("accounts", "id").unwrap();
+        catalog.drop_column("accounts", "balance").unwrap();
+        assert_eq!(
+            catalog.table("accounts").unwrap().schema(),
+            Schema::new(vec![
+                Field::new("holder", DataType::Varchar),
+                Field::new("opened", DataType::Integer),
+            ])
+        );
+        assert_eq!(
+            rows(&catalog, "accounts")[1],
+            vec![Value::from("Bob"), Value::Integer(2024)]
+        );
+        assert!(catalog.drop_column("accounts", "id").is_err());
+    }
+
+    #[test]
+    fn can_modify_rows() {
+        let mut catalog = catalog();
+        let balance = Expr::Comparison(
+            Box::new(Expr::Column("balance".to_string())),
+            ">=".to_string(),
+            Box::new(Expr::Value(0)),
+        );
+        catalog
+            .add_constraint("accounts", None, ConstraintKind::Check(balance), &check)
+            .unwrap();
+        let accounts = vec![account(3, "Carol", 30), account(4, "Dan", -1)];
+        assert_eq!(
+            catalog.apply("accounts", inserted(accounts), &check),
+            Err(CatalogError::CheckViolation {
+                table: "accounts".to_string(),
+                constraint: "accounts_balance_check".to_string(),
+            })
+        );
+        let row = Row::new(&[Value::Integer(3), Value::Null, Value::Integer(30)]);
+        assert_eq!(
+            catalog
+                .apply("accounts", inserted(vec![row]), &check)
+                .unwrap_err()
+                .to_string(),
+            "Column owner of table accounts cannot be NULL"
+        );
+        let changes = Changes {
+            inserted: vec![account(3, "Carol", 30)],
+            updated: vec![(0, account(1, "Ann", 15))],
+            deleted: vec![1],
+        };
+        catalog.apply("accounts", changes, &check).unwrap();
+        assert_eq!(
+            rows(&catalog, "accounts"),
+            vec![account(1, "Ann", 15).items, account(3, "Carol", 30).items]
+        );
+    }
+
+    #[test]
+    fn unique_keys_are_indexed() {
+        let mut catalog = catalog();
+        let unique = ConstraintKind::Unique(vec!["owner".to_string()]);
+        catalog
+            .add_constraint("accounts", None, unique.clone(), &check)
+            .unwrap();
+        let name = Some("accounts_owner_key".to_string());
+        assert_eq!(
+            catalog
+                .add_constraint("accounts", name, unique, &check)
+                .unwrap_err()
+                .to_string(),
+            "Constraint accounts_owner_key of table accounts already exists"
+        );
+        let accounts = vec![account(3, "Carol", 0), account(4, "Alice", 0)];
+        assert_eq!(
+            catalog.apply("accounts", inserted(accounts), &check).unwrap_err().to_string(),
+            "Key (owner)=(Alice) already exists in table accounts, violating constraint accounts_owner_key"
+        );
+        // Keys can be swapped by a single statement, and remain indexed once
+        // the rows before them are deleted.
+        let changes = Changes {
+            updated: vec![(0, account(1, "Bob", 10)), (1, account(2, "Alice", 20))],
+            ..Changes::default()
+        };
+        catalog.apply("accounts", changes, &check).unwrap();
+        catalog.apply("accounts", deleted(vec![0]), &check).unwrap();
+        let accounts = catalog.table("accounts").unwrap();
+        assert_eq!(
+            accounts.find("accounts_owner_key", &[Value::from("Alice")]),
+            Some(0)
+        );
+        assert_eq!(
+            accounts.find("accounts_owner_key", &[Value::from("Bob")]),
+            None
+        );
+        let primary_key = ConstraintKind::PrimaryKey(vec!["owner".to_string()]);
+        catalog
+            .add_constraint("accounts", None, primary_key.clone(), &check)
+            .unwrap();
+        assert_eq!(
+            catalog.add_constraint("accounts", None, primary_key, &check),
+            Err(CatalogError::MultiplePrimaryKeys("accounts".to_string()))
+        );
+    }
+
+    #[test]
+    fn foreign_keys_restrict_deletes() {
+        let mut catalog = transfers(ReferentialAction::Restrict);
+        assert_eq!(
+            catalog.apply("accounts", deleted(vec![0]), &check).unwrap_err().to_string(),
+            "Key (id)=(1) of table accounts is still referenced from table transfers, violating constraint transfers_account_fkey"
+        );
+        let row = Row::new(&[Value::Integer(3), Value::Integer(1)]);
+        assert_eq!(
+            catalog.apply("transfers", inserted(vec![row]), &check).unwrap_err().to_string(),
+            "Key (account)=(3) of table transfers is not present in table accounts, violating constraint transfers_account_fkey"
+        );
+        assert_eq!(
+            catalog.drop_table("accounts").unwrap_err().to_string(),
+            "Table accounts is referenced by constraint transfers_account_fkey of table transfers"
+        );
+        assert!(catalog.drop_column("accounts", "id").is_err());
+        // Updated keys cascade to the referencing rows.
+        let changes = Changes {
+            updated: vec![(0, account(10, "Alice", 10))],
+            ..Changes::default()
+        };
+        catalog.apply("accounts", changes, &check).unwrap();
+        assert_eq!(
+            rows(&catalog, "transfers"),
+            vec![
+                vec![Value::Integer(10), Value::Integer(5)],
+                vec![Value::Integer(2), Value::Integer(7)],
+                vec![Value::Integer(10), Value::Integer(3)],
+            ]
+        );
+    }
+
+    #[test]
+    fn foreign_keys_cascade_deletes() {
+        let mut catalog = transfers(ReferentialAction::Cascade);
+        catalog.apply("accounts", deleted(vec![0]), &check).unwrap();
+        assert_eq!(
+            rows(&catalog, "transfers"),
+            vec![vec![Value::Integer(2), Value::Integer(7)]]
+        );
+        let mut catalog = transfers(ReferentialAction::SetNull);
+        catalog.apply("accounts", deleted(vec![1]), &check).unwrap();
+        assert_eq!(
+            rows(&catalog, "transfers")[1],
+            vec![Value::Null, Value::Integer(7)]
+        );
+    }
+
+    #[test]
+    fn foreign_keys_must_reference_unique_keys() {
+        let mut catalog = transfers(ReferentialAction::NoAction);
+        let foreign_key = |referenced: &str| ConstraintKind::ForeignKey {
+            columns: vec!["amount".to_string()],
+            table: "accounts".to_string(),
+            referenced: vec![referenced.to_string()],
+            on_delete: ReferentialAction::NoAction,
+            on_update: ReferentialAction::NoAction,
+        };
+        assert_eq!(
+            catalog
+                .add_constraint("transfers", None, foreign_key("balance"), &check)
+                .unwrap_err()
+                .to_string(),
+            "There is no unique constraint matching columns (balance) of table accounts"
+        );
+        let unique = ConstraintKind::Unique(vec!["owner".to_string()]);
+        catalog
+            .add_constraint("accounts", None, unique, &check)
+            .unwrap();
+        assert_eq!(
+            catalog
+                .add_constraint("transfers", None, foreign_key("owner"), &check)
+                .unwrap_err()
+                .to_string(),
+            "Foreign key column amount of type INTEGER cannot reference column accounts.owner of type VARCHAR"
+        );
+    }
+
+    #[test]
+    fn statistics_are_collected_by_analyze() {
+        let mut catalog = catalog();
+        assert!(catalog.statistics("accounts").is_none());
+        catalog.analyze("accounts").unwrap();
+        let statistics = catalog.statistics("accounts").unwrap();
+        assert_eq!(statistics.row_count, 2);
+        assert_eq!(statistics.columns[1].max, Some(Value::from("Bob")));
+        assert_eq!(
+            rows(&catalog, STATISTICS_TABLE)[2],
+            vec![
+                Value::from("accounts"),
+                Value::from("balance"),
+                Value::BigInt(2),
+                Value::BigInt(2),
+                Value::Float(0.0),
+                Value::from("10"),
+                Value::from("20"),
+                Value::Array(vec![Value::from("10"), Value::from("20")]),
+            ]
+        );
+        // Statistics follow renamed columns and are dropped along with
+        // altered tables.
+        catalog
+            .rename_column("accounts", "owner", "holder")
+            .unwrap();
+        assert_eq!(
+            rows(&catalog, STATISTICS_TABLE)[1][1],
+            Value::from("holder")
+        );
+        catalog.drop_column("accounts", "holder").unwrap();
+        assert!(catalog.statistics("accounts").is_none());
+        assert!(rows(&catalog, STATISTICS_TABLE).is_empty());
+        assert_eq!(
+            catalog.analyze("transfers"),
+            Err(CatalogError::UnknownTable("transfers".to_string()))
+        );
+    }
+
+    #[test]
+    fn system_tables_cannot_be_modified() {
+        let mut catalog = catalog();
+        assert!(catalog.tables().all(|table| table.name != STATISTICS_TABLE));
+        assert_eq!(
+            catalog
+                .drop_table(STATISTICS_TABLE)
+                .unwrap_err()
+                .to_string(),
+            "Table eocene_stats is a system table and cannot be modified"
+        );
+        let table = Table::new(STATISTICS_TABLE, vec![]).unwrap();
+        assert_eq!(
+            catalog.create_table(table),
+            Err(CatalogError::SystemTable(STATISTICS_TABLE.to_string()))
+        );
+        let changes = inserted(vec![Row::new(&[Value::Null])]);
+        assert!(catalog.apply(STATISTICS_TABLE, changes, &check).is_err());
+    }
+}
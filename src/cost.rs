@@ -0,0 +1,574 @@
+//! Implementation of the cost model estimating the number of rows logical
+//! plans produce from the statistics `ANALYZE` collects, which the optimizer
+//! orders joins by and the planner chooses join operators and partitions
+//! aggregates with.
+use crate::catalog::Catalog;
+use crate::logical::LogicalPlan;
+use crate::simplify::constant;
+use crate::sql::Expr;
+use crate::statistics::ColumnStatistics;
+
+/// Number of rows assumed for relations whose size is unknown.
+pub const DEFAULT_ROWS: f64 = 1000.0;
+/// Number of input rows aggregated by each partition of an aggregate.
+pub const PARTITION_ROWS: f64 = 10_000.0;
+/// Maximum number of partitions of an aggregate.
+pub const MAX_PARTITIONS: usize = 8;
+
+// Number of rows assumed for each call of a table function.
+const TABLE_FUNCTION_ROWS: f64 = 10.0;
+// Selectivities assumed for predicates on columns without statistics.
+const EQUALITY_SELECTIVITY: f64 = 0.1;
+const RANGE_SELECTIVITY: f64 = 1.0 / 3.0;
+const DEFAULT_SELECTIVITY: f64 = 0.5;
+
+/// Operator joining the rows of two inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinMethod {
+    /// Compares every pair of rows, see `Join`.
+    NestedLoop,
+    /// Probes the right rows hashed by key with each left row, see
+    /// `HashJoin`.
+    Hash,
+    /// Merges both inputs sorted by key, see `MergeJoin`.
+    Merge,
+}
+
+impl JoinMethod {
+    /// Returns the cost of joining inputs of the given number of rows,
+    /// merge joins don't sort inputs which are already sorted by key.
+    pub fn cost(self, left: f64, right: f64, sorted: bool) -> f64 {
+        let sort = |rows: f64| rows * rows.max(2.0).log2();
+        match self {
+            JoinMethod::NestedLoop => left * right,
+            JoinMethod::Hash => left + 2.0 * right,
+            JoinMethod::Merge if sorted => left + right,
+            JoinMethod::Merge => sort(left) + sort(right) + left + right,
+        }
+    }
+
+    /// Returns the cheapest method joining inputs of the given number of
+    /// rows, only nested loops can join inputs without equality keys. Nested
+    /// loops are preferred on ties.
+    pub fn cheapest(left: f64, right: f64, keys: bool, sorted: bool) -> JoinMethod {
+        if !keys {
+            return JoinMethod::NestedLoop;
+        }
+        [JoinMethod::Hash, JoinMethod::Merge].into_iter().fold(
+            JoinMethod::NestedLoop,
+            |best, method| {
+                if method.cost(left, right, sorted) < best.cost(left, right, sorted) {
+                    method
+                } else {
+                    best
+                }
+            },
+        )
+    }
+}
+
+/// Keys of an equi-join along with the rest of its condition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JoinKeys {
+    /// Expressions evaluated on the left rows.
+    pub left: Vec<Expr>,
+    /// Expressions evaluated on the right rows, compared with the left keys
+    /// at the same position.
+    pub right: Vec<Expr>,
+    /// Conjunction of the terms which don't compare keys, evaluated on the
+    /// joined rows.
+    pub residual: Option<Expr>,
+}
+
+impl JoinKeys {
+    /// Split a join condition evaluated on rows whose first `left_width`
+    /// columns are the left ones into the equalities between an expression
+    /// of each side and the other terms.
+    pub fn new(condition: Option<Expr>, left_width: usize) -> Self {
+        let mut keys = JoinKeys {
+            left: vec![],
+            right: vec![],
+            residual: None,
+        };
+        let terms = condition.map_or(vec![], Expr::conjuncts);
+        let mut residual = vec![];
+        for term in terms {
+            match side_of(&term, left_width) {
+                Some((left, right)) => {
+                    keys.left.push(left.clone());
+                    keys.right.push(shift(right.clone(), left_width));
+                }
+                None => residual.push(term),
+            }
+        }
+        keys.residual = residual
+            .into_iter()
+            .reduce(|left, right| Expr::And(Box::new(left), Box::new(right)));
+        keys
+    }
+
+    /// Returns whether the join compares keys.
+    pub fn is_empty(&self) -> bool {
+        self.left.is_empty()
+    }
+}
+
+/// Estimates the number of rows of plans from the statistics of the tables
+/// of a catalog, or from their current number of rows when they weren't
+/// analyzed.
+pub struct Estimator<'a> {
+    catalog: &'a Catalog,
+}
+
+impl<'a> Estimator<'a> {
+    pub fn new(catalog: &'a Catalog) -> Self {
+        Self { catalog }
+    }
+
+    /// Returns the estimated number of rows a plan produces.
+    pub fn rows(&self, plan: &LogicalPlan) -> f64 {
+        match plan {
+            LogicalPlan::Scan { table, .. } => match self.catalog.statistics(table) {
+                Some(statistics) => statistics.row_count as f64,
+                None => self
+                    .catalog
+                    .table(table)
+                    .map_or(DEFAULT_ROWS, |table| table.rows().len() as f64),
+            },
+            LogicalPlan::Values { rows, .. } => rows.len() as f64,
+            LogicalPlan::TableFunction { .. } => TABLE_FUNCTION_ROWS,
+            LogicalPlan::Filter { input, predicate } => {
+                self.rows(input) * self.selectivity(predicate, input)
+            }
+            LogicalPlan::Project { input, .. } | LogicalPlan::Sort { input, .. } => {
+                self.rows(input)
+            }
+            LogicalPlan::Join {
+                left,
+                right,
+                condition,
+            } => {
+                let rows = self.rows(left) * self.rows(right);
+                condition
+                    .as_ref()
+                    .map_or(rows, |condition| rows * self.selectivity(condition, plan))
+            }
+            LogicalPlan::LateralJoin {
+                input, condition, ..
+            } => {
+                let rows = self.rows(input) * TABLE_FUNCTION_ROWS;
+                condition
+                    .as_ref()
+                    .map_or(rows, |condition| rows * self.selectivity(condition, plan))
+            }
+            // Groups are bounded by the product of the distinct counts of
+            // their keys.
+            LogicalPlan::Aggregate {
+                input, group_by, ..
+            } => {
+                if group_by.is_empty() {
+                    return 1.0;
+                }
+                let rows = self.rows(input);
+                let groups = group_by
+                    .iter()
+                    .map(|key| match self.statistics(key, input) {
+                        Some(statistics) => statistics.distinct_count.max(1) as f64,
+                        None => rows,
+                    })
+                    .product::<f64>();
+                groups.min(rows)
+            }
+            LogicalPlan::Limit { input, count } => self.rows(input).min(*count as f64),
+        }
+    }
+
+    /// Returns the estimated fraction of the rows of a plan which satisfy a
+    /// predicate evaluated on them.
+    pub fn selectivity(&self, predicate: &Expr, plan: &LogicalPlan) -> f64 {
+        let selectivity = match predicate {
+            Expr::Boolean(true) => 1.0,
+            Expr::Boolean(false) | Expr::Null => 0.0,
+            Expr::And(left, right) => self.selectivity(left, plan) * self.selectivity(right, plan),
+            Expr::Or(left, right) => {
+                let (left, right) = (self.selectivity(left, plan), self.selectivity(right, plan));
+                left + right - left * right
+            }
+            Expr::Not(expr) => 1.0 - self.selectivity(expr, plan),
+            Expr::IsNull(expr, negated) => match self.statistics(expr, plan) {
+                Some(statistics) if *negated => 1.0 - statistics.null_fraction,
+                Some(statistics) => statistics.null_fraction,
+                None => DEFAULT_SELECTIVITY,
+            },
+            Expr::Comparison(left, op, right) => self.comparison(left, op, right, plan),
+            _ => DEFAULT_SELECTIVITY,
+        };
+        selectivity.clamp(0.0, 1.0)
+    }
+
+    /// Returns the cheapest method joining two inputs with the given keys,
+    /// inputs are only known to be sorted by a single key.
+    pub fn join_method(
+        &self,
+        left: &LogicalPlan,
+        right: &LogicalPlan,
+        keys: &JoinKeys,
+    ) -> JoinMethod {
+        let sorted = match (&keys.left[..], &keys.right[..]) {
+            ([Expr::Bound(left_key)], [Expr::Bound(right_key)]) => {
+                sorted_by(left, left_key.index) && sorted_by(right, right_key.index)
+            }
+            _ => false,
+        };
+        JoinMethod::cheapest(self.rows(left), self.rows(right), !keys.is_empty(), sorted)
+    }
+
+    /// Returns the number of partitions an aggregate distributes the rows of
+    /// its input over, one per `PARTITION_ROWS` estimated rows up to
+    /// `MAX_PARTITIONS`. Partitions are aggregated independently before their
+    /// partial states are merged, on a single thread they only exercise the
+    /// merge path of accumulators.
+    pub fn partitions(&self, input: &LogicalPlan) -> usize {
+        ((self.rows(input) / PARTITION_ROWS).ceil() as usize).clamp(1, MAX_PARTITIONS)
+    }
+
+    // Estimates the selectivity of a comparison from the statistics of the
+    // columns it compares.
+    fn comparison(&self, left: &Expr, op: &str, right: &Expr, plan: &LogicalPlan) -> f64 {
+        let (column, op, value) = match (constant(left), constant(right)) {
+            (None, Some(value)) => (left, op, value),
+            (Some(value), None) => {
+                let op = match op {
+                    ">" => "<",
+                    ">=" => "<=",
+                    "<" => ">",
+                    "<=" => ">=",
+                    op => op,
+                };
+                (right, op, value)
+            }
+            // Equality of columns holds for one of the distinct values of
+            // the column with the most of them.
+            (None, None) if op == "=" => {
+                let distinct = [left, right]
+                    .iter()
+                    .map(|expr| self.statistics(expr, plan))
+                    .collect::<Option<Vec<_>>>()
+                    .map(|columns| columns.iter().map(|c| c.distinct_count).max());
+                return match distinct {
+                    Some(Some(distinct)) => 1.0 / distinct.max(1) as f64,
+                    _ => EQUALITY_SELECTIVITY,
+                };
+            }
+            _ => return fallback(op),
+        };
+        let statistics = match (column, self.statistics(column, plan)) {
+            (Expr::Bound(bound), Some(statistics)) if value.data_type() == bound.data_type => {
+                statistics
+            }
+            _ => return fallback(op),
+        };
+        if value.is_null() {
+            return 0.0;
+        }
+        let values = 1.0 - statistics.null_fraction;
+        let (Some(min), Some(max)) = (&statistics.min, &statistics.max) else {
+            return 0.0;
+        };
+        match op {
+            "=" if value < *min || value > *max => 0.0,
+            "=" => values / statistics.distinct_count.max(1) as f64,
+            "<>" if value < *min || value > *max => values,
+            "<>" => values * (1.0 - 1.0 / statistics.distinct_count.max(1) as f64),
+            // The histogram does not tell values equal to the bound apart,
+            // inclusive bounds are estimated like exclusive ones.
+            "<" | "<=" | ">" | ">=" => match statistics.histogram.fraction_below(&value) {
+                Some(below) if op.starts_with('<') => values * below,
+                Some(below) => values * (1.0 - below),
+                None => fallback(op),
+            },
+            op => fallback(op),
+        }
+    }
+
+    // Returns the statistics of the column an expression reads when it is a
+    // column of a table, following it through the plan down to its scan.
+    fn statistics(&self, expr: &Expr, plan: &LogicalPlan) -> Option<&'a ColumnStatistics> {
+        let Expr::Bound(column) = expr else {
+            return None;
+        };
+        let index = column.index;
+        match plan {
+            LogicalPlan::Scan {
+                table, projection, ..
+            } => {
+                let index = projection.as_ref().map_or(index, |columns| columns[index]);
+                self.catalog.statistics(table)?.columns.get(index)
+            }
+            LogicalPlan::Filter { input, .. }
+            | LogicalPlan::Sort { input, .. }
+            | LogicalPlan::Limit { input, .. } => self.statistics(expr, input),
+            LogicalPlan::Project { input, exprs, .. } => self.statistics(&exprs[index], input),
+            LogicalPlan::Aggregate {
+                input, group_by, ..
+            } => self.statistics(group_by.get(index)?, input),
+            LogicalPlan::Join { left, right, .. } => {
+                let width = left.schema().len();
+                if index < width {
+                    self.statistics(expr, left)
+                } else {
+                    self.statistics(&shift(expr.clone(), width), right)
+                }
+            }
+            LogicalPlan::LateralJoin { input, .. } if index < input.schema().len() => {
+                self.statistics(expr, input)
+            }
+            _ => None,
+        }
+    }
+}
+
+// Returns the selectivity assumed for a comparison without statistics.
+fn fallback(op: &str) -> f64 {
+    match op {
+        "=" => EQUALITY_SELECTIVITY,
+        "<>" => 1.0 - EQUALITY_SELECTIVITY,
+        "<" | "<=" | ">" | ">=" => RANGE_SELECTIVITY,
+        _ => DEFAULT_SELECTIVITY,
+    }
+}
+
+// Returns whether the rows of a plan are known to be sorted by a column.
+fn sorted_by(plan: &LogicalPlan, index: usize) -> bool {
+    match plan {
+        LogicalPlan::Sort { key, .. } => {
+            matches!(key, Expr::Bound(column) if column.index == index)
+        }
+        LogicalPlan::Filter { input, .. } | LogicalPlan::Limit { input, .. } => {
+            sorted_by(input, index)
+        }
+        LogicalPlan::Project { input, exprs, .. } => match &exprs[index] {
+            Expr::Bound(column) => sorted_by(input, column.index),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+// Returns the operands of an equality between an expression of the left
+// columns and an expression of the right ones, left operand first.
+fn side_of(term: &Expr, left_width: usize) -> Option<(&Expr, &Expr)> {
+    let Expr::Comparison(left, op, right) = term else {
+        return None;
+    };
+    if op != "=" {
+        return None;
+    }
+    let side = |expr: &Expr| {
+        let columns = expr.references();
+        if columns.is_empty() {
+            None
+        } else if columns.iter().all(|&index| index < left_width) {
+            Some(true)
+        } else if columns.iter().all(|&index| index >= left_width) {
+            Some(false)
+        } else {
+            None
+        }
+    };
+    match (side(left)?, side(right)?) {
+        (true, false) => Some((left, right)),
+        (false, true) => Some((right, left)),
+        _ => None,
+    }
+}
+
+// Shifts the column references of an expression of the right columns of a
+// join onto the right rows.
+fn shift(expr: Expr, left_width: usize) -> Expr {
+    expr.transform(&mut |expr| match expr {
+        Expr::Bound(mut column) => {
+            column.index -= left_width;
+            Expr::Bound(column)
+        }
+        expr => expr,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binder::Binder;
+    use crate::catalog::{Changes, Column, Table};
+    use crate::functions::FunctionRegistry;
+    use crate::row::Row;
+    use crate::sql::{Parser, Tokenizer};
+    use crate::types::{DataType, Value};
+
+    // Catalog of a table of 100 rows whose `id` is unique, `kind` holds 10
+    // values and `code` is `NULL` in half of the rows.
+    fn catalog() -> Catalog {
+        let mut catalog = Catalog::new();
+        let columns = ["id", "kind", "code"]
+            .iter()
+            .map(|column| Column::new(column, DataType::Integer))
+            .collect();
+        catalog
+            .create_table(Table::new("items", columns).unwrap())
+            .unwrap();
+        let inserted = (0..100)
+            .map(|i| {
+                let code = if i % 2 == 0 {
+                    Value::Null
+                } else {
+                    Value::Integer(i)
+                };
+                Row::new(&[Value::Integer(i), Value::Integer(i % 10), code])
+            })
+            .collect();
+        let changes = Changes {
+            inserted,
+            updated: vec![],
+            deleted: vec![],
+        };
+        catalog
+            .apply("items", changes, &|_, _| Box::new(|_: &Row| Ok(true)))
+            .unwrap();
+        catalog.analyze("items").unwrap();
+        catalog
+    }
+
+    fn rows(catalog: &Catalog, sql: &str) -> f64 {
+        let query = Parser::new(Tokenizer::new(sql)).unwrap().parse().unwrap();
+        let plan = Binder::new(catalog, &FunctionRegistry::default())
+            .bind_query(query)
+            .unwrap();
+        Estimator::new(catalog).rows(&plan)
+    }
+
+    #[test]
+    fn rows_are_estimated_from_statistics() {
+        let catalog = catalog();
+        let estimate = |sql| rows(&catalog, sql).round();
+        assert_eq!(estimate("SELECT id FROM items"), 100.0);
+        assert_eq!(estimate("SELECT id FROM items WHERE kind = 3"), 10.0);
+        assert_eq!(estimate("SELECT id FROM items WHERE kind = 30"), 0.0);
+        assert_eq!(estimate("SELECT id FROM items WHERE code = 3"), 1.0);
+        assert_eq!(estimate("SELECT id FROM items WHERE id < 50"), 55.0);
+        assert_eq!(estimate("SELECT id FROM items WHERE 50 > id"), 55.0);
+        assert_eq!(
+            estimate("SELECT id FROM items WHERE kind = 1 OR kind = 2"),
+            19.0
+        );
+        assert_eq!(estimate("SELECT id FROM items WHERE NOT kind = 1"), 90.0);
+        assert_eq!(estimate("SELECT id FROM items WHERE code IS NULL"), 50.0);
+        assert_eq!(estimate("SELECT id FROM items WHERE id IS NOT NULL"), 100.0);
+        assert_eq!(estimate("SELECT kind FROM items GROUP BY kind"), 10.0);
+        assert_eq!(estimate("SELECT count(*) FROM items"), 1.0);
+        assert_eq!(estimate("SELECT id FROM items LIMIT 5"), 5.0);
+        assert_eq!(
+            estimate("SELECT a.id FROM items a JOIN items b ON a.id = b.kind"),
+            100.0
+        );
+        assert_eq!(estimate("SELECT b.id FROM items a, items b"), 10000.0);
+        // Predicates on computed columns fall back to default selectivities.
+        assert_eq!(estimate("SELECT id FROM items WHERE id + 1 = 3"), 10.0);
+    }
+
+    #[test]
+    fn large_aggregates_are_partitioned() {
+        let catalog = catalog();
+        let partitions = |sql| {
+            let query = Parser::new(Tokenizer::new(sql)).unwrap().parse().unwrap();
+            let plan = Binder::new(&catalog, &FunctionRegistry::default())
+                .bind_query(query)
+                .unwrap();
+            Estimator::new(&catalog).partitions(&plan)
+        };
+        assert_eq!(partitions("SELECT id FROM items"), 1);
+        assert_eq!(partitions("SELECT a.id FROM items a, items b"), 1);
+        assert_eq!(
+            partitions("SELECT a.id FROM items a, items b, items c"),
+            MAX_PARTITIONS
+        );
+    }
+
+    #[test]
+    fn join_methods_are_chosen_by_cost() {
+        assert_eq!(
+            JoinMethod::cheapest(1.0, 1.0, true, false),
+            JoinMethod::NestedLoop
+        );
+        assert_eq!(
+            JoinMethod::cheapest(100.0, 100.0, true, false),
+            JoinMethod::Hash
+        );
+        assert_eq!(
+            JoinMethod::cheapest(100.0, 100.0, true, true),
+            JoinMethod::Merge
+        );
+        assert_eq!(
+            JoinMethod::cheapest(100.0, 100.0, false, false),
+            JoinMethod::NestedLoop
+        );
+    }
+
+    #[test]
+    fn sorted_inputs_are_merged() {
+        let catalog = catalog();
+        let bind = |sql| {
+            let query = Parser::new(Tokenizer::new(sql)).unwrap().parse().unwrap();
+            Binder::new(&catalog, &FunctionRegistry::default())
+                .bind_query(query)
+                .unwrap()
+        };
+        let (sorted, unsorted) = (
+            bind("SELECT id FROM items ORDER BY id"),
+            bind("SELECT id FROM items"),
+        );
+        let LogicalPlan::Project { exprs, .. } = &sorted else {
+            unreachable!()
+        };
+        let keys = JoinKeys {
+            left: exprs.clone(),
+            right: exprs.clone(),
+            residual: None,
+        };
+        let estimator = Estimator::new(&catalog);
+        assert_eq!(
+            estimator.join_method(&sorted, &sorted, &keys),
+            JoinMethod::Merge
+        );
+        assert_eq!(
+            estimator.join_method(&sorted, &unsorted, &keys),
+            JoinMethod::Hash
+        );
+        assert_eq!(
+            estimator.join_method(&sorted, &sorted, &JoinKeys::new(None, 1)),
+            JoinMethod::NestedLoop
+        );
+    }
+
+    #[test]
+    fn join_keys_are_extracted() {
+        let sql = "SELECT a.id FROM items a JOIN items b ON a.id = b.kind + 1 AND b.id = a.kind AND a.id > b.id";
+        let query = Parser::new(Tokenizer::new(sql)).unwrap().parse().unwrap();
+        let catalog = catalog();
+        let plan = Binder::new(&catalog, &FunctionRegistry::default())
+            .bind_query(query)
+            .unwrap();
+        let LogicalPlan::Project { input, .. } = plan else {
+            unreachable!()
+        };
+        let LogicalPlan::Join { condition, .. } = *input else {
+            unreachable!()
+        };
+        let keys = JoinKeys::new(condition, 3);
+        let indices = |exprs: &[Expr]| exprs.iter().flat_map(Expr::references).collect::<Vec<_>>();
+        assert_eq!(indices(&keys.left), [0, 1]);
+        assert_eq!(indices(&keys.right), [1, 0]);
+        assert_eq!(keys.right[0].to_string(), "b.kind + 1");
+        assert_eq!(keys.residual.unwrap().to_string(), "a.id > b.id");
+        assert!(JoinKeys::new(None, 3).is_empty());
+    }
+}
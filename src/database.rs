@@ -0,0 +1,477 @@
+//! Implementation of the database, the entry point running SQL scripts on the
+//! tables of its catalog and reporting their errors as values.
+use std::error::Error;
+use std::fmt;
+
+use crate::binder::BindError;
+use crate::catalog::{Catalog, CatalogError};
+use crate::functions::{Accumulator, TableFunction};
+use crate::operators::ExecutionError;
+use crate::optimizer::Optimizer;
+use crate::planner::QueryExecutor;
+use crate::row::Row;
+use crate::schema::Schema;
+use crate::sql::{parse_script, ParseError};
+use crate::temporal::Clock;
+use crate::typecheck::TypeError;
+use crate::types::{DataType, Value};
+
+/// Error reported by a statement which could not be parsed, type checked or
+/// executed, holding the message describing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseError(pub String);
+
+impl fmt::Display for DatabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for DatabaseError {}
+
+impl From<ParseError> for DatabaseError {
+    fn from(error: ParseError) -> Self {
+        DatabaseError(error.to_string())
+    }
+}
+
+impl From<BindError> for DatabaseError {
+    fn from(error: BindError) -> Self {
+        DatabaseError(error.to_string())
+    }
+}
+
+impl From<TypeError> for DatabaseError {
+    fn from(error: TypeError) -> Self {
+        DatabaseError(error.to_string())
+    }
+}
+
+impl From<CatalogError> for DatabaseError {
+    fn from(error: CatalogError) -> Self {
+        DatabaseError(error.to_string())
+    }
+}
+
+impl From<ExecutionError> for DatabaseError {
+    fn from(error: ExecutionError) -> Self {
+        DatabaseError(error.to_string())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, DatabaseError>;
+
+/// Rows produced by a statement along with their schema, DDL statements
+/// produce no rows and DML statements the number of rows they modified
+/// unless they have a `RETURNING` clause.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResultSet {
+    pub schema: Schema,
+    pub rows: Vec<Row>,
+}
+
+#[derive(Default)]
+pub struct Database {
+    executor: QueryExecutor,
+}
+
+impl Database {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run every statement of a script, returns the result of the last one.
+    /// Statements are applied as they run, so the statements preceding the
+    /// first one which fails keep their effects.
+    pub fn query(&mut self, sql: &str) -> Result<ResultSet> {
+        let mut result = ResultSet::default();
+        for statement in parse_script(sql)? {
+            result = self.executor.run(statement)?;
+        }
+        Ok(result)
+    }
+
+    /// Register a Rust closure as a scalar function callable by name from SQL
+    /// with the given argument and return types, its errors fail the query.
+    pub fn register_function<F>(
+        &mut self,
+        name: &str,
+        arg_types: &[DataType],
+        return_type: DataType,
+        fun: F,
+    ) where
+        F: Fn(&[Value]) -> std::result::Result<Value, ExecutionError> + 'static,
+    {
+        self.executor
+            .register_function(name, arg_types, return_type, fun);
+    }
+
+    /// Register an aggregate function callable by name from SQL, each group
+    /// accumulates its rows in a fresh `A`.
+    pub fn register_aggregate<A: Accumulator + 'static>(
+        &mut self,
+        name: &str,
+        arg_types: &[DataType],
+        return_type: DataType,
+    ) {
+        self.executor
+            .register_aggregate::<A>(name, arg_types, return_type);
+    }
+
+    /// Register a table function callable by name in the `FROM` clause.
+    pub fn register_table_function<F: TableFunction + 'static>(&mut self, name: &str, function: F) {
+        self.executor.register_table_function(name, function);
+    }
+
+    /// Set the clock read by `NOW()`.
+    pub fn set_clock<C: Clock + 'static>(&mut self, clock: C) {
+        self.executor.set_clock(clock);
+    }
+
+    pub fn catalog(&self) -> &Catalog {
+        self.executor.catalog()
+    }
+
+    /// Returns the optimizer rewriting the logical plans of queries, whose
+    /// rules can be enabled and disabled.
+    pub fn optimizer_mut(&mut self) -> &mut Optimizer {
+        self.executor.optimizer_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::Field;
+
+    fn database() -> Database {
+        let mut database = Database::new();
+        database
+            .query(
+                "CREATE TABLE accounts (id INTEGER PRIMARY KEY, owner VARCHAR NOT NULL);
+                 INSERT INTO accounts VALUES (1, 'Alice'), (2, 'Bob')",
+            )
+            .unwrap();
+        database
+    }
+
+    #[test]
+    fn can_query_tables() {
+        let mut database = database();
+        let result = database
+            .query("SELECT owner, id + 1 FROM accounts WHERE id > 1")
+            .unwrap();
+        assert_eq!(
+            result.schema,
+            Schema::new(vec![
+                Field::new("owner", DataType::Varchar),
+                Field::new("id + 1", DataType::Integer),
+            ])
+        );
+        assert_eq!(
+            result.rows,
+            vec![Row::new(&[Value::from("Bob"), Value::Integer(3)])]
+        );
+        assert_eq!(
+            database.catalog().table("accounts").unwrap().rows().len(),
+            2
+        );
+    }
+
+    #[test]
+    fn statements_report_modified_rows() {
+        let mut database = database();
+        let result = database
+            .query("UPDATE accounts SET owner = 'Carol' WHERE id = 2")
+            .unwrap();
+        assert_eq!(
+            result.schema,
+            Schema::new(vec![Field::new("count", DataType::BigInt)])
+        );
+        assert_eq!(result.rows, vec![Row::new(&[Value::BigInt(1)])]);
+        let result = database
+            .query("DELETE FROM accounts WHERE id = 1 RETURNING owner")
+            .unwrap();
+        assert_eq!(
+            result.schema,
+            Schema::new(vec![Field::new("owner", DataType::Varchar)])
+        );
+        assert_eq!(result.rows, vec![Row::new(&[Value::from("Alice")])]);
+        assert_eq!(
+            database.query("DROP TABLE accounts"),
+            Ok(ResultSet::default())
+        );
+    }
+
+    #[test]
+    fn optimizations_preserve_results() {
+        let mut database = database();
+        database
+            .query(
+                "CREATE TABLE orders (account INTEGER, total INTEGER);
+                 INSERT INTO orders VALUES (1, 10), (2, 20), (2, 5)",
+            )
+            .unwrap();
+        let sql = "SELECT a.owner, o.total FROM accounts a, orders o
+                   WHERE a.id = o.account AND o.total > 5 ORDER BY o.total";
+        let optimized = database.query(sql).unwrap();
+        // Unsatisfiable filters are replaced by an empty relation.
+        let result = database
+            .query("SELECT count(*) FROM accounts WHERE id > 1 AND id < 2")
+            .unwrap();
+        assert_eq!(result.rows, vec![Row::new(&[Value::BigInt(0)])]);
+        let rules = database.optimizer_mut().rules();
+        for (name, _) in rules {
+            database.optimizer_mut().set_enabled(name, false);
+        }
+        assert_eq!(database.query(sql).unwrap(), optimized);
+        assert_eq!(
+            optimized.rows,
+            vec![
+                Row::new(&[Value::from("Alice"), Value::Integer(10)]),
+                Row::new(&[Value::from("Bob"), Value::Integer(20)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn join_methods_preserve_results() {
+        let mut database = database();
+        database
+            .query(
+                "CREATE TABLE items (id INTEGER, kind INTEGER);
+                 INSERT INTO items SELECT generate_series, generate_series / 10
+                 FROM generate_series(1, 100);
+                 CREATE TABLE kinds (id INTEGER, name VARCHAR);
+                 INSERT INTO kinds VALUES (1, 'a'), (2, 'b'), (3, 'c');
+                 ANALYZE",
+            )
+            .unwrap();
+        // Items are joined by hashing the smaller input.
+        let sql = "SELECT i.id, j.id, k.name FROM items i, items j, kinds k
+                   WHERE i.kind = k.id AND j.id = i.id + 1 AND i.id < 25 ORDER BY j.id";
+        let result = database.query(sql).unwrap();
+        assert_eq!(result.rows.len(), 15);
+        assert_eq!(
+            result.rows[0],
+            Row::new(&[Value::Integer(10), Value::Integer(11), Value::from("a")])
+        );
+        // Without optimizations predicates are evaluated on nested loops.
+        let rules = database.optimizer_mut().rules();
+        for (name, _) in rules {
+            database.optimizer_mut().set_enabled(name, false);
+        }
+        assert_eq!(database.query(sql).unwrap(), result);
+    }
+
+    #[test]
+    fn large_aggregates_are_partitioned() {
+        let mut database = database();
+        database
+            .query(
+                "CREATE TABLE readings (sensor INTEGER, value INTEGER);
+                 INSERT INTO readings SELECT generate_series / 10000, generate_series
+                 FROM generate_series(1, 25000);
+                 ANALYZE readings",
+            )
+            .unwrap();
+        let sql = "SELECT sensor, count(*), avg(value) FROM readings GROUP BY sensor";
+        let plan = database.query(&format!("EXPLAIN {sql}")).unwrap();
+        assert!(plan.rows[1].items[0]
+            .to_string()
+            .starts_with("  Aggregate (partitions: 3,"));
+        // Partial states of every partition are merged.
+        let mean = |text| Value::decode(text, DataType::Decimal(38, 6)).unwrap();
+        assert_eq!(
+            database.query(sql).unwrap().rows,
+            vec![
+                Row::new(&[Value::Integer(0), Value::BigInt(9999), mean("5000.000000")]),
+                Row::new(&[
+                    Value::Integer(1),
+                    Value::BigInt(10000),
+                    mean("14999.500000")
+                ]),
+                Row::new(&[Value::Integer(2), Value::BigInt(5001), mean("22500.000000")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn queries_can_be_explained() {
+        let mut database = database();
+        let result = database
+            .query(
+                "CREATE TABLE orders (account INTEGER, total INTEGER);
+                 INSERT INTO orders SELECT generate_series / 11 + 1, generate_series
+                 FROM generate_series(1, 20);
+                 EXPLAIN SELECT a.owner, sum(o.total) FROM accounts a JOIN orders o
+                 ON a.id = o.account WHERE o.total > 5 GROUP BY a.owner ORDER BY a.owner",
+            )
+            .unwrap();
+        let lines = result
+            .rows
+            .iter()
+            .map(|row| row.items[0].to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(result.schema.fields[0].name, "plan");
+        assert_eq!(
+            lines,
+            [
+                "Project (columns: [0, 1], exprs: \"a.owner\", \"sum(o.total)\", estimated rows: 1)",
+                "  Sort (key: \"a.owner\", estimated rows: 1)",
+                "    Aggregate (group by: a.owner, aggregates: sum(o.total), estimated rows: 1)",
+                "      Project (columns: [3, 1], exprs: a.owner, o.total, estimated rows: 1)",
+                "        HashJoin (condition: a.id = o.account, estimated rows: 1)",
+                "          Filter (predicate: o.total > 5, estimated rows: 7)",
+                "            Scan (table: orders, estimated rows: 20)",
+                "          Scan (table: accounts, estimated rows: 2)",
+            ]
+        );
+        // Queries are only explained, not executed.
+        assert!(database
+            .query("EXPLAIN SELECT id / 0 FROM accounts")
+            .is_ok());
+    }
+
+    #[test]
+    fn queries_can_be_analyzed() {
+        let mut database = database();
+        let result = database
+            .query(
+                "EXPLAIN ANALYZE SELECT a.owner FROM accounts a, accounts b
+                 WHERE a.id = b.id ORDER BY a.owner LIMIT 1",
+            )
+            .unwrap();
+        let lines = result
+            .rows
+            .iter()
+            .map(|row| row.items[0].to_string())
+            .collect::<Vec<_>>();
+        // Times vary between runs, rows and calls don't.
+        let counts = lines
+            .iter()
+            .map(|line| {
+                let (operator, metrics) = line.split_once(", rows: ").unwrap();
+                let (counts, _) = metrics.split_once(", time: ").unwrap();
+                format!("{} {counts}", operator.split(" (").next().unwrap())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            [
+                "Project 1, calls: 2",
+                "  Limit 1, calls: 2",
+                "    Sort 1, calls: 1",
+                "      Join 2, calls: 3",
+                "        Scan 2, calls: 3",
+                "        Scan 2, calls: 3",
+            ]
+        );
+        // Only materializing operators report their memory.
+        let materializing = lines
+            .iter()
+            .map(|line| line.contains("self time: ") && line.contains("peak memory: "))
+            .collect::<Vec<_>>();
+        assert_eq!(materializing, [false, false, true, true, false, false]);
+    }
+
+    #[test]
+    fn plans_can_be_exported() {
+        let mut database = database();
+        let mut export = |sql| {
+            let result = database.query(sql).unwrap();
+            assert_eq!(result.rows.len(), 1);
+            result.rows[0].items[0].to_string()
+        };
+        let dot = export("EXPLAIN (FORMAT DOT) SELECT owner FROM accounts WHERE id > 1");
+        assert_eq!(
+            dot.lines().collect::<Vec<_>>(),
+            [
+                "digraph plan {",
+                "  node [shape=box];",
+                "  n0 [label=\"Project\\ncolumns: [1]\\nexprs: owner\\nschema: owner VARCHAR\\nestimated rows: 1\"];",
+                "  n1 [label=\"Filter\\npredicate: id > 1\\nschema: accounts.id INTEGER, accounts.owner VARCHAR\\nestimated rows: 1\"];",
+                "  n2 [label=\"Scan\\ntable: accounts\\nschema: accounts.id INTEGER, accounts.owner VARCHAR\\nestimated rows: 2\"];",
+                "  n1 -> n2;",
+                "  n0 -> n1;",
+                "}",
+            ]
+        );
+        let json = export("EXPLAIN (FORMAT JSON) SELECT owner FROM accounts");
+        assert!(json.starts_with("{\n  \"kind\": \"Project\","));
+        assert!(json.contains("\"metrics\": null"));
+        // Analyzed plans hold the metrics of their operators.
+        let json = export("EXPLAIN (ANALYZE, FORMAT JSON) SELECT owner FROM accounts");
+        assert!(json.contains("\"metrics\": {\n    \"rows\": 2,\n    \"calls\": 3,"));
+        assert!(!json.contains("\"metrics\": null"));
+    }
+
+    #[test]
+    fn statistics_are_visible_in_a_system_table() {
+        let mut database = database();
+        let result = database
+            .query(
+                "INSERT INTO accounts VALUES (3, 'Bob');
+                 ANALYZE accounts;
+                 SELECT column_name, row_count, distinct_count, min_value
+                 FROM eocene_stats WHERE table_name = 'accounts'",
+            )
+            .unwrap();
+        assert_eq!(
+            result.rows,
+            vec![
+                Row::new(&[
+                    Value::from("id"),
+                    Value::BigInt(3),
+                    Value::BigInt(3),
+                    Value::from("1"),
+                ]),
+                Row::new(&[
+                    Value::from("owner"),
+                    Value::BigInt(3),
+                    Value::BigInt(2),
+                    Value::from("Alice"),
+                ]),
+            ]
+        );
+        assert!(database.query("ANALYZE").is_ok());
+        assert_eq!(
+            database
+                .query("DELETE FROM eocene_stats")
+                .unwrap_err()
+                .to_string(),
+            "Table eocene_stats is a system table and cannot be modified"
+        );
+        assert_eq!(
+            database.query("ANALYZE employees").unwrap_err().to_string(),
+            "Unknown table employees"
+        );
+    }
+
+    #[test]
+    fn errors_are_returned() {
+        let mut database = database();
+        assert_eq!(
+            database.query("SELECT id FROM employees"),
+            Err(DatabaseError("Unknown table employees".to_string()))
+        );
+        assert!(database.query("SELECT FROM").is_err());
+        assert_eq!(
+            database.query("SELECT id / (id - 1) FROM accounts"),
+            Err(DatabaseError("Division by zero".to_string()))
+        );
+        // Statements preceding an error keep their effects.
+        let error = database
+            .query(
+                "INSERT INTO accounts VALUES (3, 'Carol');
+                 INSERT INTO accounts VALUES (3, 'Dan')",
+            )
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Key (id)=(3) already exists in table accounts, violating constraint accounts_pkey"
+        );
+        let result = database.query("SELECT owner FROM accounts").unwrap();
+        assert_eq!(result.rows.len(), 3);
+    }
+}
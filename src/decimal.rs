@@ -0,0 +1,320 @@
+//! Implementation of the exact fixed-point numbers backing `DECIMAL(p, s)`.
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Maximum number of digits of a decimal, the largest precision of the
+/// `DECIMAL(p, s)` type.
+pub const MAX_PRECISION: u8 = 38;
+
+/// Exact decimal number `mantissa * 10^-scale` of at most `MAX_PRECISION`
+/// digits.
+///
+/// Decimals compare and hash by their numerical value, `1.50` equals `1.5`.
+/// Operations which drop digits round half away from zero.
+#[derive(Debug, Clone, Copy)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u8,
+}
+
+impl Decimal {
+    /// Create a new decimal, returns `None` if it has more than
+    /// `MAX_PRECISION` digits.
+    pub fn new(mantissa: i128, scale: u8) -> Option<Self> {
+        let decimal = Self { mantissa, scale };
+        (scale <= MAX_PRECISION && decimal.digits() <= MAX_PRECISION).then_some(decimal)
+    }
+
+    /// Parse a decimal written `[+-]digits[.digits]`, the scale is the number
+    /// of digits after the decimal point.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(text) => (true, text),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (integer, fraction) = text.split_once('.').unwrap_or((text, ""));
+        if integer.is_empty() && fraction.is_empty()
+            || !integer
+                .chars()
+                .chain(fraction.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+        let scale = u8::try_from(fraction.len()).ok()?;
+        let mut mantissa: i128 = 0;
+        for digit in integer.chars().chain(fraction.chars()) {
+            mantissa = mantissa
+                .checked_mul(10)?
+                .checked_add(digit.to_digit(10)? as i128)?;
+        }
+        Self::new(if negative { -mantissa } else { mantissa }, scale)
+    }
+
+    /// Convert a float to a decimal of the given scale.
+    pub fn from_f64(value: f64, scale: u8) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        Self::parse(&format!("{value:.0$}", scale as usize))
+    }
+
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    /// Returns the least precision of a `DECIMAL(p, s)` holding the decimal.
+    pub fn precision(&self) -> u8 {
+        self.digits().max(self.scale).max(1)
+    }
+
+    /// Returns the decimal rounded or extended to the given scale.
+    pub fn rescale(&self, scale: u8) -> Option<Self> {
+        if scale >= self.scale {
+            let factor = pow10(scale - self.scale)?;
+            Self::new(self.mantissa.checked_mul(factor)?, scale)
+        } else {
+            let factor = pow10(self.scale - scale)?;
+            Self::new(div_round(self.mantissa, factor), scale)
+        }
+    }
+
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let (left, right) = (self.rescale(scale)?, other.rescale(scale)?);
+        Self::new(left.mantissa.checked_add(right.mantissa)?, scale)
+    }
+
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        self.checked_add(&Self {
+            mantissa: -other.mantissa,
+            scale: other.scale,
+        })
+    }
+
+    /// Multiply two decimals, the scale of the product is the sum of their
+    /// scales up to `MAX_PRECISION`.
+    pub fn checked_mul(&self, other: &Self) -> Option<Self> {
+        let product = Self {
+            mantissa: self.mantissa.checked_mul(other.mantissa)?,
+            scale: self.scale + other.scale,
+        };
+        product.rescale(product.scale.min(MAX_PRECISION))
+    }
+
+    /// Divide two decimals rounding the quotient to the given scale, returns
+    /// `None` on division by zero or overflow.
+    pub fn checked_div(&self, other: &Self, scale: u8) -> Option<Self> {
+        if other.mantissa == 0 {
+            return None;
+        }
+        // self / other = (m1 * 10^(scale + s2 - s1) / m2) * 10^-scale
+        let exponent = scale as i32 + other.scale as i32 - self.scale as i32;
+        let (numerator, denominator) = if exponent >= 0 {
+            let factor = pow10(exponent as u8)?;
+            (self.mantissa.checked_mul(factor)?, other.mantissa)
+        } else {
+            let factor = pow10((-exponent) as u8)?;
+            (self.mantissa, other.mantissa.checked_mul(factor)?)
+        };
+        Self::new(div_round(numerator, denominator), scale)
+    }
+
+    pub fn checked_neg(&self) -> Option<Self> {
+        Self::new(self.mantissa.checked_neg()?, self.scale)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa == 0
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap()
+    }
+
+    /// Returns the decimal rounded to an integer.
+    pub fn round(&self) -> i128 {
+        div_round(self.mantissa, pow10(self.scale).unwrap())
+    }
+
+    fn digits(&self) -> u8 {
+        self.mantissa
+            .unsigned_abs()
+            .checked_ilog10()
+            .map_or(1, |digits| digits as u8 + 1)
+    }
+
+    /// Returns the decimal without trailing zeros after the decimal point.
+    fn normalize(&self) -> Self {
+        let mut decimal = *self;
+        while decimal.scale > 0 && decimal.mantissa % 10 == 0 {
+            decimal.mantissa /= 10;
+            decimal.scale -= 1;
+        }
+        decimal
+    }
+}
+
+/// Returns `10^exponent` if it fits in an `i128`.
+fn pow10(exponent: u8) -> Option<i128> {
+    10i128.checked_pow(exponent as u32)
+}
+
+/// Divide rounding half away from zero.
+fn div_round(numerator: i128, denominator: i128) -> i128 {
+    let (quotient, remainder) = (numerator / denominator, numerator % denominator);
+    if remainder.unsigned_abs() >= denominator.unsigned_abs() - remainder.unsigned_abs() {
+        quotient + numerator.signum() * denominator.signum()
+    } else {
+        quotient
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = format!(
+            "{:0>width$}",
+            self.mantissa.unsigned_abs(),
+            width = self.scale as usize + 1
+        );
+        let (integer, fraction) = digits.split_at(digits.len() - self.scale as usize);
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        if fraction.is_empty() {
+            write!(f, "{sign}{integer}")
+        } else {
+            write!(f, "{sign}{integer}.{fraction}")
+        }
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Compare the integer parts first since aligning the scales of the
+        // whole numbers can overflow, fractional parts always fit.
+        let (left, right) = (pow10(self.scale).unwrap(), pow10(other.scale).unwrap());
+        let scale = self.scale.max(other.scale);
+        (self.mantissa / left).cmp(&(other.mantissa / right)).then(
+            ((self.mantissa % left) * pow10(scale - self.scale).unwrap())
+                .cmp(&((other.mantissa % right) * pow10(scale - other.scale).unwrap())),
+        )
+    }
+}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let decimal = self.normalize();
+        decimal.mantissa.hash(state);
+        decimal.scale.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(text: &str) -> Decimal {
+        Decimal::parse(text).unwrap()
+    }
+
+    #[test]
+    fn decimals_roundtrip_through_text() {
+        for text in ["0", "-0.05", "12000.50", "1", "-42", "0.000"] {
+            assert_eq!(decimal(text).to_string(), text);
+        }
+        assert_eq!(decimal("+.5").to_string(), "0.5");
+        assert_eq!(decimal("12000.50").precision(), 7);
+        assert_eq!(decimal("-0.05").precision(), 2);
+        assert_eq!(Decimal::parse("1.2.3"), None);
+        assert_eq!(Decimal::parse("."), None);
+        assert_eq!(Decimal::parse(&"9".repeat(39)), None);
+    }
+
+    #[test]
+    fn rescaling_rounds_half_away_from_zero() {
+        assert_eq!(decimal("2.5").rescale(0), Some(decimal("3")));
+        assert_eq!(decimal("-2.5").rescale(0), Some(decimal("-3")));
+        assert_eq!(decimal("1.245").rescale(2).unwrap().to_string(), "1.25");
+        assert_eq!(decimal("1.244").rescale(2).unwrap().to_string(), "1.24");
+        assert_eq!(decimal("1.5").rescale(3).unwrap().to_string(), "1.500");
+        assert_eq!(decimal(&"9".repeat(37)).rescale(2), None);
+    }
+
+    #[test]
+    fn arithmetic_is_exact() {
+        assert_eq!(
+            decimal("0.1")
+                .checked_add(&decimal("0.2"))
+                .unwrap()
+                .to_string(),
+            "0.3"
+        );
+        assert_eq!(
+            decimal("1.5")
+                .checked_sub(&decimal("2.25"))
+                .unwrap()
+                .to_string(),
+            "-0.75"
+        );
+        assert_eq!(
+            decimal("1.5")
+                .checked_mul(&decimal("-0.25"))
+                .unwrap()
+                .to_string(),
+            "-0.375"
+        );
+        assert_eq!(
+            decimal("2")
+                .checked_div(&decimal("3"), 6)
+                .unwrap()
+                .to_string(),
+            "0.666667"
+        );
+        assert_eq!(
+            decimal("1.00")
+                .checked_div(&decimal("0.3"), 2)
+                .unwrap()
+                .to_string(),
+            "3.33"
+        );
+        assert_eq!(decimal("1").checked_div(&decimal("0.0"), 2), None);
+        let large = decimal(&"9".repeat(38));
+        assert_eq!(large.checked_add(&decimal("1")), None);
+        assert_eq!(large.checked_mul(&decimal("10")), None);
+    }
+
+    #[test]
+    fn decimals_compare_by_value() {
+        assert_eq!(decimal("1.50"), decimal("1.5"));
+        assert!(decimal("-1.5") < decimal("-1.25"));
+        assert!(decimal("-0.5") < decimal("0.25"));
+        assert!(decimal(&"9".repeat(38)) > decimal("0.5"));
+
+        let hash = |decimal: Decimal| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            decimal.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(decimal("1.50")), hash(decimal("1.5")));
+        assert_eq!(decimal("2.5").round(), 3);
+        assert_eq!(decimal("0.125").to_f64(), 0.125);
+    }
+}
@@ -0,0 +1,421 @@
+//! Implementation of the export of logical plans and of trees of operators
+//! to Graphviz DOT graphs and JSON documents.
+use crate::catalog::Catalog;
+use crate::cost::Estimator;
+use crate::logical::LogicalPlan;
+use crate::operators::{Metrics, Operator};
+use crate::schema::{Field, Schema};
+
+/// Node of an exported plan, either a node of a logical plan or an operator,
+/// along with the nodes of its inputs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlanNode {
+    /// Kind of the node such as `Filter` or `HashJoin`.
+    pub kind: String,
+    /// Parameters of the node, each of which is a name and a value,
+    /// expressions being rendered as SQL.
+    pub parameters: Vec<(String, String)>,
+    /// Schema of the rows the node produces, when known.
+    pub schema: Option<Schema>,
+    /// Estimated number of rows the node produces, when known.
+    pub estimated_rows: Option<f64>,
+    /// Metrics of the execution of an operator, when collected.
+    pub metrics: Option<Metrics>,
+    /// Peak memory in bytes of an operator materializing rows, when its
+    /// metrics were collected.
+    pub peak_memory: Option<usize>,
+    pub inputs: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    /// Returns the nodes of a logical plan, their number of rows is estimated
+    /// from the statistics of the tables of the catalog.
+    pub fn logical(plan: &LogicalPlan, catalog: &Catalog) -> Self {
+        Self::estimated(plan, &Estimator::new(catalog))
+    }
+
+    /// Returns the nodes of a tree of operators, described along with the
+    /// metrics of their execution when they were collected.
+    pub fn physical(operator: &dyn Operator) -> Self {
+        let description = operator.describe();
+        let metrics = operator.metrics();
+        Self {
+            kind: description.name,
+            parameters: description.parameters,
+            schema: description.schema,
+            estimated_rows: description.estimated_rows,
+            metrics,
+            peak_memory: metrics.and(operator.peak_memory()),
+            inputs: operator.inputs().into_iter().map(Self::physical).collect(),
+        }
+    }
+
+    // Returns the nodes of a logical plan whose number of rows is estimated
+    // by the given estimator.
+    fn estimated(plan: &LogicalPlan, estimator: &Estimator) -> Self {
+        Self {
+            kind: plan.kind().to_string(),
+            parameters: plan.parameters(),
+            schema: Some(plan.schema()),
+            estimated_rows: Some(estimator.rows(plan)),
+            inputs: plan
+                .inputs()
+                .into_iter()
+                .map(|input| Self::estimated(input, estimator))
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the plan as a Graphviz DOT graph, a box per node labelled with
+    /// its kind, parameters, schema, estimates and metrics, and an edge from
+    /// each node to each of its inputs.
+    pub fn to_dot(&self) -> String {
+        let mut dot = "digraph plan {\n  node [shape=box];\n".to_string();
+        self.write_dot(&mut dot, &mut 0);
+        dot.push_str("}\n");
+        dot
+    }
+
+    // Write the statements of the node and of its inputs to a DOT graph,
+    // nodes are identified by the order in which they are written. Returns
+    // the identifier of the node.
+    fn write_dot(&self, dot: &mut String, next: &mut usize) -> usize {
+        let id = *next;
+        *next += 1;
+        let mut lines = vec![self.kind.clone()];
+        lines.extend(
+            self.parameters
+                .iter()
+                .map(|(name, value)| format!("{name}: {value}")),
+        );
+        if let Some(schema) = &self.schema {
+            let fields = schema.fields.iter().map(column).collect::<Vec<_>>();
+            lines.push(format!("schema: {}", fields.join(", ")));
+        }
+        if let Some(rows) = self.estimated_rows {
+            lines.push(format!("estimated rows: {rows:.0}"));
+        }
+        if let Some(metrics) = self.metrics {
+            lines.push(format!("rows: {}", metrics.rows));
+            lines.push(format!("calls: {}", metrics.calls));
+            lines.push(format!(
+                "time: {:.3} ms",
+                metrics.time.as_secs_f64() * 1000.0
+            ));
+        }
+        if let Some(memory) = self.peak_memory {
+            lines.push(format!("peak memory: {memory} bytes"));
+        }
+        let label = lines
+            .iter()
+            .map(|line| escape_dot(line))
+            .collect::<Vec<_>>()
+            .join("\\n");
+        dot.push_str(&format!("  n{id} [label=\"{label}\"];\n"));
+        for input in &self.inputs {
+            let input = input.write_dot(dot, next);
+            dot.push_str(&format!("  n{id} -> n{input};\n"));
+        }
+        id
+    }
+
+    /// Returns the plan as a JSON document, an object per node holding its
+    /// `kind`, `parameters`, `schema`, `estimated_rows`, `metrics` and
+    /// `inputs` in this order, unknown values being `null`.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        self.json().write(&mut json, 0);
+        json
+    }
+
+    // Returns the JSON object of the node and of its inputs.
+    fn json(&self) -> Json {
+        let parameters = self
+            .parameters
+            .iter()
+            .map(|(name, value)| (name.clone(), Json::String(value.clone())))
+            .collect();
+        let schema = self.schema.as_ref().map(|schema| {
+            let fields = schema.fields.iter().map(|field| {
+                Json::object(vec![
+                    (
+                        "table",
+                        field.table.clone().map_or(Json::Null, Json::String),
+                    ),
+                    ("name", Json::String(field.name.clone())),
+                    ("type", Json::String(field.data_type.to_string())),
+                ])
+            });
+            Json::Array(fields.collect())
+        });
+        let metrics = self.metrics.map(|metrics| {
+            Json::object(vec![
+                ("rows", Json::Number(metrics.rows.to_string())),
+                ("calls", Json::Number(metrics.calls.to_string())),
+                ("time_ms", Json::number(metrics.time.as_secs_f64() * 1000.0)),
+                (
+                    "peak_memory",
+                    self.peak_memory
+                        .map_or(Json::Null, |memory| Json::Number(memory.to_string())),
+                ),
+            ])
+        });
+        Json::object(vec![
+            ("kind", Json::String(self.kind.clone())),
+            ("parameters", Json::Object(parameters)),
+            ("schema", schema.unwrap_or(Json::Null)),
+            (
+                "estimated_rows",
+                self.estimated_rows.map_or(Json::Null, Json::number),
+            ),
+            ("metrics", metrics.unwrap_or(Json::Null)),
+            (
+                "inputs",
+                Json::Array(self.inputs.iter().map(PlanNode::json).collect()),
+            ),
+        ])
+    }
+}
+
+// Returns the name of a field, qualified by its table, followed by its type.
+fn column(field: &Field) -> String {
+    match &field.table {
+        Some(table) => format!("{table}.{} {}", field.name, field.data_type),
+        None => format!("{} {}", field.name, field.data_type),
+    }
+}
+
+// Returns the text escaped to be part of a quoted DOT string.
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// JSON value, the keys of objects keep their order.
+enum Json {
+    Null,
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    // Returns the number, null when it is infinite or not a number which
+    // JSON cannot represent.
+    fn number(value: f64) -> Json {
+        if value.is_finite() {
+            Json::Number(value.to_string())
+        } else {
+            Json::Null
+        }
+    }
+
+    fn object(entries: Vec<(&str, Json)>) -> Json {
+        Json::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    // Write the value at the given level of nesting, the items of non empty
+    // arrays and objects are written on their own lines, indented by two
+    // spaces per level.
+    fn write(&self, json: &mut String, level: usize) {
+        let indent = |json: &mut String, level: usize| json.push_str(&"  ".repeat(level));
+        match self {
+            Json::Null => json.push_str("null"),
+            Json::Number(number) => json.push_str(number),
+            Json::String(string) => quote(json, string),
+            Json::Array(items) if items.is_empty() => json.push_str("[]"),
+            Json::Object(entries) if entries.is_empty() => json.push_str("{}"),
+            Json::Array(items) => {
+                json.push_str("[\n");
+                for (i, item) in items.iter().enumerate() {
+                    indent(json, level + 1);
+                    item.write(json, level + 1);
+                    json.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                indent(json, level);
+                json.push(']');
+            }
+            Json::Object(entries) => {
+                json.push_str("{\n");
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    indent(json, level + 1);
+                    quote(json, key);
+                    json.push_str(": ");
+                    value.write(json, level + 1);
+                    json.push_str(if i + 1 < entries.len() { ",\n" } else { "\n" });
+                }
+                indent(json, level);
+                json.push('}');
+            }
+        }
+    }
+}
+
+// Write a JSON string, escaping quotes, backslashes and control characters.
+fn quote(json: &mut String, string: &str) {
+    json.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c < ' ' => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binder::Binder;
+    use crate::catalog::{Changes, Column, Table};
+    use crate::functions::FunctionRegistry;
+    use crate::row::Row;
+    use crate::sql::{Parser, Tokenizer};
+    use crate::types::{DataType, Value};
+
+    // Catalog of an `accounts` table of 4 rows.
+    fn catalog() -> Catalog {
+        let mut catalog = Catalog::new();
+        let columns = vec![
+            Column::new("id", DataType::Integer),
+            Column::new("owner", DataType::Varchar),
+        ];
+        catalog
+            .create_table(Table::new("accounts", columns).unwrap())
+            .unwrap();
+        let inserted = (0..4)
+            .map(|i| Row::new(&[Value::Integer(i), Value::Varchar(format!("owner {i}"))]))
+            .collect();
+        let changes = Changes {
+            inserted,
+            updated: vec![],
+            deleted: vec![],
+        };
+        catalog
+            .apply("accounts", changes, &|_, _| Box::new(|_: &Row| Ok(true)))
+            .unwrap();
+        catalog
+    }
+
+    fn plan(catalog: &Catalog, sql: &str) -> LogicalPlan {
+        let query = Parser::new(Tokenizer::new(sql)).unwrap().parse().unwrap();
+        Binder::new(catalog, &FunctionRegistry::default())
+            .bind_query(query)
+            .unwrap()
+    }
+
+    #[test]
+    fn logical_plans_can_be_exported_to_dot() {
+        let catalog = catalog();
+        let plan = plan(&catalog, "SELECT owner FROM accounts WHERE id > 1");
+        assert_eq!(
+            PlanNode::logical(&plan, &catalog).to_dot(),
+            "digraph plan {
+  node [shape=box];
+  n0 [label=\"Project\\nexprs: owner\\nschema: owner VARCHAR\\nestimated rows: 1\"];
+  n1 [label=\"Filter\\npredicate: id > 1\\nschema: accounts.id INTEGER, accounts.owner VARCHAR\\nestimated rows: 1\"];
+  n2 [label=\"Scan\\ntable: accounts\\nschema: accounts.id INTEGER, accounts.owner VARCHAR\\nestimated rows: 4\"];
+  n1 -> n2;
+  n0 -> n1;
+}
+"
+        );
+    }
+
+    #[test]
+    fn logical_plans_can_be_exported_to_json() {
+        let catalog = catalog();
+        let plan = plan(&catalog, "SELECT owner FROM accounts LIMIT 1");
+        assert_eq!(
+            PlanNode::logical(&plan, &catalog).to_json(),
+            r#"{
+  "kind": "Project",
+  "parameters": {
+    "exprs": "owner"
+  },
+  "schema": [
+    {
+      "table": null,
+      "name": "owner",
+      "type": "VARCHAR"
+    }
+  ],
+  "estimated_rows": 1,
+  "metrics": null,
+  "inputs": [
+    {
+      "kind": "Limit",
+      "parameters": {
+        "count": "1"
+      },
+      "schema": [
+        {
+          "table": "accounts",
+          "name": "id",
+          "type": "INTEGER"
+        },
+        {
+          "table": "accounts",
+          "name": "owner",
+          "type": "VARCHAR"
+        }
+      ],
+      "estimated_rows": 1,
+      "metrics": null,
+      "inputs": [
+        {
+          "kind": "Scan",
+          "parameters": {
+            "table": "accounts"
+          },
+          "schema": [
+            {
+              "table": "accounts",
+              "name": "id",
+              "type": "INTEGER"
+            },
+            {
+              "table": "accounts",
+              "name": "owner",
+              "type": "VARCHAR"
+            }
+          ],
+          "estimated_rows": 4,
+          "metrics": null,
+          "inputs": []
+        }
+      ]
+    }
+  ]
+}"#
+        );
+    }
+
+    #[test]
+    fn strings_are_escaped() {
+        let node = PlanNode {
+            kind: "Filter".to_string(),
+            parameters: vec![("predicate".to_string(), "name = 'a\"b\\c\n'".to_string())],
+            ..PlanNode::default()
+        };
+        assert!(node
+            .to_dot()
+            .contains(r#"n0 [label="Filter\npredicate: name = 'a\"b\\c\n'"];"#));
+        assert!(node
+            .to_json()
+            .contains(r#""predicate": "name = 'a\"b\\c\n'""#));
+    }
+}
@@ -0,0 +1,967 @@
+//! Implementation of the registry of scalar, aggregate and table functions
+//! callable from SQL, including the built-in aggregates and table functions.
+use std::any::Any;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::decimal::MAX_PRECISION;
+use crate::operators::{ExecutionError, Operator, Scan};
+use crate::row::Row;
+use crate::schema::{Field, Schema};
+use crate::temporal::{Clock, DateTimeField, SystemClock};
+use crate::types::{ArithmeticError, DataType, Value};
+
+/// Signature of the Rust closures implementing scalar functions, which fail
+/// on arguments they cannot compute a value from.
+pub type ScalarImpl = dyn Fn(&[Value]) -> Result<Value, ExecutionError>;
+
+/// A scalar function maps a fixed list of typed arguments to a single value.
+pub struct ScalarFunction {
+    pub name: String,
+    pub arg_types: Vec<DataType>,
+    pub return_type: DataType,
+    fun: Box<ScalarImpl>,
+}
+
+impl ScalarFunction {
+    /// Create a new scalar function with the given signature and implementation.
+    pub fn new<F>(name: &str, arg_types: &[DataType], return_type: DataType, fun: F) -> Self
+    where
+        F: Fn(&[Value]) -> Result<Value, ExecutionError> + 'static,
+    {
+        Self {
+            name: name.to_lowercase(),
+            arg_types: arg_types.to_vec(),
+            return_type,
+            fun: Box::new(fun),
+        }
+    }
+
+    /// Invoke the function on the given arguments, checking that both the
+    /// arguments and the returned value follow the declared signature.
+    ///
+    /// Functions are strict, a `NULL` argument yields `NULL` without calling
+    /// the implementation.
+    pub fn invoke(&self, args: &[Value]) -> Result<Value, ExecutionError> {
+        if args.len() != self.arg_types.len() {
+            return Err(ExecutionError::Function(format!(
+                "Function {} expects {} arguments got {}",
+                self.name,
+                self.arg_types.len(),
+                args.len()
+            )));
+        }
+        if args.iter().any(Value::is_null) {
+            return Ok(Value::Null);
+        }
+        for (arg, expected) in args.iter().zip(&self.arg_types) {
+            if !arg.has_type(expected) {
+                return Err(ExecutionError::Function(format!(
+                    "Function {} expects argument of type {expected} got {}",
+                    self.name,
+                    arg.data_type()
+                )));
+            }
+        }
+        let value = (self.fun)(args)?;
+        if !value.has_type(&self.return_type) {
+            return Err(ExecutionError::Function(format!(
+                "Function {} declared to return {} returned {}",
+                self.name,
+                self.return_type,
+                value.data_type()
+            )));
+        }
+        Ok(value)
+    }
+}
+
+/// Accumulators hold the running state of an aggregate function over a group
+/// of rows.
+///
+/// The state of accumulators built over disjoint partitions of a group can be
+/// merged, which lets aggregates be computed partially and combined.
+pub trait Accumulator {
+    /// Returns the state of the aggregate over an empty group.
+    fn init() -> Self;
+    /// Fold the arguments evaluated on the next row of the group, fails
+    /// when the state cannot hold them such as when a sum overflows.
+    fn update(&mut self, args: &[Value]) -> Result<(), ExecutionError>;
+    /// Merge the state of an accumulator built over another partition.
+    fn merge(&mut self, other: Self) -> Result<(), ExecutionError>;
+    /// Returns the value of the aggregate for the rows seen so far.
+    fn finalize(&self) -> Result<Value, ExecutionError>;
+    /// Returns the approximate number of bytes of the state, accumulators
+    /// holding data on the heap should add it.
+    fn memory(&self) -> usize {
+        size_of_val(self)
+    }
+}
+
+/// Type erased `Accumulator` used to store the states of different aggregates
+/// side by side.
+pub trait AccumulatorState {
+    fn update(&mut self, args: &[Value]) -> Result<(), ExecutionError>;
+    /// Merge another state, fails unless it comes from the same aggregate
+    /// function.
+    fn merge(&mut self, other: Box<dyn AccumulatorState>) -> Result<(), ExecutionError>;
+    fn finalize(&self) -> Result<Value, ExecutionError>;
+    fn memory(&self) -> usize;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<A: Accumulator + 'static> AccumulatorState for A {
+    fn update(&mut self, args: &[Value]) -> Result<(), ExecutionError> {
+        Accumulator::update(self, args)
+    }
+
+    fn merge(&mut self, other: Box<dyn AccumulatorState>) -> Result<(), ExecutionError> {
+        let other = other.into_any().downcast::<A>().map_err(|_| {
+            ExecutionError::Function(
+                "Cannot merge states of different aggregate functions".to_string(),
+            )
+        })?;
+        Accumulator::merge(self, *other)
+    }
+
+    fn finalize(&self) -> Result<Value, ExecutionError> {
+        Accumulator::finalize(self)
+    }
+
+    fn memory(&self) -> usize {
+        Accumulator::memory(self)
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// Derives the return type of an aggregate from the types of its arguments.
+pub type ReturnTypeFn = dyn Fn(&[DataType]) -> DataType;
+
+/// An aggregate function folds the arguments evaluated on every row of a group
+/// into a single value using an `Accumulator`.
+///
+/// Arguments declared as a `DECIMAL` or an array accept decimals of any
+/// precision and scale or arrays of any element type.
+#[derive(Clone)]
+pub struct AggregateFunction {
+    pub name: String,
+    pub arg_types: Vec<DataType>,
+    pub return_type: DataType,
+    init: Rc<dyn Fn() -> Box<dyn AccumulatorState>>,
+    // Derives the return type from the types of the arguments the
+    // aggregate is called with, if it depends on them.
+    derive_return_type: Option<Rc<ReturnTypeFn>>,
+}
+
+impl AggregateFunction {
+    /// Create a new aggregate function with the given signature backed by the
+    /// accumulator `A`.
+    pub fn new<A: Accumulator + 'static>(
+        name: &str,
+        arg_types: &[DataType],
+        return_type: DataType,
+    ) -> Self {
+        Self {
+            name: name.to_lowercase(),
+            arg_types: arg_types.to_vec(),
+            return_type,
+            init: Rc::new(|| Box::new(A::init())),
+            derive_return_type: None,
+        }
+    }
+
+    /// Derive the return type from the types of the arguments the aggregate
+    /// is called with, such as the precision and scale of a `DECIMAL`.
+    pub fn with_return_type<F>(mut self, return_type: F) -> Self
+    where
+        F: Fn(&[DataType]) -> DataType + 'static,
+    {
+        self.derive_return_type = Some(Rc::new(return_type));
+        self
+    }
+
+    /// Returns true if the aggregate can be called with arguments of the
+    /// given types, exactly when they are the declared types.
+    fn accepts(&self, arg_types: &[DataType], exact: bool) -> bool {
+        self.arg_types.len() == arg_types.len()
+            && self
+                .arg_types
+                .iter()
+                .zip(arg_types)
+                .all(|(expected, actual)| match (expected, actual) {
+                    (_, DataType::Null) => true,
+                    (DataType::Decimal(_, _), DataType::Decimal(_, _))
+                    | (DataType::Array(_), DataType::Array(_))
+                        if !exact =>
+                    {
+                        true
+                    }
+                    (expected, actual) => expected == actual,
+                })
+    }
+
+    // Returns the aggregate called with arguments of the given types, whose
+    // return type may depend on them.
+    fn called_with(&self, arg_types: &[DataType]) -> Self {
+        let mut function = self.clone();
+        if let Some(derive_return_type) = &self.derive_return_type {
+            function.return_type = derive_return_type(arg_types);
+        }
+        function
+    }
+
+    /// Returns a new accumulator in its initial state.
+    pub fn accumulator(&self) -> Box<dyn AccumulatorState> {
+        (self.init)()
+    }
+
+    /// Fold the arguments evaluated on a row into the given accumulator.
+    ///
+    /// Aggregates skip rows where any of the arguments is `NULL`.
+    pub fn update(
+        &self,
+        state: &mut dyn AccumulatorState,
+        args: &[Value],
+    ) -> Result<(), ExecutionError> {
+        if args.len() != self.arg_types.len() {
+            return Err(ExecutionError::Function(format!(
+                "Aggregate {} expects {} arguments got {}",
+                self.name,
+                self.arg_types.len(),
+                args.len()
+            )));
+        }
+        if args.iter().any(Value::is_null) {
+            return Ok(());
+        }
+        state.update(args)
+    }
+}
+
+/// Table functions are called in the `FROM` clause and produce a relation
+/// from their arguments.
+pub trait TableFunction {
+    /// Returns the schema of the rows produced for arguments of the given
+    /// types, fields are named after the function unless aliased. Returns
+    /// `None` if the function cannot be called with arguments of these types.
+    fn schema(&self, arg_types: &[DataType]) -> Option<Schema>;
+    /// Returns the operator producing the rows for the given arguments.
+    fn operator(&self, args: &[Value]) -> Result<Box<dyn Operator>, ExecutionError>;
+}
+
+/// Registry of functions resolved by name when evaluating `Expr::Function`,
+/// names are case insensitive.
+///
+/// Aggregates can be overloaded on their argument types, the built-in ones
+/// are registered for every type they support.
+#[derive(Clone)]
+pub struct FunctionRegistry {
+    scalars: HashMap<String, Rc<ScalarFunction>>,
+    aggregates: HashMap<String, Vec<AggregateFunction>>,
+    tables: HashMap<String, Rc<dyn TableFunction>>,
+}
+
+impl Default for FunctionRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            scalars: HashMap::new(),
+            aggregates: HashMap::new(),
+            tables: HashMap::new(),
+        };
+        registry.register_aggregate(AggregateFunction::new::<Count>(
+            "count",
+            &[],
+            DataType::BigInt,
+        ));
+        for data_type in [
+            DataType::Boolean,
+            DataType::Integer,
+            DataType::BigInt,
+            DataType::Float,
+            DataType::Date,
+            DataType::Time,
+            DataType::Timestamp,
+            DataType::Interval,
+            DataType::Varchar,
+        ] {
+            registry.register_aggregate(AggregateFunction::new::<Count>(
+                "count",
+                std::slice::from_ref(&data_type),
+                DataType::BigInt,
+            ));
+            registry.register_aggregate(AggregateFunction::new::<Min>(
+                "min",
+                std::slice::from_ref(&data_type),
+                data_type.clone(),
+            ));
+            registry.register_aggregate(AggregateFunction::new::<Max>(
+                "max",
+                std::slice::from_ref(&data_type),
+                data_type.clone(),
+            ));
+        }
+        // Integers are summed as `BIGINT` to avoid overflows, their mean is
+        // an exact `DECIMAL`.
+        let mean = DataType::Decimal(MAX_PRECISION, AVG_SCALE);
+        for (data_type, sum_type, avg_type) in [
+            (DataType::Integer, DataType::BigInt, mean.clone()),
+            (DataType::BigInt, DataType::BigInt, mean),
+            (DataType::Float, DataType::Float, DataType::Float),
+        ] {
+            registry.register_aggregate(AggregateFunction::new::<Sum>(
+                "sum",
+                std::slice::from_ref(&data_type),
+                sum_type,
+            ));
+            registry.register_aggregate(AggregateFunction::new::<Avg>(
+                "avg",
+                std::slice::from_ref(&data_type),
+                avg_type,
+            ));
+        }
+        // Decimals of any precision and scale are aggregated, their minimum
+        // and maximum have the type of the argument. They are summed at the
+        // maximum precision keeping their scale, and their mean has at least
+        // `AVG_SCALE` digits after the decimal point.
+        let decimal = DataType::Decimal(MAX_PRECISION, 0);
+        registry.register_aggregate(AggregateFunction::new::<Count>(
+            "count",
+            std::slice::from_ref(&decimal),
+            DataType::BigInt,
+        ));
+        registry.register_aggregate(
+            AggregateFunction::new::<Min>("min", std::slice::from_ref(&decimal), decimal.clone())
+                .with_return_type(|arg_types| {
+                    DataType::Decimal(decimal_precision(arg_types), decimal_scale(arg_types))
+                }),
+        );
+        registry.register_aggregate(
+            AggregateFunction::new::<Max>("max", std::slice::from_ref(&decimal), decimal.clone())
+                .with_return_type(|arg_types| {
+                    DataType::Decimal(decimal_precision(arg_types), decimal_scale(arg_types))
+                }),
+        );
+        registry.register_aggregate(
+            AggregateFunction::new::<Sum>("sum", std::slice::from_ref(&decimal), decimal.clone())
+                .with_return_type(|arg_types| {
+                    DataType::Decimal(MAX_PRECISION, decimal_scale(arg_types))
+                }),
+        );
+        registry.register_aggregate(
+            AggregateFunction::new::<Avg>(
+                "avg",
+                std::slice::from_ref(&decimal),
+                DataType::Decimal(MAX_PRECISION, AVG_SCALE),
+            )
+            .with_return_type(|arg_types| {
+                DataType::Decimal(MAX_PRECISION, decimal_scale(arg_types).max(AVG_SCALE))
+            }),
+        );
+        // Arrays of any element type are counted.
+        registry.register_aggregate(AggregateFunction::new::<Count>(
+            "count",
+            &[DataType::Array(Box::new(DataType::Null))],
+            DataType::BigInt,
+        ));
+        registry.register_scalar(ScalarFunction::new(
+            "date_trunc",
+            &[DataType::Varchar, DataType::Timestamp],
+            DataType::Timestamp,
+            date_trunc,
+        ));
+        registry.set_clock(SystemClock);
+        registry.register_table_function("generate_series", GenerateSeries);
+        registry.register_table_function("unnest", Unnest);
+        registry
+    }
+}
+
+impl FunctionRegistry {
+    /// Create a new registry holding the built-in functions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a scalar function, replacing any function with the same name.
+    pub fn register_scalar(&mut self, function: ScalarFunction) {
+        self.scalars
+            .insert(function.name.clone(), Rc::new(function));
+    }
+
+    /// Register an aggregate function, replacing any aggregate with the same
+    /// name and argument types.
+    pub fn register_aggregate(&mut self, function: AggregateFunction) {
+        let overloads = self.aggregates.entry(function.name.clone()).or_default();
+        overloads.retain(|overload| overload.arg_types != function.arg_types);
+        overloads.push(function);
+    }
+
+    /// Register a table function, replacing any table function with the same
+    /// name.
+    pub fn register_table_function<F: TableFunction + 'static>(&mut self, name: &str, function: F) {
+        self.tables.insert(name.to_lowercase(), Rc::new(function));
+    }
+
+    /// Set the clock read by `NOW()`, which returns the current timestamp.
+    pub fn set_clock<C: Clock + 'static>(&mut self, clock: C) {
+        self.register_scalar(ScalarFunction::new(
+            "now",
+            &[],
+            DataType::Timestamp,
+            move |_| Ok(Value::Timestamp(clock.now())),
+        ));
+    }
+
+    /// Returns the scalar function registered under the given name.
+    pub fn scalar(&self, name: &str) -> Option<&ScalarFunction> {
+        self.scalars.get(&name.to_lowercase()).map(|f| f.as_ref())
+    }
+
+    /// Returns the aggregate registered under the given name for arguments of
+    /// the given types, `NULL` arguments match any type. Overloads declared
+    /// for exactly these types are preferred over the ones accepting any
+    /// `DECIMAL` or array.
+    pub fn aggregate(&self, name: &str, arg_types: &[DataType]) -> Option<AggregateFunction> {
+        let overloads = self.aggregates.get(&name.to_lowercase())?;
+        overloads
+            .iter()
+            .find(|f| f.accepts(arg_types, true))
+            .or_else(|| overloads.iter().find(|f| f.accepts(arg_types, false)))
+            .map(|f| f.called_with(arg_types))
+    }
+
+    /// Returns the table function registered under the given name.
+    pub fn table_function(&self, name: &str) -> Option<&dyn TableFunction> {
+        self.tables.get(&name.to_lowercase()).map(|f| f.as_ref())
+    }
+
+    /// Returns true if an aggregate is registered under the given name.
+    pub fn is_aggregate(&self, name: &str) -> bool {
+        self.aggregates.contains_key(&name.to_lowercase())
+    }
+}
+
+/// `DATE_TRUNC(field, timestamp)` truncates a timestamp to the precision of
+/// a field, from `second` to `year`.
+fn date_trunc(args: &[Value]) -> Result<Value, ExecutionError> {
+    let (Value::Varchar(name), Value::Timestamp(timestamp)) = (&args[0], &args[1]) else {
+        unreachable!("expected field name and timestamp got {args:?}");
+    };
+    DateTimeField::parse(name)
+        .and_then(|field| timestamp.trunc(field))
+        .map(Value::Timestamp)
+        .ok_or_else(|| ExecutionError::Function(format!("Cannot truncate timestamps to {name}")))
+}
+
+// Returns the precision of the `DECIMAL` argument of an aggregate.
+fn decimal_precision(arg_types: &[DataType]) -> u8 {
+    match arg_types {
+        [DataType::Decimal(precision, _)] => *precision,
+        _ => MAX_PRECISION,
+    }
+}
+
+// Returns the scale of the `DECIMAL` argument of an aggregate.
+fn decimal_scale(arg_types: &[DataType]) -> u8 {
+    match arg_types {
+        [DataType::Decimal(_, scale)] => *scale,
+        _ => 0,
+    }
+}
+
+/// `COUNT(expr)` counts the rows of the group where `expr` is not `NULL`,
+/// `COUNT(*)` has no arguments and counts every row.
+struct Count(i64);
+
+impl Accumulator for Count {
+    fn init() -> Self {
+        Self(0)
+    }
+
+    fn update(&mut self, _: &[Value]) -> Result<(), ExecutionError> {
+        self.0 += 1;
+        Ok(())
+    }
+
+    fn merge(&mut self, other: Self) -> Result<(), ExecutionError> {
+        self.0 += other.0;
+        Ok(())
+    }
+
+    fn finalize(&self) -> Result<Value, ExecutionError> {
+        Ok(Value::BigInt(self.0))
+    }
+}
+
+/// `SUM(expr)` adds up the values of the group, fails when the sum
+/// overflows.
+struct Sum(Value);
+
+impl Accumulator for Sum {
+    fn init() -> Self {
+        Self(Value::Null)
+    }
+
+    fn update(&mut self, args: &[Value]) -> Result<(), ExecutionError> {
+        let value = match args.first() {
+            Some(Value::Integer(value)) => Value::BigInt(*value as i64),
+            Some(value) => value.clone(),
+            None => Value::Null,
+        };
+        Accumulator::merge(self, Self(value))
+    }
+
+    fn merge(&mut self, other: Self) -> Result<(), ExecutionError> {
+        if self.0.is_null() {
+            self.0 = other.0;
+        } else if !other.0.is_null() {
+            self.0 = self.0.arithmetic("+", &other.0)?;
+        }
+        Ok(())
+    }
+
+    fn finalize(&self) -> Result<Value, ExecutionError> {
+        Ok(self.0.clone())
+    }
+}
+
+/// Scale of the `DECIMAL` mean of integers, which is the scale of the
+/// quotient of decimals without digits after the decimal point.
+const AVG_SCALE: u8 = 6;
+
+/// `AVG(expr)` computes the mean of the values of the group, the mean of
+/// integers and decimals is a `DECIMAL` rounded to at least `AVG_SCALE`
+/// digits.
+struct Avg {
+    sum: Sum,
+    count: i64,
+}
+
+impl Accumulator for Avg {
+    fn init() -> Self {
+        Self {
+            sum: Sum::init(),
+            count: 0,
+        }
+    }
+
+    fn update(&mut self, args: &[Value]) -> Result<(), ExecutionError> {
+        Accumulator::update(&mut self.sum, args)?;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn merge(&mut self, other: Self) -> Result<(), ExecutionError> {
+        Accumulator::merge(&mut self.sum, other.sum)?;
+        self.count += other.count;
+        Ok(())
+    }
+
+    fn finalize(&self) -> Result<Value, ExecutionError> {
+        if self.count == 0 {
+            return Ok(Value::Null);
+        }
+        let sum = Accumulator::finalize(&self.sum)?;
+        let data_type = match &sum {
+            Value::Float(_) => DataType::Float,
+            Value::Decimal(sum) => DataType::Decimal(MAX_PRECISION, sum.scale()),
+            _ => DataType::Decimal(MAX_PRECISION, 0),
+        };
+        let overflow = || ArithmeticError::Overflow(data_type.clone());
+        let sum = sum.cast(&data_type).ok_or_else(overflow)?;
+        let count = Value::BigInt(self.count)
+            .cast(&data_type)
+            .ok_or_else(overflow)?;
+        let mean = match sum.arithmetic("/", &count)? {
+            Value::Decimal(mean) => {
+                let scale = mean.scale().max(AVG_SCALE);
+                Value::Decimal(mean)
+                    .cast(&DataType::Decimal(MAX_PRECISION, scale))
+                    .ok_or_else(overflow)?
+            }
+            mean => mean,
+        };
+        Ok(mean)
+    }
+}
+
+/// `MIN(expr)` returns the smallest value of the group.
+struct Min(Value);
+
+impl Accumulator for Min {
+    fn init() -> Self {
+        Self(Value::Null)
+    }
+
+    fn update(&mut self, args: &[Value]) -> Result<(), ExecutionError> {
+        let value = args.first().cloned().unwrap_or(Value::Null);
+        Accumulator::merge(self, Self(value))
+    }
+
+    fn merge(&mut self, other: Self) -> Result<(), ExecutionError> {
+        if self.0.is_null() || (!other.0.is_null() && other.0 < self.0) {
+            self.0 = other.0;
+        }
+        Ok(())
+    }
+
+    fn finalize(&self) -> Result<Value, ExecutionError> {
+        Ok(self.0.clone())
+    }
+}
+
+/// `MAX(expr)` returns the largest value of the group.
+struct Max(Value);
+
+impl Accumulator for Max {
+    fn init() -> Self {
+        Self(Value::Null)
+    }
+
+    fn update(&mut self, args: &[Value]) -> Result<(), ExecutionError> {
+        let value = args.first().cloned().unwrap_or(Value::Null);
+        Accumulator::merge(self, Self(value))
+    }
+
+    fn merge(&mut self, other: Self) -> Result<(), ExecutionError> {
+        if self.0.is_null() || (!other.0.is_null() && other.0 > self.0) {
+            self.0 = other.0;
+        }
+        Ok(())
+    }
+
+    fn finalize(&self) -> Result<Value, ExecutionError> {
+        Ok(self.0.clone())
+    }
+}
+
+/// `generate_series(start, stop [, step])` returns the integers from `start`
+/// to `stop` inclusive, incremented by `step` which defaults to 1.
+///
+/// The series is a series of `BIGINT` if any argument is a `BIGINT`.
+struct GenerateSeries;
+
+impl TableFunction for GenerateSeries {
+    fn schema(&self, arg_types: &[DataType]) -> Option<Schema> {
+        if !(2..=3).contains(&arg_types.len())
+            || arg_types
+                .iter()
+                .any(|t| !matches!(t, DataType::Integer | DataType::BigInt | DataType::Null))
+        {
+            return None;
+        }
+        let data_type = if arg_types.contains(&DataType::BigInt) {
+            DataType::BigInt
+        } else {
+            DataType::Integer
+        };
+        Some(Schema::new(vec![Field::new("generate_series", data_type)]))
+    }
+
+    fn operator(&self, args: &[Value]) -> Result<Box<dyn Operator>, ExecutionError> {
+        let bound = |index: usize, default: i64| match args.get(index) {
+            Some(Value::Integer(value)) => Some(*value as i64),
+            Some(Value::BigInt(value)) => Some(*value),
+            Some(Value::Null) => None,
+            None => Some(default),
+            Some(value) => unreachable!("expected INTEGER argument got {value}"),
+        };
+        let data_type = if args.iter().any(|arg| matches!(arg, Value::BigInt(_))) {
+            DataType::BigInt
+        } else {
+            DataType::Integer
+        };
+        match (bound(0, 0), bound(1, 0), bound(2, 1)) {
+            (_, _, Some(0)) => Err(ExecutionError::Function(
+                "Function generate_series step cannot be zero".to_string(),
+            )),
+            (Some(start), Some(stop), Some(step)) => Ok(Box::new(Series {
+                next: Some(start),
+                stop,
+                step,
+                data_type,
+            })),
+            // A NULL argument yields an empty relation.
+            _ => Ok(Box::new(Scan::new(&[]))),
+        }
+    }
+}
+
+/// Operator returning the values of an arithmetic series lazily, the series
+/// ends early instead of overflowing.
+struct Series {
+    next: Option<i64>,
+    stop: i64,
+    step: i64,
+    data_type: DataType,
+}
+
+impl Operator for Series {
+    fn open(&mut self) -> Result<(), ExecutionError> {
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<Row>, ExecutionError> {
+        let value = self.next.filter(|&value| {
+            if self.step > 0 {
+                value <= self.stop
+            } else {
+                value >= self.stop
+            }
+        });
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        self.next = value.checked_add(self.step);
+        Ok(Value::BigInt(value)
+            .cast(&self.data_type)
+            .map(|value| Row::new(&[value])))
+    }
+
+    fn close(&self) {}
+}
+
+/// `unnest(array)` returns one row for each element of the array.
+struct Unnest;
+
+impl TableFunction for Unnest {
+    fn schema(&self, arg_types: &[DataType]) -> Option<Schema> {
+        let element = match arg_types {
+            [DataType::Array(element)] => *element.clone(),
+            [DataType::Null] => DataType::Null,
+            _ => return None,
+        };
+        Some(Schema::new(vec![Field::new("unnest", element)]))
+    }
+
+    fn operator(&self, args: &[Value]) -> Result<Box<dyn Operator>, ExecutionError> {
+        match args {
+            [Value::Array(values)] => {
+                let rows = values
+                    .iter()
+                    .map(|value| Row::new(std::slice::from_ref(value)))
+                    .collect::<Vec<_>>();
+                Ok(Box::new(Scan::new(&rows)))
+            }
+            _ => Ok(Box::new(Scan::new(&[]))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn band() -> ScalarFunction {
+        ScalarFunction::new(
+            "band",
+            &[DataType::Integer],
+            DataType::Varchar,
+            |args| match args[0] {
+                Value::Integer(salary) if salary >= 12000 => Ok(Value::from("senior")),
+                _ => Ok(Value::from("junior")),
+            },
+        )
+    }
+
+    #[test]
+    fn can_resolve_functions_by_name() {
+        let mut registry = FunctionRegistry::new();
+        registry.register_scalar(band());
+
+        assert!(registry.scalar("BAND").is_some());
+        assert!(registry.scalar("unknown").is_none());
+        assert_eq!(
+            registry
+                .scalar("band")
+                .unwrap()
+                .invoke(&[Value::Integer(13000)]),
+            Ok(Value::from("senior"))
+        );
+    }
+
+    #[test]
+    fn invoke_checks_argument_types() {
+        let error = band().invoke(&[Value::from("13000")]).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("expects argument of type INTEGER"));
+    }
+
+    #[test]
+    fn functions_are_strict() {
+        assert_eq!(band().invoke(&[Value::Null]), Ok(Value::Null));
+    }
+
+    fn collect(operator: Result<Box<dyn Operator>, ExecutionError>) -> Vec<Row> {
+        let mut operator = operator.unwrap();
+        operator.open().unwrap();
+        let mut rows = vec![];
+        while let Some(row) = operator.next().unwrap() {
+            rows.push(row);
+        }
+        operator.close();
+        rows
+    }
+
+    #[test]
+    fn generate_series_returns_integers() {
+        let registry = FunctionRegistry::new();
+        let series = registry.table_function("GENERATE_SERIES").unwrap();
+
+        let schema = series
+            .schema(&[DataType::Integer, DataType::Integer])
+            .unwrap();
+        assert_eq!(
+            schema.fields,
+            vec![Field::new("generate_series", DataType::Integer)]
+        );
+        assert_eq!(
+            collect(series.operator(&[Value::Integer(1), Value::Integer(3)])),
+            vec![
+                Row::new(&[Value::Integer(1)]),
+                Row::new(&[Value::Integer(2)]),
+                Row::new(&[Value::Integer(3)]),
+            ]
+        );
+        assert_eq!(
+            collect(series.operator(&[Value::Integer(10), Value::Integer(1), Value::Integer(-5)])),
+            vec![
+                Row::new(&[Value::Integer(10)]),
+                Row::new(&[Value::Integer(5)]),
+            ]
+        );
+        assert!(collect(series.operator(&[Value::Null, Value::Integer(3)])).is_empty());
+    }
+
+    #[test]
+    fn unnest_returns_array_elements() {
+        let registry = FunctionRegistry::new();
+        let unnest = registry.table_function("unnest").unwrap();
+        let array = Value::Array(vec![Value::from("a"), Value::Null, Value::from("b")]);
+
+        let schema = unnest.schema(&[array.data_type()]).unwrap();
+        assert_eq!(schema.fields, vec![Field::new("unnest", DataType::Varchar)]);
+        assert_eq!(
+            collect(unnest.operator(&[array])),
+            vec![
+                Row::new(&[Value::from("a")]),
+                Row::new(&[Value::Null]),
+                Row::new(&[Value::from("b")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_resolve_aggregate_overloads() {
+        let registry = FunctionRegistry::new();
+
+        let max = registry.aggregate("MAX", &[DataType::Varchar]).unwrap();
+        assert_eq!(max.return_type, DataType::Varchar);
+        assert!(registry.aggregate("count", &[]).is_some());
+        assert!(registry.aggregate("sum", &[DataType::Varchar]).is_none());
+        let sum = registry
+            .aggregate("sum", &[DataType::Decimal(10, 2)])
+            .unwrap();
+        assert_eq!(sum.return_type, DataType::Decimal(MAX_PRECISION, 2));
+        let avg = registry
+            .aggregate("avg", &[DataType::Decimal(10, 8)])
+            .unwrap();
+        assert_eq!(avg.return_type, DataType::Decimal(MAX_PRECISION, 8));
+        let min = registry
+            .aggregate("min", &[DataType::Decimal(10, 2)])
+            .unwrap();
+        assert_eq!(min.return_type, DataType::Decimal(10, 2));
+        let tags = DataType::Array(Box::new(DataType::Varchar));
+        assert!(registry.aggregate("count", &[tags]).is_some());
+        assert!(registry.is_aggregate("avg"));
+        assert!(!registry.is_aggregate("band"));
+    }
+
+    #[test]
+    fn aggregates_skip_nulls() {
+        let registry = FunctionRegistry::new();
+        let sum = registry.aggregate("sum", &[DataType::Integer]).unwrap();
+
+        let mut state = sum.accumulator();
+        assert_eq!(state.finalize(), Ok(Value::Null));
+        for value in [Value::Integer(1), Value::Null, Value::Integer(2)] {
+            sum.update(state.as_mut(), &[value]).unwrap();
+        }
+        assert_eq!(state.finalize(), Ok(Value::BigInt(3)));
+    }
+
+    #[test]
+    fn partial_states_can_be_merged() {
+        let registry = FunctionRegistry::new();
+        let avg = registry.aggregate("avg", &[DataType::Integer]).unwrap();
+
+        let mut left = avg.accumulator();
+        let mut right = avg.accumulator();
+        avg.update(left.as_mut(), &[Value::Integer(10)]).unwrap();
+        avg.update(right.as_mut(), &[Value::Integer(20)]).unwrap();
+        avg.update(right.as_mut(), &[Value::Integer(30)]).unwrap();
+        left.merge(right).unwrap();
+
+        assert_eq!(
+            left.finalize().unwrap(),
+            Value::decode("20.000000", DataType::Decimal(MAX_PRECISION, 6)).unwrap()
+        );
+    }
+
+    #[test]
+    fn means_of_integers_are_exact() {
+        let registry = FunctionRegistry::new();
+        let avg = registry.aggregate("avg", &[DataType::Integer]).unwrap();
+        assert_eq!(avg.return_type, DataType::Decimal(MAX_PRECISION, 6));
+
+        let mut state = avg.accumulator();
+        for value in [1, 2, 2] {
+            avg.update(state.as_mut(), &[Value::Integer(value)])
+                .unwrap();
+        }
+        let mean = state.finalize().unwrap();
+        assert!(mean.has_type(&avg.return_type));
+        assert_eq!(mean.to_string(), "1.666667");
+    }
+
+    #[test]
+    fn sum_overflows_are_errors() {
+        let registry = FunctionRegistry::new();
+        let sum = registry.aggregate("sum", &[DataType::BigInt]).unwrap();
+
+        let mut state = sum.accumulator();
+        sum.update(state.as_mut(), &[Value::BigInt(i64::MAX)])
+            .unwrap();
+        assert_eq!(
+            sum.update(state.as_mut(), &[Value::BigInt(1)]),
+            Err(ExecutionError::Arithmetic(ArithmeticError::Overflow(
+                DataType::BigInt
+            )))
+        );
+    }
+
+    #[test]
+    fn mismatched_aggregate_calls_are_errors() {
+        let registry = FunctionRegistry::new();
+        let sum = registry.aggregate("sum", &[DataType::Integer]).unwrap();
+        let max = registry.aggregate("max", &[DataType::Integer]).unwrap();
+
+        let mut state = sum.accumulator();
+        assert_eq!(
+            sum.update(state.as_mut(), &[]),
+            Err(ExecutionError::Function(
+                "Aggregate sum expects 1 arguments got 0".to_string()
+            ))
+        );
+        assert_eq!(
+            state.merge(max.accumulator()),
+            Err(ExecutionError::Function(
+                "Cannot merge states of different aggregate functions".to_string()
+            ))
+        );
+    }
+}
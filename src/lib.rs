@@ -1,3 +1,19 @@
+pub mod binder;
+pub mod catalog;
+pub mod cost;
+pub mod database;
+pub mod decimal;
+pub mod export;
+pub mod functions;
+pub mod logical;
 pub mod operators;
+pub mod optimizer;
+pub mod planner;
 pub mod row;
+pub mod schema;
+pub mod simplify;
 pub mod sql;
+pub mod statistics;
+pub mod temporal;
+pub mod typecheck;
+pub mod types;
@@ -0,0 +1,337 @@
+//! Implementation of logical plans, trees of relational operations describing
+//! how the rows of a query are computed independently of the operators which
+//! execute them.
+use crate::schema::Schema;
+use crate::sql::Expr;
+
+/// Relational operation producing rows of a known schema, expressions are type
+/// checked and evaluated on the rows of the inputs of their node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogicalPlan {
+    /// Rows of a table, fields are qualified with the alias or name of the
+    /// table. Only the columns of the projection are read when there is one.
+    Scan {
+        table: String,
+        schema: Schema,
+        projection: Option<Vec<usize>>,
+    },
+    /// Rows of constant expressions.
+    Values {
+        rows: Vec<Vec<Expr>>,
+        schema: Schema,
+    },
+    /// Rows produced by a table function called with constant arguments.
+    TableFunction {
+        name: String,
+        args: Vec<Expr>,
+        schema: Schema,
+    },
+    /// Rows of the input satisfying a predicate.
+    Filter {
+        input: Box<LogicalPlan>,
+        predicate: Expr,
+    },
+    /// Expressions evaluated on each row of the input, the schema names the
+    /// columns after the expressions.
+    Project {
+        input: Box<LogicalPlan>,
+        exprs: Vec<Expr>,
+        schema: Schema,
+    },
+    /// Concatenations of the rows of both inputs satisfying a condition, every
+    /// pair of rows without one.
+    Join {
+        left: Box<LogicalPlan>,
+        right: Box<LogicalPlan>,
+        condition: Option<Expr>,
+    },
+    /// Rows of the input joined with the rows a table function produces when
+    /// called with arguments evaluated on each of them.
+    LateralJoin {
+        input: Box<LogicalPlan>,
+        function: String,
+        args: Vec<Expr>,
+        condition: Option<Expr>,
+        schema: Schema,
+    },
+    /// One row per group of input rows holding the group keys followed by
+    /// the aggregate calls, a single row without keys.
+    Aggregate {
+        input: Box<LogicalPlan>,
+        group_by: Vec<Expr>,
+        aggregates: Vec<Expr>,
+        schema: Schema,
+    },
+    /// Rows of the input ordered by a key.
+    Sort { input: Box<LogicalPlan>, key: Expr },
+    /// First rows of the input.
+    Limit {
+        input: Box<LogicalPlan>,
+        count: usize,
+    },
+}
+
+impl LogicalPlan {
+    /// Returns the relation of the given schema without any row.
+    pub fn empty(schema: Schema) -> LogicalPlan {
+        LogicalPlan::Values {
+            rows: vec![],
+            schema,
+        }
+    }
+
+    /// Returns the schema of the rows produced by the plan.
+    pub fn schema(&self) -> Schema {
+        match self {
+            LogicalPlan::Scan { schema, .. }
+            | LogicalPlan::Values { schema, .. }
+            | LogicalPlan::TableFunction { schema, .. }
+            | LogicalPlan::Project { schema, .. }
+            | LogicalPlan::LateralJoin { schema, .. }
+            | LogicalPlan::Aggregate { schema, .. } => schema.clone(),
+            LogicalPlan::Filter { input, .. }
+            | LogicalPlan::Sort { input, .. }
+            | LogicalPlan::Limit { input, .. } => input.schema(),
+            LogicalPlan::Join { left, right, .. } => left.schema().join(&right.schema()),
+        }
+    }
+
+    /// Returns the plans whose rows this plan consumes.
+    pub fn inputs(&self) -> Vec<&LogicalPlan> {
+        match self {
+            LogicalPlan::Scan { .. }
+            | LogicalPlan::Values { .. }
+            | LogicalPlan::TableFunction { .. } => vec![],
+            LogicalPlan::Filter { input, .. }
+            | LogicalPlan::Project { input, .. }
+            | LogicalPlan::LateralJoin { input, .. }
+            | LogicalPlan::Aggregate { input, .. }
+            | LogicalPlan::Sort { input, .. }
+            | LogicalPlan::Limit { input, .. } => vec![input],
+            LogicalPlan::Join { left, right, .. } => vec![left, right],
+        }
+    }
+
+    /// Returns the name of the kind of node at the root of the plan.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LogicalPlan::Scan { .. } => "Scan",
+            LogicalPlan::Values { .. } => "Values",
+            LogicalPlan::TableFunction { .. } => "TableFunction",
+            LogicalPlan::Filter { .. } => "Filter",
+            LogicalPlan::Project { .. } => "Project",
+            LogicalPlan::Join { .. } => "Join",
+            LogicalPlan::LateralJoin { .. } => "LateralJoin",
+            LogicalPlan::Aggregate { .. } => "Aggregate",
+            LogicalPlan::Sort { .. } => "Sort",
+            LogicalPlan::Limit { .. } => "Limit",
+        }
+    }
+
+    /// Returns the parameters of the root of the plan, each of which is a
+    /// name and a value, expressions being rendered as SQL.
+    pub fn parameters(&self) -> Vec<(String, String)> {
+        let list = |exprs: &[Expr]| {
+            exprs
+                .iter()
+                .map(Expr::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut parameters = vec![];
+        match self {
+            LogicalPlan::Scan {
+                table,
+                schema,
+                projection,
+            } => {
+                parameters.push(("table", table.clone()));
+                if projection.is_some() {
+                    let columns = schema.fields.iter().map(|field| field.name.as_str());
+                    parameters.push(("columns", columns.collect::<Vec<_>>().join(", ")));
+                }
+            }
+            LogicalPlan::Values { .. } => {}
+            LogicalPlan::TableFunction { name, args, .. } => {
+                parameters.push(("function", format!("{name}({})", list(args))));
+            }
+            LogicalPlan::Filter { predicate, .. } => {
+                parameters.push(("predicate", predicate.to_string()));
+            }
+            LogicalPlan::Project { exprs, .. } => parameters.push(("exprs", list(exprs))),
+            LogicalPlan::Join { condition, .. } => {
+                parameters.extend(condition.iter().map(|expr| ("condition", expr.to_string())));
+            }
+            LogicalPlan::LateralJoin {
+                function,
+                args,
+                condition,
+                ..
+            } => {
+                parameters.push(("function", format!("{function}({})", list(args))));
+                parameters.extend(condition.iter().map(|expr| ("condition", expr.to_string())));
+            }
+            LogicalPlan::Aggregate {
+                group_by,
+                aggregates,
+                ..
+            } => {
+                if !group_by.is_empty() {
+                    parameters.push(("group by", list(group_by)));
+                }
+                if !aggregates.is_empty() {
+                    parameters.push(("aggregates", list(aggregates)));
+                }
+            }
+            LogicalPlan::Sort { key, .. } => parameters.push(("key", key.to_string())),
+            LogicalPlan::Limit { count, .. } => parameters.push(("count", count.to_string())),
+        }
+        parameters
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect()
+    }
+
+    /// Returns the plan with each of its inputs replaced by the result of
+    /// `f`.
+    pub fn map_inputs<F: FnMut(LogicalPlan) -> LogicalPlan>(self, mut f: F) -> LogicalPlan {
+        let mut boxed = |input: Box<LogicalPlan>| Box::new(f(*input));
+        match self {
+            LogicalPlan::Scan { .. }
+            | LogicalPlan::Values { .. }
+            | LogicalPlan::TableFunction { .. } => self,
+            LogicalPlan::Filter { input, predicate } => LogicalPlan::Filter {
+                input: boxed(input),
+                predicate,
+            },
+            LogicalPlan::Project {
+                input,
+                exprs,
+                schema,
+            } => LogicalPlan::Project {
+                input: boxed(input),
+                exprs,
+                schema,
+            },
+            LogicalPlan::Join {
+                left,
+                right,
+                condition,
+            } => LogicalPlan::Join {
+                left: boxed(left),
+                right: boxed(right),
+                condition,
+            },
+            LogicalPlan::LateralJoin {
+                input,
+                function,
+                args,
+                condition,
+                schema,
+            } => LogicalPlan::LateralJoin {
+                input: boxed(input),
+                function,
+                args,
+                condition,
+                schema,
+            },
+            LogicalPlan::Aggregate {
+                input,
+                group_by,
+                aggregates,
+                schema,
+            } => LogicalPlan::Aggregate {
+                input: boxed(input),
+                group_by,
+                aggregates,
+                schema,
+            },
+            LogicalPlan::Sort { input, key } => LogicalPlan::Sort {
+                input: boxed(input),
+                key,
+            },
+            LogicalPlan::Limit { input, count } => LogicalPlan::Limit {
+                input: boxed(input),
+                count,
+            },
+        }
+    }
+
+    /// Rewrite the plan with `f` from the leaves up, the inputs of a node are
+    /// rewritten before the node itself.
+    pub fn transform_up<F: FnMut(LogicalPlan) -> LogicalPlan>(self, f: &mut F) -> LogicalPlan {
+        let plan = self.map_inputs(|input| input.transform_up(f));
+        f(plan)
+    }
+
+    /// Rewrite the plan with `f` from the root down, the inputs of a node are
+    /// rewritten after the node itself.
+    pub fn transform_down<F: FnMut(LogicalPlan) -> LogicalPlan>(self, f: &mut F) -> LogicalPlan {
+        f(self).map_inputs(|input| input.transform_down(f))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::Field;
+    use crate::types::DataType;
+
+    fn scan(table: &str, column: &str) -> LogicalPlan {
+        LogicalPlan::Scan {
+            table: table.to_string(),
+            schema: Schema::new(vec![Field::qualified(table, column, DataType::Integer)]),
+            projection: None,
+        }
+    }
+
+    #[test]
+    fn schemas_follow_inputs() {
+        let join = LogicalPlan::Join {
+            left: Box::new(scan("a", "x")),
+            right: Box::new(scan("b", "y")),
+            condition: None,
+        };
+        let plan = LogicalPlan::Limit {
+            input: Box::new(LogicalPlan::Filter {
+                input: Box::new(join.clone()),
+                predicate: Expr::Boolean(true),
+            }),
+            count: 1,
+        };
+        assert_eq!(
+            plan.schema(),
+            Schema::new(vec![
+                Field::qualified("a", "x", DataType::Integer),
+                Field::qualified("b", "y", DataType::Integer),
+            ])
+        );
+        assert_eq!(plan.inputs()[0].inputs(), vec![&join]);
+        assert_eq!(join.inputs().len(), 2);
+    }
+
+    #[test]
+    fn can_transform_plans() {
+        let plan = LogicalPlan::Sort {
+            input: Box::new(LogicalPlan::Limit {
+                input: Box::new(scan("a", "x")),
+                count: 1,
+            }),
+            key: Expr::Column("x".to_string()),
+        };
+        // Limits are visited before the sort above them from the leaves up,
+        // and after it from the root down.
+        let mut visited = vec![];
+        let mut visit = |plan: LogicalPlan| {
+            visited.push(plan.inputs().len());
+            plan
+        };
+        let plan = plan.transform_up(&mut visit);
+        assert_eq!(visited, vec![0, 1, 1]);
+        let plan = plan.transform_down(&mut |plan| match plan {
+            LogicalPlan::Limit { input, .. } => *input,
+            plan => plan,
+        });
+        assert_eq!(plan.inputs(), vec![&scan("a", "x")]);
+    }
+}
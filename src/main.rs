@@ -1,203 +1,47 @@
-use eocene::operators::{Filter, Limit, Operator, Project, Scan, Sort};
+use eocene::database::Database;
+use eocene::functions::Accumulator;
+use eocene::operators::ExecutionError;
 use eocene::row::Row;
-use eocene::sql::{Expr, Parser, Query, Tokenizer};
+use eocene::types::{ArithmeticError, DataType, Value};
 
-type Comparator = Box<dyn Fn(&Row, &Row) -> std::cmp::Ordering>;
-
-#[derive(Default)]
-pub struct QueryExecutor {}
-
-impl QueryExecutor {
-    pub fn new() -> Self {
-        Self {}
-    }
-
-    pub fn execute(mut pipeline: Box<dyn Operator>) -> Vec<Row> {
-        pipeline.open();
-        let mut results = Vec::new();
-        while let Some(row) = pipeline.next() {
-            results.push(row);
-        }
-        pipeline.close();
-        results
-    }
-    /// Execute the input query on the given data, assuming a fixed schema.
-    ///
-    /// `id, name, role, salary`.
-    pub fn plan(&mut self, query: Query, data: Vec<Row>) -> Box<dyn Operator> {
-        // Start with the Scan operator
-        let mut pipeline: Box<dyn Operator> = Box::new(Scan::new(&data));
-
-        // Extract query details
-        match query {
-            Query::Select {
-                columns,
-                table: _,
-                filter,
-                order_by,
-                limit,
-            } => {
-                // Apply the Filter operator if specified
-                if let Some(expr) = filter {
-                    let filter = move |row: &Row| Self::eval(expr.clone(), row);
-                    pipeline = Box::new(Filter::new(pipeline, filter));
-                }
-
-                // Apply the Sort operator if specified
-                if let Some(ref column) = order_by {
-                    let column_index: usize = match column.as_str() {
-                        "id" => 0,
-                        "name" => 1,
-                        "role" => 2,
-                        "salary" => 3,
-                        _ => unreachable!("expected column name to follow hardcoded schema"),
-                    };
-                    let sort_fn: Comparator =
-                        Box::new(move |a, b| a.get(column_index).cmp(&b.get(column_index)));
-                    pipeline = Box::new(Sort::new(pipeline, sort_fn));
-                }
-
-                // Apply the Limit operator if specified
-                if let Some(limit) = limit {
-                    pipeline = Box::new(Limit::new(pipeline, limit as usize));
-                }
-
-                // Apply the Project operator to select the desired columns
-                let column_indices = columns
-                    .iter()
-                    .map(|col| match col.as_str() {
-                        "id" => 0,
-                        "name" => 1,
-                        "role" => 2,
-                        "salary" => 3,
-                        _ => unreachable!("expected column name to follow hardcoded schema"),
-                    })
-                    .collect::<Vec<_>>();
-                pipeline = Box::new(Project::new(pipeline, &column_indices));
-            }
-        }
-
-        pipeline
-    }
-
-    fn resolve(expr: &Expr, row: &Row) -> String {
-        match expr {
-            Expr::Column(column) => match column.as_str() {
-                "id" => row.get(0).unwrap().to_string(),
-                "name" => row.get(1).unwrap().to_string(),
-                "role" => row.get(2).unwrap().to_string(),
-                "salary" => row.get(3).unwrap().to_string(),
-                _ => unreachable!("expected column name to follow hardcoded schema got {column}"),
-            },
-            Expr::Value(value) => value.to_string(),
-            Expr::Varchar(varchar) => varchar.clone(),
-            _ => todo!("Unimplemented resolver for expression {:?}", expr),
-        }
-    }
-
-    fn eval(expr: Expr, row: &Row) -> bool {
-        match expr {
-            // Not sure if this make sense for columns :/.
-            Expr::Column(_) => true,
-            Expr::Varchar(_) => true,
-            Expr::Value(_) => true,
-            Expr::And(left, right) => Self::eval(*left, row) && Self::eval(*right, row),
-            Expr::Or(left, right) => Self::eval(*left, row) || Self::eval(*right, row),
-            Expr::Comparison(left, op, right) => {
-                let left_value = Self::resolve(&left, row);
-                let right_value = Self::resolve(&right, row);
-                match op.as_str() {
-                    ">" => left_value.parse::<i64>().unwrap() > right_value.parse::<i64>().unwrap(),
-                    "<" => left_value.parse::<i64>().unwrap() < right_value.parse::<i64>().unwrap(),
-                    "=" => left_value == right_value,
-                    _ => false,
-                }
-            }
-            _ => todo!("Unimplemented evaluator for expression {:?}", expr),
-        }
+// Runs a script on the database, panicking on errors which the examples are
+// not expected to raise.
+fn query(database: &mut Database, sql: &str) -> Vec<Row> {
+    match database.query(sql) {
+        Ok(result) => result.rows,
+        Err(error) => panic!("{error}"),
     }
 }
 
-macro_rules! query {
-    ($query_str:expr, $data:expr) => {{
-        let tokenizer = Tokenizer::new($query_str);
-        let q = Parser::new(tokenizer).parse();
-
-        let mut executor = QueryExecutor {};
-        let plan = executor.plan(q, $data);
-        QueryExecutor::execute(plan)
-    }};
-}
-
 fn main() {
     // Example data
-    let data = vec![
-        Row::new(&[
-            "1".to_string(),
-            "Alice".to_string(),
-            "Manager".to_string(),
-            "12000".to_string(),
-        ]),
-        Row::new(&[
-            "2".to_string(),
-            "Bob".to_string(),
-            "Developer".to_string(),
-            "10000".to_string(),
-        ]),
-        Row::new(&[
-            "3".to_string(),
-            "Charlie".to_string(),
-            "Developer".to_string(),
-            "9000".to_string(),
-        ]),
-        Row::new(&[
-            "4".to_string(),
-            "David".to_string(),
-            "Analyst".to_string(),
-            "11000".to_string(),
-        ]),
-        Row::new(&[
-            "5".to_string(),
-            "Eve".to_string(),
-            "Manager".to_string(),
-            "13000".to_string(),
-        ]),
-        Row::new(&[
-            "6".to_string(),
-            "Frank".to_string(),
-            "Developer".to_string(),
-            "9500".to_string(),
-        ]),
-        Row::new(&[
-            "7".to_string(),
-            "Grace".to_string(),
-            "Analyst".to_string(),
-            "10500".to_string(),
-        ]),
-        Row::new(&[
-            "8".to_string(),
-            "Hannah".to_string(),
-            "Developer".to_string(),
-            "9800".to_string(),
-        ]),
-        Row::new(&[
-            "9".to_string(),
-            "Ivy".to_string(),
-            "Manager".to_string(),
-            "12500".to_string(),
-        ]),
-        Row::new(&[
-            "10".to_string(),
-            "Jack".to_string(),
-            "Analyst".to_string(),
-            "10200".to_string(),
-        ]),
-    ];
+    let mut database = Database::new();
+    query(
+        &mut database,
+        "CREATE TABLE employees (
+             id INTEGER PRIMARY KEY,
+             name VARCHAR NOT NULL,
+             role VARCHAR,
+             salary INTEGER,
+             hire_date DATE
+         );
+         INSERT INTO employees VALUES
+             (1, 'Alice', 'Manager', 12000, DATE '2015-03-01'),
+             (2, 'Bob', 'Developer', 10000, DATE '2018-07-15'),
+             (3, 'Charlie', 'Developer', 9000, DATE '2020-01-31'),
+             (4, 'David', 'Analyst', 11000, DATE '2019-11-04'),
+             (5, 'Eve', 'Manager', 13000, DATE '2012-05-20'),
+             (6, 'Frank', 'Developer', 9500, DATE '2021-09-01'),
+             (7, 'Grace', 'Analyst', 10500, DATE '2017-02-28'),
+             (8, 'Hannah', 'Developer', 9800, DATE '2022-06-13'),
+             (9, 'Ivy', 'Manager', 12500, DATE '2014-10-01'),
+             (10, 'Jack', 'Analyst', 10200, DATE '2016-08-22')",
+    );
 
     let queries = vec![
         (
-            "SELECT id FROM example WHERE name = 'Ivy' LIMIT 1",
-            vec![Row::new(&["9".to_string()])],
+            "SELECT id FROM employees WHERE name = 'Ivy' LIMIT 1",
+            vec![Row::new(&[Value::Integer(9)])],
         ),
         (
             "SELECT name FROM employees WHERE role = 'Developer'",
@@ -211,26 +55,196 @@ fn main() {
         (
             "SELECT id FROM employees WHERE salary > 9000 LIMIT 3",
             vec![
-                Row::new(&["1".to_string()]),
-                Row::new(&["2".to_string()]),
-                Row::new(&["4".to_string()]),
+                Row::new(&[Value::Integer(1)]),
+                Row::new(&[Value::Integer(2)]),
+                Row::new(&[Value::Integer(4)]),
             ],
         ),
         (
             "SELECT id FROM employees WHERE salary > 10000 ORDERBY name",
             vec![
-                Row::new(&["1".to_string()]),
-                Row::new(&["4".to_string()]),
-                Row::new(&["5".to_string()]),
-                Row::new(&["7".to_string()]),
-                Row::new(&["9".to_string()]),
-                Row::new(&["10".to_string()]),
+                Row::new(&[Value::Integer(1)]),
+                Row::new(&[Value::Integer(4)]),
+                Row::new(&[Value::Integer(5)]),
+                Row::new(&[Value::Integer(7)]),
+                Row::new(&[Value::Integer(9)]),
+                Row::new(&[Value::Integer(10)]),
             ],
         ),
     ];
-    for query in queries {
-        let results = query!(query.0, data.clone());
-        let expected = query.1;
-        assert_eq!(results, expected);
+    for (sql, expected) in queries {
+        assert_eq!(query(&mut database, sql), expected);
+    }
+
+    // Aggregates compute one row per group, means of integers are exact.
+    let mean = |text| Value::decode(text, DataType::Decimal(38, 6)).unwrap();
+    let results = query(
+        &mut database,
+        "SELECT role, count(*), avg(salary) FROM employees GROUP BY role ORDER BY role",
+    );
+    assert_eq!(
+        results,
+        vec![
+            Row::new(&[
+                Value::from("Analyst"),
+                Value::BigInt(3),
+                mean("10566.666667")
+            ]),
+            Row::new(&[
+                Value::from("Developer"),
+                Value::BigInt(4),
+                mean("9575.000000")
+            ]),
+            Row::new(&[
+                Value::from("Manager"),
+                Value::BigInt(3),
+                mean("12500.000000")
+            ]),
+        ]
+    );
+
+    // Scalar functions registered from Rust are callable by name from SQL.
+    database.register_function("band", &[DataType::Integer], DataType::Varchar, band);
+    let results = query(
+        &mut database,
+        "SELECT name, band(salary) FROM employees WHERE band(salary) = 'senior' ORDERBY name",
+    );
+    assert_eq!(
+        results,
+        vec![
+            Row::new(&["Eve".to_string(), "senior".to_string()]),
+            Row::new(&["Ivy".to_string(), "senior".to_string()]),
+        ]
+    );
+
+    // Aggregates are registered from Rust by implementing `Accumulator`.
+    database.register_aggregate::<WeightedAvg>(
+        "wavg",
+        &[DataType::Integer, DataType::Integer],
+        DataType::Integer,
+    );
+    let results = query(
+        &mut database,
+        "SELECT wavg(salary, id) FROM employees WHERE role = 'Manager'",
+    );
+    assert_eq!(results, vec![Row::new(&[Value::Integer(12633)])]);
+
+    // Table functions produce rows in the FROM clause and LATERAL calls can
+    // reference columns of the preceding items.
+    let results = query(
+        &mut database,
+        "SELECT e.name, s.n FROM employees e, LATERAL generate_series(1, e.id) AS s(n) WHERE e.id < 3",
+    );
+    assert_eq!(
+        results,
+        vec![
+            Row::new(&[Value::from("Alice"), Value::Integer(1)]),
+            Row::new(&[Value::from("Bob"), Value::Integer(1)]),
+            Row::new(&[Value::from("Bob"), Value::Integer(2)]),
+        ]
+    );
+
+    // Temporal values support literals, interval arithmetic and fields.
+    let results = query(
+        &mut database,
+        "SELECT name, hire_date + INTERVAL '1 year', EXTRACT(DOW FROM hire_date) FROM employees WHERE hire_date > DATE '2020-01-01' ORDER BY hire_date",
+    );
+    assert_eq!(
+        results,
+        vec![
+            Row::new(&[
+                Value::from("Charlie"),
+                Value::decode("2021-01-31 00:00:00", DataType::Timestamp).unwrap(),
+                Value::BigInt(5),
+            ]),
+            Row::new(&[
+                Value::from("Frank"),
+                Value::decode("2022-09-01 00:00:00", DataType::Timestamp).unwrap(),
+                Value::BigInt(3),
+            ]),
+            Row::new(&[
+                Value::from("Hannah"),
+                Value::decode("2023-06-13 00:00:00", DataType::Timestamp).unwrap(),
+                Value::BigInt(1),
+            ]),
+        ]
+    );
+
+    // DDL statements create tables in the catalog which later queries read,
+    // DML statements modify their rows.
+    let results = query(
+        &mut database,
+        "CREATE TABLE managers AS SELECT id, name FROM employees WHERE role = 'Manager';
+         ALTER TABLE managers ADD COLUMN reports INTEGER NOT NULL DEFAULT 0;
+         INSERT INTO managers (id, name) VALUES (11, 'Kate');
+         UPDATE managers SET reports = 3 WHERE name = 'Eve';
+         DELETE FROM managers WHERE id = 9;
+         SELECT name, reports FROM managers ORDER BY name",
+    );
+    assert_eq!(
+        results,
+        vec![
+            Row::new(&[Value::from("Alice"), Value::Integer(0)]),
+            Row::new(&[Value::from("Eve"), Value::Integer(3)]),
+            Row::new(&[Value::from("Kate"), Value::Integer(0)]),
+        ]
+    );
+
+    // Invalid statements are reported as errors.
+    let error = database.query("SELECT id FROM example").unwrap_err();
+    assert_eq!(error.to_string(), "Unknown table example");
+}
+
+/// Mean of values weighted by a second argument, used to demonstrate
+/// aggregate functions.
+#[derive(Default)]
+struct WeightedAvg {
+    sum: i64,
+    weights: i64,
+}
+
+impl Accumulator for WeightedAvg {
+    fn init() -> Self {
+        Self::default()
+    }
+
+    fn update(&mut self, args: &[Value]) -> Result<(), ExecutionError> {
+        match args {
+            [Value::Integer(value), Value::Integer(weight)] => self.merge(Self {
+                sum: *value as i64 * *weight as i64,
+                weights: *weight as i64,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn merge(&mut self, other: Self) -> Result<(), ExecutionError> {
+        let overflow = || ArithmeticError::Overflow(DataType::BigInt);
+        self.sum = self.sum.checked_add(other.sum).ok_or_else(overflow)?;
+        self.weights = self
+            .weights
+            .checked_add(other.weights)
+            .ok_or_else(overflow)?;
+        Ok(())
+    }
+
+    fn finalize(&self) -> Result<Value, ExecutionError> {
+        if self.weights == 0 {
+            Ok(Value::Null)
+        } else {
+            Ok(Value::Integer((self.sum / self.weights) as i32))
+        }
+    }
+}
+
+/// Classify salaries into bands, used to demonstrate scalar functions.
+fn band(args: &[Value]) -> Result<Value, ExecutionError> {
+    match args {
+        [Value::Integer(salary)] if *salary > 12000 => Ok(Value::from("senior")),
+        [Value::Integer(salary)] if *salary > 10000 => Ok(Value::from("confirmed")),
+        [_] => Ok(Value::from("junior")),
+        _ => Err(ExecutionError::Function(
+            "band expects 1 argument".to_string(),
+        )),
     }
 }
@@ -1,15 +1,301 @@
-//! Implementation of filter, project and scan operators.
+//! Implementation of filter, project, map, aggregate, join and scan operators.
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::functions::{AccumulatorState, AggregateFunction};
 use crate::row::Row;
+use crate::schema::Schema;
+use crate::temporal::DateTimeField;
+use crate::types::{ArithmeticError, DataType, Value};
+
+/// Errors raised while executing operators, when the expressions they
+/// evaluate fail on the values of a row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionError {
+    Arithmetic(ArithmeticError),
+    /// Value without a representation in the type it is cast to.
+    InvalidCast {
+        value: Value,
+        data_type: DataType,
+    },
+    /// Field which is not defined for the value it is extracted from.
+    InvalidField {
+        field: DateTimeField,
+        value: Value,
+    },
+    /// Function which cannot compute a value from its arguments, holding
+    /// the message describing why.
+    Function(String),
+    /// Expression which cannot be evaluated, such as a column reference
+    /// which was not bound, holding the message describing why.
+    InvalidExpression(String),
+}
+
+impl fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecutionError::Arithmetic(error) => write!(f, "{error}"),
+            ExecutionError::InvalidCast { value, data_type } => {
+                write!(f, "Cannot cast {value} to {data_type}")
+            }
+            ExecutionError::InvalidField { field, value } => {
+                write!(f, "Cannot extract {field} from {value}")
+            }
+            ExecutionError::Function(message) | ExecutionError::InvalidExpression(message) => {
+                write!(f, "{message}")
+            }
+        }
+    }
+}
+
+impl Error for ExecutionError {}
+
+impl From<ArithmeticError> for ExecutionError {
+    fn from(error: ArithmeticError) -> Self {
+        ExecutionError::Arithmetic(error)
+    }
+}
 
 /// The operator trait describes the interface Volcano style operators must
-/// implement.
+/// implement. Opening an operator and reading its rows fail when evaluating
+/// an expression on a row fails.
 pub trait Operator {
     // Open the iterator for consumption.
-    fn open(&mut self);
+    fn open(&mut self) -> Result<(), ExecutionError>;
     // Next returns the next row if one is available otherwise `None`.
-    fn next(&mut self) -> Option<Row>;
+    fn next(&mut self) -> Result<Option<Row>, ExecutionError>;
     // Close the iterator signaling we won't be consuming from it anymore.
     fn close(&self);
+    // Describe returns the name of the operator along with the parameters it
+    // was created with, as shown by `EXPLAIN`.
+    fn describe(&self) -> Description {
+        let name = std::any::type_name::<Self>();
+        let name = name.split('<').next().unwrap_or(name);
+        Description::new(name.rsplit("::").next().unwrap_or(name))
+    }
+    // Inputs returns the operators rows are consumed from.
+    fn inputs(&self) -> Vec<&dyn Operator> {
+        vec![]
+    }
+    // Metrics returns the metrics of the execution of the operator when they
+    // are collected, see `Annotated::with_metrics`.
+    fn metrics(&self) -> Option<Metrics> {
+        None
+    }
+    // Peak memory returns the approximate peak number of bytes of the rows
+    // the operator materialized, none for operators which don't materialize
+    // rows.
+    fn peak_memory(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Metrics of the execution of an operator, shown by `EXPLAIN ANALYZE`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Number of rows the operator produced.
+    pub rows: usize,
+    /// Number of calls to `next`, including the last one producing no row.
+    pub calls: usize,
+    /// Time spent opening the operator and producing its rows, including the
+    /// time spent in its inputs.
+    pub time: Duration,
+}
+
+/// Description of an operator made of its name and of its parameters, each
+/// of which is a name and a value, along with the schema and the estimated
+/// number of its rows when the planner annotated the operator with them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Description {
+    pub name: String,
+    pub parameters: Vec<(String, String)>,
+    pub schema: Option<Schema>,
+    pub estimated_rows: Option<f64>,
+}
+
+impl Description {
+    /// Creates the description of an operator without parameters.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Append a parameter to the description.
+    pub fn with(mut self, name: &str, value: impl ToString) -> Self {
+        self.parameters.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+impl fmt::Display for Description {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        for (i, (name, value)) in self.parameters.iter().enumerate() {
+            let separator = if i == 0 { " (" } else { ", " };
+            write!(f, "{separator}{name}: {value}")?;
+        }
+        if !self.parameters.is_empty() {
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the lines describing a tree of operators, each operator followed
+/// by its inputs indented below it. Operators whose metrics were collected
+/// are described along with them, their exclusive time excluding the time
+/// spent in their inputs.
+pub fn explain(operator: &dyn Operator) -> Vec<String> {
+    let mut description = operator.describe();
+    if let Some(rows) = description.estimated_rows {
+        description = description.with("estimated rows", format!("{rows:.0}"));
+    }
+    if let Some(metrics) = operator.metrics() {
+        let inputs = operator
+            .inputs()
+            .iter()
+            .filter_map(|input| input.metrics())
+            .map(|metrics| metrics.time)
+            .sum();
+        let milliseconds = |time: Duration| format!("{:.3} ms", time.as_secs_f64() * 1000.0);
+        description = description
+            .with("rows", metrics.rows)
+            .with("calls", metrics.calls)
+            .with("time", milliseconds(metrics.time))
+            .with(
+                "self time",
+                milliseconds(metrics.time.saturating_sub(inputs)),
+            );
+        if let Some(memory) = operator.peak_memory() {
+            description = description.with("peak memory", format!("{memory} bytes"));
+        }
+    }
+    let mut lines = vec![description.to_string()];
+    for input in operator.inputs() {
+        lines.extend(explain(input).into_iter().map(|line| format!("  {line}")));
+    }
+    lines
+}
+
+/// Annotated operator returns the rows of another operator whose description
+/// it extends with parameters the operator cannot describe itself, such as
+/// the expressions its functions evaluate. It can also collect the metrics of
+/// the execution of the operator.
+pub struct Annotated {
+    operator: Box<dyn Operator>,
+    parameters: Vec<(String, String)>,
+    schema: Option<Schema>,
+    estimated_rows: Option<f64>,
+    metrics: Option<Metrics>,
+}
+
+impl Annotated {
+    /// Creates a new `Annotated` operator adding the given parameters to the
+    /// description of the operator.
+    pub fn new(operator: Box<dyn Operator>, parameters: Vec<(String, String)>) -> Self {
+        Self {
+            operator,
+            parameters,
+            schema: None,
+            estimated_rows: None,
+            metrics: None,
+        }
+    }
+
+    /// Describe the operator along with the schema of its rows.
+    pub fn with_schema(mut self, schema: Schema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Describe the operator along with its estimated number of rows.
+    pub fn with_estimated_rows(mut self, rows: f64) -> Self {
+        self.estimated_rows = Some(rows);
+        self
+    }
+
+    /// Collect the metrics of the execution of the operator, which requires
+    /// measuring the time spent in each call.
+    pub fn with_metrics(mut self) -> Self {
+        self.metrics = Some(Metrics::default());
+        self
+    }
+}
+
+impl Operator for Annotated {
+    fn open(&mut self) -> Result<(), ExecutionError> {
+        let Some(metrics) = &mut self.metrics else {
+            return self.operator.open();
+        };
+        let start = Instant::now();
+        let result = self.operator.open();
+        metrics.time += start.elapsed();
+        result
+    }
+
+    fn next(&mut self) -> Result<Option<Row>, ExecutionError> {
+        let Some(metrics) = &mut self.metrics else {
+            return self.operator.next();
+        };
+        let start = Instant::now();
+        let row = self.operator.next();
+        metrics.time += start.elapsed();
+        metrics.calls += 1;
+        metrics.rows += usize::from(matches!(row, Ok(Some(_))));
+        row
+    }
+
+    fn close(&self) {
+        self.operator.close();
+    }
+
+    fn describe(&self) -> Description {
+        let mut description = self.operator.describe();
+        description
+            .parameters
+            .extend(self.parameters.iter().cloned());
+        description.schema = self.schema.clone().or(description.schema);
+        description.estimated_rows = self.estimated_rows.or(description.estimated_rows);
+        description
+    }
+
+    fn inputs(&self) -> Vec<&dyn Operator> {
+        self.operator.inputs()
+    }
+
+    fn metrics(&self) -> Option<Metrics> {
+        self.metrics
+    }
+
+    fn peak_memory(&self) -> Option<usize> {
+        self.operator.peak_memory()
+    }
+}
+
+// Returns the approximate number of bytes values hold.
+fn memory<'a>(values: impl IntoIterator<Item = &'a Value>) -> usize {
+    values
+        .into_iter()
+        .map(|value| {
+            size_of::<Value>()
+                + match value {
+                    Value::Varchar(varchar) => varchar.len(),
+                    Value::Array(values) => memory(values),
+                    _ => 0,
+                }
+        })
+        .sum()
+}
+
+// Returns the approximate number of bytes rows hold.
+fn rows_memory<'a>(rows: impl IntoIterator<Item = &'a Row>) -> usize {
+    rows.into_iter()
+        .map(|row| size_of::<Row>() + memory(&row.items))
+        .sum()
 }
 
 /// Projection operator returns the projected column from a row.
@@ -30,24 +316,36 @@ impl Project {
 }
 
 impl Operator for Project {
-    fn open(&mut self) {}
+    fn open(&mut self) -> Result<(), ExecutionError> {
+        self.input.open()
+    }
 
-    fn next(&mut self) -> Option<Row> {
-        match self.input.next() {
+    fn next(&mut self) -> Result<Option<Row>, ExecutionError> {
+        match self.input.next()? {
             Some(row) => {
                 let columns = self
                     .columns
                     .iter()
-                    .filter_map(|&col| Some(row.get(col)?.to_string()))
+                    .filter_map(|&col| row.get(col).cloned())
                     .collect::<Vec<_>>();
 
-                Some(Row::new(&columns))
+                Ok(Some(Row::new(&columns)))
             }
-            None => None,
+            None => Ok(None),
         }
     }
 
-    fn close(&self) {}
+    fn close(&self) {
+        self.input.close();
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("Project").with("columns", format!("{:?}", self.columns))
+    }
+
+    fn inputs(&self) -> Vec<&dyn Operator> {
+        vec![self.input.as_ref()]
+    }
 }
 
 /// Scan operator returns a batch of rows, scan is always the first operator
@@ -58,6 +356,7 @@ pub struct Scan {
 
 impl Scan {
     /// Create a new `Scan` operator over a batch of rows.
+    #[allow(clippy::unnecessary_to_owned)]
     pub fn new(rows: &[Row]) -> Self {
         Self {
             rows: rows.to_vec().into_iter(),
@@ -66,19 +365,25 @@ impl Scan {
 }
 
 impl Operator for Scan {
-    fn open(&mut self) {}
+    fn open(&mut self) -> Result<(), ExecutionError> {
+        Ok(())
+    }
 
-    fn next(&mut self) -> Option<Row> {
-        self.rows.next()
+    fn next(&mut self) -> Result<Option<Row>, ExecutionError> {
+        Ok(self.rows.next())
     }
 
     fn close(&self) {}
+
+    fn describe(&self) -> Description {
+        Description::new("Scan")
+    }
 }
 
 /// Filter operator returns the next row that matches the predicate.
 pub struct Filter<F>
 where
-    F: FnOnce(&Row) -> bool,
+    F: FnOnce(&Row) -> Result<bool, ExecutionError>,
 {
     input: Box<dyn Operator>,
     predicate: F,
@@ -86,7 +391,7 @@ where
 
 impl<F> Filter<F>
 where
-    F: FnOnce(&Row) -> bool + 'static,
+    F: FnOnce(&Row) -> Result<bool, ExecutionError> + 'static,
 {
     /// Creates a new `Filter` operator with the given input upstream operator
     /// and predicate function.
@@ -100,24 +405,84 @@ where
 
 impl<F> Operator for Filter<F>
 where
-    F: Fn(&Row) -> bool + 'static,
+    F: Fn(&Row) -> Result<bool, ExecutionError> + 'static,
 {
-    fn open(&mut self) {
-        self.input.open();
+    fn open(&mut self) -> Result<(), ExecutionError> {
+        self.input.open()
     }
 
-    fn next(&mut self) -> Option<Row> {
-        while let Some(row) = self.input.next() {
-            if (self.predicate)(&row) {
-                return Some(row);
+    fn next(&mut self) -> Result<Option<Row>, ExecutionError> {
+        while let Some(row) = self.input.next()? {
+            if (self.predicate)(&row)? {
+                return Ok(Some(row));
             }
         }
-        None
+        Ok(None)
     }
 
     fn close(&self) {
         self.input.close();
     }
+
+    fn describe(&self) -> Description {
+        Description::new("Filter")
+    }
+
+    fn inputs(&self) -> Vec<&dyn Operator> {
+        vec![self.input.as_ref()]
+    }
+}
+
+/// Map operator transforms each row using a function, it is used to compute
+/// expressions over the columns of its input.
+pub struct Map<F>
+where
+    F: Fn(&Row) -> Result<Row, ExecutionError>,
+{
+    input: Box<dyn Operator>,
+    function: F,
+}
+
+impl<F> Map<F>
+where
+    F: Fn(&Row) -> Result<Row, ExecutionError> + 'static,
+{
+    /// Creates a new `Map` operator with the given input upstream operator
+    /// and mapping function.
+    pub fn new(operator: Box<dyn Operator>, function: F) -> Self {
+        Self {
+            input: operator,
+            function,
+        }
+    }
+}
+
+impl<F> Operator for Map<F>
+where
+    F: Fn(&Row) -> Result<Row, ExecutionError> + 'static,
+{
+    fn open(&mut self) -> Result<(), ExecutionError> {
+        self.input.open()
+    }
+
+    fn next(&mut self) -> Result<Option<Row>, ExecutionError> {
+        self.input
+            .next()?
+            .map(|row| (self.function)(&row))
+            .transpose()
+    }
+
+    fn close(&self) {
+        self.input.close();
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("Map")
+    }
+
+    fn inputs(&self) -> Vec<&dyn Operator> {
+        vec![self.input.as_ref()]
+    }
 }
 
 /// Limit operator returns the next n-rows.
@@ -139,72 +504,286 @@ impl Limit {
 }
 
 impl Operator for Limit {
-    fn open(&mut self) {
-        self.input.open();
+    fn open(&mut self) -> Result<(), ExecutionError> {
+        self.input.open()
     }
 
-    fn next(&mut self) -> Option<Row> {
+    fn next(&mut self) -> Result<Option<Row>, ExecutionError> {
         if self.count < self.limit {
-            if let Some(row) = self.input.next() {
+            if let Some(row) = self.input.next()? {
                 self.count += 1;
-                return Some(row);
+                return Ok(Some(row));
             }
         }
-        None
+        Ok(None)
     }
 
     fn close(&self) {
         self.input.close();
     }
+
+    fn describe(&self) -> Description {
+        Description::new("Limit").with("limit", self.limit)
+    }
+
+    fn inputs(&self) -> Vec<&dyn Operator> {
+        vec![self.input.as_ref()]
+    }
 }
 
-/// Sort operator sorts the rows and returns them in sorted order.
+/// Sort keys are the values rows are sorted by, they are evaluated once per
+/// row.
+pub type SortKey = dyn Fn(&Row) -> Result<Value, ExecutionError>;
+
+/// Sort operator sorts the rows by key and returns them in sorted order, rows
+/// with equal keys keep the order of the input. Like in PostgreSQL, `NULL`
+/// keys sort after every other key.
+///
+/// Rows are materialized and sorted when the first row is requested.
 pub struct Sort {
-    sorted_rows: std::vec::IntoIter<Row>,
+    input: Box<dyn Operator>,
+    key: Box<SortKey>,
+    sorted_rows: Option<std::vec::IntoIter<Row>>,
+    memory: usize,
 }
 
 impl Sort {
-    pub fn new<Compare: Fn(&Row, &Row) -> std::cmp::Ordering>(
-        mut input: Box<dyn Operator>,
-        cmp: Compare,
+    pub fn new<K: Fn(&Row) -> Result<Value, ExecutionError> + 'static>(
+        input: Box<dyn Operator>,
+        key: K,
     ) -> Self {
-        let mut rows: Vec<Row> = vec![];
-        while let Some(row) = input.next() {
-            rows.push(row);
-        }
-        rows.sort_by(&cmp);
         Self {
-            sorted_rows: rows.into_iter(),
+            input,
+            key: Box::new(key),
+            sorted_rows: None,
+            memory: 0,
         }
     }
 }
 
 impl Operator for Sort {
-    fn open(&mut self) {}
+    fn open(&mut self) -> Result<(), ExecutionError> {
+        self.input.open()
+    }
 
-    fn next(&mut self) -> Option<Row> {
-        self.sorted_rows.next()
+    fn next(&mut self) -> Result<Option<Row>, ExecutionError> {
+        if self.sorted_rows.is_none() {
+            let mut rows: Vec<(Value, Row)> = vec![];
+            while let Some(row) = self.input.next()? {
+                rows.push(((self.key)(&row)?, row));
+            }
+            rows.sort_by(|(a, _), (b, _)| a.is_null().cmp(&b.is_null()).then_with(|| a.cmp(b)));
+            let rows = rows.into_iter().map(|(_, row)| row).collect::<Vec<_>>();
+            self.memory = self.memory.max(rows_memory(&rows));
+            self.sorted_rows = Some(rows.into_iter());
+        }
+        Ok(self.sorted_rows.as_mut().and_then(|rows| rows.next()))
     }
 
-    fn close(&self) {}
+    fn close(&self) {
+        self.input.close();
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("Sort")
+    }
+
+    fn inputs(&self) -> Vec<&dyn Operator> {
+        vec![self.input.as_ref()]
+    }
+
+    fn peak_memory(&self) -> Option<usize> {
+        Some(self.memory)
+    }
+}
+
+/// Evaluates one of the keys rows are grouped by.
+pub type GroupKey = dyn Fn(&Row) -> Result<Value, ExecutionError>;
+
+/// Evaluates the arguments of an aggregate function.
+pub type AggregateArgs = dyn Fn(&Row) -> Result<Vec<Value>, ExecutionError>;
+
+/// Aggregate expressions pair an aggregate function with the function used
+/// to evaluate its arguments on input rows.
+pub struct AggregateExpr {
+    function: AggregateFunction,
+    args: Box<AggregateArgs>,
+}
+
+impl AggregateExpr {
+    pub fn new<F>(function: AggregateFunction, args: F) -> Self
+    where
+        F: Fn(&Row) -> Result<Vec<Value>, ExecutionError> + 'static,
+    {
+        Self {
+            function,
+            args: Box::new(args),
+        }
+    }
+}
+
+/// Aggregate operator groups rows by their keys and returns one row per group
+/// made of the keys followed by the value of each aggregate.
+///
+/// Without any keys all rows belong to a single group, which exists even if
+/// the input is empty.
+pub struct Aggregate {
+    input: Box<dyn Operator>,
+    group_by: Vec<Box<GroupKey>>,
+    aggregates: Vec<AggregateExpr>,
+    partitions: usize,
+    groups: std::vec::IntoIter<Row>,
+    memory: usize,
+}
+
+impl Aggregate {
+    /// Creates a new `Aggregate` operator with the given input operator,
+    /// grouping keys and aggregates.
+    pub fn new(
+        operator: Box<dyn Operator>,
+        group_by: Vec<Box<GroupKey>>,
+        aggregates: Vec<AggregateExpr>,
+    ) -> Self {
+        Self {
+            input: operator,
+            group_by,
+            aggregates,
+            partitions: 1,
+            groups: vec![].into_iter(),
+            memory: 0,
+        }
+    }
+
+    /// Distribute input rows over the given number of partitions which are
+    /// aggregated independently before their partial states are merged.
+    ///
+    /// Rows are dealt round-robin and every partition is aggregated on the
+    /// current thread, so partitions exercise the merge path of accumulators
+    /// as partial aggregation would without running in parallel.
+    pub fn with_partitions(mut self, partitions: usize) -> Self {
+        self.partitions = partitions.max(1);
+        self
+    }
+
+    fn accumulators(&self) -> Vec<Box<dyn AccumulatorState>> {
+        self.aggregates
+            .iter()
+            .map(|aggregate| aggregate.function.accumulator())
+            .collect()
+    }
+}
+
+impl Operator for Aggregate {
+    fn open(&mut self) -> Result<(), ExecutionError> {
+        self.input.open()?;
+
+        // Groups are returned in the order they are first seen.
+        let mut keys: Vec<Row> = vec![];
+        let mut seen = HashSet::new();
+        let mut partitions: Vec<HashMap<Row, Vec<Box<dyn AccumulatorState>>>> =
+            (0..self.partitions).map(|_| HashMap::new()).collect();
+        let mut index = 0;
+        while let Some(row) = self.input.next()? {
+            let key = Row {
+                items: self
+                    .group_by
+                    .iter()
+                    .map(|key| key(&row))
+                    .collect::<Result<_, _>>()?,
+            };
+            if seen.insert(key.clone()) {
+                keys.push(key.clone());
+            }
+            let states = partitions[index % self.partitions]
+                .entry(key)
+                .or_insert_with(|| self.accumulators());
+            for (aggregate, state) in self.aggregates.iter().zip(states.iter_mut()) {
+                aggregate
+                    .function
+                    .update(state.as_mut(), &(aggregate.args)(&row)?)?;
+            }
+            index += 1;
+        }
+        // The partial states of every partition are held until merged.
+        let states = partitions
+            .iter()
+            .flatten()
+            .map(|(key, states)| {
+                rows_memory([key]) + states.iter().map(|state| state.memory()).sum::<usize>()
+            })
+            .sum::<usize>();
+        self.memory = self.memory.max(rows_memory(&keys) + states);
+        if keys.is_empty() && self.group_by.is_empty() {
+            keys.push(Row { items: vec![] });
+        }
+
+        let mut groups = vec![];
+        for key in keys {
+            let mut states = self.accumulators();
+            for partition in partitions.iter_mut() {
+                if let Some(partial) = partition.remove(&key) {
+                    for (state, partial) in states.iter_mut().zip(partial) {
+                        state.merge(partial)?;
+                    }
+                }
+            }
+            let mut row = key;
+            for state in &states {
+                row.items.push(state.finalize()?);
+            }
+            groups.push(row);
+        }
+        self.memory = self.memory.max(rows_memory(&groups));
+        self.groups = groups.into_iter();
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<Row>, ExecutionError> {
+        Ok(self.groups.next())
+    }
+
+    fn close(&self) {
+        self.input.close();
+    }
+
+    fn describe(&self) -> Description {
+        let description = Description::new("Aggregate");
+        if self.partitions > 1 {
+            description.with("partitions", self.partitions)
+        } else {
+            description
+        }
+    }
+
+    fn inputs(&self) -> Vec<&dyn Operator> {
+        vec![self.input.as_ref()]
+    }
+
+    fn peak_memory(&self) -> Option<usize> {
+        Some(self.memory)
+    }
 }
 
+/// Join conditions are predicates over a pair of rows from both join inputs.
+pub type JoinCondition = dyn Fn(&Row, &Row) -> Result<bool, ExecutionError>;
+
 /// The Join operator combines rows from two input operators based on a join condition.
 pub struct Join {
     left: Box<dyn Operator>,
     right: Box<dyn Operator>,
-    join_condition: Box<dyn Fn(&Row, &Row) -> bool>,
+    join_condition: Box<JoinCondition>,
     left_rows: Vec<Row>,
     right_rows: Vec<Row>,
     left_index: usize,
     right_index: usize,
+    memory: usize,
 }
 
 impl Join {
     /// Creates a new `Join` operator.
     pub fn new<F>(left: Box<dyn Operator>, right: Box<dyn Operator>, join_condition: F) -> Self
     where
-        F: Fn(&Row, &Row) -> bool + 'static,
+        F: Fn(&Row, &Row) -> Result<bool, ExecutionError> + 'static,
     {
         Self {
             left,
@@ -214,42 +793,50 @@ impl Join {
             right_rows: vec![],
             left_index: 0,
             right_index: 0,
+            memory: 0,
         }
     }
 
-    fn load_left_rows(&mut self) {
-        while let Some(row) = self.left.next() {
+    fn load_left_rows(&mut self) -> Result<(), ExecutionError> {
+        while let Some(row) = self.left.next()? {
             self.left_rows.push(row);
         }
+        Ok(())
     }
 
-    fn load_right_rows(&mut self) {
-        while let Some(row) = self.right.next() {
+    fn load_right_rows(&mut self) -> Result<(), ExecutionError> {
+        while let Some(row) = self.right.next()? {
             self.right_rows.push(row);
         }
+        Ok(())
     }
 }
 
 impl Operator for Join {
-    fn open(&mut self) {
-        self.load_left_rows();
-        self.load_right_rows();
+    fn open(&mut self) -> Result<(), ExecutionError> {
+        self.left.open()?;
+        self.right.open()?;
+        self.load_left_rows()?;
+        self.load_right_rows()?;
+        let rows = self.left_rows.iter().chain(&self.right_rows);
+        self.memory = self.memory.max(rows_memory(rows));
         self.left_index = 0;
         self.right_index = 0;
+        Ok(())
     }
 
-    fn next(&mut self) -> Option<Row> {
+    fn next(&mut self) -> Result<Option<Row>, ExecutionError> {
         while self.left_index < self.left_rows.len() {
             while self.right_index < self.right_rows.len() {
                 let left_row = &self.left_rows[self.left_index];
                 let right_row = &self.right_rows[self.right_index];
 
-                if (self.join_condition)(left_row, right_row) {
+                if (self.join_condition)(left_row, right_row)? {
                     // Create a combined row
                     let mut combined_row = left_row.clone();
                     combined_row.items.extend(right_row.items.clone());
                     self.right_index += 1;
-                    return Some(combined_row);
+                    return Ok(Some(combined_row));
                 } else {
                     self.right_index += 1;
                 }
@@ -257,10 +844,446 @@ impl Operator for Join {
             self.right_index = 0;
             self.left_index += 1;
         }
-        None
+        Ok(None)
     }
 
-    fn close(&self) {}
+    fn close(&self) {
+        self.left.close();
+        self.right.close();
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("Join")
+    }
+
+    fn inputs(&self) -> Vec<&dyn Operator> {
+        vec![self.left.as_ref(), self.right.as_ref()]
+    }
+
+    fn peak_memory(&self) -> Option<usize> {
+        Some(self.memory)
+    }
+}
+
+/// The LeftJoin operator combines rows from two input operators like `Join`,
+/// left rows without any matching right row are padded with `NULL` values.
+pub struct LeftJoin {
+    left: Box<dyn Operator>,
+    right: Box<dyn Operator>,
+    join_condition: Box<JoinCondition>,
+    right_width: usize,
+    right_rows: Vec<Row>,
+    // Current left row, the index of the next right row to match it with
+    // and whether it matched any right row so far.
+    current: Option<(Row, usize, bool)>,
+    memory: usize,
+}
+
+impl LeftJoin {
+    /// Creates a new `LeftJoin` operator whose right rows hold `right_width`
+    /// items.
+    pub fn new<F>(
+        left: Box<dyn Operator>,
+        right: Box<dyn Operator>,
+        right_width: usize,
+        join_condition: F,
+    ) -> Self
+    where
+        F: Fn(&Row, &Row) -> Result<bool, ExecutionError> + 'static,
+    {
+        Self {
+            left,
+            right,
+            join_condition: Box::new(join_condition),
+            right_width,
+            right_rows: vec![],
+            current: None,
+            memory: 0,
+        }
+    }
+}
+
+impl Operator for LeftJoin {
+    fn open(&mut self) -> Result<(), ExecutionError> {
+        self.left.open()?;
+        self.right.open()?;
+        self.right_rows.clear();
+        while let Some(row) = self.right.next()? {
+            self.right_rows.push(row);
+        }
+        self.memory = self.memory.max(rows_memory(&self.right_rows));
+        self.current = None;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<Row>, ExecutionError> {
+        loop {
+            if self.current.is_none() {
+                let Some(left_row) = self.left.next()? else {
+                    return Ok(None);
+                };
+                self.current = Some((left_row, 0, false));
+            }
+            let (left_row, index, matched) = self.current.as_mut().unwrap();
+            while *index < self.right_rows.len() {
+                let right_row = &self.right_rows[*index];
+                *index += 1;
+                if (self.join_condition)(left_row, right_row)? {
+                    *matched = true;
+                    let mut combined_row = left_row.clone();
+                    combined_row.items.extend(right_row.items.iter().cloned());
+                    return Ok(Some(combined_row));
+                }
+            }
+            let (mut left_row, _, matched) = self.current.take().unwrap();
+            if !matched {
+                left_row
+                    .items
+                    .extend(std::iter::repeat_n(Value::Null, self.right_width));
+                return Ok(Some(left_row));
+            }
+        }
+    }
+
+    fn close(&self) {
+        self.left.close();
+        self.right.close();
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("LeftJoin")
+    }
+
+    fn inputs(&self) -> Vec<&dyn Operator> {
+        vec![self.left.as_ref(), self.right.as_ref()]
+    }
+
+    fn peak_memory(&self) -> Option<usize> {
+        Some(self.memory)
+    }
+}
+
+/// Join keys are the values of a row compared for equality between both join
+/// inputs, rows with a `NULL` key never match.
+pub type JoinKey = dyn Fn(&Row) -> Result<Vec<Value>, ExecutionError>;
+
+// Returns whether a join key holds a `NULL`, which never matches.
+fn has_null(key: &[Value]) -> bool {
+    key.iter().any(Value::is_null)
+}
+
+/// The HashJoin operator combines rows from two input operators whose keys
+/// are equal and which satisfy a residual join condition. The right rows are
+/// hashed by key and probed by each left row, so rows are produced in the
+/// same order as `Join`.
+pub struct HashJoin {
+    left: Box<dyn Operator>,
+    right: Box<dyn Operator>,
+    left_key: Box<JoinKey>,
+    right_key: Box<JoinKey>,
+    join_condition: Box<JoinCondition>,
+    // Right rows by key, in the order of the right input.
+    table: HashMap<Vec<Value>, Vec<Row>>,
+    // Current left row, its key and the index of the next right row of the
+    // same key to match it with.
+    current: Option<(Row, Vec<Value>, usize)>,
+    memory: usize,
+}
+
+impl HashJoin {
+    /// Creates a new `HashJoin` operator.
+    pub fn new<L, R, F>(
+        left: Box<dyn Operator>,
+        right: Box<dyn Operator>,
+        left_key: L,
+        right_key: R,
+        join_condition: F,
+    ) -> Self
+    where
+        L: Fn(&Row) -> Result<Vec<Value>, ExecutionError> + 'static,
+        R: Fn(&Row) -> Result<Vec<Value>, ExecutionError> + 'static,
+        F: Fn(&Row, &Row) -> Result<bool, ExecutionError> + 'static,
+    {
+        Self {
+            left,
+            right,
+            left_key: Box::new(left_key),
+            right_key: Box::new(right_key),
+            join_condition: Box::new(join_condition),
+            table: HashMap::new(),
+            current: None,
+            memory: 0,
+        }
+    }
+}
+
+impl Operator for HashJoin {
+    fn open(&mut self) -> Result<(), ExecutionError> {
+        self.left.open()?;
+        self.right.open()?;
+        self.table.clear();
+        while let Some(row) = self.right.next()? {
+            let key = (self.right_key)(&row)?;
+            if !has_null(&key) {
+                self.table.entry(key).or_default().push(row);
+            }
+        }
+        let memory = self
+            .table
+            .iter()
+            .map(|(key, rows)| memory(key) + rows_memory(rows))
+            .sum();
+        self.memory = self.memory.max(memory);
+        self.current = None;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<Row>, ExecutionError> {
+        loop {
+            if self.current.is_none() {
+                let Some(row) = self.left.next()? else {
+                    return Ok(None);
+                };
+                let key = (self.left_key)(&row)?;
+                if has_null(&key) || !self.table.contains_key(&key) {
+                    continue;
+                }
+                self.current = Some((row, key, 0));
+            }
+            let (left_row, key, index) = self.current.as_mut().unwrap();
+            let matches = &self.table[key];
+            while *index < matches.len() {
+                let right_row = &matches[*index];
+                *index += 1;
+                if (self.join_condition)(left_row, right_row)? {
+                    let mut combined_row = left_row.clone();
+                    combined_row.items.extend(right_row.items.iter().cloned());
+                    return Ok(Some(combined_row));
+                }
+            }
+            self.current = None;
+        }
+    }
+
+    fn close(&self) {
+        self.left.close();
+        self.right.close();
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("HashJoin")
+    }
+
+    fn inputs(&self) -> Vec<&dyn Operator> {
+        vec![self.left.as_ref(), self.right.as_ref()]
+    }
+
+    fn peak_memory(&self) -> Option<usize> {
+        Some(self.memory)
+    }
+}
+
+/// The MergeJoin operator combines rows from two input operators whose keys
+/// are equal and which satisfy a residual join condition. Both inputs are
+/// sorted by key, which is cheap when they already are, and merged so rows
+/// are produced in the order of the keys.
+pub struct MergeJoin {
+    left: Box<dyn Operator>,
+    right: Box<dyn Operator>,
+    left_key: Box<JoinKey>,
+    right_key: Box<JoinKey>,
+    join_condition: Box<JoinCondition>,
+    left_rows: Vec<(Vec<Value>, Row)>,
+    right_rows: Vec<(Vec<Value>, Row)>,
+    left_index: usize,
+    // Index of the first right row whose key is not below the key of the
+    // current left row, and of the next right row to match it with.
+    group: usize,
+    right_index: usize,
+    memory: usize,
+}
+
+impl MergeJoin {
+    /// Creates a new `MergeJoin` operator.
+    pub fn new<L, R, F>(
+        left: Box<dyn Operator>,
+        right: Box<dyn Operator>,
+        left_key: L,
+        right_key: R,
+        join_condition: F,
+    ) -> Self
+    where
+        L: Fn(&Row) -> Result<Vec<Value>, ExecutionError> + 'static,
+        R: Fn(&Row) -> Result<Vec<Value>, ExecutionError> + 'static,
+        F: Fn(&Row, &Row) -> Result<bool, ExecutionError> + 'static,
+    {
+        Self {
+            left,
+            right,
+            left_key: Box::new(left_key),
+            right_key: Box::new(right_key),
+            join_condition: Box::new(join_condition),
+            left_rows: vec![],
+            right_rows: vec![],
+            left_index: 0,
+            group: 0,
+            right_index: 0,
+            memory: 0,
+        }
+    }
+
+    // Reads the rows of an input along with their key sorted by key, rows
+    // with a `NULL` key are dropped.
+    fn sorted(
+        input: &mut dyn Operator,
+        key: &JoinKey,
+    ) -> Result<Vec<(Vec<Value>, Row)>, ExecutionError> {
+        let mut rows = vec![];
+        while let Some(row) = input.next()? {
+            let key = key(&row)?;
+            if !has_null(&key) {
+                rows.push((key, row));
+            }
+        }
+        rows.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(rows)
+    }
+}
+
+impl Operator for MergeJoin {
+    fn open(&mut self) -> Result<(), ExecutionError> {
+        self.left.open()?;
+        self.right.open()?;
+        self.left_rows = Self::sorted(self.left.as_mut(), &self.left_key)?;
+        self.right_rows = Self::sorted(self.right.as_mut(), &self.right_key)?;
+        let memory = self
+            .left_rows
+            .iter()
+            .chain(&self.right_rows)
+            .map(|(key, row)| memory(key) + rows_memory([row]))
+            .sum();
+        self.memory = self.memory.max(memory);
+        self.left_index = 0;
+        self.group = 0;
+        self.right_index = 0;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<Row>, ExecutionError> {
+        while let Some((key, left_row)) = self.left_rows.get(self.left_index) {
+            while self.group < self.right_rows.len() && self.right_rows[self.group].0 < *key {
+                self.group += 1;
+                self.right_index = self.group;
+            }
+            match self.right_rows.get(self.right_index) {
+                Some((right_key, right_row)) if right_key == key => {
+                    self.right_index += 1;
+                    if (self.join_condition)(left_row, right_row)? {
+                        let mut combined_row = left_row.clone();
+                        combined_row.items.extend(right_row.items.iter().cloned());
+                        return Ok(Some(combined_row));
+                    }
+                }
+                _ => {
+                    self.left_index += 1;
+                    self.right_index = self.group;
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn close(&self) {
+        self.left.close();
+        self.right.close();
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("MergeJoin")
+    }
+
+    fn inputs(&self) -> Vec<&dyn Operator> {
+        vec![self.left.as_ref(), self.right.as_ref()]
+    }
+
+    fn peak_memory(&self) -> Option<usize> {
+        Some(self.memory)
+    }
+}
+
+/// Builds the operator returning the right side rows of a lateral join for a
+/// row of its left side.
+pub type LateralInput = dyn Fn(&Row) -> Result<Box<dyn Operator>, ExecutionError>;
+
+/// The LateralJoin operator combines each row of its input with the rows of an
+/// operator built from that row, such as a table function whose arguments
+/// reference the columns of preceding `FROM` items.
+pub struct LateralJoin {
+    input: Box<dyn Operator>,
+    right: Box<LateralInput>,
+    join_condition: Box<JoinCondition>,
+    current: Option<(Row, Box<dyn Operator>)>,
+}
+
+impl LateralJoin {
+    /// Creates a new `LateralJoin` operator.
+    pub fn new<R, F>(input: Box<dyn Operator>, right: R, join_condition: F) -> Self
+    where
+        R: Fn(&Row) -> Result<Box<dyn Operator>, ExecutionError> + 'static,
+        F: Fn(&Row, &Row) -> Result<bool, ExecutionError> + 'static,
+    {
+        Self {
+            input,
+            right: Box::new(right),
+            join_condition: Box::new(join_condition),
+            current: None,
+        }
+    }
+}
+
+impl Operator for LateralJoin {
+    fn open(&mut self) -> Result<(), ExecutionError> {
+        self.input.open()
+    }
+
+    fn next(&mut self) -> Result<Option<Row>, ExecutionError> {
+        loop {
+            if self.current.is_none() {
+                let Some(left_row) = self.input.next()? else {
+                    return Ok(None);
+                };
+                let mut right = (self.right)(&left_row)?;
+                right.open()?;
+                self.current = Some((left_row, right));
+            }
+            let (left_row, right) = self.current.as_mut().unwrap();
+            match right.next()? {
+                Some(right_row) => {
+                    if (self.join_condition)(left_row, &right_row)? {
+                        let mut combined_row = left_row.clone();
+                        combined_row.items.extend(right_row.items);
+                        return Ok(Some(combined_row));
+                    }
+                }
+                None => {
+                    right.close();
+                    self.current = None;
+                }
+            }
+        }
+    }
+
+    fn close(&self) {
+        self.input.close();
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("LateralJoin")
+    }
+
+    fn inputs(&self) -> Vec<&dyn Operator> {
+        vec![self.input.as_ref()]
+    }
 }
 
 #[cfg(test)]
@@ -273,8 +1296,8 @@ mod interface_tests {
         let mut scan = Box::new(Scan::new(&rows));
 
         // Interface methods
-        scan.open();
-        assert!(scan.next().is_some());
+        scan.open().unwrap();
+        assert!(scan.next().unwrap().is_some());
         scan.close();
     }
 
@@ -285,8 +1308,8 @@ mod interface_tests {
         let mut project = Project::new(scan, &[1]);
 
         // Interface methods
-        project.open();
-        assert!(project.next().is_some());
+        project.open().unwrap();
+        assert!(project.next().unwrap().is_some());
         project.close();
     }
 
@@ -294,23 +1317,45 @@ mod interface_tests {
     fn filter() {
         let rows = vec![Row::new(&["1".to_string(), "Alice".to_string()])];
         let scan = Box::new(Scan::new(&rows));
-        let filter_fn = Box::new(|row: &Row| row.get(0).unwrap() == "1");
+        let filter_fn = Box::new(|row: &Row| Ok(row.get(0).unwrap() == "1"));
         let mut filter = Box::new(Filter::new(scan, filter_fn));
 
         // Interface methods
-        filter.open();
-        assert!(filter.next().is_some());
+        filter.open().unwrap();
+        assert!(filter.next().unwrap().is_some());
         filter.close();
     }
 
+    #[test]
+    fn map() {
+        let rows = vec![Row::new(&["1".to_string(), "Alice".to_string()])];
+        let scan = Box::new(Scan::new(&rows));
+        let mut map = Map::new(scan, |row: &Row| Ok(row.clone()));
+
+        map.open().unwrap();
+        assert!(map.next().unwrap().is_some());
+        map.close();
+    }
+
+    #[test]
+    fn aggregate() {
+        let rows = vec![Row::new(&["1".to_string(), "Alice".to_string()])];
+        let scan = Box::new(Scan::new(&rows));
+        let mut aggregate = Aggregate::new(scan, vec![], vec![]);
+
+        aggregate.open().unwrap();
+        assert!(aggregate.next().unwrap().is_some());
+        aggregate.close();
+    }
+
     #[test]
     fn limit() {
         let rows = vec![Row::new(&["1".to_string(), "Alice".to_string()])];
         let scan = Box::new(Scan::new(&rows));
         let mut limit = Limit::new(scan, 1);
 
-        limit.open();
-        assert!(limit.next().is_some());
+        limit.open().unwrap();
+        assert!(limit.next().unwrap().is_some());
         limit.close();
     }
 
@@ -318,13 +1363,34 @@ mod interface_tests {
     fn sort() {
         let rows = vec![Row::new(&["1".to_string(), "Alice".to_string()])];
         let scan = Box::new(Scan::new(&rows));
-        let mut sort = Sort::new(scan, |a, b| a.get(0).cmp(&b.get(0)));
+        let mut sort = Sort::new(scan, |row: &Row| Ok(row.items[0].clone()));
 
-        sort.open();
-        assert!(sort.next().is_some());
+        sort.open().unwrap();
+        assert!(sort.next().unwrap().is_some());
         sort.close();
     }
 
+    #[test]
+    fn sort_puts_nulls_last() {
+        let rows = vec![
+            Row::new(&[Value::Null]),
+            Row::new(&[Value::Integer(2)]),
+            Row::new(&[Value::Integer(1)]),
+        ];
+        let mut sort = Sort::new(Box::new(Scan::new(&rows)), |row: &Row| {
+            Ok(row.items[0].clone())
+        });
+        sort.open().unwrap();
+        let mut keys = vec![];
+        while let Some(row) = sort.next().unwrap() {
+            keys.push(row.items[0].clone());
+        }
+        assert_eq!(
+            keys,
+            vec![Value::Integer(1), Value::Integer(2), Value::Null]
+        );
+    }
+
     #[test]
     fn join() {
         let left_rows = vec![
@@ -340,36 +1406,117 @@ mod interface_tests {
         let right = Box::new(Scan::new(&right_rows));
 
         // Define a simple join condition
-        let join_condition = |left: &Row, right: &Row| left.get(0) == right.get(0);
+        let join_condition = |left: &Row, right: &Row| Ok(left.get(0) == right.get(0));
 
         // Create the join operator
         let mut join = Join::new(left, right, join_condition);
 
         // Open the operators
-        join.open();
+        join.open().unwrap();
 
         // Check that the operator conforms to the interface
         assert!(
-            join.next().is_some(),
+            join.next().unwrap().is_some(),
             "Join operator should return some rows"
         );
         assert!(
-            join.next().is_some(),
+            join.next().unwrap().is_some(),
             "Join operator should return some rows"
         );
         assert!(
-            join.next().is_none(),
+            join.next().unwrap().is_none(),
             "Join operator should return no more rows"
         );
 
         // Close the operators
         join.close();
     }
+
+    #[test]
+    fn left_join() {
+        let left_rows = vec![
+            Row::new(&[Value::Integer(1)]),
+            Row::new(&[Value::Integer(2)]),
+            Row::new(&[Value::Integer(3)]),
+        ];
+        let right_rows = vec![
+            Row::new(&[Value::Integer(1), Value::from("A")]),
+            Row::new(&[Value::Integer(3), Value::from("C")]),
+            Row::new(&[Value::Integer(3), Value::from("D")]),
+        ];
+        let left = Box::new(Scan::new(&left_rows));
+        let right = Box::new(Scan::new(&right_rows));
+        let mut join = LeftJoin::new(left, right, 2, |left: &Row, right: &Row| {
+            Ok(left.get(0) == right.get(0))
+        });
+        join.open().unwrap();
+        let mut rows = vec![];
+        while let Some(row) = join.next().unwrap() {
+            rows.push(row);
+        }
+        join.close();
+        assert_eq!(
+            rows,
+            vec![
+                Row::new(&[Value::Integer(1), Value::Integer(1), Value::from("A")]),
+                Row::new(&[Value::Integer(2), Value::Null, Value::Null]),
+                Row::new(&[Value::Integer(3), Value::Integer(3), Value::from("C")]),
+                Row::new(&[Value::Integer(3), Value::Integer(3), Value::from("D")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn operators_can_be_explained() {
+        let rows = vec![Row::new(&[Value::Integer(1)])];
+        let filter_fn = |_: &Row| Ok(true);
+        let filter = Filter::new(Box::new(Scan::new(&rows)), filter_fn);
+        let parameters = vec![("predicate".to_string(), "TRUE".to_string())];
+        let annotated = Annotated::new(Box::new(filter), parameters);
+        let join = Join::new(
+            Box::new(annotated),
+            Box::new(Scan::new(&rows)),
+            |_: &Row, _: &Row| Ok(true),
+        );
+        let limit = Limit::new(Box::new(join), 1);
+        assert_eq!(
+            explain(&limit),
+            [
+                "Limit (limit: 1)",
+                "  Join",
+                "    Filter (predicate: TRUE)",
+                "      Scan",
+                "    Scan"
+            ]
+        );
+    }
+
+    #[test]
+    fn operators_collect_metrics() {
+        let rows = vec![Row::new(&[Value::from("b")]), Row::new(&[Value::from("a")])];
+        let sort = Sort::new(Box::new(Scan::new(&rows)), |row: &Row| {
+            Ok(row.items[0].clone())
+        });
+        let mut sort = Annotated::new(Box::new(sort), vec![]).with_metrics();
+        assert_eq!(sort.metrics(), Some(Metrics::default()));
+        sort.open().unwrap();
+        while sort.next().unwrap().is_some() {}
+        sort.close();
+        let metrics = sort.metrics().unwrap();
+        assert_eq!((metrics.rows, metrics.calls), (2, 3));
+        let row_size = size_of::<Row>() + size_of::<Value>() + 1;
+        assert_eq!(sort.peak_memory(), Some(2 * row_size));
+        // Metrics are only collected on demand.
+        assert_eq!(Scan::new(&rows).metrics(), None);
+        assert_eq!(Scan::new(&rows).peak_memory(), None);
+    }
 }
 
 #[cfg(test)]
 mod operator_tests {
     use super::*;
+    use crate::functions::FunctionRegistry;
+    use crate::types::DataType;
 
     #[test]
     fn scan_operator_returns_all_rows() {
@@ -380,7 +1527,7 @@ mod operator_tests {
         let mut scan = Box::new(Scan::new(&rows));
 
         let mut result = vec![];
-        while let Some(row) = scan.next() {
+        while let Some(row) = scan.next().unwrap() {
             result.push(row);
         }
 
@@ -397,11 +1544,11 @@ mod operator_tests {
         let scan = Box::new(Scan::new(&rows));
         let mut project = Project::new(scan, &[1, 2]);
 
-        let projected_row = project.next().unwrap();
+        let projected_row = project.next().unwrap().unwrap();
 
         assert_eq!(projected_row.get(0).unwrap(), "Alice");
         assert_eq!(projected_row.get(1).unwrap(), "Engineer");
-        assert!(project.next().is_none());
+        assert!(project.next().unwrap().is_none());
     }
 
     #[test]
@@ -411,11 +1558,11 @@ mod operator_tests {
             Row::new(&["2".to_string(), "Bob".to_string()]),
         ];
         let scan = Box::new(Scan::new(&rows));
-        let filter_fn = Box::new(|row: &Row| row.get(0).unwrap() == "1");
+        let filter_fn = Box::new(|row: &Row| Ok(row.get(0).unwrap() == "1"));
         let mut filter = Box::new(Filter::new(scan, filter_fn));
 
         let mut result = vec![];
-        while let Some(row) = filter.next() {
+        while let Some(row) = filter.next().unwrap() {
             result.push(row);
         }
 
@@ -424,6 +1571,31 @@ mod operator_tests {
         assert_eq!(result[0].get(1).unwrap(), "Alice");
     }
 
+    #[test]
+    fn map_operator_returns_transformed_rows() {
+        let rows = vec![
+            Row::new(&["1".to_string(), "Alice".to_string()]),
+            Row::new(&["2".to_string(), "Bob".to_string()]),
+        ];
+        let scan = Box::new(Scan::new(&rows));
+        let mut map = Map::new(scan, |row: &Row| {
+            Ok(Row::new(&[row.get(1).unwrap().to_string().to_uppercase()]))
+        });
+
+        let mut result = vec![];
+        while let Some(row) = map.next().unwrap() {
+            result.push(row);
+        }
+
+        assert_eq!(
+            result,
+            vec![
+                Row::new(&["ALICE".to_string()]),
+                Row::new(&["BOB".to_string()])
+            ]
+        );
+    }
+
     #[test]
     fn limit_operator_returns_limited_rows() {
         let rows = vec![
@@ -434,7 +1606,7 @@ mod operator_tests {
         let mut limit = Limit::new(scan, 1);
 
         let mut result = vec![];
-        while let Some(row) = limit.next() {
+        while let Some(row) = limit.next().unwrap() {
             result.push(row);
         }
 
@@ -459,10 +1631,10 @@ mod operator_tests {
 
         // Sort by the first column (identifier)
         let scan = Box::new(Scan::new(&rows));
-        let mut sort_by_id = Sort::new(scan, |a, b| a.get(0).cmp(&b.get(0)));
+        let mut sort_by_id = Sort::new(scan, |row: &Row| Ok(row.items[0].clone()));
 
         let mut result_by_id = vec![];
-        while let Some(row) = sort_by_id.next() {
+        while let Some(row) = sort_by_id.next().unwrap() {
             result_by_id.push(row);
         }
 
@@ -472,17 +1644,17 @@ mod operator_tests {
         assert_eq!(
             result_by_id
                 .iter()
-                .map(|r| r.get(0).unwrap())
-                .collect::<Vec<&str>>(),
+                .map(|r| r.get(0).unwrap().to_string())
+                .collect::<Vec<String>>(),
             expected_order_by_id
         );
 
         // Sort by the second column (name)
         let scan = Box::new(Scan::new(&rows));
-        let mut sort_by_name = Sort::new(scan, |a, b| a.get(1).cmp(&b.get(1)));
+        let mut sort_by_name = Sort::new(scan, |row: &Row| Ok(row.items[1].clone()));
 
         let mut result_by_name = vec![];
-        while let Some(row) = sort_by_name.next() {
+        while let Some(row) = sort_by_name.next().unwrap() {
             result_by_name.push(row);
         }
 
@@ -494,12 +1666,108 @@ mod operator_tests {
         assert_eq!(
             result_by_name
                 .iter()
-                .map(|r| r.get(1).unwrap())
-                .collect::<Vec<&str>>(),
+                .map(|r| r.get(1).unwrap().to_string())
+                .collect::<Vec<String>>(),
             expected_order_by_name
         );
     }
 
+    fn count(registry: &FunctionRegistry) -> AggregateExpr {
+        let function = registry.aggregate("count", &[]).unwrap();
+        AggregateExpr::new(function, |_: &Row| Ok(vec![]))
+    }
+
+    fn sum(registry: &FunctionRegistry, column: usize) -> AggregateExpr {
+        let function = registry.aggregate("sum", &[DataType::Integer]).unwrap();
+        AggregateExpr::new(function, move |row: &Row| {
+            Ok(vec![row.get(column).unwrap().clone()])
+        })
+    }
+
+    #[test]
+    fn aggregate_operator_returns_one_row_per_group() {
+        let registry = FunctionRegistry::new();
+        let rows = vec![
+            Row::new(&[Value::from("Manager"), Value::Integer(12000)]),
+            Row::new(&[Value::from("Developer"), Value::Integer(10000)]),
+            Row::new(&[Value::from("Manager"), Value::Integer(13000)]),
+            Row::new(&[Value::from("Developer"), Value::Null]),
+        ];
+        let scan = Box::new(Scan::new(&rows));
+        let role: Box<GroupKey> = Box::new(|row: &Row| Ok(row.get(0).unwrap().clone()));
+        let mut aggregate =
+            Aggregate::new(scan, vec![role], vec![count(&registry), sum(&registry, 1)]);
+
+        aggregate.open().unwrap();
+        let mut result = vec![];
+        while let Some(row) = aggregate.next().unwrap() {
+            result.push(row);
+        }
+
+        assert_eq!(
+            result,
+            vec![
+                Row::new(&[
+                    Value::from("Manager"),
+                    Value::BigInt(2),
+                    Value::BigInt(25000)
+                ]),
+                Row::new(&[
+                    Value::from("Developer"),
+                    Value::BigInt(2),
+                    Value::BigInt(10000)
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    fn aggregate_operator_returns_a_row_for_empty_input_without_groups() {
+        let registry = FunctionRegistry::new();
+        let scan = Box::new(Scan::new(&[]));
+        let mut aggregate = Aggregate::new(scan, vec![], vec![count(&registry), sum(&registry, 0)]);
+
+        aggregate.open().unwrap();
+        assert_eq!(
+            aggregate.next().unwrap(),
+            Some(Row::new(&[Value::BigInt(0), Value::Null]))
+        );
+        assert!(aggregate.next().unwrap().is_none());
+    }
+
+    #[test]
+    fn aggregate_operator_merges_partitions() {
+        let registry = FunctionRegistry::new();
+        let rows = (0..10)
+            .map(|i| Row::new(&[Value::Integer(i % 3), Value::Integer(i)]))
+            .collect::<Vec<_>>();
+
+        let aggregate = |partitions| {
+            let scan = Box::new(Scan::new(&rows));
+            let key: Box<GroupKey> = Box::new(|row: &Row| Ok(row.get(0).unwrap().clone()));
+            let mut aggregate =
+                Aggregate::new(scan, vec![key], vec![count(&registry), sum(&registry, 1)])
+                    .with_partitions(partitions);
+            aggregate.open().unwrap();
+            let mut result = vec![];
+            while let Some(row) = aggregate.next().unwrap() {
+                result.push(row);
+            }
+            (result, aggregate.peak_memory().unwrap())
+        };
+
+        let (merged, merged_memory) = aggregate(4);
+        let (result, memory) = aggregate(1);
+        assert_eq!(merged, result);
+        assert_eq!(
+            merged[0],
+            Row::new(&[Value::Integer(0), Value::BigInt(4), Value::BigInt(18)])
+        );
+        // Partial states of every partition are counted until merged.
+        assert!(memory > rows_memory(&result));
+        assert!(merged_memory > memory);
+    }
+
     #[test]
     fn join_operator_returns_joined_rows() {
         let left_rows = vec![
@@ -514,13 +1782,13 @@ mod operator_tests {
         let scan_left = Box::new(Scan::new(&left_rows));
         let scan_right = Box::new(Scan::new(&right_rows));
 
-        let join_condition = Box::new(|left: &Row, right: &Row| left.get(0) == right.get(0));
+        let join_condition = Box::new(|left: &Row, right: &Row| Ok(left.get(0) == right.get(0)));
         let mut join = Join::new(scan_left, scan_right, join_condition);
 
-        join.open();
+        join.open().unwrap();
 
         let mut results = vec![];
-        while let Some(row) = join.next() {
+        while let Some(row) = join.next().unwrap() {
             results.push(row);
         }
 
@@ -543,6 +1811,126 @@ mod operator_tests {
     }
 }
 
+#[cfg(test)]
+mod equi_join_tests {
+    use super::*;
+
+    fn rows(keys: &[Option<i32>]) -> Vec<Row> {
+        keys.iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let key = key.map_or(Value::Null, Value::Integer);
+                Row::new(&[key, Value::Integer(i as i32)])
+            })
+            .collect()
+    }
+
+    fn collect(mut join: impl Operator) -> Vec<Row> {
+        join.open().unwrap();
+        let mut results = vec![];
+        while let Some(row) = join.next().unwrap() {
+            results.push(row);
+        }
+        join.close();
+        results
+    }
+
+    fn key(row: &Row) -> Result<Vec<Value>, ExecutionError> {
+        Ok(vec![row.items[0].clone()])
+    }
+
+    // Nested loop join of the same inputs, on equal non-null keys.
+    fn expected(left: &[Row], right: &[Row]) -> Vec<Row> {
+        let condition = |left: &Row, right: &Row| {
+            Ok(!left.items[0].is_null() && left.items[0] == right.items[0])
+        };
+        let join = Join::new(
+            Box::new(Scan::new(left)),
+            Box::new(Scan::new(right)),
+            condition,
+        );
+        collect(join)
+    }
+
+    #[test]
+    fn hash_join_matches_nested_loop_join() {
+        let left = rows(&[Some(2), None, Some(1), Some(2), Some(3)]);
+        let right = rows(&[Some(2), Some(1), None, Some(2), Some(4)]);
+        let join = HashJoin::new(
+            Box::new(Scan::new(&left)),
+            Box::new(Scan::new(&right)),
+            key,
+            key,
+            |_: &Row, _: &Row| Ok(true),
+        );
+        let results = collect(join);
+        assert_eq!(results.len(), 5);
+        assert_eq!(results, expected(&left, &right));
+    }
+
+    #[test]
+    fn merge_join_produces_rows_in_key_order() {
+        let left = rows(&[Some(2), None, Some(1), Some(2), Some(3)]);
+        let right = rows(&[Some(2), Some(1), None, Some(2), Some(4)]);
+        // The residual condition drops pairs of rows at the same position.
+        let join = MergeJoin::new(
+            Box::new(Scan::new(&left)),
+            Box::new(Scan::new(&right)),
+            key,
+            key,
+            |left: &Row, right: &Row| Ok(left.items[1] != right.items[1]),
+        );
+        let pairs = collect(join)
+            .into_iter()
+            .map(|row| (row.items[1].clone(), row.items[3].clone()))
+            .collect::<Vec<_>>();
+        let pair = |left, right| (Value::Integer(left), Value::Integer(right));
+        assert_eq!(pairs, vec![pair(2, 1), pair(0, 3), pair(3, 0)]);
+    }
+}
+
+#[cfg(test)]
+mod lateral_tests {
+    use super::*;
+
+    #[test]
+    fn lateral_join_operator_returns_rows_built_from_each_input_row() {
+        let rows = vec![
+            Row::new(&[Value::from("Alice"), Value::Integer(2)]),
+            Row::new(&[Value::from("Bob"), Value::Integer(0)]),
+            Row::new(&[Value::from("Carol"), Value::Integer(1)]),
+        ];
+        let scan = Box::new(Scan::new(&rows));
+        // Repeat each row as many times as its second column.
+        let repeat = |row: &Row| -> Result<Box<dyn Operator>, ExecutionError> {
+            let Some(Value::Integer(count)) = row.get(1) else {
+                unreachable!()
+            };
+            let rows = (0..*count)
+                .map(|i| Row::new(&[Value::Integer(i)]))
+                .collect::<Vec<_>>();
+            Ok(Box::new(Scan::new(&rows)))
+        };
+        let mut join = LateralJoin::new(scan, repeat, |_: &Row, _: &Row| Ok(true));
+
+        join.open().unwrap();
+        let mut results = vec![];
+        while let Some(row) = join.next().unwrap() {
+            results.push(row);
+        }
+        join.close();
+
+        assert_eq!(
+            results,
+            vec![
+                Row::new(&[Value::from("Alice"), Value::Integer(2), Value::Integer(0)]),
+                Row::new(&[Value::from("Alice"), Value::Integer(2), Value::Integer(1)]),
+                Row::new(&[Value::from("Carol"), Value::Integer(1), Value::Integer(0)]),
+            ]
+        );
+    }
+}
+
 #[cfg(test)]
 mod chaining_tests {
     use super::*;
@@ -557,7 +1945,7 @@ mod chaining_tests {
         let mut project = Project::new(scan, &[1]);
 
         let mut result = vec![];
-        while let Some(row) = project.next() {
+        while let Some(row) = project.next().unwrap() {
             result.push(row);
         }
 
@@ -573,12 +1961,12 @@ mod chaining_tests {
             Row::new(&["2".to_string(), "Bob".to_string(), "Manager".to_string()]),
         ];
         let scan = Box::new(Scan::new(&rows));
-        let filter_fn = Box::new(|row: &Row| row.get(0).unwrap() == "1");
+        let filter_fn = Box::new(|row: &Row| Ok(row.get(0).unwrap() == "1"));
         let filter = Box::new(Filter::new(scan, filter_fn));
         let mut project = Project::new(filter, &[1]);
 
         let mut result = vec![];
-        while let Some(row) = project.next() {
+        while let Some(row) = project.next().unwrap() {
             result.push(row);
         }
 
@@ -594,11 +1982,11 @@ mod chaining_tests {
             Row::new(&["3".to_string(), "Carol".to_string()]),
         ];
         let scan = Box::new(Scan::new(&rows));
-        let sort = Box::new(Sort::new(scan, |a, b| a.get(0).cmp(&b.get(0))));
+        let sort = Box::new(Sort::new(scan, |row: &Row| Ok(row.items[0].clone())));
         let mut limit = Limit::new(sort, 2);
 
         let mut result = vec![];
-        while let Some(row) = limit.next() {
+        while let Some(row) = limit.next().unwrap() {
             result.push(row);
         }
 
@@ -627,21 +2015,21 @@ mod chaining_tests {
         let scan = Box::new(Scan::new(&employee_rows));
 
         // 2. Filter operator to keep only "Manager"
-        let filter_condition = |row: &Row| row.get(2) == Some("Manager");
+        let filter_condition = |row: &Row| Ok(row.get(2).is_some_and(|role| role == "Manager"));
         let filter = Box::new(Filter::new(scan, filter_condition));
 
         // 3. Sort operator to sort by ID (assuming the ID is in the first column)
-        let sort = Sort::new(filter, |a, b| a.get(0).cmp(&b.get(0)));
+        let sort = Sort::new(filter, |row: &Row| Ok(row.items[0].clone()));
 
         // 4. Project operator to project only the Name column (assuming the Name is in the second column)
         let mut project = Project::new(Box::new(sort), &[1]);
 
         // Open the operators
-        project.open();
+        project.open().unwrap();
 
         // Collect results
         let mut results = vec![];
-        while let Some(row) = project.next() {
+        while let Some(row) = project.next().unwrap() {
             results.push(row);
         }
 
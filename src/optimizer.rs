@@ -0,0 +1,1494 @@
+//! Implementation of the rule-based optimizer rewriting logical plans into
+//! equivalent ones which are cheaper to execute, predicates are pushed towards
+//! the tables they filter, joins are ordered by estimated cost and columns no
+//! operator reads are pruned.
+use std::collections::BTreeSet;
+
+use crate::catalog::Catalog;
+use crate::cost::{Estimator, JoinMethod};
+use crate::logical::LogicalPlan;
+use crate::schema::Schema;
+use crate::simplify::{simplify, simplify_predicate};
+use crate::sql::{ColumnRef, Expr};
+
+/// Rewrite of a logical plan into an equivalent one, producing the same rows
+/// under the same schema. Rows are produced in the same order unless joins
+/// are reordered, queries only order their rows with `ORDER BY`.
+pub trait Rule {
+    /// Name the rule is enabled and disabled by.
+    fn name(&self) -> &'static str;
+
+    /// Rewrite the whole plan, the catalog provides the statistics of the
+    /// tables it reads.
+    fn rewrite(&self, plan: LogicalPlan, catalog: &Catalog) -> LogicalPlan;
+}
+
+/// Ordered list of rules applied to the plans of queries, each of which can
+/// be disabled.
+pub struct Optimizer {
+    rules: Vec<(Box<dyn Rule>, bool)>,
+}
+
+impl Default for Optimizer {
+    fn default() -> Self {
+        Self::empty()
+            .with_rule(SimplifyExpressions)
+            .with_rule(EliminateEmptyRelations)
+            .with_rule(SplitConjunctions)
+            .with_rule(PushDownFilters)
+            .with_rule(MergeFilters)
+            .with_rule(ReorderJoins)
+            .with_rule(PushDownProjections)
+            .with_rule(MergeProjections)
+            .with_rule(EliminateSorts)
+    }
+}
+
+impl Optimizer {
+    /// Creates an optimizer applying the default rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an optimizer without any rule, which leaves plans unchanged.
+    pub fn empty() -> Self {
+        Self { rules: vec![] }
+    }
+
+    /// Append a rule applied after the current ones.
+    pub fn with_rule<R: Rule + 'static>(mut self, rule: R) -> Self {
+        self.rules.push((Box::new(rule), true));
+        self
+    }
+
+    /// Enable or disable the rule with the given name, returns whether there
+    /// is such a rule.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        let mut found = false;
+        for (rule, state) in &mut self.rules {
+            if rule.name() == name {
+                *state = enabled;
+                found = true;
+            }
+        }
+        found
+    }
+
+    /// Returns the names of the rules in the order they are applied along with
+    /// whether they are enabled.
+    pub fn rules(&self) -> Vec<(&'static str, bool)> {
+        self.rules
+            .iter()
+            .map(|(rule, enabled)| (rule.name(), *enabled))
+            .collect()
+    }
+
+    /// Apply the enabled rules in order to a plan reading the tables of the
+    /// catalog.
+    pub fn optimize(&self, plan: LogicalPlan, catalog: &Catalog) -> LogicalPlan {
+        self.rules
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .fold(plan, |plan, (rule, _)| rule.rewrite(plan, catalog))
+    }
+}
+
+/// Simplify the expressions of the plan, see `simplify`. Filters which always
+/// hold are removed and filters or joins whose predicate never holds are
+/// replaced by an empty relation.
+pub struct SimplifyExpressions;
+
+impl Rule for SimplifyExpressions {
+    fn name(&self) -> &'static str {
+        "simplify_expressions"
+    }
+
+    fn rewrite(&self, plan: LogicalPlan, _catalog: &Catalog) -> LogicalPlan {
+        let all = |exprs: Vec<Expr>| exprs.into_iter().map(simplify).collect::<Vec<_>>();
+        plan.transform_up(&mut |plan| match plan {
+            LogicalPlan::Filter { input, predicate } => match simplify_predicate(predicate) {
+                Expr::Boolean(true) => *input,
+                Expr::Boolean(false) => LogicalPlan::empty(input.schema()),
+                predicate => LogicalPlan::Filter { input, predicate },
+            },
+            LogicalPlan::Join {
+                left,
+                right,
+                condition,
+            } => match condition.map(simplify_predicate) {
+                Some(Expr::Boolean(false)) => {
+                    LogicalPlan::empty(left.schema().join(&right.schema()))
+                }
+                Some(Expr::Boolean(true)) | None => LogicalPlan::Join {
+                    left,
+                    right,
+                    condition: None,
+                },
+                condition => LogicalPlan::Join {
+                    left,
+                    right,
+                    condition,
+                },
+            },
+            LogicalPlan::LateralJoin {
+                input,
+                function,
+                args,
+                condition,
+                schema,
+            } => match condition.map(simplify_predicate) {
+                Some(Expr::Boolean(false)) => LogicalPlan::empty(schema),
+                condition => LogicalPlan::LateralJoin {
+                    input,
+                    function,
+                    args: all(args),
+                    condition: condition.filter(|condition| *condition != Expr::Boolean(true)),
+                    schema,
+                },
+            },
+            LogicalPlan::Project {
+                input,
+                exprs,
+                schema,
+            } => LogicalPlan::Project {
+                input,
+                exprs: all(exprs),
+                schema,
+            },
+            LogicalPlan::Aggregate {
+                input,
+                group_by,
+                aggregates,
+                schema,
+            } => LogicalPlan::Aggregate {
+                input,
+                group_by: all(group_by),
+                aggregates: all(aggregates),
+                schema,
+            },
+            LogicalPlan::Sort { input, key } => LogicalPlan::Sort {
+                input,
+                key: simplify(key),
+            },
+            LogicalPlan::Values { rows, schema } => LogicalPlan::Values {
+                rows: rows.into_iter().map(all).collect(),
+                schema,
+            },
+            LogicalPlan::TableFunction { name, args, schema } => LogicalPlan::TableFunction {
+                name,
+                args: all(args),
+                schema,
+            },
+            plan => plan,
+        })
+    }
+}
+
+/// Replace the operations on empty relations which produce no rows by empty
+/// relations, aggregates without group keys still produce a row.
+pub struct EliminateEmptyRelations;
+
+impl Rule for EliminateEmptyRelations {
+    fn name(&self) -> &'static str {
+        "eliminate_empty_relations"
+    }
+
+    fn rewrite(&self, plan: LogicalPlan, _catalog: &Catalog) -> LogicalPlan {
+        let empty = |plan: &LogicalPlan| matches!(plan, LogicalPlan::Values { rows, .. } if rows.is_empty());
+        plan.transform_up(&mut |plan| {
+            let eliminated = match &plan {
+                LogicalPlan::Aggregate { group_by, .. } if group_by.is_empty() => false,
+                LogicalPlan::Limit { count: 0, .. } => true,
+                plan => plan.inputs().into_iter().any(empty),
+            };
+            if eliminated {
+                LogicalPlan::empty(plan.schema())
+            } else {
+                plan
+            }
+        })
+    }
+}
+
+/// Split filters on conjunctions into a chain of filters on each of their
+/// terms, which can then be pushed down independently.
+pub struct SplitConjunctions;
+
+impl Rule for SplitConjunctions {
+    fn name(&self) -> &'static str {
+        "split_conjunctions"
+    }
+
+    fn rewrite(&self, plan: LogicalPlan, _catalog: &Catalog) -> LogicalPlan {
+        plan.transform_down(&mut |plan| match plan {
+            LogicalPlan::Filter { input, predicate } => filter(*input, predicate.conjuncts()),
+            plan => plan,
+        })
+    }
+}
+
+/// Push filters below projections and sorts, and into the inputs of joins
+/// when they only reference the columns of one side, or into the join
+/// condition otherwise. Filters are never pushed below limits or aggregates
+/// which would change the rows they filter.
+pub struct PushDownFilters;
+
+impl Rule for PushDownFilters {
+    fn name(&self) -> &'static str {
+        "push_down_filters"
+    }
+
+    fn rewrite(&self, plan: LogicalPlan, _catalog: &Catalog) -> LogicalPlan {
+        plan.transform_down(&mut |plan| {
+            if !matches!(plan, LogicalPlan::Filter { .. }) {
+                return plan;
+            }
+            // Take the whole chain of filters, innermost predicate first.
+            let mut predicates = vec![];
+            let mut input = plan;
+            while let LogicalPlan::Filter {
+                input: inner,
+                predicate,
+            } = input
+            {
+                predicates.push(predicate);
+                input = *inner;
+            }
+            predicates.reverse();
+            let (input, kept) = Self::push(input, predicates);
+            filter(input, kept)
+        })
+    }
+}
+
+impl PushDownFilters {
+    // Pushes predicates into a plan, returns the rewritten plan along with the
+    // predicates which must still be evaluated on its rows.
+    fn push(plan: LogicalPlan, predicates: Vec<Expr>) -> (LogicalPlan, Vec<Expr>) {
+        match plan {
+            LogicalPlan::Project {
+                input,
+                exprs,
+                schema,
+            } => {
+                let predicates = predicates
+                    .into_iter()
+                    .map(|predicate| substitute(predicate, &exprs))
+                    .collect();
+                let input = Box::new(filter(*input, predicates));
+                let plan = LogicalPlan::Project {
+                    input,
+                    exprs,
+                    schema,
+                };
+                (plan, vec![])
+            }
+            LogicalPlan::Sort { input, key } => {
+                let input = Box::new(filter(*input, predicates));
+                (LogicalPlan::Sort { input, key }, vec![])
+            }
+            LogicalPlan::Join {
+                left,
+                right,
+                condition,
+            } => {
+                let width = left.schema().len();
+                let (mut lefts, mut rights) = (vec![], vec![]);
+                let mut conditions = condition.into_iter().collect::<Vec<_>>();
+                for predicate in predicates {
+                    let columns = predicate.references();
+                    if columns.iter().all(|&index| index < width) {
+                        lefts.push(predicate);
+                    } else if columns.iter().all(|&index| index >= width) {
+                        rights.push(remap(predicate, &|index| Some(index - width)));
+                    } else {
+                        conditions.push(predicate);
+                    }
+                }
+                let plan = LogicalPlan::Join {
+                    left: Box::new(filter(*left, lefts)),
+                    right: Box::new(filter(*right, rights)),
+                    condition: conjunction(conditions),
+                };
+                (plan, vec![])
+            }
+            // The rows of the table function depend on the input rows, so
+            // only predicates on the input can be pushed into it.
+            LogicalPlan::LateralJoin {
+                input,
+                function,
+                args,
+                condition,
+                schema,
+            } => {
+                let width = input.schema().len();
+                let (mut inputs, mut conditions) =
+                    (vec![], condition.into_iter().collect::<Vec<_>>());
+                for predicate in predicates {
+                    if predicate.references().iter().all(|&index| index < width) {
+                        inputs.push(predicate);
+                    } else {
+                        conditions.push(predicate);
+                    }
+                }
+                let plan = LogicalPlan::LateralJoin {
+                    input: Box::new(filter(*input, inputs)),
+                    function,
+                    args,
+                    condition: conjunction(conditions),
+                    schema,
+                };
+                (plan, vec![])
+            }
+            plan => (plan, predicates),
+        }
+    }
+}
+
+/// Merge chains of filters into a single filter on the conjunction of their
+/// predicates, evaluated innermost first.
+pub struct MergeFilters;
+
+impl Rule for MergeFilters {
+    fn name(&self) -> &'static str {
+        "merge_filters"
+    }
+
+    fn rewrite(&self, plan: LogicalPlan, _catalog: &Catalog) -> LogicalPlan {
+        plan.transform_up(&mut |plan| match plan {
+            LogicalPlan::Filter { input, predicate } => match *input {
+                LogicalPlan::Filter {
+                    input,
+                    predicate: inner,
+                } => LogicalPlan::Filter {
+                    input,
+                    predicate: Expr::And(Box::new(inner), Box::new(predicate)),
+                },
+                input => LogicalPlan::Filter {
+                    input: Box::new(input),
+                    predicate,
+                },
+            },
+            plan => plan,
+        })
+    }
+}
+
+/// Reorder trees of joins into the tree of least estimated cost, the cost of
+/// a tree being the cost of its joins along with the number of rows each of
+/// them produces. Every tree is considered for up to `EXHAUSTIVE_JOINS`
+/// inputs, beyond which the cheapest pair of inputs is joined first until a
+/// single one remains. Plans are only rewritten when the tree is cheaper than
+/// the original one, columns are then projected back in their order.
+pub struct ReorderJoins;
+
+/// Number of inputs of the largest trees of joins whose every order is
+/// considered by `ReorderJoins`.
+pub const EXHAUSTIVE_JOINS: usize = 8;
+
+impl Rule for ReorderJoins {
+    fn name(&self) -> &'static str {
+        "reorder_joins"
+    }
+
+    fn rewrite(&self, plan: LogicalPlan, catalog: &Catalog) -> LogicalPlan {
+        Self::reorder(plan, &Estimator::new(catalog))
+    }
+}
+
+impl ReorderJoins {
+    // Reorders the trees of joins of a plan, inner trees first.
+    fn reorder(plan: LogicalPlan, estimator: &Estimator) -> LogicalPlan {
+        if !matches!(plan, LogicalPlan::Join { .. }) {
+            return plan.map_inputs(|input| Self::reorder(input, estimator));
+        }
+        let plan = Self::reorder_inputs(plan, estimator);
+        let Some((graph, original)) = JoinGraph::new(&plan, estimator) else {
+            return plan;
+        };
+        let tree = if graph.inputs.len() <= EXHAUSTIVE_JOINS {
+            graph.exhaustive()
+        } else {
+            graph.greedy()
+        }
+        .unwrap_or_else(|| original.clone());
+        if graph.cost(&tree).0 < graph.cost(&original).0 {
+            graph.build(&tree, plan.schema())
+        } else {
+            plan
+        }
+    }
+
+    // Reorders the trees of joins below the inputs of a tree of joins.
+    fn reorder_inputs(plan: LogicalPlan, estimator: &Estimator) -> LogicalPlan {
+        match plan {
+            LogicalPlan::Join { .. } => {
+                plan.map_inputs(|input| Self::reorder_inputs(input, estimator))
+            }
+            plan => Self::reorder(plan, estimator),
+        }
+    }
+}
+
+// Tree of joins of the inputs of a join graph.
+#[derive(Debug, Clone, PartialEq)]
+enum JoinTree {
+    Input(usize),
+    Join(Box<JoinTree>, Box<JoinTree>),
+}
+
+impl JoinTree {
+    // Returns the set of inputs of the tree, one bit per input.
+    fn inputs(&self) -> u64 {
+        match self {
+            JoinTree::Input(input) => 1 << input,
+            JoinTree::Join(left, right) => left.inputs() | right.inputs(),
+        }
+    }
+}
+
+// Term of the conditions of a tree of joins, reading the columns of the rows
+// the tree produces.
+struct Conjunct {
+    expr: Expr,
+    // Set of the inputs whose columns the term reads.
+    inputs: u64,
+    // Sets of the inputs read by each side of an equality, usable as a join
+    // key.
+    key: Option<(u64, u64)>,
+    selectivity: f64,
+}
+
+// Inputs of a tree of inner joins along with the terms of their conditions.
+struct JoinGraph {
+    inputs: Vec<LogicalPlan>,
+    // Index of the first column of each input in the rows of the tree.
+    offsets: Vec<usize>,
+    // Estimated number of rows of each input, once filtered by the terms
+    // which only read its columns.
+    rows: Vec<f64>,
+    conjuncts: Vec<Conjunct>,
+}
+
+impl JoinGraph {
+    // Returns the graph of a tree of joins along with the tree itself, none
+    // when it has too many inputs to be reordered.
+    fn new(plan: &LogicalPlan, estimator: &Estimator) -> Option<(JoinGraph, JoinTree)> {
+        let mut graph = JoinGraph {
+            inputs: vec![],
+            offsets: vec![],
+            rows: vec![],
+            conjuncts: vec![],
+        };
+        let mut terms = vec![];
+        let tree = graph.flatten(plan, 0, &mut terms);
+        if graph.inputs.len() > u64::BITS as usize {
+            return None;
+        }
+        graph.rows = graph
+            .inputs
+            .iter()
+            .map(|input| estimator.rows(input))
+            .collect();
+        for expr in terms {
+            let inputs = graph.inputs_of(&expr);
+            let key = match &expr {
+                Expr::Comparison(left, op, right) if op == "=" => {
+                    Some((graph.inputs_of(left), graph.inputs_of(right)))
+                        .filter(|&(left, right)| left != 0 && right != 0 && left & right == 0)
+                }
+                _ => None,
+            };
+            let selectivity = estimator.selectivity(&expr, plan);
+            if inputs.count_ones() == 1 {
+                graph.rows[inputs.trailing_zeros() as usize] *= selectivity;
+            }
+            graph.conjuncts.push(Conjunct {
+                expr,
+                inputs,
+                key,
+                selectivity,
+            });
+        }
+        Some((graph, tree))
+    }
+
+    // Collects the inputs of a tree of joins whose columns start at the given
+    // offset, along with the terms of its conditions.
+    fn flatten(&mut self, plan: &LogicalPlan, offset: usize, terms: &mut Vec<Expr>) -> JoinTree {
+        match plan {
+            LogicalPlan::Join {
+                left,
+                right,
+                condition,
+            } => {
+                let left_tree = self.flatten(left, offset, terms);
+                let right_tree = self.flatten(right, offset + left.schema().len(), terms);
+                let local = condition.clone().map_or(vec![], Expr::conjuncts);
+                terms.extend(
+                    local
+                        .into_iter()
+                        .map(|term| remap(term, &|index| Some(index + offset))),
+                );
+                JoinTree::Join(Box::new(left_tree), Box::new(right_tree))
+            }
+            plan => {
+                self.inputs.push(plan.clone());
+                self.offsets.push(offset);
+                JoinTree::Input(self.inputs.len() - 1)
+            }
+        }
+    }
+
+    // Returns the set of inputs whose columns an expression reads.
+    fn inputs_of(&self, expr: &Expr) -> u64 {
+        expr.references()
+            .into_iter()
+            .map(|index| 1 << (self.offsets.partition_point(|&offset| offset <= index) - 1))
+            .fold(0, |inputs, input| inputs | input)
+    }
+
+    // Returns the estimated number of rows of the join of a set of inputs.
+    fn rows(&self, inputs: u64) -> f64 {
+        let rows = (0..self.inputs.len())
+            .filter(|input| inputs & (1 << input) != 0)
+            .map(|input| self.rows[input])
+            .product::<f64>();
+        self.conjuncts
+            .iter()
+            .filter(|conjunct| conjunct.inputs.count_ones() > 1)
+            .filter(|conjunct| conjunct.inputs & !inputs == 0)
+            .fold(rows, |rows, conjunct| rows * conjunct.selectivity)
+    }
+
+    // Returns the cost of the cheapest join of two disjoint sets of inputs.
+    fn join_cost(&self, left: u64, right: u64, left_rows: f64, right_rows: f64) -> f64 {
+        let within = |inputs: u64, set: u64| inputs & !set == 0;
+        let keys = self.conjuncts.iter().any(|conjunct| match conjunct.key {
+            Some((a, b)) => {
+                (within(a, left) && within(b, right)) || (within(a, right) && within(b, left))
+            }
+            None => false,
+        });
+        JoinMethod::cheapest(left_rows, right_rows, keys, false).cost(left_rows, right_rows, false)
+    }
+
+    // Returns the estimated cost of a tree of joins along with its number of
+    // rows.
+    fn cost(&self, tree: &JoinTree) -> (f64, f64) {
+        match tree {
+            JoinTree::Input(input) => (0.0, self.rows[*input]),
+            JoinTree::Join(left, right) => {
+                let ((left_cost, left_rows), (right_cost, right_rows)) =
+                    (self.cost(left), self.cost(right));
+                let rows = self.rows(tree.inputs());
+                let join = self.join_cost(left.inputs(), right.inputs(), left_rows, right_rows);
+                (left_cost + right_cost + join + rows, rows)
+            }
+        }
+    }
+
+    // Returns the cheapest of every tree of joins of the inputs, considering
+    // sets of inputs by increasing size and their splits in order so that
+    // ties keep the first one. Returns `None` without any input.
+    fn exhaustive(&self) -> Option<JoinTree> {
+        let all = (1usize << self.inputs.len()) - 1;
+        let rows = (0..=all)
+            .map(|inputs| self.rows(inputs as u64))
+            .collect::<Vec<_>>();
+        // The cost of the cheapest tree of each set of inputs along with the
+        // set of inputs of its left side, which is empty for a single input.
+        let mut best: Vec<Option<(f64, usize)>> = vec![None; all + 1];
+        for input in 0..self.inputs.len() {
+            best[1 << input] = Some((0.0, 0));
+        }
+        // Subsets of a set of inputs are lower than the set itself.
+        for inputs in 1..=all {
+            if inputs.count_ones() < 2 {
+                continue;
+            }
+            for left in (1..inputs).filter(|left| left & inputs == *left) {
+                let right = inputs ^ left;
+                let (Some((left_cost, _)), Some((right_cost, _))) = (best[left], best[right])
+                else {
+                    continue;
+                };
+                let join = self.join_cost(left as u64, right as u64, rows[left], rows[right]);
+                let cost = left_cost + right_cost + join + rows[inputs];
+                if best[inputs].is_none_or(|(best_cost, _)| cost < best_cost) {
+                    best[inputs] = Some((cost, left));
+                }
+            }
+        }
+        Self::split_tree(&best, all)
+    }
+
+    // Returns the tree of a set of inputs following the left side of the
+    // cheapest split of each set.
+    fn split_tree(best: &[Option<(f64, usize)>], inputs: usize) -> Option<JoinTree> {
+        match best.get(inputs).copied().flatten()? {
+            (_, 0) => Some(JoinTree::Input(inputs.trailing_zeros() as usize)),
+            (_, left) => Some(JoinTree::Join(
+                Box::new(Self::split_tree(best, left)?),
+                Box::new(Self::split_tree(best, inputs ^ left)?),
+            )),
+        }
+    }
+
+    // Returns the tree joining the cheapest pair of trees until a single one
+    // remains, starting from the inputs. Returns `None` without any input.
+    fn greedy(&self) -> Option<JoinTree> {
+        let mut trees = (0..self.inputs.len())
+            .map(|input| (0.0, JoinTree::Input(input)))
+            .collect::<Vec<_>>();
+        while trees.len() > 1 {
+            let mut cheapest: Option<(f64, usize, usize)> = None;
+            for (i, (left_cost, left)) in trees.iter().enumerate() {
+                for (j, (right_cost, right)) in trees.iter().enumerate() {
+                    if i == j {
+                        continue;
+                    }
+                    let (left, right) = (left.inputs(), right.inputs());
+                    let join = self.join_cost(left, right, self.rows(left), self.rows(right));
+                    let cost = left_cost + right_cost + join + self.rows(left | right);
+                    if cheapest.is_none_or(|(cheapest, _, _)| cost < cheapest) {
+                        cheapest = Some((cost, i, j));
+                    }
+                }
+            }
+            let (cost, i, j) = cheapest?;
+            let (first, second) = (i.min(j), i.max(j));
+            let second_tree = trees.remove(second).1;
+            let first_tree = trees.remove(first).1;
+            let (left, right) = if i < j {
+                (first_tree, second_tree)
+            } else {
+                (second_tree, first_tree)
+            };
+            trees.insert(
+                first,
+                (cost, JoinTree::Join(Box::new(left), Box::new(right))),
+            );
+        }
+        trees.pop().map(|(_, tree)| tree)
+    }
+
+    // Returns the plan of a tree of joins producing the columns of the
+    // original one in the order of its schema. Terms reading no column are
+    // evaluated on the rows of the whole tree.
+    fn build(&self, tree: &JoinTree, schema: Schema) -> LogicalPlan {
+        let (plan, columns) = self.build_tree(tree);
+        let constants = self
+            .conjuncts
+            .iter()
+            .filter(|conjunct| conjunct.inputs == 0)
+            .map(|conjunct| conjunct.expr.clone())
+            .collect();
+        let plan = filter(plan, constants);
+        if columns
+            .iter()
+            .enumerate()
+            .all(|(position, &index)| position == index)
+        {
+            return plan;
+        }
+        let positions = positions(&columns, columns.len());
+        let exprs = schema
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                Expr::Bound(Box::new(ColumnRef {
+                    table: field.table.clone(),
+                    name: field.name.clone(),
+                    index: positions[index].unwrap(),
+                    data_type: field.data_type.clone(),
+                    qualified: field.table.is_some(),
+                }))
+            })
+            .collect();
+        LogicalPlan::Project {
+            input: Box::new(plan),
+            exprs,
+            schema,
+        }
+    }
+
+    // Returns the plan of a tree of joins along with the index in the rows of
+    // the original tree of each of its columns. Terms are evaluated by the
+    // lowest join of the inputs they read.
+    fn build_tree(&self, tree: &JoinTree) -> (LogicalPlan, Vec<usize>) {
+        match tree {
+            JoinTree::Input(input) => {
+                let offset = self.offsets[*input];
+                let predicates = self
+                    .conjuncts
+                    .iter()
+                    .filter(|conjunct| conjunct.inputs == 1 << input)
+                    .map(|conjunct| remap(conjunct.expr.clone(), &|index| Some(index - offset)))
+                    .collect();
+                let plan = filter(self.inputs[*input].clone(), predicates);
+                let width = self.inputs[*input].schema().len();
+                (plan, (offset..offset + width).collect())
+            }
+            JoinTree::Join(left, right) => {
+                let (left_plan, mut columns) = self.build_tree(left);
+                let (right_plan, right_columns) = self.build_tree(right);
+                columns.extend(right_columns);
+                let (left, right) = (left.inputs(), right.inputs());
+                let width =
+                    self.offsets.last().unwrap() + self.inputs.last().unwrap().schema().len();
+                let positions = positions(&columns, width);
+                let terms = self
+                    .conjuncts
+                    .iter()
+                    .filter(|conjunct| conjunct.inputs & !(left | right) == 0)
+                    .filter(|conjunct| conjunct.inputs & left != 0 && conjunct.inputs & right != 0)
+                    .map(|conjunct| remap(conjunct.expr.clone(), &|index| positions[index]))
+                    .collect();
+                let plan = LogicalPlan::Join {
+                    left: Box::new(left_plan),
+                    right: Box::new(right_plan),
+                    condition: conjunction(terms),
+                };
+                (plan, columns)
+            }
+        }
+    }
+}
+
+/// Prune the columns no operator reads, down to the tables which then only
+/// read the columns the query needs.
+pub struct PushDownProjections;
+
+impl Rule for PushDownProjections {
+    fn name(&self) -> &'static str {
+        "push_down_projections"
+    }
+
+    fn rewrite(&self, plan: LogicalPlan, _catalog: &Catalog) -> LogicalPlan {
+        let required = (0..plan.schema().len()).collect();
+        Self::prune(plan, &required).0
+    }
+}
+
+impl PushDownProjections {
+    // Prunes the columns of a plan which are not required, returns the pruned
+    // plan along with the new index of each column of the original one.
+    fn prune(plan: LogicalPlan, required: &BTreeSet<usize>) -> (LogicalPlan, Vec<Option<usize>>) {
+        let width = plan.schema().len();
+        match plan {
+            LogicalPlan::Scan {
+                table,
+                schema,
+                projection,
+            } if required.len() < width => {
+                let kept = required.iter().copied().collect::<Vec<_>>();
+                let schema = Schema::new(
+                    kept.iter()
+                        .map(|&index| schema.field(index).clone())
+                        .collect(),
+                );
+                let projection = kept
+                    .iter()
+                    .map(|&index| projection.as_ref().map_or(index, |columns| columns[index]))
+                    .collect();
+                let plan = LogicalPlan::Scan {
+                    table,
+                    schema,
+                    projection: Some(projection),
+                };
+                (plan, positions(&kept, width))
+            }
+            LogicalPlan::Filter { input, predicate } => {
+                let mut needed = required.clone();
+                needed.extend(predicate.references());
+                let (input, mapping) = Self::prune(*input, &needed);
+                let predicate = remap(predicate, &|index| mapping[index]);
+                let plan = LogicalPlan::Filter {
+                    input: Box::new(input),
+                    predicate,
+                };
+                (plan, mapping)
+            }
+            LogicalPlan::Sort { input, key } => {
+                let mut needed = required.clone();
+                needed.extend(key.references());
+                let (input, mapping) = Self::prune(*input, &needed);
+                let key = remap(key, &|index| mapping[index]);
+                let plan = LogicalPlan::Sort {
+                    input: Box::new(input),
+                    key,
+                };
+                (plan, mapping)
+            }
+            LogicalPlan::Limit { input, count } => {
+                let (input, mapping) = Self::prune(*input, required);
+                let plan = LogicalPlan::Limit {
+                    input: Box::new(input),
+                    count,
+                };
+                (plan, mapping)
+            }
+            LogicalPlan::Project {
+                input,
+                exprs,
+                schema,
+            } => {
+                let kept = required.iter().copied().collect::<Vec<_>>();
+                let needed = kept
+                    .iter()
+                    .flat_map(|&index| exprs[index].references())
+                    .collect();
+                let (input, mapping) = Self::prune(*input, &needed);
+                let exprs = kept
+                    .iter()
+                    .map(|&index| remap(exprs[index].clone(), &|index| mapping[index]))
+                    .collect();
+                let schema = Schema::new(
+                    kept.iter()
+                        .map(|&index| schema.field(index).clone())
+                        .collect(),
+                );
+                let plan = LogicalPlan::Project {
+                    input: Box::new(input),
+                    exprs,
+                    schema,
+                };
+                (plan, positions(&kept, width))
+            }
+            LogicalPlan::Join {
+                left,
+                right,
+                condition,
+            } => {
+                let left_width = left.schema().len();
+                let mut needed = required.clone();
+                needed.extend(condition.iter().flat_map(Expr::references));
+                let (lefts, rights): (BTreeSet<_>, BTreeSet<_>) =
+                    needed.iter().partition(|&&index| index < left_width);
+                let rights = rights.iter().map(|index| index - left_width).collect();
+                let (left, left_mapping) = Self::prune(*left, &lefts);
+                let (right, right_mapping) = Self::prune(*right, &rights);
+                let offset = left.schema().len();
+                let mapping = left_mapping
+                    .into_iter()
+                    .chain(
+                        right_mapping
+                            .into_iter()
+                            .map(|index| index.map(|index| index + offset)),
+                    )
+                    .collect::<Vec<_>>();
+                let condition = condition.map(|expr| remap(expr, &|index| mapping[index]));
+                let plan = LogicalPlan::Join {
+                    left: Box::new(left),
+                    right: Box::new(right),
+                    condition,
+                };
+                (plan, mapping)
+            }
+            // The columns of the table function are kept, only the input is
+            // pruned.
+            LogicalPlan::LateralJoin {
+                input,
+                function,
+                args,
+                condition,
+                schema,
+            } => {
+                let input_width = input.schema().len();
+                let mut needed = required.clone();
+                needed.extend(args.iter().flat_map(Expr::references));
+                needed.extend(condition.iter().flat_map(Expr::references));
+                let needed = needed
+                    .into_iter()
+                    .filter(|&index| index < input_width)
+                    .collect();
+                let (input, input_mapping) = Self::prune(*input, &needed);
+                let args = args
+                    .into_iter()
+                    .map(|arg| remap(arg, &|index| input_mapping[index]))
+                    .collect();
+                let offset = input.schema().len();
+                let mapping = input_mapping
+                    .into_iter()
+                    .chain((offset..).take(width - input_width).map(Some))
+                    .collect::<Vec<_>>();
+                let condition = condition.map(|expr| remap(expr, &|index| mapping[index]));
+                let fields = schema.fields[input_width..].to_vec();
+                let schema = input.schema().join(&Schema::new(fields));
+                let plan = LogicalPlan::LateralJoin {
+                    input: Box::new(input),
+                    function,
+                    args,
+                    condition,
+                    schema,
+                };
+                (plan, mapping)
+            }
+            // Every group key and aggregate is kept, only the input is pruned.
+            LogicalPlan::Aggregate {
+                input,
+                group_by,
+                aggregates,
+                schema,
+            } => {
+                let needed = group_by
+                    .iter()
+                    .chain(aggregates.iter())
+                    .flat_map(Expr::references)
+                    .collect();
+                let (input, mapping) = Self::prune(*input, &needed);
+                let rewrite = |exprs: Vec<Expr>| -> Vec<Expr> {
+                    exprs
+                        .into_iter()
+                        .map(|expr| remap(expr, &|index| mapping[index]))
+                        .collect()
+                };
+                let plan = LogicalPlan::Aggregate {
+                    input: Box::new(input),
+                    group_by: rewrite(group_by),
+                    aggregates: rewrite(aggregates),
+                    schema,
+                };
+                (plan, (0..width).map(Some).collect())
+            }
+            plan => (plan, (0..width).map(Some).collect()),
+        }
+    }
+}
+
+/// Merge projections of projections into a single projection of the
+/// expressions of the outer one evaluated on the input of the inner one.
+pub struct MergeProjections;
+
+impl Rule for MergeProjections {
+    fn name(&self) -> &'static str {
+        "merge_projections"
+    }
+
+    fn rewrite(&self, plan: LogicalPlan, _catalog: &Catalog) -> LogicalPlan {
+        plan.transform_up(&mut |plan| match plan {
+            LogicalPlan::Project {
+                input,
+                exprs,
+                schema,
+            } => match *input {
+                LogicalPlan::Project {
+                    input,
+                    exprs: inner,
+                    ..
+                } => LogicalPlan::Project {
+                    input,
+                    exprs: exprs
+                        .into_iter()
+                        .map(|expr| substitute(expr, &inner))
+                        .collect(),
+                    schema,
+                },
+                input => LogicalPlan::Project {
+                    input: Box::new(input),
+                    exprs,
+                    schema,
+                },
+            },
+            plan => plan,
+        })
+    }
+}
+
+/// Remove sorts which cannot change the order of their input, because their
+/// key is constant, because the input is already sorted on the same key or
+/// because it produces at most one row.
+pub struct EliminateSorts;
+
+impl Rule for EliminateSorts {
+    fn name(&self) -> &'static str {
+        "eliminate_sorts"
+    }
+
+    fn rewrite(&self, plan: LogicalPlan, _catalog: &Catalog) -> LogicalPlan {
+        plan.transform_up(&mut |plan| match plan {
+            LogicalPlan::Sort { input, key } => {
+                if key.references().is_empty() || Self::at_most_one_row(&input) {
+                    return *input;
+                }
+                match *input {
+                    LogicalPlan::Sort {
+                        input,
+                        key: ref inner,
+                    } if *inner == key => LogicalPlan::Sort { input, key },
+                    input => LogicalPlan::Sort {
+                        input: Box::new(input),
+                        key,
+                    },
+                }
+            }
+            plan => plan,
+        })
+    }
+}
+
+impl EliminateSorts {
+    // Returns whether a plan is known to produce at most one row.
+    fn at_most_one_row(plan: &LogicalPlan) -> bool {
+        match plan {
+            LogicalPlan::Aggregate { group_by, .. } => group_by.is_empty(),
+            LogicalPlan::Limit { input, count } => *count <= 1 || Self::at_most_one_row(input),
+            LogicalPlan::Values { rows, .. } => rows.len() <= 1,
+            LogicalPlan::Filter { input, .. }
+            | LogicalPlan::Project { input, .. }
+            | LogicalPlan::Sort { input, .. } => Self::at_most_one_row(input),
+            _ => false,
+        }
+    }
+}
+
+// Returns a filter evaluating the predicates in order on the rows of the
+// input, the input itself without predicates.
+fn filter(input: LogicalPlan, predicates: Vec<Expr>) -> LogicalPlan {
+    predicates
+        .into_iter()
+        .fold(input, |input, predicate| LogicalPlan::Filter {
+            input: Box::new(input),
+            predicate,
+        })
+}
+
+// Returns the conjunction of the terms, none without terms.
+fn conjunction(terms: Vec<Expr>) -> Option<Expr> {
+    terms
+        .into_iter()
+        .reduce(|left, right| Expr::And(Box::new(left), Box::new(right)))
+}
+
+// Rewrites the column references of an expression to the new index of the
+// column they read, which must have been kept.
+fn remap(expr: Expr, mapping: &dyn Fn(usize) -> Option<usize>) -> Expr {
+    expr.transform(&mut |expr| match expr {
+        Expr::Bound(mut column) => {
+            column.index = mapping(column.index)
+                .unwrap_or_else(|| unreachable!("expected column {} to be kept", column.name));
+            Expr::Bound(column)
+        }
+        expr => expr,
+    })
+}
+
+// Replaces the column references of an expression by the expressions
+// computing the columns they read.
+fn substitute(expr: Expr, exprs: &[Expr]) -> Expr {
+    expr.transform(&mut |expr| match expr {
+        Expr::Bound(column) => exprs[column.index].clone(),
+        expr => expr,
+    })
+}
+
+// Returns the new index of each of the `width` columns of a plan when only the
+// kept ones remain.
+fn positions(kept: &[usize], width: usize) -> Vec<Option<usize>> {
+    let mut mapping = vec![None; width];
+    for (position, &index) in kept.iter().enumerate() {
+        mapping[index] = Some(position);
+    }
+    mapping
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binder::Binder;
+    use crate::catalog::{Changes, Column, Table};
+    use crate::functions::FunctionRegistry;
+    use crate::row::Row;
+    use crate::sql::{Parser, Tokenizer};
+    use crate::types::{DataType, Value};
+
+    fn catalog() -> Catalog {
+        let mut catalog = Catalog::new();
+        for (name, columns) in [
+            ("accounts", ["id", "owner", "balance"]),
+            ("orders", ["id", "account", "total"]),
+        ] {
+            let columns = columns
+                .iter()
+                .map(|column| Column::new(column, DataType::Integer))
+                .collect();
+            catalog
+                .create_table(Table::new(name, columns).unwrap())
+                .unwrap();
+        }
+        catalog
+    }
+
+    fn bind(sql: &str) -> LogicalPlan {
+        let query = Parser::new(Tokenizer::new(sql)).unwrap().parse().unwrap();
+        Binder::new(&catalog(), &FunctionRegistry::default())
+            .bind_query(query)
+            .unwrap()
+    }
+
+    // Describes the shape of a plan along with its predicates, projections
+    // and scanned columns.
+    fn describe(plan: &LogicalPlan) -> String {
+        let node = match plan {
+            LogicalPlan::Scan {
+                table, projection, ..
+            } => match projection {
+                Some(columns) => format!("Scan {table} {columns:?}"),
+                None => format!("Scan {table}"),
+            },
+            LogicalPlan::Filter { predicate, .. } => format!("Filter {predicate}"),
+            LogicalPlan::Project { exprs, .. } => format!("Project {}", list(exprs)),
+            LogicalPlan::Join { condition, .. } => match condition {
+                Some(condition) => format!("Join {condition}"),
+                None => "Join".to_string(),
+            },
+            LogicalPlan::Sort { key, .. } => format!("Sort {key}"),
+            LogicalPlan::Limit { count, .. } => format!("Limit {count}"),
+            LogicalPlan::Aggregate { .. } => "Aggregate".to_string(),
+            LogicalPlan::LateralJoin { function, .. } => format!("LateralJoin {function}"),
+            LogicalPlan::Values { .. } => "Values".to_string(),
+            LogicalPlan::TableFunction { name, .. } => format!("TableFunction {name}"),
+        };
+        let inputs = plan.inputs().into_iter().map(describe).collect::<Vec<_>>();
+        if inputs.is_empty() {
+            node
+        } else {
+            format!("{node} ({})", inputs.join(", "))
+        }
+    }
+
+    fn list(exprs: &[Expr]) -> String {
+        exprs
+            .iter()
+            .map(|expr| match expr {
+                Expr::Bound(column) => format!("#{}", column.index),
+                expr => expr.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn apply(rules: &[&dyn Rule], sql: &str) -> String {
+        let plan = rules
+            .iter()
+            .fold(bind(sql), |plan, rule| rule.rewrite(plan, &catalog()));
+        describe(&plan)
+    }
+
+    #[test]
+    fn expressions_are_simplified() {
+        assert_eq!(
+            apply(
+                &[&SimplifyExpressions],
+                "SELECT id + 2 * 3 FROM accounts WHERE 1 = 1 AND balance > 5 + 5 AND balance > 20"
+            ),
+            "Project id + 6 (Filter balance > 20 (Scan accounts))"
+        );
+        assert_eq!(
+            apply(
+                &[&SimplifyExpressions],
+                "SELECT id FROM accounts WHERE 1 = 1"
+            ),
+            "Project #0 (Scan accounts)"
+        );
+        assert_eq!(
+            apply(
+                &[&SimplifyExpressions],
+                "SELECT id FROM accounts WHERE id = 1 AND id = 2"
+            ),
+            "Project #0 (Values)"
+        );
+    }
+
+    #[test]
+    fn empty_relations_are_propagated() {
+        let rules: &[&dyn Rule] = &[&SimplifyExpressions, &EliminateEmptyRelations];
+        assert_eq!(
+            apply(
+                rules,
+                "SELECT a.id FROM accounts a JOIN orders o ON a.id = o.account AND 1 > 2 ORDER BY a.id"
+            ),
+            "Values"
+        );
+        // Aggregates without group keys produce a row from no rows.
+        assert_eq!(
+            apply(rules, "SELECT count(*) FROM accounts WHERE FALSE"),
+            "Project #0 (Aggregate (Values))"
+        );
+        assert_eq!(
+            apply(
+                rules,
+                "SELECT count(*) FROM accounts WHERE FALSE GROUP BY owner"
+            ),
+            "Values"
+        );
+    }
+
+    #[test]
+    fn conjunctions_are_split() {
+        assert_eq!(
+            apply(
+                &[&SplitConjunctions],
+                "SELECT id FROM accounts WHERE id > 1 AND owner = 2 AND balance < 3"
+            ),
+            "Project #0 (Filter balance < 3 (Filter owner = 2 (Filter id > 1 (Scan accounts))))"
+        );
+    }
+
+    #[test]
+    fn filters_are_pushed_into_joins() {
+        let sql = "SELECT a.owner FROM accounts a, orders o
+                   WHERE a.id = o.account AND o.total > 10 AND a.balance > 0";
+        assert_eq!(
+            apply(&[&SplitConjunctions, &PushDownFilters], sql),
+            "Project #1 (Join a.id = o.account (Filter a.balance > 0 (Scan accounts), \
+             Filter o.total > 10 (Scan orders)))"
+        );
+        // Without splitting the conjunction references both sides.
+        assert_eq!(
+            apply(&[&PushDownFilters], sql),
+            "Project #1 (Join a.id = o.account AND o.total > 10 AND a.balance > 0 \
+             (Scan accounts, Scan orders))"
+        );
+    }
+
+    #[test]
+    fn filters_are_not_pushed_below_limits_or_aggregates() {
+        assert_eq!(
+            apply(
+                &[&PushDownFilters],
+                "SELECT count(*) FROM accounts WHERE id > 1 GROUP BY owner"
+            ),
+            "Project #1 (Aggregate (Filter id > 1 (Scan accounts)))"
+        );
+        let plan = PushDownFilters.rewrite(
+            LogicalPlan::Filter {
+                input: Box::new(bind("SELECT id + 1 FROM accounts ORDER BY id LIMIT 2")),
+                predicate: Expr::Comparison(
+                    Box::new(Expr::Bound(Box::new(ColumnRef {
+                        table: None,
+                        name: "id + 1".to_string(),
+                        index: 0,
+                        data_type: DataType::Integer,
+                        qualified: false,
+                    }))),
+                    ">".to_string(),
+                    Box::new(Expr::Value(2)),
+                ),
+            },
+            &catalog(),
+        );
+        // The predicate is rewritten on the expression the projection
+        // computes but stays above the limit.
+        assert_eq!(
+            describe(&plan),
+            "Project id + 1 (Filter id + 1 > 2 (Limit 2 (Sort id (Scan accounts))))"
+        );
+    }
+
+    #[test]
+    fn filters_are_merged() {
+        assert_eq!(
+            apply(
+                &[&SplitConjunctions, &MergeFilters],
+                "SELECT id FROM accounts WHERE id > 1 AND owner = 2 AND balance < 3"
+            ),
+            "Project #0 (Filter id > 1 AND owner = 2 AND balance < 3 (Scan accounts))"
+        );
+    }
+
+    #[test]
+    fn unused_columns_are_pruned() {
+        assert_eq!(
+            apply(
+                &[&PushDownProjections],
+                "SELECT a.owner FROM accounts a, orders o WHERE a.id = o.account ORDER BY o.total"
+            ),
+            "Project #1 (Sort o.total (Filter a.id = o.account \
+             (Join (Scan accounts [0, 1], Scan orders [1, 2]))))"
+        );
+        // Scans without needed columns still produce their rows.
+        assert_eq!(
+            apply(&[&PushDownProjections], "SELECT count(*) FROM accounts"),
+            "Project #0 (Aggregate (Scan accounts []))"
+        );
+    }
+
+    #[test]
+    fn projections_are_merged() {
+        let inner = bind("SELECT balance, id FROM accounts");
+        let plan = LogicalPlan::Project {
+            schema: inner.schema(),
+            exprs: vec![
+                Expr::Bound(Box::new(ColumnRef {
+                    table: Some("accounts".to_string()),
+                    name: "id".to_string(),
+                    index: 1,
+                    data_type: DataType::Integer,
+                    qualified: false,
+                })),
+                Expr::Value(1),
+            ],
+            input: Box::new(inner),
+        };
+        assert_eq!(
+            describe(&MergeProjections.rewrite(plan, &catalog())),
+            "Project #0, 1 (Scan accounts)"
+        );
+    }
+
+    #[test]
+    fn redundant_sorts_are_eliminated() {
+        assert_eq!(
+            apply(&[&EliminateSorts], "SELECT id FROM accounts ORDER BY 1"),
+            "Project #0 (Scan accounts)"
+        );
+        assert_eq!(
+            apply(
+                &[&EliminateSorts],
+                "SELECT count(*) FROM accounts ORDER BY count(*)"
+            ),
+            "Project #0 (Aggregate (Scan accounts))"
+        );
+        let sorted = bind("SELECT id FROM accounts ORDER BY owner");
+        let LogicalPlan::Project { input, .. } = sorted else {
+            unreachable!()
+        };
+        let LogicalPlan::Sort { key, .. } = *input.clone() else {
+            unreachable!()
+        };
+        let plan = LogicalPlan::Sort { input, key };
+        assert_eq!(
+            describe(&EliminateSorts.rewrite(plan, &catalog())),
+            "Sort owner (Scan accounts)"
+        );
+    }
+
+    // Catalog of 100 accounts owned by 10 owners and 10 orders of different
+    // accounts, whose statistics are collected.
+    fn analyzed() -> Catalog {
+        let mut catalog = catalog();
+        for (name, rows) in [("accounts", 100), ("orders", 10)] {
+            let inserted = (0..rows)
+                .map(|i| Row::new(&[Value::Integer(i), Value::Integer(i % 10), Value::Integer(i)]))
+                .collect();
+            let changes = Changes {
+                inserted,
+                updated: vec![],
+                deleted: vec![],
+            };
+            catalog
+                .apply(name, changes, &|_, _| Box::new(|_: &Row| Ok(true)))
+                .unwrap();
+            catalog.analyze(name).unwrap();
+        }
+        catalog
+    }
+
+    #[test]
+    fn joins_are_reordered_by_cost() {
+        let catalog = analyzed();
+        let reorder = |sql| {
+            let plan = [&SplitConjunctions as &dyn Rule, &PushDownFilters]
+                .iter()
+                .fold(bind(sql), |plan, rule| rule.rewrite(plan, &catalog));
+            let schema = plan.schema();
+            let plan = ReorderJoins.rewrite(plan, &catalog);
+            assert_eq!(plan.schema(), schema);
+            describe(&plan)
+        };
+        // The small join of orders with their accounts is computed first.
+        assert_eq!(
+            reorder(
+                "SELECT o.total FROM accounts a, accounts b, orders o
+                 WHERE a.id = b.owner AND b.id = o.account"
+            ),
+            "Project #8 (Join a.id = b.owner (Scan accounts, \
+             Join b.id = o.account (Scan accounts, Scan orders)))"
+        );
+        // Cross products are avoided, columns are projected back in order.
+        assert_eq!(
+            reorder(
+                "SELECT o.total FROM accounts a, orders o, accounts b
+                 WHERE a.id = b.owner AND b.id = o.account"
+            ),
+            "Project #5 (Project #0, #1, #2, #6, #7, #8, #3, #4, #5 (Join a.id = b.owner (Scan accounts, \
+             Join b.id = o.account (Scan accounts, Scan orders))))"
+        );
+        // Plans are kept when no order is cheaper.
+        let sql = "SELECT a.id FROM accounts a, orders o WHERE a.id = o.account";
+        assert_eq!(
+            reorder(sql),
+            apply(&[&SplitConjunctions, &PushDownFilters], sql)
+        );
+    }
+
+    #[test]
+    fn large_joins_are_reordered_greedily() {
+        let catalog = analyzed();
+        let tables = (0..=EXHAUSTIVE_JOINS)
+            .map(|i| format!("accounts t{i}"))
+            .collect::<Vec<_>>();
+        let predicates = (1..=EXHAUSTIVE_JOINS)
+            .map(|i| format!("t{}.owner = t{i}.id", i - 1))
+            .collect::<Vec<_>>();
+        let sql = format!(
+            "SELECT t0.id FROM {}, orders o WHERE {} AND o.account = t{EXHAUSTIVE_JOINS}.id",
+            tables.join(", "),
+            predicates.join(" AND ")
+        );
+        let plan = [&SplitConjunctions as &dyn Rule, &PushDownFilters]
+            .iter()
+            .fold(bind(&sql), |plan, rule| rule.rewrite(plan, &catalog));
+        let schema = plan.schema();
+        let plan = ReorderJoins.rewrite(plan, &catalog);
+        assert_eq!(plan.schema(), schema);
+        // Every join still has a condition, the orders are hashed to be
+        // probed by their accounts.
+        let description = describe(&plan);
+        assert_eq!(description.matches("Join ").count(), EXHAUSTIVE_JOINS + 1);
+        assert!(!description.contains("Join ("));
+        assert!(description.contains(&format!(
+            "Join o.account = t{EXHAUSTIVE_JOINS}.id (Scan accounts, Scan orders)"
+        )));
+    }
+
+    #[test]
+    fn empty_join_graphs_have_no_tree() {
+        let graph = JoinGraph {
+            inputs: vec![],
+            offsets: vec![],
+            rows: vec![],
+            conjuncts: vec![],
+        };
+        assert_eq!(graph.exhaustive(), None);
+        assert_eq!(graph.greedy(), None);
+    }
+
+    #[test]
+    fn rules_can_be_disabled() {
+        let sql =
+            "SELECT a.owner FROM accounts a, orders o WHERE a.id = o.account AND o.total > 10";
+        let mut optimizer = Optimizer::new();
+        assert_eq!(
+            describe(&optimizer.optimize(bind(sql), &catalog())),
+            "Project #1 (Join a.id = o.account (Scan accounts [0, 1], \
+             Filter o.total > 10 (Scan orders [1, 2])))"
+        );
+        assert!(optimizer.set_enabled("push_down_projections", false));
+        assert!(!optimizer.set_enabled("unknown", false));
+        assert_eq!(
+            describe(&optimizer.optimize(bind(sql), &catalog())),
+            "Project #1 (Join a.id = o.account (Scan accounts, Filter o.total > 10 (Scan orders)))"
+        );
+        assert!(optimizer
+            .rules()
+            .contains(&("push_down_projections", false)));
+        assert_eq!(
+            describe(&Optimizer::empty().optimize(bind(sql), &catalog())),
+            describe(&bind(sql))
+        );
+    }
+}
@@ -0,0 +1,2888 @@
+//! Implementation of the planner building the operators which execute a
+//! statement, along with the evaluation of the expressions they compute.
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use crate::binder::Binder;
+use crate::catalog::{Catalog, CatalogError, Changes, Column, RowCheck, Table};
+use crate::cost::{Estimator, JoinKeys, JoinMethod};
+use crate::database::{DatabaseError, ResultSet};
+use crate::export::PlanNode;
+use crate::functions::{
+    Accumulator, AggregateFunction, FunctionRegistry, ScalarFunction, TableFunction,
+};
+use crate::logical::LogicalPlan;
+use crate::operators::{
+    explain, Aggregate, AggregateExpr, Annotated, ExecutionError, Filter, GroupKey, HashJoin, Join,
+    JoinCondition, JoinKey, LateralJoin, LeftJoin, Limit, Map, MergeJoin, Operator, Project, Scan,
+    Sort,
+};
+use crate::optimizer::Optimizer;
+use crate::row::Row;
+use crate::schema::{Field, Schema};
+use crate::sql::{
+    AlterTableAction, ColumnDef, ConflictAction, ConstraintDef, ConstraintKind, ExplainFormat,
+    Expr, InsertSource, MergeAction, MergeClause, OnConflict, Query, Statement, TableRef,
+};
+use crate::temporal::Clock;
+use crate::typecheck::{TypeChecker, TypeError};
+use crate::types::{DataType, Value};
+
+/// Scope expressions are evaluated in, made of the schema of the rows they
+/// are evaluated on and of the functions they can call.
+#[derive(Clone)]
+struct Scope {
+    schema: Schema,
+    functions: Rc<FunctionRegistry>,
+}
+
+/// Function laying out the values of an `INSERT` as a row of its table.
+type Completion = dyn Fn(&Row) -> Result<Row, ExecutionError>;
+
+/// Type checked action of a `MERGE` clause, inserted values are laid out as
+/// rows of the target table by the function they are paired with.
+enum MergeStep {
+    Update(Vec<(usize, Expr)>),
+    Delete,
+    Insert(Vec<Expr>, Box<Completion>),
+    DoNothing,
+}
+
+#[derive(Default)]
+pub struct QueryExecutor {
+    functions: Rc<FunctionRegistry>,
+    catalog: Catalog,
+    optimizer: Optimizer,
+}
+
+impl QueryExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a Rust closure as a scalar function callable by name from SQL
+    /// with the given argument and return types, its errors fail the query.
+    pub fn register_function<F>(
+        &mut self,
+        name: &str,
+        arg_types: &[DataType],
+        return_type: DataType,
+        fun: F,
+    ) where
+        F: Fn(&[Value]) -> Result<Value, ExecutionError> + 'static,
+    {
+        Rc::make_mut(&mut self.functions).register_scalar(ScalarFunction::new(
+            name,
+            arg_types,
+            return_type,
+            fun,
+        ));
+    }
+
+    /// Register an aggregate function backed by the accumulator `A` callable
+    /// by name from SQL with the given argument and return types.
+    pub fn register_aggregate<A: Accumulator + 'static>(
+        &mut self,
+        name: &str,
+        arg_types: &[DataType],
+        return_type: DataType,
+    ) {
+        Rc::make_mut(&mut self.functions).register_aggregate(AggregateFunction::new::<A>(
+            name,
+            arg_types,
+            return_type,
+        ));
+    }
+
+    pub fn execute(mut pipeline: Box<dyn Operator>) -> Result<Vec<Row>, ExecutionError> {
+        pipeline.open()?;
+        let mut results = Vec::new();
+        while let Some(row) = pipeline.next()? {
+            results.push(row);
+        }
+        pipeline.close();
+        Ok(results)
+    }
+
+    /// Execute a statement on the tables of the catalog, returns the rows it
+    /// produced along with their schema. DDL statements update the catalog
+    /// and produce no rows while DML statements produce the number of rows
+    /// they modified or the rows of their `RETURNING` clause. Referencing a
+    /// table which is not part of the catalog is an error.
+    pub fn run(&mut self, statement: Statement) -> Result<ResultSet, DatabaseError> {
+        match statement {
+            Statement::Query(query) => {
+                let (pipeline, schema) = self.plan_query(query)?;
+                let rows = Self::execute(pipeline)?;
+                return Ok(ResultSet { schema, rows });
+            }
+            Statement::Explain {
+                query,
+                analyze,
+                format,
+            } => {
+                let plan = self.optimized_plan(query)?;
+                let mut pipeline = self.lower(plan, analyze)?;
+                if analyze {
+                    pipeline.open()?;
+                    while pipeline.next()?.is_some() {}
+                    pipeline.close();
+                }
+                // Graphs and documents are returned as a single value.
+                let lines = match format {
+                    ExplainFormat::Text => explain(pipeline.as_ref()),
+                    ExplainFormat::Dot => vec![PlanNode::physical(pipeline.as_ref()).to_dot()],
+                    ExplainFormat::Json => vec![PlanNode::physical(pipeline.as_ref()).to_json()],
+                };
+                let rows = lines
+                    .into_iter()
+                    .map(|line| Row::new(&[Value::Varchar(line)]))
+                    .collect();
+                let schema = Schema::new(vec![Field::new("plan", DataType::Varchar)]);
+                return Ok(ResultSet { schema, rows });
+            }
+            Statement::Insert {
+                table,
+                columns,
+                source,
+                on_conflict,
+                returning,
+            } => return self.insert(&table, columns, source, on_conflict, returning),
+            Statement::Update {
+                table,
+                assignments,
+                filter,
+                returning,
+            } => return self.update(&table, assignments, filter, returning),
+            Statement::Delete {
+                table,
+                filter,
+                returning,
+            } => return self.delete(&table, filter, returning),
+            Statement::Merge {
+                target,
+                alias,
+                source,
+                on,
+                clauses,
+            } => return self.merge(&target, alias, source, on, clauses),
+            Statement::CreateTable {
+                name,
+                if_not_exists,
+                columns,
+                mut constraints,
+            } => {
+                if !(if_not_exists && self.catalog.table(&name).is_some()) {
+                    // Column constraints are added before table constraints.
+                    let mut column_constraints = vec![];
+                    let mut definitions = vec![];
+                    for column in columns {
+                        let (column, constraints) = self.define_column(column)?;
+                        column_constraints.extend(constraints);
+                        definitions.push(column);
+                    }
+                    column_constraints.append(&mut constraints);
+                    let table = Table::new(&name, definitions)?;
+                    let constraints =
+                        Self::check_constraints(column_constraints, &self.table_scope(&table))?;
+                    let result = self
+                        .catalog
+                        .create_table(table)
+                        .and_then(|_| self.add_constraints(&name, constraints));
+                    if let Err(error) = result {
+                        self.catalog.drop_table(&name).ok();
+                        return Err(error.into());
+                    }
+                }
+            }
+            Statement::CreateTableAs {
+                name,
+                if_not_exists,
+                query,
+            } => {
+                if !(if_not_exists && self.catalog.table(&name).is_some()) {
+                    let (pipeline, schema) = self.plan_query(query)?;
+                    let columns = schema
+                        .fields
+                        .iter()
+                        .map(|field| Column::new(&field.name, field.data_type.clone()))
+                        .collect();
+                    // The query runs first so a failing query leaves no table.
+                    let changes = Changes {
+                        inserted: Self::execute(pipeline)?,
+                        ..Changes::default()
+                    };
+                    Table::new(&name, columns)
+                        .and_then(|table| self.catalog.create_table(table))?;
+                    if let Err(error) = self.apply(&name, changes) {
+                        self.catalog.drop_table(&name).ok();
+                        return Err(error.into());
+                    }
+                }
+            }
+            Statement::DropTable { name, if_exists } => {
+                if !(if_exists && self.catalog.table(&name).is_none()) {
+                    self.catalog.drop_table(&name)?;
+                }
+            }
+            Statement::AlterTable { name, action } => self.alter_table(&name, action)?,
+            Statement::Analyze { table } => {
+                let names = match table {
+                    Some(name) => vec![name],
+                    None => self
+                        .catalog
+                        .tables()
+                        .map(|table| table.name.clone())
+                        .collect(),
+                };
+                for name in names {
+                    self.catalog.analyze(&name)?;
+                }
+            }
+        }
+        Ok(ResultSet::default())
+    }
+
+    /// Returns the catalog of the tables created by DDL statements.
+    pub fn catalog(&self) -> &Catalog {
+        &self.catalog
+    }
+
+    /// Set the clock read by `NOW()`, tests use a `FixedClock` to get
+    /// reproducible results.
+    pub fn set_clock<C: Clock + 'static>(&mut self, clock: C) {
+        Rc::make_mut(&mut self.functions).set_clock(clock);
+    }
+
+    /// Register a table function callable by name in the `FROM` clause.
+    pub fn register_table_function<F: TableFunction + 'static>(&mut self, name: &str, function: F) {
+        Rc::make_mut(&mut self.functions).register_table_function(name, function);
+    }
+
+    /// Returns the optimizer rewriting the logical plans of queries, whose
+    /// rules can be enabled and disabled.
+    pub fn optimizer_mut(&mut self) -> &mut Optimizer {
+        &mut self.optimizer
+    }
+
+    /// Returns the logical plan of a query on the tables of the catalog,
+    /// rewritten by the optimizer. See `PlanNode` to export it.
+    pub fn logical_plan(&self, query: Query) -> Result<LogicalPlan, DatabaseError> {
+        self.optimized_plan(query)
+    }
+
+    /// Returns the operators executing a query on the tables of the catalog,
+    /// annotated with the expressions they evaluate, the schema of their rows
+    /// and their estimated number of rows. See `PlanNode` to export them.
+    pub fn physical_plan(&self, query: Query) -> Result<Box<dyn Operator>, DatabaseError> {
+        let plan = self.optimized_plan(query)?;
+        self.lower(plan, false)
+    }
+
+    /// Plan a query, returns the operators producing its rows along with the
+    /// schema of these rows where columns are named after the expressions of
+    /// the select list.
+    fn plan_query(&self, query: Query) -> Result<(Box<dyn Operator>, Schema), DatabaseError> {
+        let plan = self.optimized_plan(query)?;
+        let schema = plan.schema();
+        Ok((self.lower(plan, false)?, schema))
+    }
+
+    /// Returns the logical plan of a query rewritten by the optimizer.
+    fn optimized_plan(&self, query: Query) -> Result<LogicalPlan, DatabaseError> {
+        let plan = Binder::new(&self.catalog, &self.functions).bind_query(query)?;
+        Ok(self.optimizer.optimize(plan, &self.catalog))
+    }
+
+    /// Plan the operators producing the rows of a `FROM` item along with the
+    /// schema of these rows.
+    fn plan_from(&self, item: TableRef) -> Result<(Box<dyn Operator>, Schema), DatabaseError> {
+        let plan = Binder::new(&self.catalog, &self.functions).bind_from(item)?;
+        let plan = self.optimizer.optimize(plan, &self.catalog);
+        let schema = plan.schema();
+        Ok((self.lower(plan, false)?, schema))
+    }
+
+    /// Lower a logical plan onto the operators executing it. Operators are
+    /// annotated with the expressions they evaluate and their estimated number
+    /// of rows, instrumented operators collect the metrics of their execution.
+    fn lower(
+        &self,
+        plan: LogicalPlan,
+        instrumented: bool,
+    ) -> Result<Box<dyn Operator>, DatabaseError> {
+        let parameters = match &plan {
+            // The limit describes its own count.
+            LogicalPlan::Limit { .. } => vec![],
+            plan => plan.parameters(),
+        };
+        let rows = Estimator::new(&self.catalog).rows(&plan);
+        let schema = plan.schema();
+        let operator = Annotated::new(self.lower_node(plan, instrumented)?, parameters)
+            .with_schema(schema)
+            .with_estimated_rows(rows);
+        if instrumented {
+            Ok(Box::new(operator.with_metrics()))
+        } else {
+            Ok(Box::new(operator))
+        }
+    }
+
+    /// Lower the root of a logical plan onto the operator executing it, its
+    /// inputs are lowered by `lower`.
+    fn lower_node(
+        &self,
+        plan: LogicalPlan,
+        instrumented: bool,
+    ) -> Result<Box<dyn Operator>, DatabaseError> {
+        Ok(match plan {
+            LogicalPlan::Scan {
+                table, projection, ..
+            } => {
+                let rows = self.table(&table)?.rows();
+                match projection {
+                    Some(columns) => {
+                        let rows = rows
+                            .iter()
+                            .map(|row| Row {
+                                items: columns.iter().map(|&i| row.items[i].clone()).collect(),
+                            })
+                            .collect::<Vec<_>>();
+                        Box::new(Scan::new(&rows))
+                    }
+                    None => Box::new(Scan::new(rows)),
+                }
+            }
+            LogicalPlan::Values { rows, .. } => {
+                let scope = self.scope(Schema::default());
+                let rows = rows
+                    .iter()
+                    .map(|row| {
+                        let items = row
+                            .iter()
+                            .map(|expr| Self::resolve(expr, &Row { items: vec![] }, &scope))
+                            .collect::<Result<_, _>>()?;
+                        Ok(Row { items })
+                    })
+                    .collect::<Result<Vec<_>, ExecutionError>>()?;
+                Box::new(Scan::new(&rows))
+            }
+            LogicalPlan::TableFunction { name, args, .. } => {
+                let function = self.table_function(name, args, Schema::default());
+                function(&Row { items: vec![] })?
+            }
+            LogicalPlan::Filter { input, predicate } => {
+                let scope = self.scope(input.schema());
+                let filter = move |row: &Row| Self::eval(&predicate, row, &scope);
+                Box::new(Filter::new(self.lower(*input, instrumented)?, filter))
+            }
+            // The Project operator selects columns, the Map operator computes
+            // expressions.
+            LogicalPlan::Project { input, exprs, .. } => {
+                let scope = self.scope(input.schema());
+                let input = self.lower(*input, instrumented)?;
+                let columns = exprs
+                    .iter()
+                    .map(|expr| match expr {
+                        Expr::Bound(column) => Some(column.index),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>();
+                if let Some(columns) = columns {
+                    Box::new(Project::new(input, &columns))
+                } else {
+                    let map = move |row: &Row| {
+                        let items = exprs
+                            .iter()
+                            .map(|expr| Self::resolve(expr, row, &scope))
+                            .collect::<Result<_, _>>()?;
+                        Ok(Row { items })
+                    };
+                    Box::new(Map::new(input, map))
+                }
+            }
+            LogicalPlan::Join {
+                left,
+                right,
+                condition,
+            } => {
+                let (left_schema, right_schema) = (left.schema(), right.schema());
+                let schema = left_schema.join(&right_schema);
+                let keys = JoinKeys::new(condition.clone(), left_schema.len());
+                let method = Estimator::new(&self.catalog).join_method(&left, &right, &keys);
+                let (left, right) = (
+                    self.lower(*left, instrumented)?,
+                    self.lower(*right, instrumented)?,
+                );
+                match method {
+                    JoinMethod::NestedLoop => {
+                        let condition = self.join_condition(condition, &schema);
+                        Box::new(Join::new(left, right, condition))
+                    }
+                    JoinMethod::Hash => Box::new(HashJoin::new(
+                        left,
+                        right,
+                        self.join_key(keys.left, left_schema),
+                        self.join_key(keys.right, right_schema),
+                        self.join_condition(keys.residual, &schema),
+                    )),
+                    JoinMethod::Merge => Box::new(MergeJoin::new(
+                        left,
+                        right,
+                        self.join_key(keys.left, left_schema),
+                        self.join_key(keys.right, right_schema),
+                        self.join_condition(keys.residual, &schema),
+                    )),
+                }
+            }
+            LogicalPlan::LateralJoin {
+                input,
+                function,
+                args,
+                condition,
+                schema,
+            } => {
+                let right = self.table_function(function, args, input.schema());
+                let condition = self.join_condition(condition, &schema);
+                Box::new(LateralJoin::new(
+                    self.lower(*input, instrumented)?,
+                    right,
+                    condition,
+                ))
+            }
+            LogicalPlan::Aggregate {
+                input,
+                group_by,
+                aggregates,
+                ..
+            } => {
+                let scope = self.scope(input.schema());
+                let partitions = Estimator::new(&self.catalog).partitions(&input);
+                Self::plan_aggregate(
+                    self.lower(*input, instrumented)?,
+                    &scope,
+                    &group_by,
+                    &aggregates,
+                    partitions,
+                )?
+            }
+            LogicalPlan::Sort { input, key } => {
+                let scope = self.scope(input.schema());
+                let sort_key = move |row: &Row| Self::resolve(&key, row, &scope);
+                Box::new(Sort::new(self.lower(*input, instrumented)?, sort_key))
+            }
+            LogicalPlan::Limit { input, count } => {
+                Box::new(Limit::new(self.lower(*input, instrumented)?, count))
+            }
+        })
+    }
+
+    /// Returns the scope of expressions evaluated on rows of the given schema.
+    fn scope(&self, schema: Schema) -> Scope {
+        Scope {
+            schema,
+            functions: self.functions.clone(),
+        }
+    }
+
+    /// Returns the function building the operator producing the rows of a
+    /// table function given a row of the schema its arguments are evaluated
+    /// on.
+    fn table_function(
+        &self,
+        name: String,
+        args: Vec<Expr>,
+        schema: Schema,
+    ) -> impl Fn(&Row) -> Result<Box<dyn Operator>, ExecutionError> {
+        let scope = self.scope(schema);
+        move |row: &Row| {
+            let args = args
+                .iter()
+                .map(|arg| Self::resolve(arg, row, &scope))
+                .collect::<Result<Vec<_>, _>>()?;
+            scope
+                .functions
+                .table_function(&name)
+                .ok_or_else(|| ExecutionError::Function(format!("Unknown function {name}")))?
+                .operator(&args)
+        }
+    }
+
+    /// Execute an `INSERT`, the source rows are cast to the types of the
+    /// target columns and completed with the defaults of the other columns.
+    fn insert(
+        &mut self,
+        name: &str,
+        columns: Vec<String>,
+        source: InsertSource,
+        on_conflict: Option<OnConflict>,
+        returning: Vec<Expr>,
+    ) -> Result<ResultSet, DatabaseError> {
+        let table = self.table(name)?;
+        let targets = Self::insert_targets(table, &columns)?;
+        let types = targets
+            .iter()
+            .map(|&index| table.columns[index].data_type.clone())
+            .collect::<Vec<_>>();
+        let complete = self.plan_completion(table, &targets);
+        let (returning, returning_scope) = self.check_returning(name, returning)?;
+        let scope = Scope {
+            schema: Schema::default(),
+            functions: self.functions.clone(),
+        };
+
+        // Start with the operators producing the values of the target columns
+        let mut pipeline: Box<dyn Operator> = match source {
+            InsertSource::Values(rows) => {
+                let mut values = vec![];
+                for exprs in rows {
+                    Self::check_arity(exprs.len(), targets.len())?;
+                    let mut items = vec![];
+                    for (expr, data_type) in exprs.into_iter().zip(&types) {
+                        let expr = Self::check_assignment(expr, data_type, &scope)?;
+                        items.push(Self::resolve(&expr, &Row { items: vec![] }, &scope)?);
+                    }
+                    values.push(Row { items });
+                }
+                Box::new(Scan::new(&values))
+            }
+            InsertSource::Query(query) => {
+                let (pipeline, schema) = self.plan_query(*query)?;
+                Self::check_arity(schema.len(), targets.len())?;
+                for (field, data_type) in schema.fields.iter().zip(&types) {
+                    if !field.data_type.assigns_to(data_type) {
+                        return Err(TypeError::InvalidCast {
+                            from: field.data_type.clone(),
+                            to: data_type.clone(),
+                        }
+                        .into());
+                    }
+                }
+                let cast = move |row: &Row| {
+                    let items = row
+                        .items
+                        .iter()
+                        .zip(&types)
+                        .map(|(value, data_type)| Self::cast(value.clone(), data_type))
+                        .collect::<Result<_, _>>()?;
+                    Ok(Row { items })
+                };
+                Box::new(Map::new(pipeline, cast))
+            }
+        };
+
+        // Apply the Map operator laying the values out as rows of the table
+        pipeline = Box::new(Map::new(pipeline, complete));
+
+        let mut rows = Self::execute(pipeline)?;
+        match on_conflict {
+            Some(on_conflict) => rows = self.upsert(name, rows, on_conflict)?,
+            None => {
+                let changes = Changes {
+                    inserted: rows.clone(),
+                    ..Changes::default()
+                };
+                self.apply(name, changes)?;
+            }
+        }
+        self.returning(returning, returning_scope, rows)
+    }
+
+    /// Insert the rows whose keys differ from those of every row of the table
+    /// for the unique constraint on the conflict columns, or for every unique
+    /// constraint without conflict columns. The action of the `ON CONFLICT`
+    /// clause applies to the conflicting row otherwise. Returns the inserted
+    /// and the updated rows.
+    ///
+    /// Keys holding a `NULL` value never conflict.
+    fn upsert(
+        &mut self,
+        name: &str,
+        rows: Vec<Row>,
+        on_conflict: OnConflict,
+    ) -> Result<Vec<Row>, DatabaseError> {
+        let table = self.table(name)?;
+        let columns = on_conflict
+            .columns
+            .iter()
+            .map(|column| Self::column_index(table, column))
+            .collect::<Result<Vec<_>, _>>()?;
+        let unique_keys = table
+            .unique_keys()
+            .into_iter()
+            .filter(|(_, key)| {
+                columns.is_empty()
+                    || (key.len() == columns.len() && key.iter().all(|c| columns.contains(c)))
+            })
+            .collect::<Vec<_>>();
+        if !columns.is_empty() && unique_keys.is_empty() {
+            return Err(DatabaseError(
+                "There is no unique constraint matching the ON CONFLICT columns".to_string(),
+            ));
+        }
+        // Assignments are evaluated on the existing row followed by the
+        // inserted one, which is referenced by the `excluded` table name.
+        let scope = Scope {
+            schema: table
+                .schema()
+                .qualify(name)
+                .join(&table.schema().qualify("excluded")),
+            functions: self.functions.clone(),
+        };
+        let action = match on_conflict.action {
+            ConflictAction::DoNothing => None,
+            ConflictAction::DoUpdate { .. } if columns.is_empty() => {
+                return Err(DatabaseError(
+                    "ON CONFLICT DO UPDATE requires conflict columns".to_string(),
+                ))
+            }
+            ConflictAction::DoUpdate {
+                assignments,
+                filter,
+            } => Some((
+                Self::check_assignments(table, assignments, &scope)?,
+                filter
+                    .map(|expr| Self::check_predicate(expr, &scope))
+                    .transpose()?,
+            )),
+        };
+
+        let (mut inserted, mut updates, mut affected) = (vec![], vec![], vec![]);
+        // Keys of the rows inserted by the statement for each unique
+        // constraint, along with the positions of the updated rows.
+        let mut inserted_keys = HashSet::new();
+        let mut updated_positions = HashSet::new();
+        for row in rows {
+            let keys = unique_keys
+                .iter()
+                .enumerate()
+                .filter_map(|(constraint, (_, columns))| {
+                    let key = columns
+                        .iter()
+                        .map(|&index| row.items[index].clone())
+                        .collect::<Vec<_>>();
+                    (!key.iter().any(Value::is_null)).then_some((constraint, key))
+                })
+                .collect::<Vec<_>>();
+            // Position of the conflicting row, which is `None` for a row
+            // inserted by the statement.
+            let conflict = keys.iter().find_map(|(constraint, key)| {
+                match table.find(unique_keys[*constraint].0, key) {
+                    Some(position) => Some(Some(position)),
+                    None => inserted_keys
+                        .contains(&(*constraint, key.clone()))
+                        .then_some(None),
+                }
+            });
+            let Some(position) = conflict else {
+                inserted_keys.extend(keys);
+                affected.push(row.clone());
+                inserted.push(row);
+                continue;
+            };
+            let Some((assignments, filter)) = &action else {
+                continue;
+            };
+            let Some(position) = position.filter(|&position| updated_positions.insert(position))
+            else {
+                return Err(DatabaseError(
+                    "ON CONFLICT DO UPDATE cannot affect a row a second time".to_string(),
+                ));
+            };
+            let mut joined = table.rows()[position].clone();
+            joined.items.extend(row.items);
+            if let Some(filter) = filter {
+                if !Self::eval(filter, &joined, &scope)? {
+                    continue;
+                }
+            }
+            let mut updated = table.rows()[position].clone();
+            for (index, expr) in assignments {
+                updated.items[*index] = Self::resolve(expr, &joined, &scope)?;
+            }
+            affected.push(updated.clone());
+            updates.push((position, updated));
+        }
+
+        let changes = Changes {
+            inserted,
+            updated: updates,
+            deleted: vec![],
+        };
+        self.apply(name, changes)?;
+        Ok(affected)
+    }
+
+    /// Execute a `MERGE`, source rows are left joined with the rows of the
+    /// target table and each joined row applies the first clause matching
+    /// it. Produces the number of inserted, updated and deleted rows.
+    fn merge(
+        &mut self,
+        name: &str,
+        alias: Option<String>,
+        source: TableRef,
+        on: Expr,
+        clauses: Vec<MergeClause>,
+    ) -> Result<ResultSet, DatabaseError> {
+        let table = self.table(name)?;
+        let target_schema = table.schema().qualify(alias.as_deref().unwrap_or(name));
+        let (source, source_schema) = self.plan_from(source)?;
+        let width = source_schema.len();
+        let source_scope = Scope {
+            schema: source_schema.clone(),
+            functions: self.functions.clone(),
+        };
+        let scope = Scope {
+            schema: source_schema.join(&target_schema),
+            functions: self.functions.clone(),
+        };
+
+        // Type check the clauses, matched rows are evaluated in the scope of
+        // the join while unmatched rows only have their source columns.
+        let mut checked = vec![];
+        for clause in clauses {
+            let clause_scope = if clause.matched {
+                &scope
+            } else {
+                &source_scope
+            };
+            let condition = clause
+                .condition
+                .map(|expr| Self::check_predicate(expr, clause_scope))
+                .transpose()?;
+            let step = match clause.action {
+                MergeAction::Update(assignments) => {
+                    MergeStep::Update(Self::check_assignments(table, assignments, &scope)?)
+                }
+                MergeAction::Delete => MergeStep::Delete,
+                MergeAction::Insert { columns, values } => {
+                    let targets = Self::insert_targets(table, &columns)?;
+                    Self::check_arity(values.len(), targets.len())?;
+                    let values = values
+                        .into_iter()
+                        .zip(&targets)
+                        .map(|(expr, &index)| {
+                            let data_type = &table.columns[index].data_type;
+                            Self::check_assignment(expr, data_type, &source_scope)
+                        })
+                        .collect::<Result<_, _>>()?;
+                    MergeStep::Insert(values, Box::new(self.plan_completion(table, &targets)))
+                }
+                MergeAction::DoNothing => MergeStep::DoNothing,
+            };
+            checked.push((clause.matched, condition, step));
+        }
+
+        // Join the source rows with the rows of the target table followed by
+        // their position, which is `NULL` for unmatched source rows.
+        let target = self.plan_positions(table, None, &scope)?;
+        let on = Self::check_predicate(on, &scope)?;
+        let condition = self.join_condition(Some(on), &scope.schema);
+        let pipeline = Box::new(LeftJoin::new(
+            source,
+            target,
+            table.columns.len() + 1,
+            condition,
+        ));
+
+        let (mut inserted, mut updates, mut deleted) = (vec![], vec![], vec![]);
+        let mut affected = HashSet::new();
+        for mut row in Self::execute(pipeline)? {
+            let position = match row.items.pop() {
+                Some(Value::BigInt(position)) => Some(position as usize),
+                _ => None,
+            };
+            let mut step = None;
+            for (matched, condition, clause_step) in &checked {
+                if *matched != position.is_some() {
+                    continue;
+                }
+                let clause_scope = if *matched { &scope } else { &source_scope };
+                if let Some(condition) = condition {
+                    if !Self::eval(condition, &row, clause_scope)? {
+                        continue;
+                    }
+                }
+                step = Some(clause_step);
+                break;
+            }
+            let Some(step) = step else {
+                continue;
+            };
+            if let (Some(position), MergeStep::Update(_) | MergeStep::Delete) = (position, step) {
+                if !affected.insert(position) {
+                    return Err(DatabaseError(
+                        "MERGE cannot affect a row a second time".to_string(),
+                    ));
+                }
+            }
+            match step {
+                MergeStep::Update(assignments) => {
+                    let mut updated = Row {
+                        items: row.items[width..].to_vec(),
+                    };
+                    for (index, expr) in assignments {
+                        updated.items[*index] = Self::resolve(expr, &row, &scope)?;
+                    }
+                    updates.push((position.unwrap(), updated));
+                }
+                MergeStep::Delete => deleted.push(position.unwrap()),
+                MergeStep::Insert(values, complete) => {
+                    let values = Row {
+                        items: values
+                            .iter()
+                            .map(|expr| Self::resolve(expr, &row, &source_scope))
+                            .collect::<Result<_, _>>()?,
+                    };
+                    inserted.push(complete(&values)?);
+                }
+                MergeStep::DoNothing => {}
+            }
+        }
+
+        let count = inserted.len() + updates.len() + deleted.len();
+        let changes = Changes {
+            inserted,
+            updated: updates,
+            deleted,
+        };
+        self.apply(name, changes)?;
+        Ok(Self::count(count))
+    }
+
+    /// Returns the indices of the columns of a table listed by an `INSERT`,
+    /// which are all the columns of the table when none is listed.
+    fn insert_targets(table: &Table, columns: &[String]) -> Result<Vec<usize>, DatabaseError> {
+        if columns.is_empty() {
+            return Ok((0..table.columns.len()).collect());
+        }
+        let mut targets = vec![];
+        for column in columns {
+            let index = Self::column_index(table, column)?;
+            if targets.contains(&index) {
+                return Err(DatabaseError(format!(
+                    "Column {column} is specified more than once"
+                )));
+            }
+            targets.push(index);
+        }
+        Ok(targets)
+    }
+
+    /// Ensure an inserted row has one value per target column.
+    fn check_arity(values: usize, targets: usize) -> Result<(), DatabaseError> {
+        let message = match values.cmp(&targets) {
+            Ordering::Greater => "INSERT has more expressions than target columns",
+            Ordering::Less => "INSERT has more target columns than expressions",
+            Ordering::Equal => return Ok(()),
+        };
+        Err(DatabaseError(message.to_string()))
+    }
+
+    /// Returns the function laying out the values of the target columns of an
+    /// `INSERT` as a row of the table, other columns take their default.
+    fn plan_completion(
+        &self,
+        table: &Table,
+        targets: &[usize],
+    ) -> impl Fn(&Row) -> Result<Row, ExecutionError> + 'static {
+        let scope = Scope {
+            schema: Schema::default(),
+            functions: self.functions.clone(),
+        };
+        let columns = table
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| {
+                let source = targets.iter().position(|&target| target == index);
+                (source, column.default.clone())
+            })
+            .collect::<Vec<_>>();
+        move |row: &Row| {
+            let items = columns
+                .iter()
+                .map(|(source, default)| match (source, default) {
+                    (Some(source), _) => Ok(row.items[*source].clone()),
+                    (None, Some(expr)) => Self::resolve(expr, &Row { items: vec![] }, &scope),
+                    (None, None) => Ok(Value::Null),
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(Row { items })
+        }
+    }
+
+    /// Type check the assignments of a `SET` clause to columns of a table,
+    /// returns the indices of the assigned columns along with the checked
+    /// expressions.
+    fn check_assignments(
+        table: &Table,
+        assignments: Vec<(String, Expr)>,
+        scope: &Scope,
+    ) -> Result<Vec<(usize, Expr)>, DatabaseError> {
+        let mut targets = vec![];
+        let mut checked = vec![];
+        for (column, expr) in assignments {
+            let index = Self::column_index(table, &column)?;
+            if targets.contains(&index) {
+                return Err(DatabaseError(format!(
+                    "Column {column} is assigned more than once"
+                )));
+            }
+            targets.push(index);
+            let data_type = &table.columns[index].data_type;
+            checked.push((index, Self::check_assignment(expr, data_type, scope)?));
+        }
+        Ok(checked)
+    }
+
+    /// Execute an `UPDATE`, assignments are evaluated on the rows matching the
+    /// filter before any of their columns is updated.
+    fn update(
+        &mut self,
+        name: &str,
+        assignments: Vec<(String, Expr)>,
+        filter: Option<Expr>,
+        returning: Vec<Expr>,
+    ) -> Result<ResultSet, DatabaseError> {
+        let table = self.table(name)?;
+        let scope = self.table_scope(table);
+        let assignments = Self::check_assignments(table, assignments, &scope)?;
+        let (returning, returning_scope) = self.check_returning(name, returning)?;
+        let mut pipeline = self.plan_positions(table, filter, &scope)?;
+
+        // Apply the Map operator computing the updated rows, which keep their
+        // trailing position.
+        let map = move |row: &Row| {
+            let mut updated = row.clone();
+            for (index, expr) in &assignments {
+                updated.items[*index] = Self::resolve(expr, row, &scope)?;
+            }
+            Ok(updated)
+        };
+        pipeline = Box::new(Map::new(pipeline, map));
+
+        let updates = Self::execute(pipeline)?
+            .into_iter()
+            .map(|mut row| (Self::position(&mut row), row))
+            .collect::<Vec<_>>();
+        let rows = updates.iter().map(|(_, row)| row.clone()).collect();
+        let changes = Changes {
+            updated: updates,
+            ..Changes::default()
+        };
+        self.apply(name, changes)?;
+        self.returning(returning, returning_scope, rows)
+    }
+
+    /// Execute a `DELETE` removing the rows matching the filter.
+    fn delete(
+        &mut self,
+        name: &str,
+        filter: Option<Expr>,
+        returning: Vec<Expr>,
+    ) -> Result<ResultSet, DatabaseError> {
+        let table = self.table(name)?;
+        let scope = self.table_scope(table);
+        let (returning, returning_scope) = self.check_returning(name, returning)?;
+        let (deleted, rows) = Self::execute(self.plan_positions(table, filter, &scope)?)?
+            .into_iter()
+            .map(|mut row| (Self::position(&mut row), row))
+            .unzip();
+        let changes = Changes {
+            deleted,
+            ..Changes::default()
+        };
+        self.apply(name, changes)?;
+        self.returning(returning, returning_scope, rows)
+    }
+
+    /// Plan the operators producing the rows of a table matching a filter,
+    /// every row is followed by its position in the table which is not part
+    /// of the schema of the scope.
+    fn plan_positions(
+        &self,
+        table: &Table,
+        filter: Option<Expr>,
+        scope: &Scope,
+    ) -> Result<Box<dyn Operator>, TypeError> {
+        let rows = table
+            .rows()
+            .iter()
+            .enumerate()
+            .map(|(position, row)| {
+                let mut row = row.clone();
+                row.items.push(Value::BigInt(position as i64));
+                row
+            })
+            .collect::<Vec<_>>();
+        let mut pipeline: Box<dyn Operator> = Box::new(Scan::new(&rows));
+        if let Some(expr) = filter {
+            let expr = Self::check_predicate(expr, scope)?;
+            let filter_scope = scope.clone();
+            let filter = move |row: &Row| Self::eval(&expr, row, &filter_scope);
+            pipeline = Box::new(Filter::new(pipeline, filter));
+        }
+        Ok(pipeline)
+    }
+
+    /// Remove the trailing position from a row produced by `plan_positions`.
+    fn position(row: &mut Row) -> usize {
+        match row.items.pop() {
+            Some(Value::BigInt(position)) => position as usize,
+            item => unreachable!("expected row position got {item:?}"),
+        }
+    }
+
+    /// Type check the `RETURNING` clause of a DML statement, which is
+    /// evaluated on the rows of the table it modifies.
+    fn check_returning(
+        &self,
+        name: &str,
+        returning: Vec<Expr>,
+    ) -> Result<(Vec<(String, Expr)>, Scope), DatabaseError> {
+        let scope = self.table_scope(self.table(name)?);
+        let returning = returning
+            .into_iter()
+            .map(|expr| Ok((Binder::output_name(&expr), Self::check(expr, &scope)?)))
+            .collect::<Result<_, TypeError>>()?;
+        Ok((returning, scope))
+    }
+
+    /// Returns the rows produced by a DML statement, its `RETURNING`
+    /// expressions evaluated on the rows it modified, or the number of these
+    /// rows without a `RETURNING` clause.
+    fn returning(
+        &self,
+        returning: Vec<(String, Expr)>,
+        scope: Scope,
+        rows: Vec<Row>,
+    ) -> Result<ResultSet, DatabaseError> {
+        if returning.is_empty() {
+            return Ok(Self::count(rows.len()));
+        }
+        let (names, returning): (Vec<_>, Vec<_>) = returning.into_iter().unzip();
+        let schema = Binder::new(&self.catalog, &self.functions).output_schema(
+            &names,
+            &returning,
+            &scope.schema,
+        )?;
+        let map = move |row: &Row| {
+            let items = returning
+                .iter()
+                .map(|expr| Self::resolve(expr, row, &scope))
+                .collect::<Result<_, _>>()?;
+            Ok(Row { items })
+        };
+        let rows = Self::execute(Box::new(Map::new(Box::new(Scan::new(&rows)), map)))?;
+        Ok(ResultSet { schema, rows })
+    }
+
+    /// Returns the single row holding the number of rows modified by a DML
+    /// statement.
+    fn count(count: usize) -> ResultSet {
+        ResultSet {
+            schema: Schema::new(vec![Field::new("count", DataType::BigInt)]),
+            rows: vec![Row::new(&[Value::BigInt(count as i64)])],
+        }
+    }
+
+    /// Returns a table of the catalog, fails if there is no such table.
+    fn table(&self, name: &str) -> Result<&Table, CatalogError> {
+        self.catalog
+            .table(name)
+            .ok_or_else(|| CatalogError::UnknownTable(name.to_string()))
+    }
+
+    /// Returns the scope of expressions evaluated on the rows of a table.
+    fn table_scope(&self, table: &Table) -> Scope {
+        Scope {
+            schema: table.schema().qualify(&table.name),
+            functions: self.functions.clone(),
+        }
+    }
+
+    /// Returns the index of a column of a table, fails if there is no such
+    /// column.
+    fn column_index(table: &Table, column: &str) -> Result<usize, CatalogError> {
+        table
+            .column_index(column)
+            .ok_or_else(|| CatalogError::UnknownColumn {
+                table: table.name.clone(),
+                column: column.to_string(),
+            })
+    }
+
+    /// Type check an expression whose value is stored in a column of the
+    /// given type, it is cast to that type when it has another type.
+    fn check_assignment(
+        expr: Expr,
+        data_type: &DataType,
+        scope: &Scope,
+    ) -> Result<Expr, TypeError> {
+        let expr = Self::check(expr, scope)?;
+        let expr_type = Self::type_of(&expr, scope)?;
+        if expr_type == *data_type {
+            return Ok(expr);
+        }
+        // String literals are read as values of the column type.
+        if !expr_type.assigns_to(data_type) && !matches!(expr, Expr::Varchar(_)) {
+            return Err(TypeError::InvalidCast {
+                from: expr_type,
+                to: data_type.clone(),
+            });
+        }
+        Self::check(Expr::Cast(Box::new(expr), data_type.clone()), scope)
+    }
+
+    /// Apply an `ALTER TABLE` action to a table of the catalog, columns added
+    /// to a table fill its existing rows with their default.
+    fn alter_table(&mut self, name: &str, action: AlterTableAction) -> Result<(), DatabaseError> {
+        match action {
+            AlterTableAction::AddColumn(column) => {
+                let (column, constraints) = self.define_column(column)?;
+                let value = match &column.default {
+                    Some(expr) => {
+                        let scope = Scope {
+                            schema: Schema::default(),
+                            functions: self.functions.clone(),
+                        };
+                        Self::resolve(expr, &Row { items: vec![] }, &scope)?
+                    }
+                    None => Value::Null,
+                };
+                // Constraints are type checked against the table as it is
+                // once the column is added, before the catalog changes.
+                let mut columns = self.table(name)?.columns.clone();
+                columns.push(column.clone());
+                let scope = self.table_scope(&Table::new(name, columns)?);
+                let constraints = Self::check_constraints(constraints, &scope)?;
+                let column_name = column.name.clone();
+                self.catalog.add_column(name, column, value)?;
+                // The column is only added along with its constraints.
+                if let Err(error) = self.add_constraints(name, constraints) {
+                    self.catalog.drop_column(name, &column_name).ok();
+                    return Err(error.into());
+                }
+                Ok(())
+            }
+            AlterTableAction::DropColumn {
+                name: column,
+                if_exists,
+            } => match self.catalog.table(name) {
+                Some(table) if if_exists && table.column_index(&column).is_none() => Ok(()),
+                _ => Ok(self.catalog.drop_column(name, &column)?),
+            },
+            AlterTableAction::RenameColumn { from, to } => {
+                Ok(self.catalog.rename_column(name, &from, &to)?)
+            }
+        }
+    }
+
+    /// Returns the catalog column of a column definition along with its
+    /// constraints, its default is type checked and cast to the type of the
+    /// column.
+    fn define_column(&self, column: ColumnDef) -> Result<(Column, Vec<ConstraintDef>), TypeError> {
+        let scope = Scope {
+            schema: Schema::default(),
+            functions: self.functions.clone(),
+        };
+        let default = column
+            .default
+            .map(|expr| Self::check_assignment(expr, &column.data_type, &scope))
+            .transpose()?;
+        let definition = Column {
+            name: column.name,
+            data_type: column.data_type,
+            not_null: column.not_null,
+            default,
+        };
+        Ok((definition, column.constraints))
+    }
+
+    /// Type check the predicates of check constraints, which are evaluated
+    /// on the rows of their table. Their column references are left unbound
+    /// so the catalog can follow the columns they reference by name.
+    fn check_constraints(
+        constraints: Vec<ConstraintDef>,
+        scope: &Scope,
+    ) -> Result<Vec<ConstraintDef>, DatabaseError> {
+        let mut checked = vec![];
+        for constraint in constraints {
+            checked.push(match constraint.kind {
+                ConstraintKind::Check(expr) => ConstraintDef {
+                    kind: ConstraintKind::Check(
+                        TypeChecker::new(&scope.schema, &scope.functions).check_predicate(expr)?,
+                    ),
+                    ..constraint
+                },
+                _ => constraint,
+            });
+        }
+        Ok(checked)
+    }
+
+    /// Add type checked constraints to a table of the catalog.
+    fn add_constraints(
+        &mut self,
+        name: &str,
+        constraints: Vec<ConstraintDef>,
+    ) -> Result<(), CatalogError> {
+        let check = self.constraint_check();
+        for constraint in constraints {
+            self.catalog
+                .add_constraint(name, constraint.name, constraint.kind, &check)?;
+        }
+        Ok(())
+    }
+
+    /// Apply the changes of a DML statement to a table of the catalog, fails
+    /// if they violate any of its constraints.
+    fn apply(&mut self, name: &str, changes: Changes) -> Result<(), CatalogError> {
+        let check = self.constraint_check();
+        self.catalog.apply(name, changes, &check)
+    }
+
+    /// Returns the function binding the predicate of a check constraint to
+    /// the current columns of its table, into the function evaluating it on
+    /// the rows of the table, which only violate it when it is false.
+    fn constraint_check(&self) -> impl Fn(&Table, &Expr) -> Box<RowCheck> + 'static {
+        let functions = self.functions.clone();
+        move |table: &Table, expr: &Expr| {
+            let scope = Scope {
+                schema: table.schema().qualify(&table.name),
+                functions: functions.clone(),
+            };
+            let expr = Binder::resolve_columns(expr.clone(), &scope.schema);
+            Box::new(move |row: &Row| {
+                let value = Self::resolve(&expr, row, &scope)?;
+                Ok(!matches!(value, Value::Boolean(false)))
+            })
+        }
+    }
+
+    /// Returns the condition joining rows whose concatenation follows the
+    /// given schema, rows are joined unconditionally without a condition.
+    fn join_condition(&self, condition: Option<Expr>, schema: &Schema) -> Box<JoinCondition> {
+        match condition {
+            Some(expr) => {
+                let scope = self.scope(schema.clone());
+                Box::new(move |left: &Row, right: &Row| {
+                    let mut row = left.clone();
+                    row.items.extend(right.items.iter().cloned());
+                    Self::eval(&expr, &row, &scope)
+                })
+            }
+            None => Box::new(|_: &Row, _: &Row| Ok(true)),
+        }
+    }
+
+    /// Returns the function computing the key of a join from the rows of one
+    /// of its inputs, whose schema is given.
+    fn join_key(&self, exprs: Vec<Expr>, schema: Schema) -> Box<JoinKey> {
+        let scope = self.scope(schema);
+        Box::new(move |row: &Row| {
+            exprs
+                .iter()
+                .map(|expr| Self::resolve(expr, row, &scope))
+                .collect()
+        })
+    }
+
+    /// Plan the Aggregate operator computing the given group keys and
+    /// aggregate calls over the given number of partitions.
+    fn plan_aggregate(
+        input: Box<dyn Operator>,
+        scope: &Scope,
+        group_by: &[Expr],
+        aggregates: &[Expr],
+        partitions: usize,
+    ) -> Result<Box<dyn Operator>, DatabaseError> {
+        let mut keys: Vec<Box<GroupKey>> = vec![];
+        for expr in group_by {
+            let (expr, key_scope) = (expr.clone(), scope.clone());
+            keys.push(Box::new(move |row: &Row| {
+                Self::resolve(&expr, row, &key_scope)
+            }));
+        }
+
+        let mut exprs = vec![];
+        for expr in aggregates {
+            let Expr::Function(name, args) = expr else {
+                unreachable!("expected aggregate to be a function call got {expr}")
+            };
+            let arg_types = args
+                .iter()
+                .map(|arg| Self::type_of(arg, scope))
+                .collect::<Result<Vec<_>, _>>()?;
+            let function = scope.functions.aggregate(name, &arg_types).ok_or_else(|| {
+                DatabaseError(format!(
+                    "No aggregate {name} accepting arguments {arg_types:?}"
+                ))
+            })?;
+            let (args, args_scope) = (args.clone(), scope.clone());
+            exprs.push(AggregateExpr::new(function, move |row: &Row| {
+                args.iter()
+                    .map(|arg| Self::resolve(arg, row, &args_scope))
+                    .collect()
+            }));
+        }
+
+        Ok(Box::new(
+            Aggregate::new(input, keys, exprs).with_partitions(partitions),
+        ))
+    }
+
+    /// Type check an expression evaluated in the given scope, returns the
+    /// expression with implicit coercions made explicit and column references
+    /// bound to the columns of the scope.
+    fn check(expr: Expr, scope: &Scope) -> Result<Expr, TypeError> {
+        TypeChecker::new(&scope.schema, &scope.functions)
+            .check(expr)
+            .map(|(expr, _)| Binder::resolve_columns(expr, &scope.schema))
+    }
+
+    /// Type check a predicate evaluated in the given scope.
+    fn check_predicate(expr: Expr, scope: &Scope) -> Result<Expr, TypeError> {
+        TypeChecker::new(&scope.schema, &scope.functions)
+            .check_predicate(expr)
+            .map(|expr| Binder::resolve_columns(expr, &scope.schema))
+    }
+
+    /// Returns the type of the values an expression evaluates to.
+    fn type_of(expr: &Expr, scope: &Scope) -> Result<DataType, TypeError> {
+        TypeChecker::new(&scope.schema, &scope.functions).type_of(expr)
+    }
+
+    /// Cast a value to the given type, fails if it has no value of that
+    /// type.
+    fn cast(value: Value, data_type: &DataType) -> Result<Value, ExecutionError> {
+        value
+            .cast(data_type)
+            .ok_or_else(|| ExecutionError::InvalidCast {
+                value,
+                data_type: data_type.clone(),
+            })
+    }
+
+    fn resolve(expr: &Expr, row: &Row, scope: &Scope) -> Result<Value, ExecutionError> {
+        Ok(match expr {
+            Expr::Bound(column) => row.items.get(column.index).cloned().ok_or_else(|| {
+                ExecutionError::InvalidExpression(format!("Column {expr} is not in the row"))
+            })?,
+            Expr::Column(_) | Expr::QualifiedColumn(_, _) => {
+                return Err(ExecutionError::InvalidExpression(format!(
+                    "Column {expr} is not bound"
+                )))
+            }
+            Expr::Null => Value::Null,
+            Expr::Boolean(value) => Value::Boolean(*value),
+            Expr::Value(value) => Value::integer(*value),
+            Expr::Numeric(numeric) => Value::numeric(numeric).ok_or_else(|| {
+                ExecutionError::InvalidExpression(format!("Invalid numeric literal {numeric}"))
+            })?,
+            Expr::Constant(value) => value.clone(),
+            // Both sides have the same type once type checked, comparisons
+            // involving `NULL` are `NULL`.
+            Expr::Comparison(left, op, right) => {
+                let left = Self::resolve(left, row, scope)?;
+                let right = Self::resolve(right, row, scope)?;
+                if left.is_null() || right.is_null() {
+                    return Ok(Value::Null);
+                }
+                Value::Boolean(left.compare(op, &right).ok_or_else(|| {
+                    ExecutionError::InvalidExpression(format!("Unknown comparison operator {op}"))
+                })?)
+            }
+            // Conjunctions and disjunctions follow three-valued logic, the
+            // right side is only evaluated when the left side does not
+            // determine the result.
+            Expr::And(left, right) => match Self::resolve(left, row, scope)? {
+                Value::Boolean(false) => Value::Boolean(false),
+                left => match (left, Self::resolve(right, row, scope)?) {
+                    (_, Value::Boolean(false)) => Value::Boolean(false),
+                    (Value::Boolean(true), right) => right,
+                    _ => Value::Null,
+                },
+            },
+            Expr::Or(left, right) => match Self::resolve(left, row, scope)? {
+                Value::Boolean(true) => Value::Boolean(true),
+                left => match (left, Self::resolve(right, row, scope)?) {
+                    (_, Value::Boolean(true)) => Value::Boolean(true),
+                    (Value::Boolean(false), right) => right,
+                    _ => Value::Null,
+                },
+            },
+            Expr::Not(expr) => match Self::resolve(expr, row, scope)? {
+                Value::Boolean(value) => Value::Boolean(!value),
+                value => value,
+            },
+            Expr::IsNull(expr, negated) => {
+                Value::Boolean(Self::resolve(expr, row, scope)?.is_null() != *negated)
+            }
+            Expr::Arithmetic(left, op, right) => Self::resolve(left, row, scope)?
+                .arithmetic(op, &Self::resolve(right, row, scope)?)?,
+            Expr::Negate(expr) => Self::resolve(expr, row, scope)?.negate()?,
+            Expr::Varchar(varchar) => Value::Varchar(varchar.clone()),
+            Expr::Array(elements) => Value::Array(
+                elements
+                    .iter()
+                    .map(|element| Self::resolve(element, row, scope))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Expr::Function(name, args) => {
+                let Some(function) = scope.functions.scalar(name) else {
+                    if scope.functions.is_aggregate(name) {
+                        return Err(ExecutionError::Function(format!(
+                            "Aggregate function {name} is not allowed here"
+                        )));
+                    }
+                    return Err(ExecutionError::Function(format!("Unknown function {name}")));
+                };
+                let args = args
+                    .iter()
+                    .map(|arg| Self::resolve(arg, row, scope))
+                    .collect::<Result<Vec<_>, _>>()?;
+                function.invoke(&args)?
+            }
+            Expr::Cast(expr, data_type) => Self::cast(Self::resolve(expr, row, scope)?, data_type)?,
+            Expr::Extract(field, expr) => {
+                let value = Self::resolve(expr, row, scope)?;
+                match value.extract(*field) {
+                    Some(extracted) => extracted,
+                    None => {
+                        return Err(ExecutionError::InvalidField {
+                            field: *field,
+                            value,
+                        })
+                    }
+                }
+            }
+        })
+    }
+
+    /// Evaluate a predicate, which is satisfied only when it is true and
+    /// neither false nor `NULL`.
+    fn eval(expr: &Expr, row: &Row, scope: &Scope) -> Result<bool, ExecutionError> {
+        Ok(matches!(
+            Self::resolve(expr, row, scope)?,
+            Value::Boolean(true)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decimal::{Decimal, MAX_PRECISION};
+    use crate::sql::{parse_script, Parser, Tokenizer};
+    use crate::temporal::{FixedClock, Timestamp};
+    use crate::types::ArithmeticError;
+
+    // Runs a query on an executor after creating the `employees` table.
+    macro_rules! query {
+        ($query_str:expr) => {{
+            query!(QueryExecutor::new(), $query_str)
+        }};
+        ($executor:expr, $query_str:expr) => {{
+            let tokenizer = Tokenizer::new($query_str);
+            let q = Parser::new(tokenizer).unwrap().parse().unwrap();
+
+            let mut executor = $executor;
+            create_employees(&mut executor);
+            executor.run(Statement::Query(q)).unwrap().rows
+        }};
+    }
+
+    // Runs a failing query on an executor after creating the `employees`
+    // table, returns the message of its error.
+    macro_rules! query_error {
+        ($query_str:expr) => {{
+            query_error!(QueryExecutor::new(), $query_str)
+        }};
+        ($executor:expr, $query_str:expr) => {{
+            let tokenizer = Tokenizer::new($query_str);
+            let q = Parser::new(tokenizer).unwrap().parse().unwrap();
+
+            let mut executor = $executor;
+            create_employees(&mut executor);
+            executor.run(Statement::Query(q)).unwrap_err().to_string()
+        }};
+    }
+
+    /// Mean of values weighted by a second argument.
+    #[derive(Default)]
+    struct WeightedAvg {
+        sum: i64,
+        weights: i64,
+    }
+
+    impl Accumulator for WeightedAvg {
+        fn init() -> Self {
+            Self::default()
+        }
+
+        fn update(&mut self, args: &[Value]) -> Result<(), ExecutionError> {
+            match args {
+                [Value::Integer(value), Value::Integer(weight)] => self.merge(Self {
+                    sum: *value as i64 * *weight as i64,
+                    weights: *weight as i64,
+                }),
+                _ => Ok(()),
+            }
+        }
+
+        fn merge(&mut self, other: Self) -> Result<(), ExecutionError> {
+            let overflow = || ArithmeticError::Overflow(DataType::BigInt);
+            self.sum = self.sum.checked_add(other.sum).ok_or_else(overflow)?;
+            self.weights = self
+                .weights
+                .checked_add(other.weights)
+                .ok_or_else(overflow)?;
+            Ok(())
+        }
+
+        fn finalize(&self) -> Result<Value, ExecutionError> {
+            if self.weights == 0 {
+                Ok(Value::Null)
+            } else {
+                Ok(Value::Integer((self.sum / self.weights) as i32))
+            }
+        }
+    }
+
+    /// Classify salaries into bands.
+    fn band(args: &[Value]) -> Result<Value, ExecutionError> {
+        match args {
+            [Value::Integer(salary)] if *salary > 12000 => Ok(Value::from("senior")),
+            [Value::Integer(salary)] if *salary > 10000 => Ok(Value::from("confirmed")),
+            [_] => Ok(Value::from("junior")),
+            _ => Err(ExecutionError::Function(
+                "band expects 1 argument".to_string(),
+            )),
+        }
+    }
+
+    // Runs every statement of a script, returns the rows each produced.
+    fn run_script(executor: &mut QueryExecutor, script: &str) -> Vec<Vec<Row>> {
+        parse_script(script)
+            .unwrap()
+            .into_iter()
+            .map(|statement| executor.run(statement).unwrap().rows)
+            .collect()
+    }
+
+    // Runs the statements of a script up to the first failing one, returns
+    // the message of its error.
+    fn script_error(executor: &mut QueryExecutor, script: &str) -> String {
+        for statement in parse_script(script).unwrap() {
+            if let Err(error) = executor.run(statement) {
+                return error.to_string();
+            }
+        }
+        panic!("Script ran without errors")
+    }
+
+    fn create_employees(executor: &mut QueryExecutor) {
+        run_script(
+            executor,
+            "CREATE TABLE employees (
+                 id INTEGER,
+                 name VARCHAR,
+                 role VARCHAR,
+                 salary INTEGER,
+                 hire_date DATE
+             );
+             INSERT INTO employees VALUES
+                 (1, 'Alice', 'Manager', 12000, DATE '2015-03-01'),
+                 (2, 'Bob', 'Developer', 10000, DATE '2018-07-15'),
+                 (3, 'Charlie', 'Developer', 9000, DATE '2020-01-31')",
+        );
+    }
+
+    fn employees() -> QueryExecutor {
+        let mut executor = QueryExecutor::new();
+        create_employees(&mut executor);
+        executor
+    }
+
+    fn executor() -> QueryExecutor {
+        let mut executor = QueryExecutor::new();
+        executor.register_function("band", &[DataType::Integer], DataType::Varchar, band);
+        executor.register_function("negate", &[DataType::Integer], DataType::Integer, |args| {
+            match args {
+                [value] => Ok(value.negate()?),
+                _ => Err(ExecutionError::Function(
+                    "negate expects 1 argument".to_string(),
+                )),
+            }
+        });
+        executor
+    }
+
+    #[test]
+    fn can_call_functions_in_filters() {
+        let results = query!(
+            executor(),
+            "SELECT name FROM employees WHERE band(salary) = 'confirmed'"
+        );
+        assert_eq!(results, vec![Row::new(&["Alice".to_string()])]);
+    }
+
+    #[test]
+    fn can_call_functions_in_select_list() {
+        let results = query!(executor(), "SELECT id, BAND(salary) FROM employees LIMIT 2");
+        assert_eq!(
+            results,
+            vec![
+                Row::new(&[Value::Integer(1), Value::from("confirmed")]),
+                Row::new(&[Value::Integer(2), Value::from("junior")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn function_errors_fail_queries() {
+        let mut executor = QueryExecutor::new();
+        executor.register_function("quota", &[DataType::Integer], DataType::Integer, |args| {
+            match args {
+                [Value::Integer(salary)] if *salary > 10000 => {
+                    Err(ExecutionError::Function(format!("No quota above {salary}")))
+                }
+                [value] => Ok(value.clone()),
+                _ => Err(ExecutionError::Function(
+                    "quota expects 1 argument".to_string(),
+                )),
+            }
+        });
+        let error = query_error!(executor, "SELECT quota(salary) FROM employees");
+        assert!(error.contains("No quota above 12000"), "{error}");
+    }
+
+    #[test]
+    fn can_call_functions_in_order_by() {
+        let results = query!(
+            executor(),
+            "SELECT name FROM employees ORDERBY negate(salary)"
+        );
+        assert_eq!(
+            results,
+            vec![
+                Row::new(&["Alice".to_string()]),
+                Row::new(&["Bob".to_string()]),
+                Row::new(&["Charlie".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_group_rows() {
+        let results =
+            query!("SELECT role, COUNT(*), max(salary) FROM employees GROUP BY role ORDER BY role");
+        assert_eq!(
+            results,
+            vec![
+                Row::new(&[
+                    Value::from("Developer"),
+                    Value::BigInt(2),
+                    Value::Integer(10000)
+                ]),
+                Row::new(&[
+                    Value::from("Manager"),
+                    Value::BigInt(1),
+                    Value::Integer(12000)
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_aggregate_without_groups() {
+        let results =
+            query!("SELECT count(*), sum(salary), min(name) FROM employees WHERE salary > 20000");
+        assert_eq!(
+            results,
+            vec![Row::new(&[Value::BigInt(0), Value::Null, Value::Null])]
+        );
+    }
+
+    #[test]
+    fn can_order_groups_by_aggregates() {
+        let results = query!(
+            executor(),
+            "SELECT band(salary) FROM employees GROUP BY band(salary) ORDER BY count(*)"
+        );
+        assert_eq!(
+            results,
+            vec![
+                Row::new(&[Value::from("confirmed")]),
+                Row::new(&[Value::from("junior")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_call_user_defined_aggregates() {
+        let mut executor = executor();
+        executor.register_aggregate::<WeightedAvg>(
+            "wavg",
+            &[DataType::Integer, DataType::Integer],
+            DataType::Integer,
+        );
+        let results = query!(
+            executor,
+            "SELECT role, wavg(salary, id) FROM employees GROUP BY role"
+        );
+        assert_eq!(
+            results,
+            vec![
+                Row::new(&[Value::from("Manager"), Value::Integer(12000)]),
+                Row::new(&[Value::from("Developer"), Value::Integer(9400)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn ungrouped_columns_are_rejected() {
+        let error = query_error!("SELECT name FROM employees GROUP BY role");
+        assert!(
+            error.contains("must appear in the GROUP BY clause"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn unknown_functions_are_rejected() {
+        let error = query_error!(
+            executor(),
+            "SELECT name FROM employees WHERE missing(salary) = 1"
+        );
+        assert!(error.contains("Unknown function"), "{error}");
+    }
+
+    /// Repeat a value a given number of times.
+    struct Repeat;
+
+    impl TableFunction for Repeat {
+        fn schema(&self, arg_types: &[DataType]) -> Option<Schema> {
+            Some(Schema::new(vec![Field::new(
+                "repeat",
+                arg_types[0].clone(),
+            )]))
+        }
+
+        fn operator(&self, args: &[Value]) -> Result<Box<dyn Operator>, ExecutionError> {
+            let rows = match args {
+                [value, Value::Integer(times)] => {
+                    vec![Row::new(std::slice::from_ref(value)); *times as usize]
+                }
+                _ => vec![],
+            };
+            Ok(Box::new(Scan::new(&rows)))
+        }
+    }
+
+    #[test]
+    fn can_select_from_table_functions() {
+        let results = query!("SELECT generate_series FROM generate_series(1, 7, 3)");
+        assert_eq!(
+            results,
+            vec![
+                Row::new(&[Value::Integer(1)]),
+                Row::new(&[Value::Integer(4)]),
+                Row::new(&[Value::Integer(7)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_unnest_arrays() {
+        let results = query!("SELECT x FROM unnest(ARRAY['a', 'b']) AS t(x)");
+        assert_eq!(
+            results,
+            vec![Row::new(&[Value::from("a")]), Row::new(&[Value::from("b")])]
+        );
+    }
+
+    #[test]
+    fn can_call_table_functions_laterally() {
+        let results = query!(
+            "SELECT name, n FROM employees JOIN LATERAL generate_series(2, id) AS s(n) ON n = id"
+        );
+        assert_eq!(
+            results,
+            vec![
+                Row::new(&[Value::from("Bob"), Value::Integer(2)]),
+                Row::new(&[Value::from("Charlie"), Value::Integer(3)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_call_functions_in_join_conditions() {
+        let results = query!(
+            executor(),
+            "SELECT e.name, m.name FROM employees e JOIN employees m ON band(e.salary) = band(m.salary) WHERE e.id > m.id");
+        assert_eq!(
+            results,
+            vec![Row::new(&[Value::from("Charlie"), Value::from("Bob")])]
+        );
+    }
+
+    #[test]
+    fn can_call_user_defined_table_functions() {
+        let mut executor = QueryExecutor::new();
+        executor.register_table_function("repeat", Repeat);
+        let results = query!(
+            executor,
+            "SELECT name, r.repeat FROM employees CROSS JOIN LATERAL repeat(role, id) r WHERE id > 1 LIMIT 3");
+        assert_eq!(
+            results,
+            vec![
+                Row::new(&[Value::from("Bob"), Value::from("Developer")]),
+                Row::new(&[Value::from("Bob"), Value::from("Developer")]),
+                Row::new(&[Value::from("Charlie"), Value::from("Developer")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn string_literals_are_coerced_in_comparisons() {
+        let results = query!(
+            "SELECT name FROM employees WHERE salary = '10000' OR salary > 11000 AND NOT id = 2"
+        );
+        assert_eq!(
+            results,
+            vec![
+                Row::new(&["Alice".to_string()]),
+                Row::new(&["Bob".to_string()])
+            ]
+        );
+    }
+
+    #[test]
+    fn can_cast_values() {
+        let results = query!(
+            "SELECT CAST(id AS VARCHAR), salary::varchar::int FROM employees WHERE name::text = 'Bob'");
+        assert_eq!(
+            results,
+            vec![Row::new(&[Value::from("2"), Value::Integer(10000)])]
+        );
+    }
+
+    #[test]
+    fn incompatible_comparisons_are_rejected() {
+        let error = query_error!("SELECT id FROM employees WHERE name > 10");
+        assert!(
+            error.contains("Cannot compare VARCHAR > INTEGER"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn invalid_casts_fail_at_runtime() {
+        let error = query_error!("SELECT name::integer FROM employees");
+        assert!(error.contains("Cannot cast Alice to INTEGER"), "{error}");
+    }
+
+    #[test]
+    fn can_compute_arithmetic() {
+        let results = query!(
+            "SELECT salary * 1.05, salary / 3, salary / 3.0, -salary + 1e3 FROM employees WHERE id = 1");
+        assert_eq!(
+            results,
+            vec![Row::new(&[
+                Value::numeric("12600.0000").unwrap(),
+                Value::Integer(4000),
+                Value::numeric("4000.000000").unwrap(),
+                Value::Float(-11000.0),
+            ])]
+        );
+    }
+
+    #[test]
+    fn can_compare_numbers_of_different_types() {
+        let results = query!("SELECT name FROM employees WHERE salary > 9999.5 AND salary < 1.2e4");
+        assert_eq!(results, vec![Row::new(&["Bob".to_string()])]);
+    }
+
+    #[test]
+    fn nulls_are_sorted_last() {
+        let mut executor = QueryExecutor::new();
+        let results = run_script(
+            &mut executor,
+            "CREATE TABLE scores (name VARCHAR, score INTEGER);
+             INSERT INTO scores VALUES ('a', NULL), ('b', 3), ('c', 1);
+             SELECT name FROM scores ORDER BY score",
+        );
+        assert_eq!(
+            results[2],
+            vec![
+                Row::new(&["c".to_string()]),
+                Row::new(&["b".to_string()]),
+                Row::new(&["a".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_filter_with_every_comparison_operator() {
+        let results = query!(
+            "SELECT name FROM employees WHERE salary >= 10000 AND salary <= 12000 AND id != 1"
+        );
+        assert_eq!(results, vec![Row::new(&["Bob".to_string()])]);
+        let results =
+            query!("SELECT name FROM employees WHERE role <> 'Developer' OR salary <= 9000");
+        assert_eq!(
+            results,
+            vec![
+                Row::new(&["Alice".to_string()]),
+                Row::new(&["Charlie".to_string()])
+            ]
+        );
+    }
+
+    #[test]
+    fn integer_overflows_are_detected() {
+        let error = query_error!("SELECT salary * 1000000 FROM employees");
+        assert!(error.contains("INTEGER out of range"), "{error}");
+    }
+
+    #[test]
+    fn divisions_by_zero_are_detected() {
+        let error = query_error!("SELECT salary / (id - 1) FROM employees");
+        assert!(error.contains("Division by zero"), "{error}");
+    }
+
+    #[test]
+    fn unresolvable_expressions_are_errors() {
+        let scope = Scope {
+            schema: Schema::default(),
+            functions: Rc::new(FunctionRegistry::new()),
+        };
+        let resolve = |expr| QueryExecutor::resolve(&expr, &Row { items: vec![] }, &scope);
+        assert_eq!(
+            resolve(Expr::Column("id".to_string())),
+            Err(ExecutionError::InvalidExpression(
+                "Column id is not bound".to_string()
+            ))
+        );
+        assert_eq!(
+            resolve(Expr::Numeric("1e".to_string())),
+            Err(ExecutionError::InvalidExpression(
+                "Invalid numeric literal 1e".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn can_select_and_order_by_predicates() {
+        let results =
+            query!("SELECT name, salary > 9500 AND TRUE FROM employees ORDER BY salary > 9500");
+        assert_eq!(
+            results,
+            vec![
+                Row::new(&[Value::from("Charlie"), Value::Boolean(false)]),
+                Row::new(&[Value::from("Alice"), Value::Boolean(true)]),
+                Row::new(&[Value::from("Bob"), Value::Boolean(true)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_filter_on_boolean_columns() {
+        let results = query!(
+            "SELECT e.name, t.senior FROM employees e, LATERAL unnest(ARRAY[e.salary > 9500, NULL]) AS t(senior) WHERE senior OR e.id = 3");
+        assert_eq!(
+            results,
+            vec![
+                Row::new(&[Value::from("Alice"), Value::Boolean(true)]),
+                Row::new(&[Value::from("Bob"), Value::Boolean(true)]),
+                Row::new(&[Value::from("Charlie"), Value::Boolean(false)]),
+                Row::new(&[Value::from("Charlie"), Value::Null]),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_group_by_predicates() {
+        let results = query!(
+            "SELECT salary > 9500, count(*) FROM employees GROUP BY salary > 9500 ORDER BY salary > 9500");
+        assert_eq!(
+            results,
+            vec![
+                Row::new(&[Value::Boolean(false), Value::BigInt(1)]),
+                Row::new(&[Value::Boolean(true), Value::BigInt(2)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn comparisons_with_null_are_unknown() {
+        let results = query!(
+            "SELECT name FROM employees WHERE NOT salary = NULL OR NOT (salary > 9500 AND NULL)"
+        );
+        assert_eq!(results, vec![Row::new(&["Charlie".to_string()])]);
+    }
+
+    #[test]
+    fn can_store_and_test_booleans() {
+        let results = run_script(
+            &mut QueryExecutor::new(),
+            "CREATE TABLE flags (id INTEGER, active BOOLEAN);
+             INSERT INTO flags VALUES (1, TRUE), (2, 'off'::bool), (3, NULL);
+             SELECT id FROM flags WHERE active IS NULL OR active;
+             SELECT id, active IS NOT NULL, CAST(id - 1 AS boolean) FROM flags ORDER BY id",
+        );
+        assert_eq!(
+            results[2],
+            vec![
+                Row::new(&[Value::Integer(1)]),
+                Row::new(&[Value::Integer(3)])
+            ]
+        );
+        assert_eq!(
+            results[3],
+            vec![
+                Row::new(&[
+                    Value::Integer(1),
+                    Value::Boolean(true),
+                    Value::Boolean(false)
+                ]),
+                Row::new(&[
+                    Value::Integer(2),
+                    Value::Boolean(true),
+                    Value::Boolean(true)
+                ]),
+                Row::new(&[
+                    Value::Integer(3),
+                    Value::Boolean(false),
+                    Value::Boolean(true)
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    fn non_boolean_predicates_are_rejected() {
+        let error = query_error!("SELECT name FROM employees WHERE salary");
+        assert!(
+            error.contains("Expression salary is not a predicate"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn can_select_string_literals() {
+        let results = query!(
+            "SELECT 'O''Brien', E'Zo\\u00eb', name FROM employees WHERE name > 'Bob' OR role = 'Big Boss'");
+        assert_eq!(
+            results,
+            vec![Row::new(&[
+                Value::from("O'Brien"),
+                Value::from("Zoë"),
+                Value::from("Charlie")
+            ])]
+        );
+    }
+
+    #[test]
+    fn identifiers_are_case_folded() {
+        let results = query!(
+            "SELECT E.Name /* folded */, s.\"Serial Number\" -- quoted
+             FROM Employees E, LATERAL generate_series(1, e.ID) AS s(\"Serial Number\")
+             WHERE E.id = 2"
+        );
+        assert_eq!(
+            results,
+            vec![
+                Row::new(&[Value::from("Bob"), Value::Integer(1)]),
+                Row::new(&[Value::from("Bob"), Value::Integer(2)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn quoted_identifiers_are_case_sensitive() {
+        let error = query_error!("SELECT \"Name\" FROM employees");
+        assert!(error.contains("Unknown column \"Name\""), "{error}");
+    }
+
+    #[test]
+    fn can_execute_scripts() {
+        let results = run_script(
+            &mut employees(),
+            "SELECT name FROM employees WHERE id = 1;
+             -- Statements run in order.
+             SELECT count(*) FROM employees;",
+        );
+        assert_eq!(
+            results,
+            vec![
+                vec![Row::new(&["Alice".to_string()])],
+                vec![Row::new(&[Value::BigInt(3)])],
+            ]
+        );
+    }
+
+    #[test]
+    fn can_query_values() {
+        let decimal = |text| Value::Decimal(Decimal::parse(text).unwrap());
+        let results = run_script(
+            &mut employees(),
+            "VALUES (1, 'a'), (2, NULL);
+             SELECT e.name, t.label, t.column3 FROM employees e
+                JOIN (VALUES (1, 'first', 1.5), ('3', 'third', 2)) AS t(id, label)
+                ON e.id = t.id
+                ORDER BY t.id",
+        );
+        assert_eq!(
+            results[0],
+            vec![
+                Row::new(&[Value::Integer(1), Value::from("a")]),
+                Row::new(&[Value::Integer(2), Value::Null]),
+            ]
+        );
+        assert_eq!(
+            results[1],
+            vec![
+                Row::new(&[Value::from("Alice"), Value::from("first"), decimal("1.5")]),
+                Row::new(&[Value::from("Charlie"), Value::from("third"), decimal("2.0")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_filter_on_dates() {
+        let results = query!(
+            "SELECT name FROM employees WHERE hire_date > '2016-01-01' AND hire_date < DATE '2020-01-31'");
+        assert_eq!(results, vec![Row::new(&["Bob".to_string()])]);
+    }
+
+    #[test]
+    fn can_compute_with_dates() {
+        let results = query!(
+            "SELECT hire_date + INTERVAL '1 month', hire_date - DATE '2020-01-01', hire_date + 30, TIME '23:30' + INTERVAL '45 minutes' FROM employees WHERE id = 3");
+        assert_eq!(
+            results,
+            vec![Row::new(&[
+                Value::decode("2020-02-29", DataType::Timestamp).unwrap(),
+                Value::Integer(30),
+                Value::decode("2020-03-01", DataType::Date).unwrap(),
+                Value::decode("00:15:00", DataType::Time).unwrap(),
+            ])]
+        );
+    }
+
+    #[test]
+    fn can_extract_and_truncate_fields() {
+        let results = query!(
+            "SELECT EXTRACT(YEAR FROM hire_date), date_trunc('quarter', hire_date) FROM employees ORDER BY EXTRACT(DOY FROM hire_date)");
+        assert_eq!(
+            results,
+            vec![
+                Row::new(&[
+                    Value::BigInt(2020),
+                    Value::decode("2020-01-01", DataType::Timestamp).unwrap()
+                ]),
+                Row::new(&[
+                    Value::BigInt(2015),
+                    Value::decode("2015-01-01", DataType::Timestamp).unwrap()
+                ]),
+                Row::new(&[
+                    Value::BigInt(2018),
+                    Value::decode("2018-07-01", DataType::Timestamp).unwrap()
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_aggregate_dates() {
+        let results = query!(
+            "SELECT role, max(hire_date), min(hire_date) FROM employees GROUP BY role ORDER BY role");
+        assert_eq!(
+            results,
+            vec![
+                Row::new(&[
+                    Value::from("Developer"),
+                    Value::decode("2020-01-31", DataType::Date).unwrap(),
+                    Value::decode("2018-07-15", DataType::Date).unwrap(),
+                ]),
+                Row::new(&[
+                    Value::from("Manager"),
+                    Value::decode("2015-03-01", DataType::Date).unwrap(),
+                    Value::decode("2015-03-01", DataType::Date).unwrap(),
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_aggregate_decimals_and_arrays() {
+        let mut executor = QueryExecutor::new();
+        let results = run_script(
+            &mut executor,
+            "CREATE TABLE items (price DECIMAL(10, 2), tags VARCHAR[]);
+             INSERT INTO items VALUES (1.25, ARRAY['a']), (2.5, NULL), (NULL, ARRAY['b', 'c']);
+             SELECT count(price), min(price), max(price), sum(price), avg(price), count(tags)
+                FROM items",
+        );
+        let decimal = |text, scale| Value::decode(text, DataType::Decimal(MAX_PRECISION, scale));
+        assert_eq!(
+            results[2],
+            vec![Row::new(&[
+                Value::BigInt(2),
+                Value::decode("1.25", DataType::Decimal(10, 2)).unwrap(),
+                Value::decode("2.50", DataType::Decimal(10, 2)).unwrap(),
+                decimal("3.75", 2).unwrap(),
+                decimal("1.875", 6).unwrap(),
+                Value::BigInt(2),
+            ])]
+        );
+    }
+
+    #[test]
+    fn now_reads_the_clock() {
+        let mut executor = QueryExecutor::new();
+        executor.set_clock(FixedClock(Timestamp::parse("2023-01-01 12:00").unwrap()));
+        let results = query!(
+            executor,
+            "SELECT name, now() - hire_date FROM employees WHERE hire_date + INTERVAL '5 years' < NOW()");
+        assert_eq!(
+            results,
+            vec![Row::new(&[
+                Value::from("Alice"),
+                Value::decode("2863 days 12:00:00", DataType::Interval).unwrap(),
+            ])]
+        );
+    }
+
+    #[test]
+    fn undefined_fields_are_rejected() {
+        let error = query_error!("SELECT EXTRACT(DOW FROM INTERVAL '1 day') FROM employees");
+        assert!(
+            error.contains("Cannot extract DOW from INTERVAL"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn invalid_date_arithmetic_is_rejected() {
+        let error = query_error!("SELECT hire_date * 2 FROM employees");
+        assert!(
+            error.contains("Cannot apply * to DATE and INTEGER"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn ambiguous_join_columns_are_rejected() {
+        let error = query_error!("SELECT name FROM employees e JOIN employees m ON e.id = m.id");
+        assert!(error.contains("ambiguous"), "{error}");
+    }
+
+    #[test]
+    fn can_create_tables_from_queries() {
+        let mut executor = employees();
+        let results = run_script(
+            &mut executor,
+            "CREATE TABLE developers AS
+                SELECT id, name, salary * 2 FROM employees WHERE role = 'Developer';
+             SELECT \"salary * 2\", name FROM developers ORDER BY name;
+             SELECT d.name FROM developers d JOIN employees e ON d.id = e.id + 1",
+        );
+        assert_eq!(
+            results,
+            vec![
+                vec![],
+                vec![
+                    Row::new(&[Value::Integer(20000), Value::from("Bob")]),
+                    Row::new(&[Value::Integer(18000), Value::from("Charlie")]),
+                ],
+                vec![
+                    Row::new(&[Value::from("Bob")]),
+                    Row::new(&[Value::from("Charlie")]),
+                ],
+            ]
+        );
+        let table = executor.catalog().table("developers").unwrap();
+        assert_eq!(
+            table.schema(),
+            Schema::new(vec![
+                Field::new("id", DataType::Integer),
+                Field::new("name", DataType::Varchar),
+                Field::new("salary * 2", DataType::Integer),
+            ])
+        );
+    }
+
+    #[test]
+    fn can_alter_tables() {
+        let mut executor = employees();
+        let results = run_script(
+            &mut executor,
+            "CREATE TABLE staff AS SELECT id, name FROM employees;
+             ALTER TABLE staff ADD COLUMN bonus DECIMAL(6, 2) NOT NULL DEFAULT 100 + 50;
+             ALTER TABLE staff ADD since DATE;
+             ALTER TABLE staff RENAME COLUMN name TO full_name;
+             ALTER TABLE staff DROP COLUMN id;
+             ALTER TABLE staff DROP COLUMN IF EXISTS id;
+             SELECT full_name, bonus, since FROM staff WHERE full_name = 'Bob'",
+        );
+        assert_eq!(
+            results[6],
+            vec![Row::new(&[
+                Value::from("Bob"),
+                Value::decode("150.00", DataType::Decimal(6, 2)).unwrap(),
+                Value::Null,
+            ])]
+        );
+        let columns = &executor.catalog().table("staff").unwrap().columns;
+        assert!(columns[1].not_null);
+        assert_eq!(
+            columns[1].default.as_ref().unwrap().to_string(),
+            "CAST(100 + 50 AS DECIMAL(6, 2))"
+        );
+    }
+
+    #[test]
+    fn can_create_and_drop_tables() {
+        let mut executor = QueryExecutor::new();
+        run_script(
+            &mut executor,
+            "CREATE TABLE accounts (id INTEGER NOT NULL, owner VARCHAR DEFAULT 'nobody');
+             CREATE TABLE IF NOT EXISTS accounts (id INTEGER);
+             DROP TABLE IF EXISTS missing",
+        );
+        let table = executor.catalog().table("accounts").unwrap();
+        assert_eq!(table.columns.len(), 2);
+        assert_eq!(
+            table.columns[1].default,
+            Some(Expr::Varchar("nobody".to_string()))
+        );
+        assert_eq!(
+            run_script(&mut executor, "SELECT id FROM accounts"),
+            vec![vec![]]
+        );
+        run_script(&mut executor, "DROP TABLE accounts");
+        assert!(executor.catalog().tables().next().is_none());
+    }
+
+    #[test]
+    fn duplicate_tables_are_rejected() {
+        let error = script_error(
+            &mut QueryExecutor::new(),
+            "CREATE TABLE accounts (id INTEGER); CREATE TABLE accounts (id INTEGER)",
+        );
+        assert!(error.contains("Table accounts already exists"), "{error}");
+    }
+
+    #[test]
+    fn dropping_unknown_tables_is_rejected() {
+        let error = script_error(&mut QueryExecutor::new(), "DROP TABLE accounts");
+        assert!(error.contains("Unknown table accounts"), "{error}");
+    }
+
+    #[test]
+    fn not_null_columns_need_a_default() {
+        let error = script_error(
+            &mut employees(),
+            "CREATE TABLE staff AS SELECT id FROM employees;
+             ALTER TABLE staff ADD COLUMN owner VARCHAR NOT NULL",
+        );
+        assert!(
+            error.contains("Column owner of table staff cannot be NULL"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn defaults_are_type_checked() {
+        let error = script_error(
+            &mut QueryExecutor::new(),
+            "CREATE TABLE accounts (id INTEGER DEFAULT 'none')",
+        );
+        assert!(error.contains("Invalid INTEGER literal 'none'"), "{error}");
+    }
+
+    #[test]
+    fn can_insert_rows() {
+        let mut executor = employees();
+        let results = run_script(
+            &mut executor,
+            "CREATE TABLE accounts (
+                id BIGINT NOT NULL,
+                owner VARCHAR DEFAULT 'nobody',
+                balance DECIMAL(8, 2) DEFAULT 0
+             );
+             INSERT INTO accounts VALUES (1, 'Alice', 10.5), (2, NULL, 20);
+             INSERT INTO accounts (id) VALUES (3) RETURNING id, owner, balance;
+             INSERT INTO accounts (balance, id) SELECT salary, id + 10 FROM employees WHERE id > 1;
+             SELECT id, owner, balance FROM accounts WHERE id > 10",
+        );
+        let decimal = |text| Value::decode(text, DataType::Decimal(8, 2)).unwrap();
+        assert_eq!(
+            results[1..],
+            [
+                vec![Row::new(&[Value::BigInt(2)])],
+                vec![Row::new(&[
+                    Value::BigInt(3),
+                    Value::from("nobody"),
+                    decimal("0.00")
+                ])],
+                vec![Row::new(&[Value::BigInt(2)])],
+                vec![
+                    Row::new(&[
+                        Value::BigInt(12),
+                        Value::from("nobody"),
+                        decimal("10000.00")
+                    ]),
+                    Row::new(&[Value::BigInt(13), Value::from("nobody"), decimal("9000.00")]),
+                ],
+            ]
+        );
+        let rows = executor.catalog().table("accounts").unwrap().rows();
+        assert_eq!(
+            rows[1],
+            Row::new(&[Value::BigInt(2), Value::Null, decimal("20.00")])
+        );
+    }
+
+    #[test]
+    fn can_update_rows() {
+        let mut executor = employees();
+        let results = run_script(
+            &mut executor,
+            "CREATE TABLE staff AS SELECT id, name, salary FROM employees;
+             UPDATE staff SET salary = salary + id * 100, name = 'Dev' WHERE salary < 11000
+                RETURNING staff.id, name, salary;
+             UPDATE staff SET id = id * 2;
+             SELECT id, salary FROM staff ORDER BY id",
+        );
+        assert_eq!(
+            results[1..],
+            [
+                vec![
+                    Row::new(&[Value::Integer(2), Value::from("Dev"), Value::Integer(10200)]),
+                    Row::new(&[Value::Integer(3), Value::from("Dev"), Value::Integer(9300)]),
+                ],
+                vec![Row::new(&[Value::BigInt(3)])],
+                vec![
+                    Row::new(&[Value::Integer(2), Value::Integer(12000)]),
+                    Row::new(&[Value::Integer(4), Value::Integer(10200)]),
+                    Row::new(&[Value::Integer(6), Value::Integer(9300)]),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn can_delete_rows() {
+        let mut executor = employees();
+        let results = run_script(
+            &mut executor,
+            "CREATE TABLE staff AS SELECT id, name FROM employees;
+             DELETE FROM staff WHERE id = 2 RETURNING name;
+             DELETE FROM staff WHERE id > 5;
+             SELECT name FROM staff;
+             DELETE FROM staff;
+             SELECT count(*) FROM staff",
+        );
+        assert_eq!(
+            results[1..],
+            [
+                vec![Row::new(&[Value::from("Bob")])],
+                vec![Row::new(&[Value::BigInt(0)])],
+                vec![
+                    Row::new(&[Value::from("Alice")]),
+                    Row::new(&[Value::from("Charlie")]),
+                ],
+                vec![Row::new(&[Value::BigInt(2)])],
+                vec![Row::new(&[Value::BigInt(0)])],
+            ]
+        );
+    }
+
+    #[test]
+    fn not_null_columns_reject_null_values() {
+        let error = script_error(
+            &mut QueryExecutor::new(),
+            "CREATE TABLE accounts (id INTEGER NOT NULL, owner VARCHAR);
+             INSERT INTO accounts (owner) VALUES ('Alice')",
+        );
+        assert!(
+            error.contains("Column id of table accounts cannot be NULL"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn inserted_rows_must_match_target_columns() {
+        let error = script_error(
+            &mut QueryExecutor::new(),
+            "CREATE TABLE accounts (id INTEGER);
+             INSERT INTO accounts VALUES (1, 'Alice')",
+        );
+        assert!(
+            error.contains("INSERT has more expressions than target columns"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn assigned_values_are_type_checked() {
+        let error = script_error(
+            &mut QueryExecutor::new(),
+            "CREATE TABLE accounts (id INTEGER);
+             UPDATE accounts SET id = TRUE",
+        );
+        assert!(error.contains("Cannot cast BOOLEAN to INTEGER"), "{error}");
+    }
+
+    #[test]
+    fn can_upsert_rows() {
+        let mut executor = employees();
+        let results = run_script(
+            &mut executor,
+            "CREATE TABLE salaries (name VARCHAR UNIQUE, salary INTEGER, updates INTEGER DEFAULT 0);
+             INSERT INTO salaries (name, salary) VALUES ('Alice', 1000), ('Bob', 1000);
+             INSERT INTO salaries (name, salary) SELECT name, salary FROM employees
+                ON CONFLICT (name) DO UPDATE
+                SET salary = excluded.salary, updates = salaries.updates + 1
+                WHERE excluded.salary > 11000
+                RETURNING name, salary, updates;
+             INSERT INTO salaries VALUES ('Alice', 0, 0), ('Dave', 0, 0), ('Dave', 1, 0)
+                ON CONFLICT (name) DO NOTHING;
+             SELECT name, salary, updates FROM salaries",
+        );
+        assert_eq!(
+            results[2],
+            vec![
+                Row::new(&[
+                    Value::from("Alice"),
+                    Value::Integer(12000),
+                    Value::Integer(1)
+                ]),
+                Row::new(&[
+                    Value::from("Charlie"),
+                    Value::Integer(9000),
+                    Value::Integer(0)
+                ]),
+            ]
+        );
+        assert_eq!(results[3], vec![Row::new(&[Value::BigInt(1)])]);
+        assert_eq!(
+            results[4],
+            vec![
+                Row::new(&[
+                    Value::from("Alice"),
+                    Value::Integer(12000),
+                    Value::Integer(1)
+                ]),
+                Row::new(&[Value::from("Bob"), Value::Integer(1000), Value::Integer(0)]),
+                Row::new(&[
+                    Value::from("Charlie"),
+                    Value::Integer(9000),
+                    Value::Integer(0)
+                ]),
+                Row::new(&[Value::from("Dave"), Value::Integer(0), Value::Integer(0)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn upserts_cannot_update_rows_twice() {
+        let error = script_error(
+            &mut QueryExecutor::new(),
+            "CREATE TABLE accounts (id INTEGER PRIMARY KEY, owner VARCHAR);
+             INSERT INTO accounts VALUES (1, 'Alice'), (1, 'Bob')
+                ON CONFLICT (id) DO UPDATE SET owner = excluded.owner",
+        );
+        assert!(
+            error.contains("ON CONFLICT DO UPDATE cannot affect a row a second time"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn can_merge_rows() {
+        let mut executor = employees();
+        let results = run_script(
+            &mut executor,
+            "CREATE TABLE staff (id INTEGER NOT NULL, name VARCHAR, salary INTEGER DEFAULT 0);
+             INSERT INTO staff VALUES (1, 'Alice', 100), (2, 'Bob', 100), (4, 'David', 100);
+             MERGE INTO staff s USING employees e ON s.id = e.id
+                WHEN MATCHED AND e.role = 'Manager' THEN UPDATE SET salary = e.salary
+                WHEN MATCHED THEN DELETE
+                WHEN NOT MATCHED AND e.salary > 10000 THEN DO NOTHING
+                WHEN NOT MATCHED THEN INSERT (id, name) VALUES (e.id, e.name);
+             SELECT id, name, salary FROM staff",
+        );
+        assert_eq!(results[2], vec![Row::new(&[Value::BigInt(3)])]);
+        assert_eq!(
+            results[3],
+            vec![
+                Row::new(&[
+                    Value::Integer(1),
+                    Value::from("Alice"),
+                    Value::Integer(12000)
+                ]),
+                Row::new(&[Value::Integer(4), Value::from("David"), Value::Integer(100)]),
+                Row::new(&[Value::Integer(3), Value::from("Charlie"), Value::Integer(0)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn merges_cannot_update_rows_twice() {
+        let error = script_error(
+            &mut employees(),
+            "CREATE TABLE staff (id INTEGER);
+             INSERT INTO staff VALUES (1);
+             MERGE INTO staff USING employees e ON staff.id < e.id
+                WHEN MATCHED THEN UPDATE SET id = e.id",
+        );
+        assert!(
+            error.contains("MERGE cannot affect a row a second time"),
+            "{error}"
+        );
+    }
+
+    fn accounts_and_transfers() -> &'static str {
+        "CREATE TABLE accounts (
+            id INTEGER PRIMARY KEY,
+            owner VARCHAR NOT NULL,
+            balance INTEGER DEFAULT 0 CHECK (NOT balance < 0),
+            UNIQUE (owner)
+         );
+         CREATE TABLE transfers (
+            account INTEGER REFERENCES accounts ON DELETE CASCADE ON UPDATE SET NULL,
+            amount INTEGER NOT NULL,
+            CONSTRAINT positive CHECK (amount > 0)
+         );
+         INSERT INTO accounts VALUES (1, 'Alice', 10), (2, 'Bob', 20), (3, 'Carol', 30);
+         INSERT INTO transfers VALUES (1, 5), (2, 7), (1, 3), (3, 1);"
+    }
+
+    #[test]
+    fn can_enforce_constraints() {
+        let mut executor = QueryExecutor::new();
+        let script = format!(
+            "{}
+             DELETE FROM accounts WHERE id = 1;
+             UPDATE accounts SET id = 4 WHERE owner = 'Bob';
+             INSERT INTO accounts (id, owner) VALUES (5, 'Dave') ON CONFLICT DO NOTHING;
+             INSERT INTO accounts (id, owner) VALUES (4, 'Eve') ON CONFLICT DO NOTHING;
+             SELECT account, amount FROM transfers",
+            accounts_and_transfers()
+        );
+        let results = run_script(&mut executor, &script);
+        let counts = results[4..8]
+            .iter()
+            .map(|rows| rows[0].items[0].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            vec![
+                Value::BigInt(1),
+                Value::BigInt(1),
+                Value::BigInt(1),
+                Value::BigInt(0)
+            ]
+        );
+        assert_eq!(
+            results[8],
+            vec![
+                Row::new(&[Value::Null, Value::Integer(7)]),
+                Row::new(&[Value::Integer(3), Value::Integer(1)]),
+            ]
+        );
+        let table = executor.catalog().table("accounts").unwrap();
+        let names = table
+            .constraints
+            .iter()
+            .map(|constraint| constraint.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "accounts_pkey",
+                "accounts_balance_check",
+                "accounts_owner_key"
+            ]
+        );
+        assert!(table.columns[0].not_null);
+    }
+
+    #[test]
+    fn unique_constraints_reject_duplicate_keys() {
+        let script = format!(
+            "{} UPDATE accounts SET owner = 'Alice' WHERE id = 2",
+            accounts_and_transfers()
+        );
+        let error = script_error(&mut QueryExecutor::new(), &script);
+        assert!(
+            error.contains(
+                "Key (owner)=(Alice) already exists in table accounts, violating constraint accounts_owner_key"
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn check_constraints_reject_false_predicates() {
+        let script = format!(
+            "{} INSERT INTO transfers VALUES (NULL, 1), (1, -1)",
+            accounts_and_transfers()
+        );
+        let error = script_error(&mut QueryExecutor::new(), &script);
+        assert!(
+            error.contains("Row of table transfers violates check constraint positive"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn check_constraints_accept_inclusive_bounds() {
+        let mut executor = QueryExecutor::new();
+        run_script(
+            &mut executor,
+            "CREATE TABLE stock (item VARCHAR, qty INTEGER CHECK (qty >= 0));
+             INSERT INTO stock VALUES ('bolt', 0), ('nut', 5);
+             UPDATE stock SET qty = qty - 1 WHERE item = 'nut'",
+        );
+        let error = script_error(&mut executor, "UPDATE stock SET qty = qty - 1");
+        assert!(
+            error.contains("Row of table stock violates check constraint stock_qty_check"),
+            "{error}"
+        );
+        let results = run_script(&mut executor, "SELECT item, qty FROM stock");
+        assert_eq!(
+            results[0],
+            vec![
+                Row::new(&[Value::from("bolt"), Value::Integer(0)]),
+                Row::new(&[Value::from("nut"), Value::Integer(4)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn foreign_keys_reject_unknown_keys() {
+        let script = format!(
+            "{} UPDATE transfers SET account = account + 1",
+            accounts_and_transfers()
+        );
+        let error = script_error(&mut QueryExecutor::new(), &script);
+        assert!(
+            error.contains(
+                "Key (account)=(4) of table transfers is not present in table accounts, violating constraint transfers_account_fkey"
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn foreign_keys_restrict_deletes() {
+        let script = format!(
+            "{} CREATE TABLE ledger (account INTEGER, FOREIGN KEY (account) REFERENCES accounts (id));
+             INSERT INTO ledger VALUES (1);
+             DELETE FROM accounts",
+            accounts_and_transfers()
+        );
+        let error = script_error(&mut QueryExecutor::new(), &script);
+        assert!(
+            error.contains(
+                "Key (id)=(1) of table accounts is still referenced from table ledger, violating constraint ledger_account_fkey"
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn referenced_tables_cannot_be_dropped() {
+        let script = format!("{} DROP TABLE accounts", accounts_and_transfers());
+        let error = script_error(&mut QueryExecutor::new(), &script);
+        assert!(
+            error.contains(
+                "Table accounts is referenced by constraint transfers_account_fkey of table transfers"
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn invalid_constraints_do_not_create_tables() {
+        let mut executor = QueryExecutor::new();
+        let error = script_error(
+            &mut executor,
+            "CREATE TABLE accounts (id INTEGER REFERENCES employees)",
+        );
+        assert_eq!(error, "Unknown table employees");
+        assert!(executor.catalog().table("accounts").is_none());
+    }
+
+    #[test]
+    fn failing_queries_do_not_create_tables() {
+        let mut executor = QueryExecutor::new();
+        let error = script_error(
+            &mut executor,
+            "CREATE TABLE t (a INTEGER);
+             INSERT INTO t VALUES (0);
+             CREATE TABLE u AS SELECT 10 / a FROM t",
+        );
+        assert_eq!(error, "Division by zero");
+        assert!(executor.catalog().table("u").is_none());
+    }
+
+    #[test]
+    fn invalid_constraints_do_not_add_columns() {
+        let mut executor = QueryExecutor::new();
+        let error = script_error(
+            &mut executor,
+            "CREATE TABLE t (a INTEGER);
+             ALTER TABLE t ADD COLUMN z INTEGER CHECK (z > 'abc')",
+        );
+        assert!(error.contains("Invalid INTEGER literal 'abc'"), "{error}");
+        let table = executor.catalog().table("t").unwrap();
+        assert_eq!(table.columns.len(), 1);
+    }
+
+    #[test]
+    fn failing_checks_of_referential_actions_change_no_table() {
+        let mut executor = QueryExecutor::new();
+        let error = script_error(
+            &mut executor,
+            "CREATE TABLE p (id INTEGER PRIMARY KEY);
+             CREATE TABLE c (
+                pid INTEGER REFERENCES p (id) ON UPDATE CASCADE,
+                CHECK (10 / pid > 0)
+             );
+             INSERT INTO p VALUES (1);
+             INSERT INTO c VALUES (1);
+             UPDATE p SET id = 0",
+        );
+        assert_eq!(error, "Division by zero");
+        assert_eq!(
+            run_script(&mut executor, "SELECT id FROM p; SELECT pid FROM c"),
+            vec![
+                vec![Row::new(&[Value::Integer(1)])],
+                vec![Row::new(&[Value::Integer(1)])],
+            ]
+        );
+    }
+
+    #[test]
+    fn upserts_need_a_unique_constraint() {
+        let script = format!(
+            "{} INSERT INTO accounts VALUES (4, 'Dave', 0) ON CONFLICT (balance) DO NOTHING",
+            accounts_and_transfers()
+        );
+        let error = script_error(&mut QueryExecutor::new(), &script);
+        assert!(
+            error.contains("There is no unique constraint matching the ON CONFLICT columns"),
+            "{error}"
+        );
+    }
+}
@@ -1,20 +1,21 @@
-//! Implementation of in-memory rows, represented as `Vec<String>`.
+//! Implementation of in-memory rows, represented as `Vec<Value>`.
+use crate::types::Value;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Row {
-    pub items: Vec<String>,
+    pub items: Vec<Value>,
 }
 
 impl Row {
-    /// Create a new row from a slice of strings.
-    pub fn new(items: &[String]) -> Self {
+    /// Create a new row from a slice of values or of items convertible to values.
+    pub fn new<V: Clone + Into<Value>>(items: &[V]) -> Self {
         Self {
-            items: items.to_vec(),
+            items: items.iter().cloned().map(Into::into).collect(),
         }
     }
 
     /// Returns item at given index.
-    pub fn get(&self, index: usize) -> Option<&str> {
-        self.items.get(index).map(|x| x.as_str())
+    pub fn get(&self, index: usize) -> Option<&Value> {
+        self.items.get(index)
     }
 }
@@ -0,0 +1,129 @@
+//! Implementation of schemas describing the columns of the rows produced by
+//! operators.
+use crate::types::DataType;
+
+/// A named and typed column, optionally qualified by the name of the table
+/// or `FROM` item it comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub table: Option<String>,
+    pub name: String,
+    pub data_type: DataType,
+}
+
+impl Field {
+    pub fn new(name: &str, data_type: DataType) -> Self {
+        Self {
+            table: None,
+            name: name.to_string(),
+            data_type,
+        }
+    }
+
+    /// Create a new field qualified by the given table name.
+    pub fn qualified(table: &str, name: &str, data_type: DataType) -> Self {
+        Self {
+            table: Some(table.to_string()),
+            name: name.to_string(),
+            data_type,
+        }
+    }
+}
+
+/// Ordered list of fields, the i-th field describes the i-th item of a row.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Schema {
+    pub fields: Vec<Field>,
+}
+
+impl Schema {
+    pub fn new(fields: Vec<Field>) -> Self {
+        Self { fields }
+    }
+
+    /// Returns the index of the field with the given name.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|field| field.name == name)
+    }
+
+    /// Returns the index of the field referenced by a column name, optionally
+    /// qualified by a table name, panics if the reference is ambiguous.
+    pub fn resolve(&self, table: Option<&str>, name: &str) -> Option<usize> {
+        match self.candidates(table, name)[..] {
+            [] => None,
+            [index] => Some(index),
+            _ => panic!("Column reference {name} is ambiguous"),
+        }
+    }
+
+    /// Returns the indexes of the fields a column name, optionally qualified
+    /// by a table name, can refer to.
+    pub fn candidates(&self, table: Option<&str>, name: &str) -> Vec<usize> {
+        self.fields
+            .iter()
+            .enumerate()
+            .filter(|(_, field)| {
+                field.name == name && (table.is_none() || field.table.as_deref() == table)
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Returns the field at the given index.
+    pub fn field(&self, index: usize) -> &Field {
+        &self.fields[index]
+    }
+
+    /// Returns a copy of the schema with every field qualified by the given
+    /// table name.
+    pub fn qualify(&self, table: &str) -> Self {
+        Self::new(
+            self.fields
+                .iter()
+                .map(|field| Field::qualified(table, &field.name, field.data_type.clone()))
+                .collect(),
+        )
+    }
+
+    /// Returns the schema of the rows made of the items of a row of this schema
+    /// followed by the items of a row of the other schema.
+    pub fn join(&self, other: &Schema) -> Self {
+        Self::new(self.fields.iter().chain(&other.fields).cloned().collect())
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_resolve_qualified_columns() {
+        let employees = Schema::new(vec![
+            Field::new("id", DataType::Integer),
+            Field::new("name", DataType::Varchar),
+        ]);
+        let schema = employees.qualify("e").join(&employees.qualify("m"));
+
+        assert_eq!(schema.resolve(Some("m"), "id"), Some(2));
+        assert_eq!(schema.resolve(Some("e"), "name"), Some(1));
+        assert_eq!(schema.resolve(Some("x"), "name"), None);
+        assert_eq!(schema.resolve(None, "salary"), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ambiguous")]
+    fn ambiguous_columns_are_rejected() {
+        let employees = Schema::new(vec![Field::new("id", DataType::Integer)]);
+        let schema = employees.qualify("e").join(&employees.qualify("m"));
+
+        schema.resolve(None, "id");
+    }
+}
@@ -0,0 +1,412 @@
+//! Implementation of the expression simplifier, rewriting type checked
+//! expressions into equivalent ones which are cheaper to evaluate by folding
+//! constants, applying boolean identities and merging range predicates.
+use crate::sql::Expr;
+use crate::types::Value;
+use std::cmp::Ordering;
+
+/// Simplify an expression into one evaluating to the same value on every
+/// row. Constant sub-expressions are folded, unless evaluating them fails so
+/// the error is still raised when they are evaluated, and boolean identities
+/// such as `NOT NOT x` or `x AND FALSE` are applied following three-valued
+/// logic. Function calls are never folded as they may not be deterministic.
+pub fn simplify(expr: Expr) -> Expr {
+    expr.transform(&mut |expr| match fold(&expr) {
+        Some(folded) => folded,
+        None => identities(expr),
+    })
+}
+
+/// Simplify a predicate into one satisfied by the same rows, which may
+/// evaluate to `FALSE` where the original one evaluates to `NULL`. In addition
+/// to `simplify`, comparisons of a column with constants are merged, so
+/// `a > 5 AND a > 7` becomes `a > 7`, and unsatisfiable conjunctions such as
+/// `a = 1 AND a = 2` become `FALSE`.
+pub fn simplify_predicate(expr: Expr) -> Expr {
+    match satisfiable(simplify(expr)) {
+        Expr::Null => Expr::Boolean(false),
+        expr => expr,
+    }
+}
+
+// Merges the ranges of the conjunctions of a predicate, disjunctions are
+// satisfied when one of their sides is so each side is merged on its own.
+fn satisfiable(expr: Expr) -> Expr {
+    match expr {
+        Expr::Or(left, right) => {
+            let (left, right) = (satisfiable(*left), satisfiable(*right));
+            identities(Expr::Or(Box::new(left), Box::new(right)))
+        }
+        Expr::And(_, _) => {
+            let terms = expr.conjuncts().into_iter().map(satisfiable).collect();
+            match merge_ranges(terms) {
+                Some(terms) => terms
+                    .into_iter()
+                    .reduce(|left, right| identities(Expr::And(Box::new(left), Box::new(right))))
+                    .unwrap_or(Expr::Boolean(true)),
+                None => Expr::Boolean(false),
+            }
+        }
+        expr => expr,
+    }
+}
+
+/// Returns the value of a literal, `None` for other expressions.
+pub fn constant(expr: &Expr) -> Option<Value> {
+    match expr {
+        Expr::Null => Some(Value::Null),
+        Expr::Boolean(value) => Some(Value::Boolean(*value)),
+        Expr::Value(value) => Some(Value::integer(*value)),
+        Expr::Numeric(numeric) => Value::numeric(numeric),
+        Expr::Varchar(varchar) => Some(Value::Varchar(varchar.clone())),
+        Expr::Constant(value) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+// Returns the literal of a value, written as a plain literal when it has the
+// type the literal would be checked as.
+fn literal(value: Value) -> Expr {
+    match value {
+        Value::Null => Expr::Null,
+        Value::Boolean(value) => Expr::Boolean(value),
+        Value::Integer(value) => Expr::Value(value as i64),
+        Value::Varchar(varchar) => Expr::Varchar(varchar),
+        value => Expr::Constant(value),
+    }
+}
+
+// Evaluates an operator whose operands are literals. Only boolean operators
+// are folded to `NULL`, whose type is otherwise lost.
+fn fold(expr: &Expr) -> Option<Expr> {
+    let children = expr.children();
+    if children.is_empty() || matches!(expr, Expr::Function(_, _)) {
+        return None;
+    }
+    let mut values = children
+        .into_iter()
+        .map(constant)
+        .collect::<Option<Vec<_>>>()?
+        .into_iter();
+    let mut value = || values.next().unwrap();
+    let result = match expr {
+        Expr::Comparison(_, op, _) => {
+            let (left, right) = (value(), value());
+            if left.is_null() || right.is_null() {
+                return Some(Expr::Null);
+            }
+            Value::Boolean(left.compare(op, &right)?)
+        }
+        Expr::And(_, _) => match (value(), value()) {
+            (Value::Boolean(false), _) | (_, Value::Boolean(false)) => Value::Boolean(false),
+            (Value::Boolean(true), Value::Boolean(true)) => Value::Boolean(true),
+            _ => return Some(Expr::Null),
+        },
+        Expr::Or(_, _) => match (value(), value()) {
+            (Value::Boolean(true), _) | (_, Value::Boolean(true)) => Value::Boolean(true),
+            (Value::Boolean(false), Value::Boolean(false)) => Value::Boolean(false),
+            _ => return Some(Expr::Null),
+        },
+        Expr::Not(_) => match value() {
+            Value::Boolean(value) => Value::Boolean(!value),
+            _ => return Some(Expr::Null),
+        },
+        Expr::IsNull(_, negated) => Value::Boolean(value().is_null() != *negated),
+        Expr::Arithmetic(_, op, _) => {
+            let left = value();
+            left.arithmetic(op, &value()).ok()?
+        }
+        Expr::Negate(_) => value().negate().ok()?,
+        Expr::Cast(_, data_type) => value().cast(data_type)?,
+        Expr::Extract(field, _) => value().extract(*field)?,
+        Expr::Array(_) => Value::Array(values.collect()),
+        _ => return None,
+    };
+    match result {
+        Value::Null => None,
+        result => Some(literal(result)),
+    }
+}
+
+// Applies the boolean identities holding in three-valued logic to an
+// operator whose operands are simplified.
+fn identities(expr: Expr) -> Expr {
+    match expr {
+        Expr::Not(inner) => match *inner {
+            Expr::Not(expr) => *expr,
+            inner => Expr::Not(Box::new(inner)),
+        },
+        Expr::And(left, right) => match (*left, *right) {
+            (Expr::Boolean(false), _) | (_, Expr::Boolean(false)) => Expr::Boolean(false),
+            (Expr::Boolean(true), expr) | (expr, Expr::Boolean(true)) => expr,
+            (left, right) if left == right => left,
+            (left, right) => Expr::And(Box::new(left), Box::new(right)),
+        },
+        Expr::Or(left, right) => match (*left, *right) {
+            (Expr::Boolean(true), _) | (_, Expr::Boolean(true)) => Expr::Boolean(true),
+            (Expr::Boolean(false), expr) | (expr, Expr::Boolean(false)) => expr,
+            (left, right) if left == right => left,
+            (left, right) => Expr::Or(Box::new(left), Box::new(right)),
+        },
+        expr => expr,
+    }
+}
+
+/// Values a column is compared with in a conjunction.
+struct Range {
+    column: Expr,
+    // Bounds along with whether they are inclusive.
+    lower: Option<(Value, bool)>,
+    upper: Option<(Value, bool)>,
+    equal: Option<Value>,
+    satisfiable: bool,
+}
+
+impl Range {
+    // Narrows the range by a comparison with a value of the column's type.
+    fn narrow(&mut self, op: &str, value: Value) {
+        match op {
+            ">" | ">=" => {
+                let bound = (value, op == ">=");
+                self.lower = Some(match self.lower.take() {
+                    Some(lower) => tighter(lower, bound, Ordering::Greater),
+                    None => bound,
+                })
+            }
+            "<" | "<=" => {
+                let bound = (value, op == "<=");
+                self.upper = Some(match self.upper.take() {
+                    Some(upper) => tighter(upper, bound, Ordering::Less),
+                    None => bound,
+                })
+            }
+            _ => match &self.equal {
+                Some(equal) if *equal != value => self.satisfiable = false,
+                _ => self.equal = Some(value),
+            },
+        }
+        // Two bounds are disjoint when they are crossed, or meet without
+        // both including the value they meet at.
+        let disjoint = |(lower, inclusive): &(Value, bool),
+                        (upper, upper_inclusive): &(Value, bool)| {
+            lower > upper || lower == upper && !(*inclusive && *upper_inclusive)
+        };
+        let equal = self.equal.clone().map(|equal| (equal, true));
+        if matches!((&self.lower, &self.upper), (Some(lower), Some(upper)) if disjoint(lower, upper))
+            || matches!((&equal, &self.lower), (Some(equal), Some(lower)) if disjoint(lower, equal))
+            || matches!((&equal, &self.upper), (Some(equal), Some(upper)) if disjoint(equal, upper))
+        {
+            self.satisfiable = false;
+        }
+    }
+
+    // Returns the comparisons the range is made of.
+    fn terms(self) -> Vec<Expr> {
+        let compare = |op: &str, value: Value| {
+            Expr::Comparison(
+                Box::new(self.column.clone()),
+                op.to_string(),
+                Box::new(literal(value)),
+            )
+        };
+        match (self.equal, self.lower, self.upper) {
+            (Some(equal), _, _) => vec![compare("=", equal)],
+            (None, Some((lower, true)), Some((upper, true))) if lower == upper => {
+                vec![compare("=", lower)]
+            }
+            (None, lower, upper) => {
+                lower
+                    .map(|(lower, inclusive)| compare(if inclusive { ">=" } else { ">" }, lower))
+                    .into_iter()
+                    .chain(upper.map(|(upper, inclusive)| {
+                        compare(if inclusive { "<=" } else { "<" }, upper)
+                    }))
+                    .collect()
+            }
+        }
+    }
+}
+
+// Returns the tighter of two bounds on the same side of a range, `toward`
+// being the direction in which the bound narrows the range. An exclusive
+// bound is tighter than an inclusive one at the same value.
+fn tighter(left: (Value, bool), right: (Value, bool), toward: Ordering) -> (Value, bool) {
+    match left.0.cmp(&right.0) {
+        Ordering::Equal => (left.0, left.1 && right.1),
+        ordering if ordering == toward => left,
+        _ => right,
+    }
+}
+
+// Returns the column and constant a term compares, along with the
+// comparison written with the column on the left.
+fn comparison(term: &Expr) -> Option<(usize, &Expr, String, Value)> {
+    let Expr::Comparison(left, op, right) = term else {
+        return None;
+    };
+    let (column, op, value) = match (left.as_ref(), constant(right), constant(left)) {
+        (Expr::Bound(_), Some(value), _) => (left.as_ref(), op.clone(), value),
+        (_, _, Some(value)) if matches!(right.as_ref(), Expr::Bound(_)) => {
+            let op = match op.as_str() {
+                ">" => "<",
+                ">=" => "<=",
+                "<" => ">",
+                "<=" => ">=",
+                op => op,
+            };
+            (right.as_ref(), op.to_string(), value)
+        }
+        _ => return None,
+    };
+    let Expr::Bound(bound) = column else {
+        unreachable!()
+    };
+    match (op.as_str(), value) {
+        (_, Value::Null) => None,
+        (">" | ">=" | "<" | "<=" | "=", value) if value.data_type() == bound.data_type => {
+            Some((bound.index, column, op, value))
+        }
+        _ => None,
+    }
+}
+
+// Merges the comparisons of the same column with constants, keeping each
+// merged range at the position of its first comparison. Returns `None` when
+// a range is empty.
+fn merge_ranges(terms: Vec<Expr>) -> Option<Vec<Expr>> {
+    // Terms are either kept as is or refer to a range by index.
+    let mut merged: Vec<Result<Expr, usize>> = vec![];
+    let mut ranges: Vec<(usize, Range)> = vec![];
+    for term in terms {
+        let Some((index, column, op, value)) = comparison(&term) else {
+            merged.push(Ok(term));
+            continue;
+        };
+        let position = match ranges.iter().position(|(column, _)| *column == index) {
+            Some(position) => position,
+            None => {
+                ranges.push((
+                    index,
+                    Range {
+                        column: column.clone(),
+                        lower: None,
+                        upper: None,
+                        equal: None,
+                        satisfiable: true,
+                    },
+                ));
+                merged.push(Err(ranges.len() - 1));
+                ranges.len() - 1
+            }
+        };
+        ranges[position].1.narrow(&op, value);
+    }
+    if ranges.iter().any(|(_, range)| !range.satisfiable) {
+        return None;
+    }
+    let mut ranges = ranges
+        .into_iter()
+        .map(|(_, range)| Some(range))
+        .collect::<Vec<_>>();
+    Some(
+        merged
+            .into_iter()
+            .flat_map(|term| match term {
+                Ok(term) => vec![term],
+                Err(position) => ranges[position].take().unwrap().terms(),
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binder::Binder;
+    use crate::functions::FunctionRegistry;
+    use crate::schema::{Field, Schema};
+    use crate::sql::{Parser, Tokenizer};
+    use crate::typecheck::TypeChecker;
+    use crate::types::DataType;
+
+    fn bind(sql: &str) -> Expr {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Integer),
+            Field::new("b", DataType::BigInt),
+            Field::new("active", DataType::Boolean),
+        ]);
+        let functions = FunctionRegistry::new();
+        let expr = Parser::new(Tokenizer::new(sql)).unwrap().expr().unwrap();
+        let expr = TypeChecker::new(&schema, &functions).check(expr).unwrap().0;
+        Binder::resolve_columns(expr, &schema)
+    }
+
+    fn simplified(sql: &str) -> String {
+        simplify(bind(sql)).to_string()
+    }
+
+    fn predicate(sql: &str) -> String {
+        simplify_predicate(bind(sql)).to_string()
+    }
+
+    #[test]
+    fn constants_are_folded() {
+        assert_eq!(simplified("a > 5 + 5"), "a > 10");
+        assert_eq!(simplified("1 = 1 AND a > 2 * 3 - 1"), "a > 5");
+        assert_eq!(simplified("b + 1"), "b + 1");
+        assert_eq!(simplified("b > 1 + 1"), "b > 2");
+        assert_eq!(simplified("DATE '2024-01-31' + 1"), "DATE '2024-02-01'");
+        // Errors are raised when the expression is evaluated.
+        assert_eq!(simplified("a + 1 / 0"), "a + 1 / 0");
+        assert_eq!(simplified("NULL = 1 OR active"), "NULL OR active");
+    }
+
+    #[test]
+    fn boolean_identities_are_applied() {
+        assert_eq!(simplified("NOT NOT active"), "active");
+        assert_eq!(simplified("active AND 1 > 2"), "FALSE");
+        assert_eq!(simplified("active OR 1 < 2"), "TRUE");
+        assert_eq!(simplified("active AND active OR FALSE"), "active");
+        // Unknown values are preserved.
+        assert_eq!(simplified("NOT active AND 1 = 1"), "NOT active");
+        assert_eq!(simplified("NULL AND active"), "NULL AND active");
+    }
+
+    #[test]
+    fn ranges_are_merged() {
+        assert_eq!(predicate("a > 5 AND a > 7"), "a > 7");
+        assert_eq!(
+            predicate("a < 5 AND active AND 3 < a"),
+            "a > 3 AND a < 5 AND active"
+        );
+        assert_eq!(predicate("a = 4 AND a > 1 AND b > 1"), "a = 4 AND b > 1");
+        assert_eq!(
+            predicate("a > 1 AND a > 2 OR a < 0 AND a < 1"),
+            "a > 2 OR a < 0"
+        );
+        assert_eq!(predicate("a >= 5 AND a > 5"), "a > 5");
+        assert_eq!(
+            predicate("a >= 5 AND a >= 3 AND 7 >= a"),
+            "a >= 5 AND a <= 7"
+        );
+        assert_eq!(predicate("a <= 5 AND a < 6 AND a >= 5"), "a = 5");
+        // Inequalities are kept as is.
+        assert_eq!(predicate("a <> 5 AND a > 1"), "a <> 5 AND a > 1");
+        // Ranges are only merged in predicates, where NULL is false.
+        assert_eq!(simplified("a > 5 AND a > 7"), "a > 5 AND a > 7");
+    }
+
+    #[test]
+    fn contradictions_are_detected() {
+        assert_eq!(predicate("a = 1 AND a = 2"), "FALSE");
+        assert_eq!(predicate("a > 5 AND active AND a < 3"), "FALSE");
+        assert_eq!(predicate("a > 5 AND a < 6"), "a > 5 AND a < 6");
+        assert_eq!(predicate("a >= 5 AND a < 5"), "FALSE");
+        assert_eq!(predicate("a >= 5 AND a <= 4"), "FALSE");
+        assert_eq!(predicate("a = 5 AND a <= 5"), "a = 5");
+        assert_eq!(predicate("a = 4 AND a >= 5"), "FALSE");
+        assert_eq!(predicate("a = 5 AND a > 5"), "FALSE");
+        assert_eq!(predicate("a = 1 AND a = 2 OR active"), "active");
+        assert_eq!(predicate("NULL = 1"), "FALSE");
+    }
+}
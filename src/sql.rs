@@ -1,27 +1,113 @@
 //! Implementation of a minimal SQL tokenizer and parser for the subset of operators
 //! that we have implemented.
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt;
 use std::marker::PhantomData;
 
+use crate::decimal::MAX_PRECISION;
+use crate::temporal::DateTimeField;
+use crate::types::{DataType, Value};
+
+/// Error reported by the tokenizer and the parser on text which is not a
+/// valid statement, holding the message describing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError(pub String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for ParseError {}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Token {
     Select,
     From,
     Where,
+    GroupBy,
     OrderBy,
     Limit,
     And,
     Or,
     Not,
+    Is,
+    As,
+    Join,
+    Inner,
+    Cross,
+    Left,
+    Right,
+    Full,
+    Outer,
+    Natural,
+    On,
+    Lateral,
+    Array,
+    Cast,
+    True,
+    False,
+    Null,
+    Create,
+    Drop,
+    Alter,
+    Table,
+    Column,
+    Add,
+    Rename,
+    To,
+    If,
+    Exists,
+    Default,
+    Insert,
+    Into,
+    Values,
+    Update,
+    Set,
+    Delete,
+    Returning,
+    Conflict,
+    Do,
+    Nothing,
+    Merge,
+    Using,
+    When,
+    Matched,
+    Then,
+    Constraint,
+    Primary,
+    Unique,
+    Check,
+    Foreign,
+    References,
+    Analyze,
+    Explain,
     Identifier(String),
     Varchar(String),
     Number(i64),
+    /// Numeric literal with a fractional part, an exponent or too large for
+    /// an `i64`, kept as written.
+    Numeric(String),
     Comma,
+    Dot,
+    DoubleColon,
+    Star,
+    Plus,
+    Minus,
+    Slash,
     Semicolon,
     OpenParen,
     CloseParen,
+    OpenBracket,
+    CloseBracket,
     Equal,
+    NotEqual,
     GreaterThan,
+    GreaterThanOrEqual,
     LessThan,
+    LessThanOrEqual,
     EOF,
 }
 
@@ -35,23 +121,54 @@ impl<'a> Tokenizer<'a> {
         Self { input, pos: 0 }
     }
 
-    pub fn next(&mut self) -> Token {
-        self.skip_whitespace();
+    // `Iterator::next` wraps the token returned here in `Some`.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Token, ParseError> {
+        self.skip_whitespace()?;
 
         if self.pos >= self.input.len() {
-            return Token::EOF;
+            return Ok(Token::EOF);
         }
 
         let current_char = self.peek();
 
-        match current_char {
-            'a'..='z' | 'A'..='Z' => self.ident(),
+        let token = match current_char {
+            'e' | 'E' if self.peek_at(1) == Some('\'') => {
+                self.advance();
+                self.varchar(true)?
+            }
+            c if c.is_alphabetic() || c == '_' => self.ident(),
+            '"' => self.quoted_ident()?,
             '0'..='9' => self.number(),
-            '\'' => self.varchar(),
+            '\'' => self.varchar(false)?,
             ',' => {
                 self.pos += 1;
                 Token::Comma
             }
+            '.' => {
+                self.pos += 1;
+                Token::Dot
+            }
+            ':' if self.input[self.pos..].starts_with("::") => {
+                self.pos += 2;
+                Token::DoubleColon
+            }
+            '*' => {
+                self.pos += 1;
+                Token::Star
+            }
+            '+' => {
+                self.pos += 1;
+                Token::Plus
+            }
+            '-' => {
+                self.pos += 1;
+                Token::Minus
+            }
+            '/' => {
+                self.pos += 1;
+                Token::Slash
+            }
             ';' => {
                 self.pos += 1;
                 Token::Semicolon
@@ -64,76 +181,343 @@ impl<'a> Tokenizer<'a> {
                 self.pos += 1;
                 Token::CloseParen
             }
+            '[' => {
+                self.pos += 1;
+                Token::OpenBracket
+            }
+            ']' => {
+                self.pos += 1;
+                Token::CloseBracket
+            }
             '=' => {
                 self.pos += 1;
                 Token::Equal
             }
+            '>' if self.input[self.pos..].starts_with(">=") => {
+                self.pos += 2;
+                Token::GreaterThanOrEqual
+            }
             '>' => {
                 self.pos += 1;
                 Token::GreaterThan
             }
+            '<' if self.input[self.pos..].starts_with("<=") => {
+                self.pos += 2;
+                Token::LessThanOrEqual
+            }
+            '<' if self.input[self.pos..].starts_with("<>") => {
+                self.pos += 2;
+                Token::NotEqual
+            }
             '<' => {
                 self.pos += 1;
                 Token::LessThan
             }
-            _ => panic!("Unexpected character: {}", current_char),
-        }
+            '!' if self.input[self.pos..].starts_with("!=") => {
+                self.pos += 2;
+                Token::NotEqual
+            }
+            _ => return Err(ParseError(format!("Unexpected character: {current_char}"))),
+        };
+        Ok(token)
     }
 
     fn peek(&self) -> char {
         self.input[self.pos..].chars().next().unwrap()
     }
 
-    fn skip_whitespace(&mut self) {
-        while self.pos < self.input.len() && self.peek().is_whitespace() {
-            self.pos += 1;
+    // Move past the current character.
+    fn advance(&mut self) {
+        self.pos += self.peek().len_utf8();
+    }
+
+    // Skip whitespace along with `--` line comments and `/* */` block
+    // comments, which can be nested.
+    fn skip_whitespace(&mut self) -> Result<(), ParseError> {
+        loop {
+            let rest = &self.input[self.pos..];
+            if rest.starts_with(char::is_whitespace) {
+                self.advance();
+            } else if rest.starts_with("--") {
+                self.pos += rest.find('\n').unwrap_or(rest.len());
+            } else if rest.starts_with("/*") {
+                self.block_comment()?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn block_comment(&mut self) -> Result<(), ParseError> {
+        let start_pos = self.pos;
+        let mut depth = 0;
+        loop {
+            let rest = &self.input[self.pos..];
+            if rest.starts_with("/*") {
+                self.pos += 2;
+                depth += 1;
+            } else if rest.starts_with("*/") {
+                self.pos += 2;
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(());
+                }
+            } else if rest.is_empty() {
+                return Err(ParseError(format!(
+                    "Unterminated comment starting at position {start_pos}"
+                )));
+            } else {
+                self.advance();
+            }
         }
     }
 
+    // Tokenize a keyword or an unquoted identifier, made of letters, digits,
+    // underscores and dollar signs, which is folded to lower case.
     fn ident(&mut self) -> Token {
         let start_pos = self.pos;
-        while self.pos < self.input.len() && self.peek().is_alphanumeric() {
-            self.pos += 1;
+        while self.pos < self.input.len()
+            && (self.peek().is_alphanumeric() || self.peek() == '_' || self.peek() == '$')
+        {
+            self.advance();
         }
-        let identifier = &self.input[start_pos..self.pos];
-        match identifier.to_lowercase().as_str() {
+        let identifier = self.input[start_pos..self.pos].to_lowercase();
+        match identifier.as_str() {
             "select" => Token::Select,
             "from" => Token::From,
             "where" => Token::Where,
+            "group" if self.keyword("by") => Token::GroupBy,
+            "order" if self.keyword("by") => Token::OrderBy,
+            "groupby" => Token::GroupBy,
             "orderby" => Token::OrderBy,
             "limit" => Token::Limit,
             "and" => Token::And,
             "or" => Token::Or,
             "not" => Token::Not,
-            _ => Token::Identifier(identifier.to_string()),
+            "is" => Token::Is,
+            "as" => Token::As,
+            "join" => Token::Join,
+            "inner" => Token::Inner,
+            "cross" => Token::Cross,
+            "left" => Token::Left,
+            "right" => Token::Right,
+            "full" => Token::Full,
+            "outer" => Token::Outer,
+            "natural" => Token::Natural,
+            "on" => Token::On,
+            "lateral" => Token::Lateral,
+            "array" => Token::Array,
+            "cast" => Token::Cast,
+            "true" => Token::True,
+            "false" => Token::False,
+            "null" => Token::Null,
+            "create" => Token::Create,
+            "drop" => Token::Drop,
+            "alter" => Token::Alter,
+            "table" => Token::Table,
+            "column" => Token::Column,
+            "add" => Token::Add,
+            "rename" => Token::Rename,
+            "to" => Token::To,
+            "if" => Token::If,
+            "exists" => Token::Exists,
+            "default" => Token::Default,
+            "insert" => Token::Insert,
+            "into" => Token::Into,
+            "values" => Token::Values,
+            "update" => Token::Update,
+            "set" => Token::Set,
+            "delete" => Token::Delete,
+            "returning" => Token::Returning,
+            "conflict" => Token::Conflict,
+            "do" => Token::Do,
+            "nothing" => Token::Nothing,
+            "merge" => Token::Merge,
+            "using" => Token::Using,
+            "when" => Token::When,
+            "matched" => Token::Matched,
+            "then" => Token::Then,
+            "constraint" => Token::Constraint,
+            "primary" => Token::Primary,
+            "unique" => Token::Unique,
+            "check" => Token::Check,
+            "foreign" => Token::Foreign,
+            "references" => Token::References,
+            "analyze" => Token::Analyze,
+            "explain" => Token::Explain,
+            _ => Token::Identifier(identifier),
         }
     }
 
-    fn varchar(&mut self) -> Token {
+    // Tokenize an identifier between double quotes, which keeps its case and
+    // is never a keyword, quotes are escaped by doubling them.
+    fn quoted_ident(&mut self) -> Result<Token, ParseError> {
+        let start_pos = self.pos;
         // Skip opening quote.
-        self.pos += 1;
+        self.advance();
+        let mut identifier = String::new();
+        loop {
+            let Some(c) = self.peek_at(0) else {
+                return Err(ParseError(format!(
+                    "Unterminated quoted identifier starting at position {start_pos}"
+                )));
+            };
+            self.advance();
+            match c {
+                '"' if self.peek_at(0) == Some('"') => {
+                    self.advance();
+                    identifier.push('"');
+                }
+                '"' => break,
+                c => identifier.push(c),
+            }
+        }
+        if identifier.is_empty() {
+            return Err(ParseError(format!(
+                "Zero-length quoted identifier at position {start_pos}"
+            )));
+        }
+        Ok(Token::Identifier(identifier))
+    }
+
+    // Consume the next word if it is the given keyword, invalid text
+    // following the current word is reported when it is tokenized.
+    fn keyword(&mut self, keyword: &str) -> bool {
         let start_pos = self.pos;
-        while self.pos < self.input.len() && self.peek().is_ascii_alphanumeric() {
-            self.pos += 1;
+        if self.skip_whitespace().is_err() {
+            self.pos = start_pos;
+            return false;
+        }
+        let word_pos = self.pos;
+        while self.pos < self.input.len() && self.peek().is_alphanumeric() {
+            self.advance();
+        }
+        if self.input[word_pos..self.pos].eq_ignore_ascii_case(keyword) {
+            true
+        } else {
+            self.pos = start_pos;
+            false
+        }
+    }
+
+    // Tokenize a string literal, quotes are escaped by doubling them and
+    // `E'...'` literals also accept backslash escapes.
+    fn varchar(&mut self, escapes: bool) -> Result<Token, ParseError> {
+        let start_pos = self.pos;
+        // Skip opening quote.
+        self.advance();
+        let mut varchar = String::new();
+        loop {
+            let Some(c) = self.peek_at(0) else {
+                return Err(ParseError(format!(
+                    "Unterminated string literal starting at position {start_pos}"
+                )));
+            };
+            self.advance();
+            match c {
+                '\'' if self.peek_at(0) == Some('\'') => {
+                    self.advance();
+                    varchar.push('\'');
+                }
+                '\'' => break,
+                '\\' if escapes => varchar.push(self.escape()?),
+                c => varchar.push(c),
+            }
         }
-        let varchar: String = self.input[start_pos..self.pos].to_string();
-        // Skip closing quote.
-        self.pos += 1;
-        Token::Varchar(varchar)
+        Ok(Token::Varchar(varchar))
     }
 
+    // Decode the escape sequence following a backslash, `\b`, `\f`, `\n`, `\r`
+    // and `\t` are control characters, `\ooo`, `\xhh`, `\uxxxx` and
+    // `\Uxxxxxxxx` octal or hexadecimal code points, and any other character
+    // stands for itself.
+    fn escape(&mut self) -> Result<char, ParseError> {
+        let Some(c) = self.peek_at(0) else {
+            return Ok('\\');
+        };
+        self.advance();
+        let c = match c {
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'x' => return self.code_point(16, 2),
+            'u' => return self.code_point(16, 4),
+            'U' => return self.code_point(16, 8),
+            '0'..='7' => {
+                self.pos -= 1;
+                return self.code_point(8, 3);
+            }
+            c => c,
+        };
+        Ok(c)
+    }
+
+    // Decode a code point written with up to `max_digits` digits in the given
+    // radix, `\u` and `\U` escapes require all of them.
+    fn code_point(&mut self, radix: u32, max_digits: usize) -> Result<char, ParseError> {
+        let start_pos = self.pos;
+        while self.pos - start_pos < max_digits
+            && self.peek_at(0).is_some_and(|c| c.is_digit(radix))
+        {
+            self.advance();
+        }
+        let digits = &self.input[start_pos..self.pos];
+        if digits.is_empty() || max_digits >= 4 && digits.len() < max_digits {
+            return Err(ParseError(format!(
+                "Invalid escape sequence at position {start_pos}"
+            )));
+        }
+        u32::from_str_radix(digits, radix)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| {
+                ParseError(format!(
+                    "Invalid code point {digits} at position {start_pos}"
+                ))
+            })
+    }
+
+    // Tokenize a number `digits[.digits][e[+-]digits]`.
     fn number(&mut self) -> Token {
         let start_pos = self.pos;
+        self.digits();
+        let mut integer = true;
+        if self.peek_at(0) == Some('.') && self.peek_at(1).is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+            self.digits();
+            integer = false;
+        }
+        if matches!(self.peek_at(0), Some('e' | 'E')) {
+            let sign = matches!(self.peek_at(1), Some('+' | '-')) as usize;
+            if self.peek_at(1 + sign).is_some_and(|c| c.is_ascii_digit()) {
+                self.pos += 1 + sign;
+                self.digits();
+                integer = false;
+            }
+        }
+        let number = &self.input[start_pos..self.pos];
+        match number.parse() {
+            Ok(number) if integer => Token::Number(number),
+            _ => Token::Numeric(number.to_string()),
+        }
+    }
+
+    fn digits(&mut self) {
         while self.pos < self.input.len() && self.peek().is_ascii_digit() {
             self.pos += 1;
         }
-        let number: i64 = self.input[start_pos..self.pos].parse().unwrap();
-        Token::Number(number)
+    }
+
+    // Returns the character at the given offset from the current position.
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.input[self.pos..].chars().nth(offset)
     }
 }
 
 impl<'a> Iterator for Tokenizer<'a> {
-    type Item = Token;
+    type Item = Result<Token, ParseError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         Some(self.next())
@@ -143,202 +527,1661 @@ impl<'a> Iterator for Tokenizer<'a> {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Expr {
     Column(String),
+    /// Column qualified by the name or alias of the `FROM` item it belongs to.
+    QualifiedColumn(String, String),
+    /// Column reference resolved by the binder, see `ColumnRef`.
+    Bound(Box<ColumnRef>),
+    Null,
+    Boolean(bool),
     Value(i64),
+    /// Numeric literal which is not an `i64`, see `Token::Numeric`.
+    Numeric(String),
     Varchar(String),
+    /// Value computed while planning, such as the result of a constant
+    /// expression folded by the simplifier.
+    Constant(Value),
     Comparison(Box<Expr>, String, Box<Expr>),
+    /// Arithmetic operation `+`, `-`, `*` or `/`.
+    Arithmetic(Box<Expr>, String, Box<Expr>),
+    Negate(Box<Expr>),
     And(Box<Expr>, Box<Expr>),
     Or(Box<Expr>, Box<Expr>),
     Not(Box<Expr>),
+    /// Test of whether a value is `NULL`, written `expr IS NULL` or, when
+    /// negated, `expr IS NOT NULL`.
+    IsNull(Box<Expr>, bool),
+    Function(String, Vec<Expr>),
+    Array(Vec<Expr>),
+    /// Conversion of a value to the given type, written `CAST(expr AS type)`
+    /// or `expr::type`. Typed literals such as `DATE '2024-01-31'` are casts
+    /// of string literals.
+    Cast(Box<Expr>, DataType),
+    /// Field of a temporal value, written `EXTRACT(field FROM expr)`.
+    Extract(DateTimeField, Box<Expr>),
+}
+
+/// Column reference resolved to the index of its column in the rows the
+/// expression is evaluated on, along with the type of its values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnRef {
+    /// Name or alias of the `FROM` item the column belongs to, if any.
+    pub table: Option<String>,
+    pub name: String,
+    pub index: usize,
+    pub data_type: DataType,
+    /// Whether the reference is written qualified by the table.
+    pub qualified: bool,
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Column(column) => write!(f, "{}", quote_identifier(column)),
+            Expr::QualifiedColumn(table, column) => write!(
+                f,
+                "{}.{}",
+                quote_identifier(table),
+                quote_identifier(column)
+            ),
+            // Bound references are written as they were before binding.
+            Expr::Bound(column) => match &column.table {
+                Some(table) if column.qualified => write!(
+                    f,
+                    "{}.{}",
+                    quote_identifier(table),
+                    quote_identifier(&column.name)
+                ),
+                _ => write!(f, "{}", quote_identifier(&column.name)),
+            },
+            Expr::Null => write!(f, "NULL"),
+            Expr::Boolean(true) => write!(f, "TRUE"),
+            Expr::Boolean(false) => write!(f, "FALSE"),
+            Expr::Value(value) => write!(f, "{value}"),
+            Expr::Numeric(numeric) => write!(f, "{numeric}"),
+            Expr::Varchar(varchar) => write!(f, "'{}'", varchar.replace('\'', "''")),
+            Expr::Constant(value) => write_constant(f, value),
+            Expr::Comparison(left, op, right) => write!(f, "{left} {op} {right}"),
+            Expr::And(left, right) => {
+                // Disjunctions bind looser than conjunctions.
+                let operand = |expr: &Expr| match expr {
+                    Expr::Or(_, _) => format!("({expr})"),
+                    expr => expr.to_string(),
+                };
+                write!(f, "{} AND {}", operand(left), operand(right))
+            }
+            Expr::Or(left, right) => write!(f, "{left} OR {right}"),
+            Expr::Arithmetic(left, op, right) => {
+                // Operands binding looser than the operator are parenthesized,
+                // operators are left associative.
+                let left = match left.as_ref() {
+                    Expr::Arithmetic(_, inner, _) if precedence(inner) < precedence(op) => {
+                        format!("({left})")
+                    }
+                    left => left.to_string(),
+                };
+                let right = match right.as_ref() {
+                    Expr::Arithmetic(_, inner, _) if precedence(inner) <= precedence(op) => {
+                        format!("({right})")
+                    }
+                    right => right.to_string(),
+                };
+                write!(f, "{left} {op} {right}")
+            }
+            Expr::Negate(expr) => match expr.as_ref() {
+                Expr::Value(_)
+                | Expr::Numeric(_)
+                | Expr::Column(_)
+                | Expr::QualifiedColumn(_, _)
+                | Expr::Bound(_) => {
+                    write!(f, "-{expr}")
+                }
+                expr => write!(f, "-({expr})"),
+            },
+            Expr::Not(expr) => match expr.as_ref() {
+                Expr::And(_, _) | Expr::Or(_, _) => write!(f, "NOT ({expr})"),
+                expr => write!(f, "NOT {expr}"),
+            },
+            Expr::IsNull(expr, negated) => {
+                let not = if *negated { " NOT" } else { "" };
+                match expr.as_ref() {
+                    Expr::And(_, _) | Expr::Or(_, _) | Expr::Not(_) => {
+                        write!(f, "({expr}) IS{not} NULL")
+                    }
+                    expr => write!(f, "{expr} IS{not} NULL"),
+                }
+            }
+            // `COUNT(*)` is the only call written with a star.
+            Expr::Function(name, args) if args.is_empty() && name.eq_ignore_ascii_case("count") => {
+                write!(f, "{name}(*)")
+            }
+            Expr::Function(name, args) => {
+                let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+                write!(f, "{name}({})", args.join(", "))
+            }
+            Expr::Array(elements) => {
+                let elements = elements.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+                write!(f, "ARRAY[{}]", elements.join(", "))
+            }
+            Expr::Cast(expr, data_type) => write!(f, "CAST({expr} AS {data_type})"),
+            Expr::Extract(field, expr) => write!(f, "EXTRACT({field} FROM {expr})"),
+        }
+    }
+}
+
+impl Expr {
+    /// Returns the direct sub-expressions of the expression.
+    pub fn children(&self) -> Vec<&Expr> {
+        match self {
+            Expr::Comparison(left, _, right)
+            | Expr::Arithmetic(left, _, right)
+            | Expr::And(left, right)
+            | Expr::Or(left, right) => vec![left, right],
+            Expr::Negate(expr)
+            | Expr::Not(expr)
+            | Expr::IsNull(expr, _)
+            | Expr::Cast(expr, _)
+            | Expr::Extract(_, expr) => vec![expr],
+            Expr::Function(_, exprs) | Expr::Array(exprs) => exprs.iter().collect(),
+            Expr::Column(_)
+            | Expr::QualifiedColumn(_, _)
+            | Expr::Bound(_)
+            | Expr::Null
+            | Expr::Boolean(_)
+            | Expr::Value(_)
+            | Expr::Numeric(_)
+            | Expr::Varchar(_)
+            | Expr::Constant(_) => vec![],
+        }
+    }
+
+    /// Rewrite the sub-expressions of the expression with `f` from the leaves
+    /// up, then the expression itself.
+    pub fn transform<F: FnMut(Expr) -> Expr>(self, f: &mut F) -> Expr {
+        let boxed = |expr: Box<Expr>, f: &mut F| Box::new(expr.transform(f));
+        let expr = match self {
+            Expr::Comparison(left, op, right) => {
+                let left = boxed(left, f);
+                Expr::Comparison(left, op, boxed(right, f))
+            }
+            Expr::Arithmetic(left, op, right) => {
+                let left = boxed(left, f);
+                Expr::Arithmetic(left, op, boxed(right, f))
+            }
+            Expr::And(left, right) => {
+                let left = boxed(left, f);
+                Expr::And(left, boxed(right, f))
+            }
+            Expr::Or(left, right) => {
+                let left = boxed(left, f);
+                Expr::Or(left, boxed(right, f))
+            }
+            Expr::Negate(expr) => Expr::Negate(boxed(expr, f)),
+            Expr::Not(expr) => Expr::Not(boxed(expr, f)),
+            Expr::IsNull(expr, negated) => Expr::IsNull(boxed(expr, f), negated),
+            Expr::Cast(expr, data_type) => Expr::Cast(boxed(expr, f), data_type),
+            Expr::Extract(field, expr) => Expr::Extract(field, boxed(expr, f)),
+            Expr::Function(name, args) => {
+                Expr::Function(name, args.into_iter().map(|arg| arg.transform(f)).collect())
+            }
+            Expr::Array(elements) => Expr::Array(
+                elements
+                    .into_iter()
+                    .map(|element| element.transform(f))
+                    .collect(),
+            ),
+            expr => expr,
+        };
+        f(expr)
+    }
+
+    /// Returns the terms of the conjunction the expression is, left to right,
+    /// an expression which is not a conjunction is its only term.
+    pub fn conjuncts(self) -> Vec<Expr> {
+        match self {
+            Expr::And(left, right) => {
+                let mut terms = left.conjuncts();
+                terms.extend(right.conjuncts());
+                terms
+            }
+            expr => vec![expr],
+        }
+    }
+
+    /// Returns the indices of the bound columns the expression reads.
+    pub fn references(&self) -> BTreeSet<usize> {
+        match self {
+            Expr::Bound(column) => BTreeSet::from([column.index]),
+            expr => expr
+                .children()
+                .into_iter()
+                .flat_map(Expr::references)
+                .collect(),
+        }
+    }
+
+    /// Returns the names of the columns referenced by the expression, in
+    /// order of appearance and including duplicates.
+    pub fn columns(&self) -> Vec<&str> {
+        match self {
+            Expr::Column(column) | Expr::QualifiedColumn(_, column) => vec![column],
+            Expr::Bound(column) => vec![&column.name],
+            expr => expr
+                .children()
+                .into_iter()
+                .flat_map(|child| child.columns())
+                .collect(),
+        }
+    }
+}
+
+/// Returns an identifier as written in SQL text, identifiers which would not
+/// be read back unchanged when unquoted are quoted.
+fn quote_identifier(identifier: &str) -> String {
+    let mut chars = identifier.chars();
+    let lowercase = |c: char| c.is_alphabetic() && !c.is_uppercase();
+    let plain = chars.next().is_some_and(|c| c == '_' || lowercase(c))
+        && chars.all(|c| c == '_' || c == '$' || c.is_numeric() || lowercase(c))
+        && Tokenizer::new(identifier).next() == Ok(Token::Identifier(identifier.to_string()));
+    if plain {
+        identifier.to_string()
+    } else {
+        format!("\"{}\"", identifier.replace('"', "\"\""))
+    }
+}
+
+/// Writes a constant as the literal it would be written as, temporal values
+/// as typed string literals.
+fn write_constant(f: &mut fmt::Formatter<'_>, value: &Value) -> fmt::Result {
+    match value {
+        Value::Null => write!(f, "NULL"),
+        Value::Boolean(true) => write!(f, "TRUE"),
+        Value::Boolean(false) => write!(f, "FALSE"),
+        Value::Integer(_) | Value::BigInt(_) | Value::Decimal(_) | Value::Float(_) => {
+            write!(f, "{value}")
+        }
+        Value::Varchar(varchar) => write!(f, "'{}'", varchar.replace('\'', "''")),
+        Value::Array(values) => {
+            write!(f, "ARRAY[")?;
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write_constant(f, value)?;
+            }
+            write!(f, "]")
+        }
+        Value::Date(_) | Value::Time(_) | Value::Timestamp(_) | Value::Interval(_) => {
+            write!(f, "{} '{value}'", value.data_type())
+        }
+    }
+}
+
+/// Returns the precedence of an arithmetic operator.
+fn precedence(op: &str) -> u8 {
+    match op {
+        "*" | "/" => 2,
+        _ => 1,
+    }
+}
+
+/// Alias given to a `FROM` item and optionally to its columns, `AS t(a, b)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alias {
+    pub name: String,
+    pub columns: Vec<String>,
+}
+
+/// Items of the `FROM` clause.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableRef {
+    /// Table referenced by name.
+    Table { name: String, alias: Option<Alias> },
+    /// Call to a table function, `LATERAL` calls can reference the columns of
+    /// the items preceding them.
+    Function {
+        name: String,
+        args: Vec<Expr>,
+        lateral: bool,
+        alias: Option<Alias>,
+    },
+    /// `(VALUES (expr, ...), ...) [AS] alias [(column, ...)]`, the alias is
+    /// required as its columns are otherwise named `column1`, `column2`...
+    Values { rows: Vec<Vec<Expr>>, alias: Alias },
+    /// Join of two items, items separated by commas are joined without any
+    /// condition.
+    Join {
+        left: Box<TableRef>,
+        right: Box<TableRef>,
+        on: Option<Expr>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Query {
     Select {
+        columns: Vec<Expr>,
+        from: Box<TableRef>,
+        filter: Option<Expr>,
+        group_by: Vec<Expr>,
+        order_by: Option<Expr>,
+        limit: Option<i64>,
+    },
+    /// `VALUES (expr, ...), ...`, rows of constant expressions whose columns
+    /// are named `column1`, `column2`...
+    Values(Vec<Vec<Expr>>),
+}
+
+/// Definition of a column in `CREATE TABLE` and `ALTER TABLE ADD COLUMN`,
+/// `name TYPE [NOT NULL] [DEFAULT expr] [constraint ...]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnDef {
+    pub name: String,
+    pub data_type: DataType,
+    pub not_null: bool,
+    pub default: Option<Expr>,
+    /// Constraints declared along with the column, which apply to it.
+    pub constraints: Vec<ConstraintDef>,
+}
+
+/// Constraint declared by `CREATE TABLE`, optionally named by a
+/// `CONSTRAINT name` prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstraintDef {
+    pub name: Option<String>,
+    pub kind: ConstraintKind,
+}
+
+/// Constraints on the rows of a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConstraintKind {
+    PrimaryKey(Vec<String>),
+    Unique(Vec<String>),
+    /// Predicate every row must satisfy, rows for which it is `NULL` do.
+    Check(Expr),
+    /// `FOREIGN KEY (column, ...) REFERENCES table [(column, ...)]`, the
+    /// primary key of the referenced table when no column is referenced.
+    ForeignKey {
         columns: Vec<String>,
         table: String,
+        referenced: Vec<String>,
+        on_delete: ReferentialAction,
+        on_update: ReferentialAction,
+    },
+}
+
+/// Action taken on the rows referencing a deleted or updated key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReferentialAction {
+    #[default]
+    NoAction,
+    Restrict,
+    Cascade,
+    SetNull,
+}
+
+/// Format of the plans `EXPLAIN` describes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExplainFormat {
+    /// One line per operator, inputs indented below the operators consuming
+    /// their rows.
+    #[default]
+    Text,
+    /// A Graphviz DOT graph.
+    Dot,
+    /// A JSON document.
+    Json,
+}
+
+/// Changes made to a table by `ALTER TABLE`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlterTableAction {
+    AddColumn(ColumnDef),
+    DropColumn { name: String, if_exists: bool },
+    RenameColumn { from: String, to: String },
+}
+
+/// Rows inserted by `INSERT`, either lists of expressions or the rows of a
+/// query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InsertSource {
+    Values(Vec<Vec<Expr>>),
+    Query(Box<Query>),
+}
+
+/// `ON CONFLICT [(column, ...)] action` clause of an `INSERT`, rows whose
+/// values for the columns equal those of an existing row are not inserted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnConflict {
+    pub columns: Vec<String>,
+    pub action: ConflictAction,
+}
+
+/// Action taken on the existing row an inserted row conflicts with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictAction {
+    DoNothing,
+    /// `DO UPDATE SET column = expr, ... [WHERE filter]`, the inserted row
+    /// is referenced by the `excluded` table name.
+    DoUpdate {
+        assignments: Vec<(String, Expr)>,
         filter: Option<Expr>,
-        order_by: Option<String>,
-        limit: Option<i64>,
     },
 }
 
-pub struct Parser<'a, T: Iterator<Item = Token>> {
+/// `WHEN [NOT] MATCHED [AND condition] THEN action` clause of a `MERGE`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeClause {
+    pub matched: bool,
+    pub condition: Option<Expr>,
+    pub action: MergeAction,
+}
+
+/// Actions of `MERGE` clauses, matched rows can be updated or deleted while
+/// source rows without a match can be inserted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeAction {
+    Update(Vec<(String, Expr)>),
+    Delete,
+    Insert {
+        columns: Vec<String>,
+        values: Vec<Expr>,
+    },
+    DoNothing,
+}
+
+/// Statements of a SQL script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Statement {
+    Query(Query),
+    CreateTable {
+        name: String,
+        if_not_exists: bool,
+        columns: Vec<ColumnDef>,
+        constraints: Vec<ConstraintDef>,
+    },
+    /// `CREATE TABLE name AS query`, the table takes the columns and rows of
+    /// the query.
+    CreateTableAs {
+        name: String,
+        if_not_exists: bool,
+        query: Query,
+    },
+    DropTable {
+        name: String,
+        if_exists: bool,
+    },
+    AlterTable {
+        name: String,
+        action: AlterTableAction,
+    },
+    /// `INSERT INTO table [(column, ...)] source`, columns which are not
+    /// listed take their default.
+    Insert {
+        table: String,
+        columns: Vec<String>,
+        source: InsertSource,
+        on_conflict: Option<OnConflict>,
+        returning: Vec<Expr>,
+    },
+    /// `UPDATE table SET column = expr, ... [WHERE filter]`.
+    Update {
+        table: String,
+        assignments: Vec<(String, Expr)>,
+        filter: Option<Expr>,
+        returning: Vec<Expr>,
+    },
+    Delete {
+        table: String,
+        filter: Option<Expr>,
+        returning: Vec<Expr>,
+    },
+    /// `MERGE INTO target [alias] USING source ON condition clause ...`, the
+    /// first clause whose condition holds applies to each source row.
+    Merge {
+        target: String,
+        alias: Option<String>,
+        source: TableRef,
+        on: Expr,
+        clauses: Vec<MergeClause>,
+    },
+    /// `ANALYZE [table]`, collects the statistics of a table or of every
+    /// table of the catalog.
+    Analyze {
+        table: Option<String>,
+    },
+    /// `EXPLAIN [ANALYZE] [(option, ...)] query`, describes the operators
+    /// executing a query instead of returning its rows. With `ANALYZE` the
+    /// query is executed and operators are described along with the metrics
+    /// of their execution. Options are `ANALYZE` and `FORMAT TEXT|DOT|JSON`.
+    Explain {
+        query: Query,
+        analyze: bool,
+        format: ExplainFormat,
+    },
+}
+
+/// Parse every statement of a SQL script, see `Parser::parse_script`.
+pub fn parse_script(script: &str) -> Result<Vec<Statement>, ParseError> {
+    Parser::new(Tokenizer::new(script))?.parse_script()
+}
+
+pub struct Parser<'a, T: Iterator<Item = Result<Token, ParseError>>> {
     tokenizer: T,
     current_token: Token,
     phantom: PhantomData<&'a T>,
 }
 
-impl<'a, T: Iterator<Item = Token>> Parser<'a, T> {
-    pub fn new(tokenizer: T) -> Self {
+impl<'a, T: Iterator<Item = Result<Token, ParseError>>> Parser<'a, T> {
+    /// Create a parser reading the tokens of `tokenizer`, fails when the
+    /// first token cannot be read.
+    pub fn new(tokenizer: T) -> Result<Self, ParseError> {
         let mut parser = Self {
             tokenizer,
             current_token: Token::EOF,
             phantom: PhantomData,
         };
         // Synchronize the first token in the parser.
-        parser.next();
-        parser
+        parser.next()?;
+        Ok(parser)
     }
 
     // Update `current_token` with the next token.
-    fn next(&mut self) {
+    fn next(&mut self) -> Result<(), ParseError> {
         self.current_token = match self.tokenizer.next() {
-            Some(token) => token,
+            Some(token) => token?,
             None => Token::EOF,
         };
+        Ok(())
+    }
+
+    // Report that the current token is not the expected one.
+    fn unexpected<R>(&self, expected: &str) -> Result<R, ParseError> {
+        Err(ParseError(format!(
+            "Expected {expected} found {:?}",
+            self.current_token
+        )))
     }
 
     // Parse an identifier.
-    fn ident(&mut self) -> String {
+    fn ident(&mut self) -> Result<String, ParseError> {
         if let Token::Identifier(ref id) = self.current_token {
             let identifier = id.clone();
-            self.next();
-            identifier
+            self.next()?;
+            Ok(identifier)
         } else {
-            panic!("Expected identifier")
+            self.unexpected("identifier")
+        }
+    }
+
+    // Consume the current token if it is the expected one.
+    fn expect(&mut self, token: Token) -> Result<(), ParseError> {
+        if self.current_token != token {
+            return self.unexpected(&format!("{token:?}"));
+        }
+        self.next()
+    }
+
+    // Consume the current token if it is an identifier spelling the given
+    // non-reserved keyword, returns whether it was consumed.
+    fn accept_keyword(&mut self, keyword: &str) -> Result<bool, ParseError> {
+        if !matches!(&self.current_token, Token::Identifier(id) if id == keyword) {
+            return Ok(false);
+        }
+        self.next()?;
+        Ok(true)
+    }
+
+    // Consume the current token if it spells the given non-reserved keyword.
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), ParseError> {
+        if !self.accept_keyword(keyword)? {
+            return self.unexpected(&keyword.to_uppercase());
+        }
+        Ok(())
+    }
+
+    // Consume the current token if it is the given one, returns whether it
+    // was consumed.
+    fn accept(&mut self, token: Token) -> Result<bool, ParseError> {
+        if self.current_token != token {
+            return Ok(false);
         }
+        self.next()?;
+        Ok(true)
     }
 
     // Parse a numerical value.
-    fn number(&mut self) -> i64 {
+    fn number(&mut self) -> Result<i64, ParseError> {
         if let Token::Number(num) = self.current_token {
-            self.next();
-            num
+            self.next()?;
+            Ok(num)
         } else {
-            panic!("Expected number")
+            self.unexpected("number")
         }
     }
 
-    // Parse an expression, expression parsing is done without much care for precedence.
-    fn expr(&mut self) -> Expr {
-        // Parse primary expressions (identifiers or numbers)
-        let mut left = match self.current_token {
-            Token::Identifier(ref id) => {
-                // Here we assume all identifiers are columns, no schema required.
-                let identifier = id.clone();
-                self.next();
-                Expr::Column(identifier)
-            }
-            Token::Varchar(ref ident) => {
-                let ident = ident.clone();
-                self.next();
-                Expr::Varchar(ident)
-            }
-            Token::Number(num) => {
-                self.next(); // Move past number
-                Expr::Value(num)
-            }
-            Token::OpenParen => {
-                self.next(); // Move past open parenthesis
-                let expr = self.expr(); // Parse expression within parentheses
-                if let Token::CloseParen = self.current_token {
-                    self.next(); // Move past close parenthesis
-                    expr
-                } else {
-                    panic!("Expected closing parenthesis")
+    // Parse a type name or an array type `type[]`, `DECIMAL` without
+    // precision is a `DECIMAL(18, 3)`.
+    fn data_type(&mut self) -> Result<DataType, ParseError> {
+        let name = self.ident()?;
+        let mut data_type = match name.to_lowercase().as_str() {
+            "integer" | "int" | "int4" => DataType::Integer,
+            "bigint" | "int8" => DataType::BigInt,
+            "decimal" | "numeric" => {
+                let (mut precision, mut scale) = (18, 3);
+                if let Token::OpenParen = self.current_token {
+                    self.next()?;
+                    precision = self.number()?;
+                    scale = 0;
+                    if let Token::Comma = self.current_token {
+                        self.next()?;
+                        scale = self.number()?;
+                    }
+                    self.expect(Token::CloseParen)?;
                 }
+                if !(1..=MAX_PRECISION as i64).contains(&precision)
+                    || !(0..=precision).contains(&scale)
+                {
+                    return Err(ParseError(format!(
+                        "Invalid DECIMAL precision {precision} and scale {scale}"
+                    )));
+                }
+                DataType::Decimal(precision as u8, scale as u8)
             }
-            _ => panic!("Unexpected token: {:?}", self.current_token),
+            "float" | "double" | "real" => DataType::Float,
+            "varchar" | "text" => DataType::Varchar,
+            "boolean" | "bool" => DataType::Boolean,
+            "date" => DataType::Date,
+            "time" => DataType::Time,
+            "timestamp" => DataType::Timestamp,
+            "interval" => DataType::Interval,
+            _ => return Err(ParseError(format!("Unknown type {name}"))),
         };
+        while let Token::OpenBracket = self.current_token {
+            self.next()?;
+            self.expect(Token::CloseBracket)?;
+            data_type = DataType::Array(Box::new(data_type));
+        }
+        Ok(data_type)
+    }
 
-        // Handle binary operators and logical operators
-        while matches!(
-            self.current_token,
-            Token::Equal | Token::GreaterThan | Token::LessThan | Token::And | Token::Or
-        ) {
-            let op = match self.current_token {
-                Token::Equal => "=".to_string(),
-                Token::GreaterThan => ">".to_string(),
-                Token::LessThan => "<".to_string(),
-                Token::And => "AND".to_string(),
-                Token::Or => "OR".to_string(),
-                _ => unreachable!(),
-            };
-            // Move past the operator
-            self.next();
-            // Recursively parse the right-hand side expression
-            let right = self.expr();
-
-            left = if op == "AND" {
-                Expr::And(Box::new(left), Box::new(right))
-            } else if op == "OR" {
-                Expr::Or(Box::new(left), Box::new(right))
-            } else {
-                // Comparison operators
-                Expr::Comparison(Box::new(left), op, Box::new(right))
+    // Parse the comma separated arguments of a function call up to and
+    // including the closing parenthesis.
+    fn args(&mut self) -> Result<Vec<Expr>, ParseError> {
+        let mut args = vec![];
+        // `COUNT(*)` is represented as a call without arguments.
+        if let Token::Star = self.current_token {
+            self.next()?;
+        }
+        while self.current_token != Token::CloseParen {
+            args.push(self.expr()?);
+            if let Token::Comma = self.current_token {
+                self.next()?;
+            } else if self.current_token != Token::CloseParen {
+                return self.unexpected("comma or closing parenthesis in function call");
             }
         }
+        // Move past close parenthesis
+        self.next()?;
+        Ok(args)
+    }
 
-        left
+    /// Parse an expression, operators from the loosest to the tightest
+    /// binding are `OR`, `AND`, `NOT`, comparisons and postfix casts.
+    pub fn expr(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.conjunction()?;
+        while let Token::Or = self.current_token {
+            self.next()?; // Move past OR
+            left = Expr::Or(Box::new(left), Box::new(self.conjunction()?));
+        }
+        Ok(left)
     }
 
-    // Parse the tokenized query returning a `Query` object.
-    pub fn parse(&mut self) -> Query {
-        // Ensure we're starting with a SELECT statement
-        if let Token::Select = self.current_token {
-            self.next(); // Move past SELECT
-
-            // Parse columns
-            let mut columns = vec![];
-            while let Token::Identifier(ref col) = self.current_token {
-                columns.push(col.clone());
-                self.next();
-                if let Token::Comma = self.current_token {
-                    self.next();
-                } else {
-                    break;
-                }
-            }
+    // Parse a conjunction of possibly negated comparisons.
+    fn conjunction(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.negation()?;
+        while let Token::And = self.current_token {
+            self.next()?; // Move past AND
+            left = Expr::And(Box::new(left), Box::new(self.negation()?));
+        }
+        Ok(left)
+    }
 
-            // Ensure we're at the FROM keyword
-            if let Token::From = self.current_token {
-                self.next(); // Move past FROM
-                let table = self.ident();
+    // Parse a possibly negated comparison.
+    fn negation(&mut self) -> Result<Expr, ParseError> {
+        if let Token::Not = self.current_token {
+            self.next()?; // Move past NOT
+            return Ok(Expr::Not(Box::new(self.negation()?)));
+        }
+        let left = self.sum()?;
+        // `!=` is written `<>` like in standard SQL.
+        let op = match self.current_token {
+            Token::Equal => "=",
+            Token::NotEqual => "<>",
+            Token::GreaterThan => ">",
+            Token::GreaterThanOrEqual => ">=",
+            Token::LessThan => "<",
+            Token::LessThanOrEqual => "<=",
+            _ => return self.null_test(left),
+        };
+        self.next()?; // Move past the operator
+        let comparison = Expr::Comparison(Box::new(left), op.to_string(), Box::new(self.sum()?));
+        self.null_test(comparison)
+    }
 
-                // Parse optional WHERE clause
-                let mut filter = None;
-                if let Token::Where = self.current_token {
-                    self.next(); // Move past WHERE
-                    filter = Some(self.expr());
-                }
+    // Parse any `IS [NOT] NULL` tests following an expression.
+    fn null_test(&mut self, mut expr: Expr) -> Result<Expr, ParseError> {
+        while let Token::Is = self.current_token {
+            self.next()?; // Move past IS
+            let negated = self.accept(Token::Not)?;
+            self.expect(Token::Null)?;
+            expr = Expr::IsNull(Box::new(expr), negated);
+        }
+        Ok(expr)
+    }
 
-                // Parse optional ORDER BY clause
-                let mut order_by = None;
-                if let Token::OrderBy = self.current_token {
-                    self.next(); // Move past ORDER BY
-                    order_by = Some(self.ident());
-                }
+    // Parse additions and subtractions of products.
+    fn sum(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.product()?;
+        while let Token::Plus | Token::Minus = self.current_token {
+            let op = if self.current_token == Token::Plus {
+                "+"
+            } else {
+                "-"
+            };
+            self.next()?; // Move past the operator
+            left = Expr::Arithmetic(Box::new(left), op.to_string(), Box::new(self.product()?));
+        }
+        Ok(left)
+    }
 
-                // Parse optional LIMIT clause
-                let mut limit = None;
-                if let Token::Limit = self.current_token {
-                    self.next(); // Move past LIMIT
-                    limit = Some(self.number());
+    // Parse multiplications and divisions of possibly negated expressions.
+    fn product(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.unary()?;
+        while let Token::Star | Token::Slash = self.current_token {
+            let op = if self.current_token == Token::Star {
+                "*"
+            } else {
+                "/"
+            };
+            self.next()?; // Move past the operator
+            left = Expr::Arithmetic(Box::new(left), op.to_string(), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    // Parse a possibly negated primary expression, negated number literals
+    // are folded into negative literals.
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        match self.current_token {
+            Token::Minus => {
+                self.next()?; // Move past minus
+                Ok(match self.unary()? {
+                    Expr::Value(value) => Expr::Value(-value),
+                    Expr::Numeric(numeric) => match numeric.strip_prefix('-') {
+                        Some(numeric) => Expr::Numeric(numeric.to_string()),
+                        None => Expr::Numeric(format!("-{numeric}")),
+                    },
+                    expr => Expr::Negate(Box::new(expr)),
+                })
+            }
+            Token::Plus => {
+                self.next()?; // Move past plus
+                self.unary()
+            }
+            _ => self.primary(),
+        }
+    }
+
+    // Parse a primary expression followed by any postfix cast.
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        // Parse primary expressions (identifiers or numbers)
+        let mut left = match self.current_token {
+            Token::Identifier(ref id) => {
+                let identifier = id.clone();
+                self.next()?;
+                let name = identifier.to_lowercase();
+                if let Token::Varchar(ref literal) = self.current_token {
+                    // Type names followed by a string literal are typed literals.
+                    let literal = literal.clone();
+                    let data_type = match name.as_str() {
+                        "date" => DataType::Date,
+                        "time" => DataType::Time,
+                        "timestamp" => DataType::Timestamp,
+                        "interval" => DataType::Interval,
+                        _ => {
+                            return Err(ParseError(format!(
+                                "Unexpected string literal after {identifier}"
+                            )))
+                        }
+                    };
+                    self.next()?;
+                    Expr::Cast(Box::new(Expr::Varchar(literal)), data_type)
+                } else if name == "extract" && self.current_token == Token::OpenParen {
+                    self.next()?;
+                    let name = self.ident()?;
+                    let field = DateTimeField::parse(&name)
+                        .ok_or_else(|| ParseError(format!("Unknown field {name}")))?;
+                    self.expect(Token::From)?;
+                    let expr = self.expr()?;
+                    self.expect(Token::CloseParen)?;
+                    Expr::Extract(field, Box::new(expr))
+                } else if let Token::OpenParen = self.current_token {
+                    // Identifiers followed by an open parenthesis are function calls.
+                    self.next()?;
+                    Expr::Function(name, self.args()?)
+                } else if let Token::Dot = self.current_token {
+                    self.next()?;
+                    Expr::QualifiedColumn(identifier, self.ident()?)
+                } else {
+                    // Here we assume all other identifiers are columns, no schema required.
+                    Expr::Column(identifier)
+                }
+            }
+            Token::Varchar(ref ident) => {
+                let ident = ident.clone();
+                self.next()?;
+                Expr::Varchar(ident)
+            }
+            Token::Null => {
+                self.next()?; // Move past NULL
+                Expr::Null
+            }
+            Token::True | Token::False => {
+                let value = self.current_token == Token::True;
+                self.next()?; // Move past boolean
+                Expr::Boolean(value)
+            }
+            Token::Number(num) => {
+                self.next()?; // Move past number
+                Expr::Value(num)
+            }
+            Token::Numeric(ref numeric) => {
+                let numeric = numeric.clone();
+                self.next()?; // Move past number
+                Expr::Numeric(numeric)
+            }
+            Token::Array => {
+                self.next()?; // Move past ARRAY
+                self.expect(Token::OpenBracket)?;
+                let mut elements = vec![];
+                while self.current_token != Token::CloseBracket {
+                    elements.push(self.expr()?);
+                    if let Token::Comma = self.current_token {
+                        self.next()?;
+                    } else if self.current_token != Token::CloseBracket {
+                        return self.unexpected("comma or closing bracket in array");
+                    }
                 }
+                self.next()?; // Move past close bracket
+                Expr::Array(elements)
+            }
+            Token::Cast => {
+                self.next()?; // Move past CAST
+                self.expect(Token::OpenParen)?;
+                let expr = self.expr()?;
+                self.expect(Token::As)?;
+                let data_type = self.data_type()?;
+                self.expect(Token::CloseParen)?;
+                Expr::Cast(Box::new(expr), data_type)
+            }
+            Token::OpenParen => {
+                self.next()?; // Move past open parenthesis
+                let expr = self.expr()?; // Parse expression within parentheses
+                self.expect(Token::CloseParen)?;
+                expr
+            }
+            _ => {
+                return Err(ParseError(format!(
+                    "Unexpected token: {:?}",
+                    self.current_token
+                )))
+            }
+        };
+
+        // Postfix casts bind tighter than any operator.
+        while let Token::DoubleColon = self.current_token {
+            self.next()?;
+            left = Expr::Cast(Box::new(left), self.data_type()?);
+        }
+
+        Ok(left)
+    }
 
-                // Ensure we're at the end of the statement
-                if let Token::Semicolon = self.current_token {
-                    self.next(); // Move past semicolon
-                } else if self.current_token != Token::EOF {
-                    panic!(
-                        "Expected semicolon or end of input found {:?}",
-                        self.current_token
-                    );
+    // Parse the items of the FROM clause and the joins between them.
+    fn from(&mut self) -> Result<TableRef, ParseError> {
+        let mut left = self.table_ref()?;
+        loop {
+            let on = match self.current_token {
+                Token::Comma => {
+                    self.next()?;
+                    false
+                }
+                Token::Cross => {
+                    self.next()?;
+                    self.expect(Token::Join)?;
+                    false
+                }
+                Token::Inner => {
+                    self.next()?;
+                    self.expect(Token::Join)?;
+                    true
+                }
+                Token::Join => {
+                    self.next()?;
+                    true
                 }
+                Token::Left => return Err(ParseError("LEFT JOIN is not supported".into())),
+                Token::Right => return Err(ParseError("RIGHT JOIN is not supported".into())),
+                Token::Full => return Err(ParseError("FULL JOIN is not supported".into())),
+                Token::Natural => return Err(ParseError("NATURAL JOIN is not supported".into())),
+                _ => break,
+            };
+            let right = self.table_ref()?;
+            let on = if on {
+                self.expect(Token::On)?;
+                Some(self.expr()?)
+            } else {
+                None
+            };
+            left = TableRef::Join {
+                left: Box::new(left),
+                right: Box::new(right),
+                on,
+            };
+        }
+        Ok(left)
+    }
 
-                Query::Select {
-                    columns,
-                    table,
+    // Parse a single item of the FROM clause, a table, a function call or a
+    // parenthesized VALUES list.
+    fn table_ref(&mut self) -> Result<TableRef, ParseError> {
+        let lateral = self.accept(Token::Lateral)?;
+        if !lateral && self.accept(Token::OpenParen)? {
+            let rows = self.values()?;
+            self.expect(Token::CloseParen)?;
+            let alias = self
+                .alias()?
+                .ok_or_else(|| ParseError("VALUES in FROM must have an alias".to_string()))?;
+            return Ok(TableRef::Values { rows, alias });
+        }
+        let name = self.ident()?;
+        if let Token::OpenParen = self.current_token {
+            self.next()?;
+            let args = self.args()?;
+            Ok(TableRef::Function {
+                name: name.to_lowercase(),
+                args,
+                lateral,
+                alias: self.alias()?,
+            })
+        } else if lateral {
+            self.unexpected("function call after LATERAL")
+        } else {
+            Ok(TableRef::Table {
+                name,
+                alias: self.alias()?,
+            })
+        }
+    }
+
+    // Parse an optional alias of a FROM item, `[AS] name [(column, ...)]`.
+    fn alias(&mut self) -> Result<Option<Alias>, ParseError> {
+        if let Token::As = self.current_token {
+            self.next()?;
+        } else if !matches!(self.current_token, Token::Identifier(_)) {
+            return Ok(None);
+        }
+        let name = self.ident()?;
+        let mut columns = vec![];
+        if let Token::OpenParen = self.current_token {
+            self.next()?;
+            columns.push(self.ident()?);
+            while let Token::Comma = self.current_token {
+                self.next()?;
+                columns.push(self.ident()?);
+            }
+            self.expect(Token::CloseParen)?;
+        }
+        Ok(Some(Alias { name, columns }))
+    }
+
+    /// Parse the tokenized query returning a `Query` object.
+    pub fn parse(&mut self) -> Result<Query, ParseError> {
+        let query = self.query()?;
+        self.terminator()?;
+        Ok(query)
+    }
+
+    /// Parse every statement of a script, statements are separated by
+    /// semicolons and empty statements are skipped.
+    pub fn parse_script(&mut self) -> Result<Vec<Statement>, ParseError> {
+        let mut statements = vec![];
+        loop {
+            while let Token::Semicolon = self.current_token {
+                self.next()?;
+            }
+            if let Token::EOF = self.current_token {
+                return Ok(statements);
+            }
+            statements.push(self.statement()?);
+            self.terminator()?;
+        }
+    }
+
+    // Parse a statement up to its terminating semicolon.
+    fn statement(&mut self) -> Result<Statement, ParseError> {
+        match self.current_token {
+            Token::Select | Token::Values => Ok(Statement::Query(self.query()?)),
+            Token::Create => self.create_table(),
+            Token::Drop => self.drop_table(),
+            Token::Alter => self.alter_table(),
+            Token::Insert => self.insert(),
+            Token::Update => self.update(),
+            Token::Delete => self.delete(),
+            Token::Merge => self.merge(),
+            Token::Analyze => self.analyze(),
+            Token::Explain => self.explain(),
+            _ => self.unexpected("statement"),
+        }
+    }
+
+    // Parse `INSERT INTO table [(column, ...)]` followed by
+    // `VALUES (expr, ...), ...` or a query.
+    fn insert(&mut self) -> Result<Statement, ParseError> {
+        self.expect(Token::Insert)?;
+        self.expect(Token::Into)?;
+        let table = self.ident()?;
+        let columns = self.column_list()?;
+        let source = match self.current_token {
+            Token::Values => InsertSource::Values(self.values()?),
+            Token::Select => InsertSource::Query(Box::new(self.query()?)),
+            _ => return self.unexpected("VALUES or SELECT"),
+        };
+        let on_conflict = self.on_conflict()?;
+        let returning = self.returning()?;
+        Ok(Statement::Insert {
+            table,
+            columns,
+            source,
+            on_conflict,
+            returning,
+        })
+    }
+
+    // Parse an optional `ON CONFLICT [(column, ...)]` clause followed by
+    // `DO NOTHING` or `DO UPDATE SET column = expr, ... [WHERE filter]`.
+    fn on_conflict(&mut self) -> Result<Option<OnConflict>, ParseError> {
+        if !self.accept(Token::On)? {
+            return Ok(None);
+        }
+        self.expect(Token::Conflict)?;
+        let columns = self.column_list()?;
+        self.expect(Token::Do)?;
+        let action = match self.current_token {
+            Token::Nothing => {
+                self.next()?;
+                ConflictAction::DoNothing
+            }
+            Token::Update => {
+                self.next()?;
+                self.expect(Token::Set)?;
+                let assignments = self.assignments()?;
+                let filter = self
+                    .accept(Token::Where)?
+                    .then(|| self.expr())
+                    .transpose()?;
+                ConflictAction::DoUpdate {
+                    assignments,
                     filter,
-                    order_by,
-                    limit,
                 }
-            } else {
-                panic!("Expected FROM keyword")
             }
+            _ => return self.unexpected("NOTHING or UPDATE"),
+        };
+        Ok(Some(OnConflict { columns, action }))
+    }
+
+    // Parse an optional parenthesized list of column names.
+    fn column_list(&mut self) -> Result<Vec<String>, ParseError> {
+        let mut columns = vec![];
+        if self.accept(Token::OpenParen)? {
+            columns.push(self.ident()?);
+            while self.accept(Token::Comma)? {
+                columns.push(self.ident()?);
+            }
+            self.expect(Token::CloseParen)?;
+        }
+        Ok(columns)
+    }
+
+    // Parse the `column = expr, ...` assignments of a `SET` clause.
+    fn assignments(&mut self) -> Result<Vec<(String, Expr)>, ParseError> {
+        let mut assignments = vec![];
+        loop {
+            let column = self.ident()?;
+            self.expect(Token::Equal)?;
+            assignments.push((column, self.expr()?));
+            if !self.accept(Token::Comma)? {
+                return Ok(assignments);
+            }
+        }
+    }
+
+    // Parse `VALUES (expr, ...), ...`, every row has the same number of
+    // expressions.
+    fn values(&mut self) -> Result<Vec<Vec<Expr>>, ParseError> {
+        self.expect(Token::Values)?;
+        let mut rows = vec![self.values_row()?];
+        while self.accept(Token::Comma)? {
+            let row = self.values_row()?;
+            if row.len() != rows[0].len() {
+                return Err(ParseError(
+                    "VALUES lists must all be the same length".to_string(),
+                ));
+            }
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+
+    // Parse a parenthesized list of expressions of a `VALUES` clause.
+    fn values_row(&mut self) -> Result<Vec<Expr>, ParseError> {
+        self.expect(Token::OpenParen)?;
+        let mut row = vec![self.expr()?];
+        while self.accept(Token::Comma)? {
+            row.push(self.expr()?);
+        }
+        self.expect(Token::CloseParen)?;
+        Ok(row)
+    }
+
+    // Parse `UPDATE table SET column = expr, ... [WHERE filter]`.
+    fn update(&mut self) -> Result<Statement, ParseError> {
+        self.expect(Token::Update)?;
+        let table = self.ident()?;
+        self.expect(Token::Set)?;
+        let assignments = self.assignments()?;
+        let filter = self
+            .accept(Token::Where)?
+            .then(|| self.expr())
+            .transpose()?;
+        let returning = self.returning()?;
+        Ok(Statement::Update {
+            table,
+            assignments,
+            filter,
+            returning,
+        })
+    }
+
+    // Parse `DELETE FROM table [WHERE filter]`.
+    fn delete(&mut self) -> Result<Statement, ParseError> {
+        self.expect(Token::Delete)?;
+        self.expect(Token::From)?;
+        let table = self.ident()?;
+        let filter = self
+            .accept(Token::Where)?
+            .then(|| self.expr())
+            .transpose()?;
+        let returning = self.returning()?;
+        Ok(Statement::Delete {
+            table,
+            filter,
+            returning,
+        })
+    }
+
+    // Parse `MERGE INTO target [[AS] alias] USING source ON condition`
+    // followed by its `WHEN` clauses.
+    fn merge(&mut self) -> Result<Statement, ParseError> {
+        self.expect(Token::Merge)?;
+        self.expect(Token::Into)?;
+        let target = self.ident()?;
+        let alias = (self.accept(Token::As)? || matches!(self.current_token, Token::Identifier(_)))
+            .then(|| self.ident())
+            .transpose()?;
+        self.expect(Token::Using)?;
+        let source = self.table_ref()?;
+        self.expect(Token::On)?;
+        let on = self.expr()?;
+        let mut clauses = vec![self.merge_clause()?];
+        while let Token::When = self.current_token {
+            clauses.push(self.merge_clause()?);
+        }
+        Ok(Statement::Merge {
+            target,
+            alias,
+            source,
+            on,
+            clauses,
+        })
+    }
+
+    // Parse `WHEN MATCHED [AND condition] THEN UPDATE SET ... | DELETE |
+    // DO NOTHING` or `WHEN NOT MATCHED [AND condition] THEN INSERT
+    // [(column, ...)] VALUES (expr, ...) | DO NOTHING`.
+    fn merge_clause(&mut self) -> Result<MergeClause, ParseError> {
+        self.expect(Token::When)?;
+        let matched = !self.accept(Token::Not)?;
+        self.expect(Token::Matched)?;
+        let condition = self.accept(Token::And)?.then(|| self.expr()).transpose()?;
+        self.expect(Token::Then)?;
+        let action = match self.current_token {
+            Token::Update if matched => {
+                self.next()?;
+                self.expect(Token::Set)?;
+                MergeAction::Update(self.assignments()?)
+            }
+            Token::Delete if matched => {
+                self.next()?;
+                MergeAction::Delete
+            }
+            Token::Insert if !matched => {
+                self.next()?;
+                let columns = self.column_list()?;
+                self.expect(Token::Values)?;
+                MergeAction::Insert {
+                    columns,
+                    values: self.values_row()?,
+                }
+            }
+            Token::Do => {
+                self.next()?;
+                self.expect(Token::Nothing)?;
+                MergeAction::DoNothing
+            }
+            _ if matched => return self.unexpected("UPDATE, DELETE or DO NOTHING"),
+            _ => return self.unexpected("INSERT or DO NOTHING"),
+        };
+        Ok(MergeClause {
+            matched,
+            condition,
+            action,
+        })
+    }
+
+    // Parse the optional `RETURNING expr, ...` clause of DML statements.
+    fn returning(&mut self) -> Result<Vec<Expr>, ParseError> {
+        let mut returning = vec![];
+        if self.accept(Token::Returning)? {
+            returning.push(self.expr()?);
+            while self.accept(Token::Comma)? {
+                returning.push(self.expr()?);
+            }
+        }
+        Ok(returning)
+    }
+
+    // Parse `CREATE TABLE [IF NOT EXISTS] name (column, ...)` or
+    // `CREATE TABLE [IF NOT EXISTS] name AS query`.
+    fn create_table(&mut self) -> Result<Statement, ParseError> {
+        self.expect(Token::Create)?;
+        self.expect(Token::Table)?;
+        let if_not_exists = self.if_exists(true)?;
+        let name = self.ident()?;
+        if self.accept(Token::As)? {
+            let query = self.query()?;
+            return Ok(Statement::CreateTableAs {
+                name,
+                if_not_exists,
+                query,
+            });
+        }
+        self.expect(Token::OpenParen)?;
+        let (mut columns, mut constraints) = (vec![], vec![]);
+        loop {
+            match self.current_token {
+                Token::Constraint
+                | Token::Primary
+                | Token::Unique
+                | Token::Check
+                | Token::Foreign => constraints.push(self.table_constraint()?),
+                _ => columns.push(self.column_def()?),
+            }
+            if !self.accept(Token::Comma)? {
+                break;
+            }
+        }
+        self.expect(Token::CloseParen)?;
+        Ok(Statement::CreateTable {
+            name,
+            if_not_exists,
+            columns,
+            constraints,
+        })
+    }
+
+    // Parse a table constraint, `[CONSTRAINT name]` followed by
+    // `PRIMARY KEY (column, ...)`, `UNIQUE (column, ...)`, `CHECK (expr)` or
+    // `FOREIGN KEY (column, ...) REFERENCES ...`.
+    fn table_constraint(&mut self) -> Result<ConstraintDef, ParseError> {
+        let name = self
+            .accept(Token::Constraint)?
+            .then(|| self.ident())
+            .transpose()?;
+        let kind = match self.current_token {
+            Token::Primary => {
+                self.next()?;
+                self.expect_keyword("key")?;
+                ConstraintKind::PrimaryKey(self.columns()?)
+            }
+            Token::Unique => {
+                self.next()?;
+                ConstraintKind::Unique(self.columns()?)
+            }
+            Token::Check => self.check()?,
+            Token::Foreign => {
+                self.next()?;
+                self.expect_keyword("key")?;
+                let columns = self.columns()?;
+                self.expect(Token::References)?;
+                self.references(columns)?
+            }
+            _ => return self.unexpected("PRIMARY KEY, UNIQUE, CHECK or FOREIGN KEY"),
+        };
+        Ok(ConstraintDef { name, kind })
+    }
+
+    // Parse a non empty parenthesized list of column names.
+    fn columns(&mut self) -> Result<Vec<String>, ParseError> {
+        if self.current_token != Token::OpenParen {
+            return self.unexpected("OpenParen");
+        }
+        self.column_list()
+    }
+
+    // Parse `CHECK (expr)`.
+    fn check(&mut self) -> Result<ConstraintKind, ParseError> {
+        self.expect(Token::Check)?;
+        self.expect(Token::OpenParen)?;
+        let expr = self.expr()?;
+        self.expect(Token::CloseParen)?;
+        Ok(ConstraintKind::Check(expr))
+    }
+
+    // Parse the referenced table and columns of a foreign key following
+    // `REFERENCES`, along with its `ON DELETE` and `ON UPDATE` actions.
+    fn references(&mut self, columns: Vec<String>) -> Result<ConstraintKind, ParseError> {
+        let table = self.ident()?;
+        let referenced = self.column_list()?;
+        let (mut on_delete, mut on_update) = Default::default();
+        while self.accept(Token::On)? {
+            match self.current_token {
+                Token::Delete => {
+                    self.next()?;
+                    on_delete = self.referential_action()?;
+                }
+                Token::Update => {
+                    self.next()?;
+                    on_update = self.referential_action()?;
+                }
+                _ => return self.unexpected("DELETE or UPDATE"),
+            }
+        }
+        Ok(ConstraintKind::ForeignKey {
+            columns,
+            table,
+            referenced,
+            on_delete,
+            on_update,
+        })
+    }
+
+    // Parse `NO ACTION`, `RESTRICT`, `CASCADE` or `SET NULL`.
+    fn referential_action(&mut self) -> Result<ReferentialAction, ParseError> {
+        if self.accept(Token::Set)? {
+            self.expect(Token::Null)?;
+            Ok(ReferentialAction::SetNull)
+        } else if self.accept_keyword("cascade")? {
+            Ok(ReferentialAction::Cascade)
+        } else if self.accept_keyword("restrict")? {
+            Ok(ReferentialAction::Restrict)
+        } else if self.accept_keyword("no")? {
+            self.expect_keyword("action")?;
+            Ok(ReferentialAction::NoAction)
+        } else {
+            self.unexpected("NO ACTION, RESTRICT, CASCADE or SET NULL")
+        }
+    }
+
+    // Parse `DROP TABLE [IF EXISTS] name`.
+    fn drop_table(&mut self) -> Result<Statement, ParseError> {
+        self.expect(Token::Drop)?;
+        self.expect(Token::Table)?;
+        let if_exists = self.if_exists(false)?;
+        let name = self.ident()?;
+        Ok(Statement::DropTable { name, if_exists })
+    }
+
+    // Parse `ANALYZE [table]`.
+    fn analyze(&mut self) -> Result<Statement, ParseError> {
+        self.expect(Token::Analyze)?;
+        let table = matches!(self.current_token, Token::Identifier(_))
+            .then(|| self.ident())
+            .transpose()?;
+        Ok(Statement::Analyze { table })
+    }
+
+    // Parse `EXPLAIN [ANALYZE] [(option, ...)] query`.
+    fn explain(&mut self) -> Result<Statement, ParseError> {
+        self.expect(Token::Explain)?;
+        let mut analyze = self.accept(Token::Analyze)?;
+        let mut format = ExplainFormat::Text;
+        if self.accept(Token::OpenParen)? {
+            loop {
+                if self.accept(Token::Analyze)? {
+                    analyze = true;
+                } else {
+                    self.expect_keyword("format")?;
+                    format = self.explain_format()?;
+                }
+                if !self.accept(Token::Comma)? {
+                    break;
+                }
+            }
+            self.expect(Token::CloseParen)?;
+        }
+        Ok(Statement::Explain {
+            query: self.query()?,
+            analyze,
+            format,
+        })
+    }
+
+    // Parse the `TEXT`, `DOT` or `JSON` format of `EXPLAIN`.
+    fn explain_format(&mut self) -> Result<ExplainFormat, ParseError> {
+        if self.accept_keyword("text")? {
+            Ok(ExplainFormat::Text)
+        } else if self.accept_keyword("dot")? {
+            Ok(ExplainFormat::Dot)
+        } else if self.accept_keyword("json")? {
+            Ok(ExplainFormat::Json)
+        } else {
+            self.unexpected("TEXT, DOT or JSON")
+        }
+    }
+
+    // Parse `ALTER TABLE name` followed by `ADD [COLUMN] column`,
+    // `DROP [COLUMN] [IF EXISTS] name` or `RENAME [COLUMN] name TO name`.
+    fn alter_table(&mut self) -> Result<Statement, ParseError> {
+        self.expect(Token::Alter)?;
+        self.expect(Token::Table)?;
+        let name = self.ident()?;
+        let action = match self.current_token {
+            Token::Add => {
+                self.next()?;
+                self.accept(Token::Column)?;
+                AlterTableAction::AddColumn(self.column_def()?)
+            }
+            Token::Drop => {
+                self.next()?;
+                self.accept(Token::Column)?;
+                let if_exists = self.if_exists(false)?;
+                let name = self.ident()?;
+                AlterTableAction::DropColumn { name, if_exists }
+            }
+            Token::Rename => {
+                self.next()?;
+                self.accept(Token::Column)?;
+                let from = self.ident()?;
+                self.expect(Token::To)?;
+                let to = self.ident()?;
+                AlterTableAction::RenameColumn { from, to }
+            }
+            _ => return self.unexpected("ADD, DROP or RENAME"),
+        };
+        Ok(Statement::AlterTable { name, action })
+    }
+
+    // Parse a column definition, its type is followed by any number of
+    // `NOT NULL`, `NULL` and `DEFAULT expr` clauses and of column
+    // constraints, `PRIMARY KEY`, `UNIQUE`, `CHECK (expr)` or `REFERENCES`,
+    // optionally named by a `CONSTRAINT name` prefix.
+    fn column_def(&mut self) -> Result<ColumnDef, ParseError> {
+        let name = self.ident()?;
+        let data_type = self.data_type()?;
+        let mut column = ColumnDef {
+            name,
+            data_type,
+            not_null: false,
+            default: None,
+            constraints: vec![],
+        };
+        loop {
+            let name = self
+                .accept(Token::Constraint)?
+                .then(|| self.ident())
+                .transpose()?;
+            let kind = match self.current_token {
+                Token::Not => {
+                    self.next()?;
+                    self.expect(Token::Null)?;
+                    column.not_null = true;
+                    continue;
+                }
+                Token::Null => {
+                    self.next()?;
+                    column.not_null = false;
+                    continue;
+                }
+                Token::Default if name.is_none() => {
+                    self.next()?;
+                    column.default = Some(self.expr()?);
+                    continue;
+                }
+                Token::Primary => {
+                    self.next()?;
+                    self.expect_keyword("key")?;
+                    ConstraintKind::PrimaryKey(vec![column.name.clone()])
+                }
+                Token::Unique => {
+                    self.next()?;
+                    ConstraintKind::Unique(vec![column.name.clone()])
+                }
+                Token::Check => self.check()?,
+                Token::References => {
+                    self.next()?;
+                    self.references(vec![column.name.clone()])?
+                }
+                _ if name.is_some() => return self.unexpected("constraint"),
+                _ => return Ok(column),
+            };
+            column.constraints.push(ConstraintDef { name, kind });
+        }
+    }
+
+    // Parse an optional `IF EXISTS` clause, or `IF NOT EXISTS` when `not` is
+    // set, returns whether it was present.
+    fn if_exists(&mut self, not: bool) -> Result<bool, ParseError> {
+        if !self.accept(Token::If)? {
+            return Ok(false);
+        }
+        if not {
+            self.expect(Token::Not)?;
+        }
+        self.expect(Token::Exists)?;
+        Ok(true)
+    }
+
+    // Ensure we're at the end of the statement.
+    fn terminator(&mut self) -> Result<(), ParseError> {
+        if let Token::Semicolon = self.current_token {
+            self.next() // Move past semicolon
+        } else if self.current_token != Token::EOF {
+            self.unexpected("semicolon or end of input")
         } else {
-            panic!("Expected SELECT keyword")
+            Ok(())
+        }
+    }
+
+    // Parse a SELECT query or a VALUES list.
+    fn query(&mut self) -> Result<Query, ParseError> {
+        if let Token::Values = self.current_token {
+            return Ok(Query::Values(self.values()?));
+        }
+        // Ensure we're starting with a SELECT statement
+        if self.current_token != Token::Select {
+            return Err(ParseError("Expected SELECT keyword".to_string()));
+        }
+        self.next()?; // Move past SELECT
+
+        // Parse the select list
+        let mut columns = vec![];
+        while self.current_token != Token::From {
+            columns.push(self.expr()?);
+            if let Token::Comma = self.current_token {
+                self.next()?;
+            } else {
+                break;
+            }
+        }
+
+        // Ensure we're at the FROM keyword
+        if self.current_token != Token::From {
+            return Err(ParseError("Expected FROM keyword".to_string()));
         }
+        self.next()?; // Move past FROM
+        let from = self.from()?;
+
+        // Parse optional WHERE clause
+        let mut filter = None;
+        if let Token::Where = self.current_token {
+            self.next()?; // Move past WHERE
+            filter = Some(self.expr()?);
+        }
+
+        // Parse optional GROUP BY clause
+        let mut group_by = vec![];
+        if let Token::GroupBy = self.current_token {
+            self.next()?; // Move past GROUP BY
+            group_by.push(self.expr()?);
+            while let Token::Comma = self.current_token {
+                self.next()?;
+                group_by.push(self.expr()?);
+            }
+        }
+
+        // Parse optional ORDER BY clause
+        let mut order_by = None;
+        if let Token::OrderBy = self.current_token {
+            self.next()?; // Move past ORDER BY
+            order_by = Some(self.expr()?);
+        }
+
+        // Parse optional LIMIT clause
+        let mut limit = None;
+        if let Token::Limit = self.current_token {
+            self.next()?; // Move past LIMIT
+            limit = Some(self.number()?);
+        }
+
+        Ok(Query::Select {
+            columns,
+            from: Box::new(from),
+            filter,
+            group_by,
+            order_by,
+            limit,
+        })
     }
 }
 
@@ -359,13 +2202,13 @@ mod tests {
     }
 
     impl<'a> Iterator for MockTokenizer<'a> {
-        type Item = Token;
+        type Item = Result<Token, ParseError>;
 
         fn next(&mut self) -> Option<Self::Item> {
             if self.index < self.tokens.len() {
                 let token = self.tokens[self.index].clone();
                 self.index += 1;
-                Some(token)
+                Some(Ok(token))
             } else {
                 None
             }
@@ -380,9 +2223,9 @@ mod tests {
             Token::Number(30),
             Token::EOF,
         ];
-        let mut parser = Parser::new(tokens.into_iter());
+        let mut parser = Parser::new(tokens.into_iter().map(Ok)).unwrap();
 
-        let expr = parser.expr();
+        let expr = parser.expr().unwrap();
         assert_eq!(
             expr,
             Expr::Comparison(
@@ -393,6 +2236,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn can_parse_operators_by_precedence() {
+        let mut parser =
+            Parser::new(Tokenizer::new("NOT a = 1 OR b > 2 AND c < 3 OR d::int = 4")).unwrap();
+
+        let expr = parser.expr().unwrap();
+        assert_eq!(
+            expr.to_string(),
+            "NOT a = 1 OR b > 2 AND c < 3 OR CAST(d AS INTEGER) = 4"
+        );
+        let Expr::Or(left, _) = expr else {
+            panic!("Expected disjunction got {expr:?}");
+        };
+        let Expr::Or(negation, conjunction) = *left else {
+            panic!("Expected disjunction got {left:?}");
+        };
+        assert!(matches!(*negation, Expr::Not(_)));
+        assert!(matches!(*conjunction, Expr::And(_, _)));
+    }
+
     #[test]
     fn can_parse_parenthesized_expression() {
         let tokens = vec![
@@ -409,9 +2272,9 @@ mod tests {
             Token::CloseParen,
             Token::EOF,
         ];
-        let mut parser = Parser::new(tokens.into_iter());
+        let mut parser = Parser::new(tokens.into_iter().map(Ok)).unwrap();
 
-        let expr = parser.expr();
+        let expr = parser.expr().unwrap();
         assert_eq!(
             expr,
             Expr::And(
@@ -429,6 +2292,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn can_parse_function_calls() {
+        let tokens = vec![
+            Token::Identifier("band".to_string()),
+            Token::OpenParen,
+            Token::Identifier("salary".to_string()),
+            Token::Comma,
+            Token::Number(2),
+            Token::CloseParen,
+            Token::Equal,
+            Token::Varchar("senior".to_string()),
+            Token::EOF,
+        ];
+        let mut parser = Parser::new(tokens.into_iter().map(Ok)).unwrap();
+
+        let expr = parser.expr().unwrap();
+        assert_eq!(
+            expr,
+            Expr::Comparison(
+                Box::new(Expr::Function(
+                    "band".to_string(),
+                    vec![Expr::Column("salary".to_string()), Expr::Value(2)]
+                )),
+                "=".to_string(),
+                Box::new(Expr::Varchar("senior".to_string()))
+            )
+        );
+    }
+
     #[test]
     fn can_parse_basic_query() {
         let tokens = vec![
@@ -441,15 +2333,22 @@ mod tests {
             Token::EOF,
         ];
         let tokenizer = MockTokenizer::new(&tokens);
-        let mut parser = Parser::new(tokenizer);
-        let query = parser.parse();
+        let mut parser = Parser::new(tokenizer).unwrap();
+        let query = parser.parse().unwrap();
 
         assert_eq!(
             query,
             Query::Select {
-                columns: vec!["id".to_string(), "name".to_string()],
-                table: "employees".to_string(),
+                columns: vec![
+                    Expr::Column("id".to_string()),
+                    Expr::Column("name".to_string())
+                ],
+                from: Box::new(TableRef::Table {
+                    name: "employees".to_string(),
+                    alias: None
+                }),
                 filter: None,
+                group_by: vec![],
                 order_by: None,
                 limit: None
             }
@@ -472,19 +2371,26 @@ mod tests {
             Token::EOF,
         ];
         let tokenizer = MockTokenizer::new(&tokens);
-        let mut parser = Parser::new(tokenizer);
-        let query = parser.parse();
+        let mut parser = Parser::new(tokenizer).unwrap();
+        let query = parser.parse().unwrap();
 
         assert_eq!(
             query,
             Query::Select {
-                columns: vec!["id".to_string(), "name".to_string()],
-                table: "employees".to_string(),
+                columns: vec![
+                    Expr::Column("id".to_string()),
+                    Expr::Column("name".to_string())
+                ],
+                from: Box::new(TableRef::Table {
+                    name: "employees".to_string(),
+                    alias: None
+                }),
                 filter: Some(Expr::Comparison(
                     Box::new(Expr::Column("role".to_string())),
                     "=".to_string(),
                     Box::new(Expr::Value(1))
                 )),
+                group_by: vec![],
                 order_by: None,
                 limit: None
             }
@@ -505,21 +2411,1023 @@ mod tests {
             Token::EOF,
         ];
         let tokenizer = MockTokenizer::new(&tokens);
-        let mut parser = Parser::new(tokenizer);
-        let query = parser.parse();
+        let mut parser = Parser::new(tokenizer).unwrap();
+        let query = parser.parse().unwrap();
 
         assert_eq!(
             query,
             Query::Select {
-                columns: vec!["id".to_string(), "name".to_string()],
-                table: "employees".to_string(),
+                columns: vec![
+                    Expr::Column("id".to_string()),
+                    Expr::Column("name".to_string())
+                ],
+                from: Box::new(TableRef::Table {
+                    name: "employees".to_string(),
+                    alias: None
+                }),
                 filter: None,
-                order_by: Some("id".to_string()),
+                group_by: vec![],
+                order_by: Some(Expr::Column("id".to_string())),
                 limit: None
             }
         );
     }
 
+    #[test]
+    fn can_parse_query_with_group_by() {
+        let tokens = vec![
+            Token::Select,
+            Token::Identifier("role".to_string()),
+            Token::Comma,
+            Token::Identifier("COUNT".to_string()),
+            Token::OpenParen,
+            Token::Star,
+            Token::CloseParen,
+            Token::From,
+            Token::Identifier("employees".to_string()),
+            Token::GroupBy,
+            Token::Identifier("role".to_string()),
+            Token::EOF,
+        ];
+        let tokenizer = MockTokenizer::new(&tokens);
+        let mut parser = Parser::new(tokenizer).unwrap();
+        let query = parser.parse().unwrap();
+
+        assert_eq!(
+            query,
+            Query::Select {
+                columns: vec![
+                    Expr::Column("role".to_string()),
+                    Expr::Function("count".to_string(), vec![])
+                ],
+                from: Box::new(TableRef::Table {
+                    name: "employees".to_string(),
+                    alias: None
+                }),
+                filter: None,
+                group_by: vec![Expr::Column("role".to_string())],
+                order_by: None,
+                limit: None
+            }
+        );
+    }
+
+    #[test]
+    fn only_count_calls_are_rendered_with_a_star() {
+        let mut parser =
+            Parser::new(Tokenizer::new("count(*) + EXTRACT(year FROM now())")).unwrap();
+        assert_eq!(
+            parser.expr().unwrap().to_string(),
+            "count(*) + EXTRACT(YEAR FROM now())"
+        );
+    }
+
+    #[test]
+    fn can_parse_joins_and_table_functions() {
+        let query = Parser::new(Tokenizer::new(
+            "SELECT e.name, s.n FROM employees AS e \
+             JOIN employees m ON e.id = m.id, \
+             LATERAL generate_series(1, e.id) s(n), unnest(ARRAY[1, 2])",
+        ))
+        .unwrap()
+        .parse()
+        .unwrap();
+
+        let Query::Select { columns, from, .. } = query else {
+            panic!("expected SELECT got {query:?}");
+        };
+        assert_eq!(
+            columns,
+            vec![
+                Expr::QualifiedColumn("e".to_string(), "name".to_string()),
+                Expr::QualifiedColumn("s".to_string(), "n".to_string()),
+            ]
+        );
+        let employees = |alias: &str| TableRef::Table {
+            name: "employees".to_string(),
+            alias: Some(Alias {
+                name: alias.to_string(),
+                columns: vec![],
+            }),
+        };
+        assert_eq!(
+            *from,
+            TableRef::Join {
+                left: Box::new(TableRef::Join {
+                    left: Box::new(TableRef::Join {
+                        left: Box::new(employees("e")),
+                        right: Box::new(employees("m")),
+                        on: Some(Expr::Comparison(
+                            Box::new(Expr::QualifiedColumn("e".to_string(), "id".to_string())),
+                            "=".to_string(),
+                            Box::new(Expr::QualifiedColumn("m".to_string(), "id".to_string())),
+                        )),
+                    }),
+                    right: Box::new(TableRef::Function {
+                        name: "generate_series".to_string(),
+                        args: vec![
+                            Expr::Value(1),
+                            Expr::QualifiedColumn("e".to_string(), "id".to_string())
+                        ],
+                        lateral: true,
+                        alias: Some(Alias {
+                            name: "s".to_string(),
+                            columns: vec!["n".to_string()],
+                        }),
+                    }),
+                    on: None,
+                }),
+                right: Box::new(TableRef::Function {
+                    name: "unnest".to_string(),
+                    args: vec![Expr::Array(vec![Expr::Value(1), Expr::Value(2)])],
+                    lateral: false,
+                    alias: None,
+                }),
+                on: None,
+            }
+        );
+    }
+
+    #[test]
+    fn can_parse_casts() {
+        let mut parser =
+            Parser::new(Tokenizer::new("CAST(salary AS varchar) = id::TEXT::int[]")).unwrap();
+
+        let expr = parser.expr().unwrap();
+        assert_eq!(
+            expr,
+            Expr::Comparison(
+                Box::new(Expr::Cast(
+                    Box::new(Expr::Column("salary".to_string())),
+                    DataType::Varchar
+                )),
+                "=".to_string(),
+                Box::new(Expr::Cast(
+                    Box::new(Expr::Cast(
+                        Box::new(Expr::Column("id".to_string())),
+                        DataType::Varchar
+                    )),
+                    DataType::Array(Box::new(DataType::Integer))
+                ))
+            )
+        );
+        assert_eq!(
+            expr.to_string(),
+            "CAST(salary AS VARCHAR) = CAST(CAST(id AS VARCHAR) AS INTEGER[])"
+        );
+    }
+
+    #[test]
+    fn can_parse_boolean_literals() {
+        let mut parser =
+            Parser::new(Tokenizer::new("NOT active = true OR false AND null")).unwrap();
+        assert_eq!(
+            parser.expr().unwrap(),
+            Expr::Or(
+                Box::new(Expr::Not(Box::new(Expr::Comparison(
+                    Box::new(Expr::Column("active".to_string())),
+                    "=".to_string(),
+                    Box::new(Expr::Boolean(true))
+                )))),
+                Box::new(Expr::And(
+                    Box::new(Expr::Boolean(false)),
+                    Box::new(Expr::Null)
+                ))
+            )
+        );
+    }
+
+    #[test]
+    fn can_parse_boolean_types_and_null_tests() {
+        let mut parser = Parser::new(Tokenizer::new(
+            "CAST(flag AS boolean) = 'yes'::bool OR NOT (a OR b) IS NOT NULL AND c IS NULL",
+        ))
+        .unwrap();
+        let expr = parser.expr().unwrap();
+        assert_eq!(
+            expr,
+            Expr::Or(
+                Box::new(Expr::Comparison(
+                    Box::new(Expr::Cast(
+                        Box::new(Expr::Column("flag".to_string())),
+                        DataType::Boolean
+                    )),
+                    "=".to_string(),
+                    Box::new(Expr::Cast(
+                        Box::new(Expr::Varchar("yes".to_string())),
+                        DataType::Boolean
+                    ))
+                )),
+                Box::new(Expr::And(
+                    Box::new(Expr::Not(Box::new(Expr::IsNull(
+                        Box::new(Expr::Or(
+                            Box::new(Expr::Column("a".to_string())),
+                            Box::new(Expr::Column("b".to_string()))
+                        )),
+                        true
+                    )))),
+                    Box::new(Expr::IsNull(Box::new(Expr::Column("c".to_string())), false))
+                ))
+            )
+        );
+        assert_eq!(
+            expr.to_string(),
+            "CAST(flag AS BOOLEAN) = CAST('yes' AS BOOLEAN) OR NOT (a OR b) IS NOT NULL AND c IS NULL"
+        );
+        let Statement::CreateTable { columns, .. } =
+            &parse_script("CREATE TABLE flags (active BOOLEAN, seen bool)").unwrap()[0]
+        else {
+            panic!("expected CREATE TABLE");
+        };
+        assert!(columns
+            .iter()
+            .all(|column| column.data_type == DataType::Boolean));
+    }
+
+    #[test]
+    fn can_parse_temporal_literals() {
+        let mut parser = Parser::new(Tokenizer::new(
+            "hire_date + INTERVAL '1 year 2 mons' > TIMESTAMP '2024-01-31 08:30:00'",
+        ))
+        .unwrap();
+        assert_eq!(
+            parser.expr().unwrap().to_string(),
+            "hire_date + CAST('1 year 2 mons' AS INTERVAL) > CAST('2024-01-31 08:30:00' AS TIMESTAMP)"
+        );
+
+        let mut parser =
+            Parser::new(Tokenizer::new("EXTRACT(dow FROM hire_date - 1)::date")).unwrap();
+        assert_eq!(
+            parser.expr().unwrap(),
+            Expr::Cast(
+                Box::new(Expr::Extract(
+                    DateTimeField::DayOfWeek,
+                    Box::new(Expr::Arithmetic(
+                        Box::new(Expr::Column("hire_date".to_string())),
+                        "-".to_string(),
+                        Box::new(Expr::Value(1))
+                    ))
+                )),
+                DataType::Date
+            )
+        );
+    }
+
+    #[test]
+    fn can_tokenize_numbers() {
+        let tokens = Tokenizer::new("12000.50 1e6 2.5E-3 7 99999999999999999999 1.x 3e")
+            .map(Result::unwrap)
+            .take_while(|token| *token != Token::EOF)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Numeric("12000.50".to_string()),
+                Token::Numeric("1e6".to_string()),
+                Token::Numeric("2.5E-3".to_string()),
+                Token::Number(7),
+                Token::Numeric("99999999999999999999".to_string()),
+                Token::Number(1),
+                Token::Dot,
+                Token::Identifier("x".to_string()),
+                Token::Number(3),
+                Token::Identifier("e".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_tokenize_comparison_operators() {
+        let tokens = Tokenizer::new("= <> != < <= > >= <=>")
+            .map(Result::unwrap)
+            .take_while(|token| *token != Token::EOF)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Equal,
+                Token::NotEqual,
+                Token::NotEqual,
+                Token::LessThan,
+                Token::LessThanOrEqual,
+                Token::GreaterThan,
+                Token::GreaterThanOrEqual,
+                Token::LessThanOrEqual,
+                Token::GreaterThan,
+            ]
+        );
+        assert_eq!(tokenize_error("a ! b"), "Unexpected character: !");
+    }
+
+    #[test]
+    fn can_parse_inclusive_and_inequality_comparisons() {
+        let mut parser =
+            Parser::new(Tokenizer::new("a >= 1 AND b <= 2 OR c <> 3 OR d != 4")).unwrap();
+        assert_eq!(
+            parser.expr().unwrap().to_string(),
+            "a >= 1 AND b <= 2 OR c <> 3 OR d <> 4"
+        );
+    }
+
+    #[test]
+    fn can_parse_arithmetic() {
+        let mut parser = Parser::new(Tokenizer::new(
+            "-a - -2 * (b + 1.5) / c::decimal(10, 2) - -(d) > 1",
+        ))
+        .unwrap();
+
+        let expr = parser.expr().unwrap();
+        assert_eq!(
+            expr.to_string(),
+            "-a - -2 * (b + 1.5) / CAST(c AS DECIMAL(10, 2)) - -d > 1"
+        );
+        let Expr::Comparison(left, _, _) = expr else {
+            panic!("Expected comparison got {expr:?}");
+        };
+        let Expr::Arithmetic(left, op, _) = *left else {
+            panic!("Expected arithmetic got {left:?}");
+        };
+        assert_eq!(op, "-");
+        assert!(matches!(*left, Expr::Arithmetic(_, ref op, _) if op == "-"));
+
+        let expr = Parser::new(Tokenizer::new("a - (b - c)"))
+            .unwrap()
+            .expr()
+            .unwrap();
+        assert_eq!(expr.to_string(), "a - (b - c)");
+    }
+
+    #[test]
+    fn can_tokenize_string_literals() {
+        let tokens = Tokenizer::new(
+            "'Mary Ann' 'O''Brien' 'Zoë' '' E'a\\tb\\'c\\\\' e'\\x41\\101\\u00e9\\U0001F600' 'a\\n'",
+        )
+        .map(Result::unwrap)
+            .take_while(|token| *token != Token::EOF)
+        .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Varchar("Mary Ann".to_string()),
+                Token::Varchar("O'Brien".to_string()),
+                Token::Varchar("Zoë".to_string()),
+                Token::Varchar("".to_string()),
+                Token::Varchar("a\tb'c\\".to_string()),
+                Token::Varchar("AAé😀".to_string()),
+                Token::Varchar("a\\n".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_tokenize_unicode_text() {
+        let tokens = Tokenizer::new("SELECT café\u{3000}FROM  łódź\t")
+            .map(Result::unwrap)
+            .take_while(|token| *token != Token::EOF)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Select,
+                Token::Identifier("café".to_string()),
+                Token::From,
+                Token::Identifier("łódź".to_string()),
+            ]
+        );
+    }
+
+    // Parse the statements of `sql` and return the message of the error.
+    fn parse_error(sql: &str) -> String {
+        parse_script(sql).unwrap_err().0
+    }
+
+    // Tokenize `sql` up to its first error and return the message.
+    fn tokenize_error(sql: &str) -> String {
+        Tokenizer::new(sql).find_map(Result::err).unwrap().0
+    }
+
+    #[test]
+    fn unterminated_strings_are_rejected() {
+        assert_eq!(
+            tokenize_error("name = 'O''Brien"),
+            "Unterminated string literal starting at position 7"
+        );
+    }
+
+    #[test]
+    fn invalid_escapes_are_rejected() {
+        assert_eq!(
+            tokenize_error("E'\\u12'"),
+            "Invalid escape sequence at position 4"
+        );
+    }
+
+    #[test]
+    fn can_tokenize_identifiers_and_comments() {
+        let tokens = Tokenizer::new(
+            "SELECT Name, _row$1, \"Mixed \"\"Case\"\"\", \"select\" -- trailing comment\n\
+             FROM /* block /* nested */ comment */ Employees--",
+        )
+        .map(Result::unwrap)
+        .take_while(|token| *token != Token::EOF)
+        .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Select,
+                Token::Identifier("name".to_string()),
+                Token::Comma,
+                Token::Identifier("_row$1".to_string()),
+                Token::Comma,
+                Token::Identifier("Mixed \"Case\"".to_string()),
+                Token::Comma,
+                Token::Identifier("select".to_string()),
+                Token::From,
+                Token::Identifier("employees".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn identifiers_are_quoted_when_needed() {
+        let expr = Parser::new(Tokenizer::new(
+            "\"T\".\"Mixed Case\" = \"select\" AND lower_case$ = t.\"a\"\"b\"",
+        ))
+        .unwrap()
+        .expr()
+        .unwrap();
+        assert_eq!(
+            expr.to_string(),
+            "\"T\".\"Mixed Case\" = \"select\" AND lower_case$ = t.\"a\"\"b\""
+        );
+    }
+
+    #[test]
+    fn unterminated_comments_are_rejected() {
+        assert_eq!(
+            tokenize_error("SELECT /* /* */ id"),
+            "Unterminated comment starting at position 7"
+        );
+    }
+
+    #[test]
+    fn unterminated_quoted_identifiers_are_rejected() {
+        assert_eq!(
+            tokenize_error("\"id"),
+            "Unterminated quoted identifier starting at position 0"
+        );
+    }
+
+    #[test]
+    fn can_transform_expressions() {
+        let tokens = Tokenizer::new("a + f(b, -a) * 2").take_while(|t| *t != Ok(Token::EOF));
+        let expr = Parser::new(tokens).unwrap().expr().unwrap();
+        assert_eq!(expr.columns(), vec!["a", "b", "a"]);
+        let expr = expr.transform(&mut |expr| match expr {
+            Expr::Column(name) if name == "a" => Expr::QualifiedColumn("t".to_string(), name),
+            expr => expr,
+        });
+        assert_eq!(expr.to_string(), "t.a + f(b, -t.a) * 2");
+    }
+
+    #[test]
+    fn can_split_conjunctions() {
+        let tokens =
+            Tokenizer::new("a > 1 AND (b = 2 OR c) AND NOT d").take_while(|t| *t != Ok(Token::EOF));
+        let terms = Parser::new(tokens).unwrap().expr().unwrap().conjuncts();
+        let terms = terms
+            .iter()
+            .map(|term| term.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(terms, ["a > 1", "b = 2 OR c", "NOT d"]);
+    }
+
+    #[test]
+    fn can_parse_scripts() {
+        let statements = parse_script(
+            ";SELECT id FROM employees;; -- seed data
+             SELECT name FROM employees WHERE id = 1;
+             SELECT count(*) FROM employees",
+        )
+        .unwrap();
+        assert_eq!(statements.len(), 3);
+        let Statement::Query(Query::Select { filter, .. }) = &statements[1] else {
+            panic!("expected a query got {:?}", statements[1]);
+        };
+        assert_eq!(filter.as_ref().unwrap().to_string(), "id = 1");
+        assert_eq!(parse_script(" ; -- nothing to see\n").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn can_parse_create_table() {
+        let statements = parse_script(
+            "CREATE TABLE IF NOT EXISTS accounts (
+                id INTEGER NOT NULL,
+                owner VARCHAR NULL DEFAULT 'nobody',
+                balance DECIMAL(10, 2) DEFAULT 0 NOT NULL
+            );
+            CREATE TABLE managers AS SELECT name FROM employees WHERE role = 'Manager'",
+        )
+        .unwrap();
+        assert_eq!(
+            statements[0],
+            Statement::CreateTable {
+                name: "accounts".to_string(),
+                if_not_exists: true,
+                columns: vec![
+                    ColumnDef {
+                        name: "id".to_string(),
+                        data_type: DataType::Integer,
+                        not_null: true,
+                        default: None,
+                        constraints: vec![],
+                    },
+                    ColumnDef {
+                        name: "owner".to_string(),
+                        data_type: DataType::Varchar,
+                        not_null: false,
+                        default: Some(Expr::Varchar("nobody".to_string())),
+                        constraints: vec![],
+                    },
+                    ColumnDef {
+                        name: "balance".to_string(),
+                        data_type: DataType::Decimal(10, 2),
+                        not_null: true,
+                        default: Some(Expr::Value(0)),
+                        constraints: vec![],
+                    },
+                ],
+                constraints: vec![],
+            }
+        );
+        let Statement::CreateTableAs {
+            name,
+            if_not_exists: false,
+            query: Query::Select { filter, .. },
+        } = &statements[1]
+        else {
+            panic!("expected CREATE TABLE AS got {:?}", statements[1]);
+        };
+        assert_eq!(name, "managers");
+        assert_eq!(filter.as_ref().unwrap().to_string(), "role = 'Manager'");
+    }
+
+    #[test]
+    fn can_parse_drop_and_alter_table() {
+        let statements = parse_script(
+            "DROP TABLE IF EXISTS accounts;
+             DROP TABLE \"Accounts\";
+             ALTER TABLE accounts ADD COLUMN opened DATE DEFAULT DATE '2024-01-01';
+             ALTER TABLE accounts DROP IF EXISTS owner;
+             ALTER TABLE accounts RENAME COLUMN balance TO amount",
+        )
+        .unwrap();
+        assert_eq!(
+            statements,
+            vec![
+                Statement::DropTable {
+                    name: "accounts".to_string(),
+                    if_exists: true,
+                },
+                Statement::DropTable {
+                    name: "Accounts".to_string(),
+                    if_exists: false,
+                },
+                Statement::AlterTable {
+                    name: "accounts".to_string(),
+                    action: AlterTableAction::AddColumn(ColumnDef {
+                        name: "opened".to_string(),
+                        data_type: DataType::Date,
+                        not_null: false,
+                        default: Some(Expr::Cast(
+                            Box::new(Expr::Varchar("2024-01-01".to_string())),
+                            DataType::Date,
+                        )),
+                        constraints: vec![],
+                    }),
+                },
+                Statement::AlterTable {
+                    name: "accounts".to_string(),
+                    action: AlterTableAction::DropColumn {
+                        name: "owner".to_string(),
+                        if_exists: true,
+                    },
+                },
+                Statement::AlterTable {
+                    name: "accounts".to_string(),
+                    action: AlterTableAction::RenameColumn {
+                        from: "balance".to_string(),
+                        to: "amount".to_string(),
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn can_parse_analyze() {
+        assert_eq!(
+            parse_script("ANALYZE; ANALYZE accounts").unwrap(),
+            vec![
+                Statement::Analyze { table: None },
+                Statement::Analyze {
+                    table: Some("accounts".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn can_parse_explain() {
+        let statements = parse_script(
+            "EXPLAIN SELECT id FROM accounts; EXPLAIN ANALYZE SELECT id FROM accounts;
+             EXPLAIN (FORMAT DOT) SELECT id FROM accounts;
+             EXPLAIN (ANALYZE, FORMAT JSON) SELECT id FROM accounts",
+        )
+        .unwrap();
+        let query = match parse_script("SELECT id FROM accounts").unwrap().remove(0) {
+            Statement::Query(query) => query,
+            statement => panic!("expected a query got {statement:?}"),
+        };
+        assert_eq!(
+            statements,
+            vec![
+                Statement::Explain {
+                    query: query.clone(),
+                    analyze: false,
+                    format: ExplainFormat::Text,
+                },
+                Statement::Explain {
+                    query: query.clone(),
+                    analyze: true,
+                    format: ExplainFormat::Text,
+                },
+                Statement::Explain {
+                    query: query.clone(),
+                    analyze: false,
+                    format: ExplainFormat::Dot,
+                },
+                Statement::Explain {
+                    query,
+                    analyze: true,
+                    format: ExplainFormat::Json,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn can_parse_dml_statements() {
+        let statements = parse_script(
+            "INSERT INTO accounts (id, owner) VALUES (1, 'Alice'), (2, NULL) RETURNING id;
+             INSERT INTO accounts SELECT id, name FROM employees;
+             UPDATE accounts SET owner = 'Bob', id = id + 1 WHERE id = 2;
+             DELETE FROM accounts RETURNING id, owner",
+        )
+        .unwrap();
+        assert_eq!(
+            statements[0],
+            Statement::Insert {
+                table: "accounts".to_string(),
+                columns: vec!["id".to_string(), "owner".to_string()],
+                source: InsertSource::Values(vec![
+                    vec![Expr::Value(1), Expr::Varchar("Alice".to_string())],
+                    vec![Expr::Value(2), Expr::Null],
+                ]),
+                on_conflict: None,
+                returning: vec![Expr::Column("id".to_string())],
+            }
+        );
+        assert!(matches!(
+            &statements[1],
+            Statement::Insert { columns, source: InsertSource::Query(_), returning, .. }
+                if columns.is_empty() && returning.is_empty()
+        ));
+        let Statement::Update {
+            assignments,
+            filter: Some(filter),
+            ..
+        } = &statements[2]
+        else {
+            panic!("expected UPDATE got {:?}", statements[2]);
+        };
+        let assignments = assignments
+            .iter()
+            .map(|(column, expr)| format!("{column} = {expr}"))
+            .collect::<Vec<_>>();
+        assert_eq!(assignments, vec!["owner = 'Bob'", "id = id + 1"]);
+        assert_eq!(filter.to_string(), "id = 2");
+        assert_eq!(
+            statements[3],
+            Statement::Delete {
+                table: "accounts".to_string(),
+                filter: None,
+                returning: vec![
+                    Expr::Column("id".to_string()),
+                    Expr::Column("owner".to_string())
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn can_parse_upserts() {
+        let statements = parse_script(
+            "INSERT INTO accounts VALUES (1, 'Alice') ON CONFLICT (id) DO NOTHING;
+             INSERT INTO accounts SELECT id, name FROM employees
+                ON CONFLICT (id) DO UPDATE SET owner = excluded.owner WHERE accounts.id > 1
+                RETURNING id",
+        )
+        .unwrap();
+        let on_conflicts = statements
+            .iter()
+            .map(|statement| match statement {
+                Statement::Insert { on_conflict, .. } => on_conflict.clone().unwrap(),
+                _ => panic!("expected INSERT got {statement:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            on_conflicts,
+            vec![
+                OnConflict {
+                    columns: vec!["id".to_string()],
+                    action: ConflictAction::DoNothing,
+                },
+                OnConflict {
+                    columns: vec!["id".to_string()],
+                    action: ConflictAction::DoUpdate {
+                        assignments: vec![(
+                            "owner".to_string(),
+                            Expr::QualifiedColumn("excluded".to_string(), "owner".to_string())
+                        )],
+                        filter: Some(Expr::Comparison(
+                            Box::new(Expr::QualifiedColumn(
+                                "accounts".to_string(),
+                                "id".to_string()
+                            )),
+                            ">".to_string(),
+                            Box::new(Expr::Value(1))
+                        )),
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn can_parse_merge() {
+        let statements = parse_script(
+            "MERGE INTO accounts a USING employees e ON a.id = e.id
+             WHEN MATCHED AND e.role = 'Manager' THEN DELETE
+             WHEN MATCHED THEN UPDATE SET owner = e.name
+             WHEN NOT MATCHED AND e.salary > 1000 THEN INSERT (id, owner) VALUES (e.id, e.name)
+             WHEN NOT MATCHED THEN DO NOTHING",
+        )
+        .unwrap();
+        let Statement::Merge {
+            target,
+            alias,
+            source,
+            on,
+            clauses,
+        } = &statements[0]
+        else {
+            panic!("expected MERGE got {:?}", statements[0]);
+        };
+        assert_eq!(target, "accounts");
+        assert_eq!(alias.as_deref(), Some("a"));
+        assert!(matches!(source, TableRef::Table { name, .. } if name == "employees"));
+        assert_eq!(on.to_string(), "a.id = e.id");
+        let clauses = clauses
+            .iter()
+            .map(|clause| (clause.matched, clause.condition.is_some(), &clause.action))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            clauses,
+            vec![
+                (true, true, &MergeAction::Delete),
+                (
+                    true,
+                    false,
+                    &MergeAction::Update(vec![(
+                        "owner".to_string(),
+                        Expr::QualifiedColumn("e".to_string(), "name".to_string())
+                    )])
+                ),
+                (
+                    false,
+                    true,
+                    &MergeAction::Insert {
+                        columns: vec!["id".to_string(), "owner".to_string()],
+                        values: vec![
+                            Expr::QualifiedColumn("e".to_string(), "id".to_string()),
+                            Expr::QualifiedColumn("e".to_string(), "name".to_string())
+                        ],
+                    }
+                ),
+                (false, false, &MergeAction::DoNothing),
+            ]
+        );
+    }
+
+    #[test]
+    fn unmatched_rows_cannot_be_deleted() {
+        assert_eq!(
+            parse_error(
+                "MERGE INTO accounts USING employees ON id = id WHEN NOT MATCHED THEN DELETE"
+            ),
+            "Expected INSERT or DO NOTHING found Delete"
+        );
+    }
+
+    #[test]
+    fn insert_needs_a_source() {
+        assert_eq!(
+            parse_error("INSERT INTO accounts employees"),
+            "Expected VALUES or SELECT found Identifier(\"employees\")"
+        );
+    }
+
+    #[test]
+    fn invalid_alter_table_actions_are_rejected() {
+        assert_eq!(
+            parse_error("ALTER TABLE accounts SELECT"),
+            "Expected ADD, DROP or RENAME found Select"
+        );
+    }
+
+    #[test]
+    fn can_parse_constraints() {
+        let statements = parse_script(
+            "CREATE TABLE transfers (
+                id INTEGER PRIMARY KEY,
+                account INTEGER NOT NULL REFERENCES accounts ON DELETE CASCADE,
+                amount INTEGER CONSTRAINT positive CHECK (amount > 0) DEFAULT 1,
+                reference VARCHAR UNIQUE,
+                CONSTRAINT owner FOREIGN KEY (account, reference) REFERENCES accounts (id, owner)
+                    ON UPDATE SET NULL ON DELETE NO ACTION,
+                UNIQUE (account, amount)
+            )",
+        )
+        .unwrap();
+        let Statement::CreateTable {
+            columns,
+            constraints,
+            ..
+        } = &statements[0]
+        else {
+            panic!("expected CREATE TABLE got {:?}", statements[0]);
+        };
+        let column_constraints = columns
+            .iter()
+            .map(|column| column.constraints.clone())
+            .collect::<Vec<_>>();
+        let columns = |columns: &[&str]| columns.iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            column_constraints,
+            vec![
+                vec![ConstraintDef {
+                    name: None,
+                    kind: ConstraintKind::PrimaryKey(columns(&["id"])),
+                }],
+                vec![ConstraintDef {
+                    name: None,
+                    kind: ConstraintKind::ForeignKey {
+                        columns: columns(&["account"]),
+                        table: "accounts".to_string(),
+                        referenced: vec![],
+                        on_delete: ReferentialAction::Cascade,
+                        on_update: ReferentialAction::NoAction,
+                    },
+                }],
+                vec![ConstraintDef {
+                    name: Some("positive".to_string()),
+                    kind: ConstraintKind::Check(Expr::Comparison(
+                        Box::new(Expr::Column("amount".to_string())),
+                        ">".to_string(),
+                        Box::new(Expr::Value(0)),
+                    )),
+                }],
+                vec![ConstraintDef {
+                    name: None,
+                    kind: ConstraintKind::Unique(columns(&["reference"])),
+                }],
+            ]
+        );
+        assert_eq!(
+            constraints,
+            &vec![
+                ConstraintDef {
+                    name: Some("owner".to_string()),
+                    kind: ConstraintKind::ForeignKey {
+                        columns: columns(&["account", "reference"]),
+                        table: "accounts".to_string(),
+                        referenced: columns(&["id", "owner"]),
+                        on_delete: ReferentialAction::NoAction,
+                        on_update: ReferentialAction::SetNull,
+                    },
+                },
+                ConstraintDef {
+                    name: None,
+                    kind: ConstraintKind::Unique(columns(&["account", "amount"])),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn can_parse_values() {
+        let statements = parse_script(
+            "VALUES (1, 'a'), (2, 'b');
+             SELECT t.id FROM (VALUES (1, 'a')) AS t(id, name)",
+        )
+        .unwrap();
+        let rows = vec![
+            vec![Expr::Value(1), Expr::Varchar("a".to_string())],
+            vec![Expr::Value(2), Expr::Varchar("b".to_string())],
+        ];
+        assert_eq!(statements[0], Statement::Query(Query::Values(rows.clone())));
+        let Statement::Query(Query::Select { from, .. }) = &statements[1] else {
+            panic!("expected SELECT got {:?}", statements[1]);
+        };
+        assert_eq!(
+            **from,
+            TableRef::Values {
+                rows: rows[..1].to_vec(),
+                alias: Alias {
+                    name: "t".to_string(),
+                    columns: vec!["id".to_string(), "name".to_string()],
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn values_rows_have_the_same_length() {
+        assert_eq!(
+            parse_error("VALUES (1, 'a'), (2)"),
+            "VALUES lists must all be the same length"
+        );
+    }
+
+    #[test]
+    fn values_in_from_need_an_alias() {
+        assert_eq!(
+            parse_error("SELECT 1 FROM (VALUES (1))"),
+            "VALUES in FROM must have an alias"
+        );
+    }
+
+    #[test]
+    fn outer_joins_are_rejected() {
+        assert_eq!(
+            parse_error("SELECT e.name FROM employees e LEFT JOIN departments d ON e.dept = d.id"),
+            "LEFT JOIN is not supported"
+        );
+    }
+
+    #[test]
+    fn join_keywords_are_not_aliases() {
+        assert_eq!(
+            parse_error("SELECT name FROM employees NATURAL JOIN departments"),
+            "NATURAL JOIN is not supported"
+        );
+    }
+
+    #[test]
+    fn named_constraints_cannot_be_defaults() {
+        assert_eq!(
+            parse_error("CREATE TABLE accounts (id INTEGER CONSTRAINT initial DEFAULT 0)"),
+            "Expected constraint found Default"
+        );
+    }
+
+    #[test]
+    fn statements_must_be_separated() {
+        assert_eq!(
+            parse_error("SELECT id FROM employees SELECT name FROM employees"),
+            "Expected semicolon or end of input found Select"
+        );
+    }
+
+    #[test]
+    fn can_tokenize_two_word_keywords() {
+        let tokens = Tokenizer::new("GROUP BY role order  by byline orderby")
+            .map(Result::unwrap)
+            .take_while(|token| *token != Token::EOF)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::GroupBy,
+                Token::Identifier("role".to_string()),
+                Token::OrderBy,
+                Token::Identifier("byline".to_string()),
+                Token::OrderBy
+            ]
+        );
+    }
+
     #[test]
     fn can_parse_query_with_limit() {
         let tokens = vec![
@@ -534,15 +3442,22 @@ mod tests {
             Token::EOF,
         ];
         let tokenizer = MockTokenizer::new(&tokens);
-        let mut parser = Parser::new(tokenizer);
-        let query = parser.parse();
+        let mut parser = Parser::new(tokenizer).unwrap();
+        let query = parser.parse().unwrap();
 
         assert_eq!(
             query,
             Query::Select {
-                columns: vec!["id".to_string(), "name".to_string()],
-                table: "employees".to_string(),
+                columns: vec![
+                    Expr::Column("id".to_string()),
+                    Expr::Column("name".to_string())
+                ],
+                from: Box::new(TableRef::Table {
+                    name: "employees".to_string(),
+                    alias: None
+                }),
                 filter: None,
+                group_by: vec![],
                 order_by: None,
                 limit: Some(10)
             }
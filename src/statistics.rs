@@ -0,0 +1,167 @@
+//! Implementation of the statistics `ANALYZE` collects on the rows of a
+//! table, which the planner uses to estimate the number of rows operators
+//! produce.
+use std::collections::HashSet;
+
+use crate::row::Row;
+use crate::types::Value;
+
+/// Number of buckets of the histograms collected by `ANALYZE`.
+pub const HISTOGRAM_BUCKETS: usize = 10;
+
+/// Statistics of the rows of a table when it was last analyzed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableStatistics {
+    pub row_count: usize,
+    /// Statistics of each column, in the order of the columns of the table.
+    pub columns: Vec<ColumnStatistics>,
+}
+
+/// Statistics of the values of a column, `NULL`s are only accounted for by
+/// the null fraction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStatistics {
+    pub distinct_count: usize,
+    /// Fraction of the rows whose value is `NULL`, zero without rows.
+    pub null_fraction: f64,
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+    pub histogram: Histogram,
+}
+
+/// Equi-depth histogram of the values of a column, made of the bounds of
+/// buckets holding about the same number of values. The first bound is the
+/// minimum and the last one the maximum, there are no bounds without values.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Histogram {
+    pub bounds: Vec<Value>,
+}
+
+impl TableStatistics {
+    /// Collect the statistics of rows holding the given number of columns.
+    pub fn collect(rows: &[Row], columns: usize) -> Self {
+        Self {
+            row_count: rows.len(),
+            columns: (0..columns)
+                .map(|index| {
+                    let values = rows.iter().map(|row| &row.items[index]);
+                    ColumnStatistics::collect(values, rows.len())
+                })
+                .collect(),
+        }
+    }
+}
+
+impl ColumnStatistics {
+    /// Collect the statistics of the values of a column among the given
+    /// number of rows.
+    pub fn collect<'a>(values: impl Iterator<Item = &'a Value>, rows: usize) -> Self {
+        let mut values = values
+            .filter(|value| !value.is_null())
+            .cloned()
+            .collect::<Vec<_>>();
+        values.sort();
+        let nulls = rows - values.len();
+        Self {
+            distinct_count: values.iter().collect::<HashSet<_>>().len(),
+            null_fraction: if rows == 0 {
+                0.0
+            } else {
+                nulls as f64 / rows as f64
+            },
+            min: values.first().cloned(),
+            max: values.last().cloned(),
+            histogram: Histogram::new(&values, HISTOGRAM_BUCKETS),
+        }
+    }
+}
+
+impl Histogram {
+    /// Build the histogram of sorted values with at most the given number of
+    /// buckets, one fewer than the values when there are fewer values so no
+    /// bound repeats a value.
+    pub fn new(sorted: &[Value], buckets: usize) -> Self {
+        let buckets = buckets.min(sorted.len().saturating_sub(1));
+        if buckets == 0 {
+            return Self::default();
+        }
+        let last = sorted.len() - 1;
+        Self {
+            bounds: (0..=buckets)
+                .map(|bucket| sorted[bucket * last / buckets].clone())
+                .collect(),
+        }
+    }
+
+    /// Returns the estimated fraction of the values which are lower than the
+    /// given one, values are assumed to be in the middle of their bucket.
+    pub fn fraction_below(&self, value: &Value) -> Option<f64> {
+        let buckets = self.bounds.len().checked_sub(1).filter(|&n| n > 0)?;
+        let below = self.bounds.partition_point(|bound| bound < value);
+        Some(match below {
+            0 => 0.0,
+            below if below > buckets => 1.0,
+            below => (below as f64 - 0.5) / buckets as f64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_collect_statistics() {
+        let rows = (0..20)
+            .map(|i| {
+                let value = if i % 4 == 0 {
+                    Value::Null
+                } else {
+                    Value::Integer(i / 2)
+                };
+                Row::new(&[Value::Integer(i), value])
+            })
+            .collect::<Vec<_>>();
+        let statistics = TableStatistics::collect(&rows, 2);
+        assert_eq!(statistics.row_count, 20);
+        let [id, half] = &statistics.columns[..] else {
+            unreachable!()
+        };
+        assert_eq!(id.distinct_count, 20);
+        assert_eq!(id.null_fraction, 0.0);
+        assert_eq!(
+            (id.min.clone(), id.max.clone()),
+            (Some(Value::Integer(0)), Some(Value::Integer(19)))
+        );
+        assert_eq!(id.histogram.bounds.len(), HISTOGRAM_BUCKETS + 1);
+        // Halves of the indices which are not multiples of four, 0, 1, 1, 2,
+        // 3, 3, ...
+        assert_eq!(half.distinct_count, 10);
+        assert_eq!(half.null_fraction, 0.25);
+        assert_eq!(half.min, Some(Value::Integer(0)));
+        assert_eq!(half.max, Some(Value::Integer(9)));
+    }
+
+    #[test]
+    fn histograms_are_equi_depth() {
+        let values = (1..=9).map(Value::Integer).collect::<Vec<_>>();
+        let histogram = Histogram::new(&values, 4);
+        assert_eq!(
+            histogram.bounds,
+            [1, 3, 5, 7, 9].map(Value::Integer).to_vec()
+        );
+        assert_eq!(
+            Histogram::new(&values[..3], 4).bounds,
+            [1, 2, 3].map(Value::Integer).to_vec()
+        );
+        assert_eq!(Histogram::new(&values[..1], 4), Histogram::default());
+        assert_eq!(Histogram::new(&[], 4), Histogram::default());
+        assert_eq!(histogram.fraction_below(&Value::Integer(1)), Some(0.0));
+        assert_eq!(histogram.fraction_below(&Value::Integer(4)), Some(0.375));
+        assert_eq!(histogram.fraction_below(&Value::Integer(10)), Some(1.0));
+        assert_eq!(
+            Histogram::default().fraction_below(&Value::Integer(1)),
+            None
+        );
+    }
+}
@@ -0,0 +1,870 @@
+//! Implementation of the calendar arithmetic backing the `DATE`, `TIME`,
+//! `TIMESTAMP` and `INTERVAL` types.
+//!
+//! Dates follow the proleptic Gregorian calendar from year 1 to 9999, times
+//! have a precision of a microsecond and no time zone.
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::decimal::Decimal;
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const MICROS_PER_MINUTE: i64 = 60 * MICROS_PER_SECOND;
+const MICROS_PER_HOUR: i64 = 60 * MICROS_PER_MINUTE;
+const MICROS_PER_DAY: i64 = 24 * MICROS_PER_HOUR;
+/// Days of a month and of a year when converting intervals to a duration.
+const DAYS_PER_MONTH: i64 = 30;
+const MICROS_PER_YEAR: i64 = 365 * MICROS_PER_DAY + MICROS_PER_DAY / 4;
+
+/// Days between 1970-01-01 and the first and last supported dates.
+const MIN_DAYS: i32 = days_from_civil(1, 1, 1);
+const MAX_DAYS: i32 = days_from_civil(9999, 12, 31);
+
+/// Calendar date, stored as a number of days since 1970-01-01.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date(i32);
+
+impl Date {
+    /// Create a date from its year, month and day, returns `None` if it does
+    /// not exist.
+    pub fn from_ymd(year: i32, month: u32, day: u32) -> Option<Self> {
+        if !(1..=9999).contains(&year)
+            || !(1..=12).contains(&month)
+            || !(1..=days_in_month(year, month)).contains(&day)
+        {
+            return None;
+        }
+        Some(Self(days_from_civil(year, month, day)))
+    }
+
+    /// Create a date from a number of days since 1970-01-01.
+    pub fn from_days(days: i64) -> Option<Self> {
+        (MIN_DAYS as i64..=MAX_DAYS as i64)
+            .contains(&days)
+            .then_some(Self(days as i32))
+    }
+
+    /// Parse a date written `YYYY-MM-DD`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.splitn(3, '-');
+        let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+        if [year, month, day]
+            .iter()
+            .any(|part| part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()))
+        {
+            return None;
+        }
+        Self::from_ymd(year.parse().ok()?, month.parse().ok()?, day.parse().ok()?)
+    }
+
+    /// Returns the number of days since 1970-01-01.
+    pub fn days(&self) -> i32 {
+        self.0
+    }
+
+    /// Returns the year, month and day of the date.
+    pub fn ymd(&self) -> (i32, u32, u32) {
+        civil_from_days(self.0)
+    }
+
+    /// Returns the day of the week from 0 for Sunday to 6 for Saturday.
+    pub fn weekday(&self) -> u32 {
+        // 1970-01-01 was a Thursday.
+        (self.0 + 4).rem_euclid(7) as u32
+    }
+
+    /// Returns the day of the year starting from 1.
+    pub fn ordinal(&self) -> u32 {
+        let (year, _, _) = self.ymd();
+        (self.0 - days_from_civil(year, 1, 1)) as u32 + 1
+    }
+
+    /// Returns the ISO 8601 week number, weeks start on Monday and the first
+    /// week of a year is the one holding its first Thursday.
+    pub fn iso_week(&self) -> u32 {
+        let (year, _, _) = self.ymd();
+        let weekday = (self.weekday() + 6) % 7 + 1;
+        let week = (self.ordinal() as i32 - weekday as i32 + 10) / 7;
+        if week < 1 {
+            iso_weeks_in_year(year - 1)
+        } else if week as u32 > iso_weeks_in_year(year) {
+            1
+        } else {
+            week as u32
+        }
+    }
+
+    pub fn checked_add_days(&self, days: i64) -> Option<Self> {
+        Self::from_days(self.0 as i64 + days)
+    }
+
+    /// Add a number of months, the day is clamped to the last day of the
+    /// resulting month so `2024-01-31` plus a month is `2024-02-29`.
+    pub fn checked_add_months(&self, months: i32) -> Option<Self> {
+        let (year, month, day) = self.ymd();
+        let month = (year as i64) * 12 + (month as i64 - 1) + months as i64;
+        let (year, month) = (month.div_euclid(12), month.rem_euclid(12) as u32 + 1);
+        let year = i32::try_from(year).ok()?;
+        if !(1..=9999).contains(&year) {
+            return None;
+        }
+        Self::from_ymd(year, month, day.min(days_in_month(year, month)))
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = self.ymd();
+        write!(f, "{year:04}-{month:02}-{day:02}")
+    }
+}
+
+/// Time of the day, stored as a number of microseconds since midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Time(i64);
+
+impl Time {
+    pub const MIDNIGHT: Time = Time(0);
+
+    /// Create a time from a number of microseconds since midnight.
+    pub fn from_micros(micros: i64) -> Option<Self> {
+        (0..MICROS_PER_DAY)
+            .contains(&micros)
+            .then_some(Self(micros))
+    }
+
+    /// Parse a time written `HH:MM[:SS[.ffffff]]`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (hours, micros) = parse_clock(text)?;
+        if hours > 23 {
+            return None;
+        }
+        Self::from_micros(hours * MICROS_PER_HOUR + micros)
+    }
+
+    /// Returns the number of microseconds since midnight.
+    pub fn micros(&self) -> i64 {
+        self.0
+    }
+
+    /// Add the time part of an interval, wrapping around midnight.
+    pub fn add(&self, interval: &Interval) -> Self {
+        Self((self.0 + interval.micros % MICROS_PER_DAY).rem_euclid(MICROS_PER_DAY))
+    }
+
+    /// Returns the interval between two times of the same day.
+    pub fn sub(&self, other: &Time) -> Interval {
+        Interval::new(0, 0, self.0 - other.0)
+    }
+
+    /// Extract a field, only the fields of the time of the day are defined.
+    pub fn extract(&self, field: DateTimeField) -> Option<Decimal> {
+        match field {
+            DateTimeField::Hour | DateTimeField::Minute | DateTimeField::Second => {
+                Timestamp(self.0).extract(field)
+            }
+            DateTimeField::Epoch => Decimal::new(self.0 as i128, 6),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format_clock(self.0))
+    }
+}
+
+/// Date and time of the day, stored as a number of microseconds since
+/// 1970-01-01 00:00:00.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    pub fn new(date: Date, time: Time) -> Self {
+        Self(date.0 as i64 * MICROS_PER_DAY + time.0)
+    }
+
+    /// Create a timestamp from a number of microseconds since 1970-01-01,
+    /// returns `None` if its date is out of range.
+    pub fn from_micros(micros: i64) -> Option<Self> {
+        Date::from_days(micros.div_euclid(MICROS_PER_DAY)).map(|_| Self(micros))
+    }
+
+    /// Parse a timestamp written `YYYY-MM-DD[( |T)HH:MM[:SS[.ffffff]]]`.
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.split_once([' ', 'T']) {
+            Some((date, time)) => Some(Self::new(Date::parse(date)?, Time::parse(time.trim())?)),
+            None => Some(Self::new(Date::parse(text)?, Time::MIDNIGHT)),
+        }
+    }
+
+    /// Returns the number of microseconds since 1970-01-01.
+    pub fn micros(&self) -> i64 {
+        self.0
+    }
+
+    pub fn date(&self) -> Date {
+        Date(self.0.div_euclid(MICROS_PER_DAY) as i32)
+    }
+
+    pub fn time(&self) -> Time {
+        Time(self.0.rem_euclid(MICROS_PER_DAY))
+    }
+
+    /// Add an interval, months are added first then days and the time part.
+    pub fn checked_add(&self, interval: &Interval) -> Option<Self> {
+        let date = self
+            .date()
+            .checked_add_months(interval.months)?
+            .checked_add_days(interval.days as i64)?;
+        Self::from_micros(
+            Self::new(date, self.time())
+                .0
+                .checked_add(interval.micros)?,
+        )
+    }
+
+    /// Returns the interval between two timestamps, expressed in days and
+    /// microseconds.
+    pub fn checked_sub(&self, other: &Timestamp) -> Option<Interval> {
+        let micros = self.0.checked_sub(other.0)?;
+        Some(Interval::new(
+            0,
+            i32::try_from(micros / MICROS_PER_DAY).ok()?,
+            micros % MICROS_PER_DAY,
+        ))
+    }
+
+    /// Extract a field of the timestamp, `SECOND` and `EPOCH` have a scale of
+    /// 6 and the other fields are integers.
+    pub fn extract(&self, field: DateTimeField) -> Option<Decimal> {
+        let date = self.date();
+        let (year, month, day) = date.ymd();
+        let micros = self.time().0;
+        let value = match field {
+            DateTimeField::Year => year as i64,
+            DateTimeField::Quarter => (month as i64 - 1) / 3 + 1,
+            DateTimeField::Month => month as i64,
+            DateTimeField::Week => date.iso_week() as i64,
+            DateTimeField::Day => day as i64,
+            DateTimeField::DayOfWeek => date.weekday() as i64,
+            DateTimeField::DayOfYear => date.ordinal() as i64,
+            DateTimeField::Hour => micros / MICROS_PER_HOUR,
+            DateTimeField::Minute => micros / MICROS_PER_MINUTE % 60,
+            DateTimeField::Second => {
+                return Decimal::new((micros % MICROS_PER_MINUTE) as i128, 6);
+            }
+            DateTimeField::Epoch => return Decimal::new(self.0 as i128, 6),
+        };
+        Decimal::new(value as i128, 0)
+    }
+
+    /// Truncate the timestamp to the given precision, weeks start on Monday.
+    pub fn trunc(&self, field: DateTimeField) -> Option<Self> {
+        let date = self.date();
+        let (year, month, _) = date.ymd();
+        let truncate = |unit: i64| Self(self.0 - self.0.rem_euclid(unit));
+        match field {
+            DateTimeField::Year => Some(Self::new(Date::from_ymd(year, 1, 1)?, Time::MIDNIGHT)),
+            DateTimeField::Quarter => Some(Self::new(
+                Date::from_ymd(year, (month - 1) / 3 * 3 + 1, 1)?,
+                Time::MIDNIGHT,
+            )),
+            DateTimeField::Month => {
+                Some(Self::new(Date::from_ymd(year, month, 1)?, Time::MIDNIGHT))
+            }
+            DateTimeField::Week => Some(Self::new(
+                date.checked_add_days(-(((date.weekday() + 6) % 7) as i64))?,
+                Time::MIDNIGHT,
+            )),
+            DateTimeField::Day => Some(truncate(MICROS_PER_DAY)),
+            DateTimeField::Hour => Some(truncate(MICROS_PER_HOUR)),
+            DateTimeField::Minute => Some(truncate(MICROS_PER_MINUTE)),
+            DateTimeField::Second => Some(truncate(MICROS_PER_SECOND)),
+            DateTimeField::DayOfWeek | DateTimeField::DayOfYear | DateTimeField::Epoch => None,
+        }
+    }
+}
+
+impl From<Date> for Timestamp {
+    fn from(date: Date) -> Self {
+        Self::new(date, Time::MIDNIGHT)
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.date(), self.time())
+    }
+}
+
+/// Span of time made of months, days and microseconds, which are kept apart
+/// since months and days do not have a fixed length.
+///
+/// Intervals compare and hash by their approximate duration, counting 30 days
+/// per month, so `1 mon` equals `30 days`.
+#[derive(Debug, Clone, Copy)]
+pub struct Interval {
+    months: i32,
+    days: i32,
+    micros: i64,
+}
+
+impl Interval {
+    pub fn new(months: i32, days: i32, micros: i64) -> Self {
+        Self {
+            months,
+            days,
+            micros,
+        }
+    }
+
+    /// Parse an interval written as a list of quantities followed by their
+    /// unit, `1 year 2 months 3 days`, optionally ending with a time part
+    /// `[-]HH:MM[:SS[.ffffff]]`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut interval = Self::new(0, 0, 0);
+        let mut words = text.split_whitespace().peekable();
+        words.peek()?;
+        while let Some(word) = words.next() {
+            if word.contains(':') {
+                let (negative, clock) = match word.strip_prefix('-') {
+                    Some(clock) => (true, clock),
+                    None => (false, word),
+                };
+                let (hours, micros) = parse_clock(clock)?;
+                let micros = hours.checked_mul(MICROS_PER_HOUR)?.checked_add(micros)?;
+                let micros = if negative { -micros } else { micros };
+                interval.micros = interval.micros.checked_add(micros)?;
+                continue;
+            }
+            let unit = words.next()?.to_lowercase();
+            let unit = unit.strip_suffix('s').unwrap_or(&unit);
+            let quantity = || word.parse::<i32>().ok();
+            let part = match unit {
+                "year" => Self::new(quantity()?.checked_mul(12)?, 0, 0),
+                "mon" | "month" => Self::new(quantity()?, 0, 0),
+                "week" => Self::new(0, quantity()?.checked_mul(7)?, 0),
+                "day" => Self::new(0, quantity()?, 0),
+                "hour" => Self::new(
+                    0,
+                    0,
+                    word.parse::<i64>().ok()?.checked_mul(MICROS_PER_HOUR)?,
+                ),
+                "min" | "minute" => Self::new(
+                    0,
+                    0,
+                    word.parse::<i64>().ok()?.checked_mul(MICROS_PER_MINUTE)?,
+                ),
+                "sec" | "second" => {
+                    let (negative, seconds) = match word.strip_prefix('-') {
+                        Some(seconds) => (true, seconds),
+                        None => (false, word),
+                    };
+                    let micros = parse_seconds(seconds)?;
+                    Self::new(0, 0, if negative { -micros } else { micros })
+                }
+                "millisecond" => Self::new(0, 0, word.parse::<i64>().ok()?.checked_mul(1000)?),
+                "microsecond" => Self::new(0, 0, word.parse().ok()?),
+                _ => return None,
+            };
+            interval = interval.checked_add(&part)?;
+        }
+        Some(interval)
+    }
+
+    pub fn months(&self) -> i32 {
+        self.months
+    }
+
+    pub fn days(&self) -> i32 {
+        self.days
+    }
+
+    pub fn micros(&self) -> i64 {
+        self.micros
+    }
+
+    pub fn checked_add(&self, other: &Interval) -> Option<Self> {
+        Some(Self::new(
+            self.months.checked_add(other.months)?,
+            self.days.checked_add(other.days)?,
+            self.micros.checked_add(other.micros)?,
+        ))
+    }
+
+    pub fn checked_sub(&self, other: &Interval) -> Option<Self> {
+        self.checked_add(&other.checked_neg()?)
+    }
+
+    pub fn checked_neg(&self) -> Option<Self> {
+        Some(Self::new(
+            self.months.checked_neg()?,
+            self.days.checked_neg()?,
+            self.micros.checked_neg()?,
+        ))
+    }
+
+    pub fn checked_mul(&self, factor: i64) -> Option<Self> {
+        let factor32 = i32::try_from(factor).ok()?;
+        Some(Self::new(
+            self.months.checked_mul(factor32)?,
+            self.days.checked_mul(factor32)?,
+            self.micros.checked_mul(factor)?,
+        ))
+    }
+
+    /// Divide the interval, the remainders of months and days are carried
+    /// to the smaller units so `1 mon / 2` is `15 days`.
+    pub fn checked_div(&self, divisor: i64) -> Option<Self> {
+        if divisor == 0 {
+            return None;
+        }
+        let days = self.days as i64 + self.months as i64 % divisor * DAYS_PER_MONTH;
+        let micros = (days % divisor)
+            .checked_mul(MICROS_PER_DAY)?
+            .checked_add(self.micros)?;
+        Some(Self::new(
+            i32::try_from(self.months as i64 / divisor).ok()?,
+            i32::try_from(days / divisor).ok()?,
+            micros / divisor,
+        ))
+    }
+
+    /// Extract a field of the interval, years are the whole years of its
+    /// months, and `EPOCH` its duration in seconds counting 365.25 days per
+    /// year and 30 days per month.
+    pub fn extract(&self, field: DateTimeField) -> Option<Decimal> {
+        let value = match field {
+            DateTimeField::Year => self.months as i64 / 12,
+            DateTimeField::Quarter => self.months as i64 % 12 / 3 + 1,
+            DateTimeField::Month => self.months as i64 % 12,
+            DateTimeField::Day => self.days as i64,
+            DateTimeField::Hour => self.micros / MICROS_PER_HOUR,
+            DateTimeField::Minute => self.micros / MICROS_PER_MINUTE % 60,
+            DateTimeField::Second => {
+                return Decimal::new((self.micros % MICROS_PER_MINUTE) as i128, 6)
+            }
+            DateTimeField::Epoch => {
+                let micros = (self.months / 12) as i128 * MICROS_PER_YEAR as i128
+                    + (self.months % 12) as i128 * (DAYS_PER_MONTH * MICROS_PER_DAY) as i128
+                    + self.days as i128 * MICROS_PER_DAY as i128
+                    + self.micros as i128;
+                return Decimal::new(micros, 6);
+            }
+            DateTimeField::Week | DateTimeField::DayOfWeek | DateTimeField::DayOfYear => {
+                return None
+            }
+        };
+        Decimal::new(value as i128, 0)
+    }
+
+    /// Returns the approximate duration of the interval in microseconds.
+    fn duration(&self) -> i128 {
+        (self.months as i128 * DAYS_PER_MONTH as i128 + self.days as i128) * MICROS_PER_DAY as i128
+            + self.micros as i128
+    }
+}
+
+impl PartialEq for Interval {
+    fn eq(&self, other: &Self) -> bool {
+        self.duration() == other.duration()
+    }
+}
+
+impl Eq for Interval {}
+
+impl PartialOrd for Interval {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Interval {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.duration().cmp(&other.duration())
+    }
+}
+
+impl Hash for Interval {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.duration().hash(state)
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = |n: i32| if n == 1 { "" } else { "s" };
+        let mut parts = vec![];
+        let (years, months) = (self.months / 12, self.months % 12);
+        if years != 0 {
+            parts.push(format!("{years} year{}", plural(years)));
+        }
+        if months != 0 {
+            parts.push(format!("{months} mon{}", plural(months)));
+        }
+        if self.days != 0 {
+            parts.push(format!("{} day{}", self.days, plural(self.days)));
+        }
+        if self.micros != 0 || parts.is_empty() {
+            let sign = if self.micros < 0 { "-" } else { "" };
+            parts.push(format!("{sign}{}", format_clock(self.micros.abs())));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// Fields of temporal values read by `EXTRACT` and `DATE_TRUNC`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DateTimeField {
+    Year,
+    Quarter,
+    Month,
+    /// ISO 8601 week of the year.
+    Week,
+    Day,
+    /// Day of the week from 0 for Sunday to 6 for Saturday.
+    DayOfWeek,
+    /// Day of the year starting from 1.
+    DayOfYear,
+    Hour,
+    Minute,
+    /// Seconds including their fractional part.
+    Second,
+    /// Number of seconds since 1970-01-01 00:00:00, or in an interval.
+    Epoch,
+}
+
+impl DateTimeField {
+    /// Parse the name of a field, names are case insensitive.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "year" => Some(DateTimeField::Year),
+            "quarter" => Some(DateTimeField::Quarter),
+            "month" => Some(DateTimeField::Month),
+            "week" => Some(DateTimeField::Week),
+            "day" => Some(DateTimeField::Day),
+            "dow" => Some(DateTimeField::DayOfWeek),
+            "doy" => Some(DateTimeField::DayOfYear),
+            "hour" => Some(DateTimeField::Hour),
+            "minute" => Some(DateTimeField::Minute),
+            "second" => Some(DateTimeField::Second),
+            "epoch" => Some(DateTimeField::Epoch),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DateTimeField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DateTimeField::Year => "YEAR",
+            DateTimeField::Quarter => "QUARTER",
+            DateTimeField::Month => "MONTH",
+            DateTimeField::Week => "WEEK",
+            DateTimeField::Day => "DAY",
+            DateTimeField::DayOfWeek => "DOW",
+            DateTimeField::DayOfYear => "DOY",
+            DateTimeField::Hour => "HOUR",
+            DateTimeField::Minute => "MINUTE",
+            DateTimeField::Second => "SECOND",
+            DateTimeField::Epoch => "EPOCH",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Source of the current time read by `NOW()`, which lets tests pin it.
+pub trait Clock {
+    fn now(&self) -> Timestamp;
+}
+
+/// Clock reading the time of the operating system.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Timestamp {
+        let micros = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(elapsed) => elapsed.as_micros() as i64,
+            Err(error) => -(error.duration().as_micros() as i64),
+        };
+        Timestamp(micros)
+    }
+}
+
+/// Clock stopped at a given time.
+pub struct FixedClock(pub Timestamp);
+
+impl Clock for FixedClock {
+    fn now(&self) -> Timestamp {
+        self.0
+    }
+}
+
+/// Returns the number of days between 1970-01-01 and the given date.
+///
+/// See Howard Hinnant's `days_from_civil`, years start in March so the leap
+/// day is the last day of the year.
+const fn days_from_civil(year: i32, month: u32, day: u32) -> i32 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400) as u32;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era as i32 - 719468
+}
+
+/// Returns the year, month and day of the date the given number of days after
+/// 1970-01-01, the inverse of `days_from_civil`.
+fn civil_from_days(days: i32) -> (i32, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097) as u32;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era as i32 + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+fn is_leap_year(year: i32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Returns the number of ISO 8601 weeks of a year, years starting on a
+/// Thursday and leap years starting on a Wednesday have 53 weeks.
+fn iso_weeks_in_year(year: i32) -> u32 {
+    let first = Date(days_from_civil(year, 1, 1)).weekday();
+    if first == 4 || first == 3 && is_leap_year(year) {
+        53
+    } else {
+        52
+    }
+}
+
+/// Parse a clock `HH:MM[:SS[.ffffff]]` into hours and the microseconds past
+/// the hour, hours are unbounded.
+fn parse_clock(text: &str) -> Option<(i64, i64)> {
+    let mut parts = text.splitn(3, ':');
+    let (hours, minutes) = (parts.next()?, parts.next()?);
+    let seconds = parts.next().map_or(Some(0), parse_seconds)?;
+    let digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    if !digits(hours) || !digits(minutes) {
+        return None;
+    }
+    let minutes: i64 = minutes.parse().ok()?;
+    if minutes > 59 || seconds >= MICROS_PER_MINUTE {
+        return None;
+    }
+    Some((hours.parse().ok()?, minutes * MICROS_PER_MINUTE + seconds))
+}
+
+/// Parse seconds `SS[.ffffff]` into microseconds.
+fn parse_seconds(text: &str) -> Option<i64> {
+    let (seconds, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if seconds.is_empty()
+        || fraction.len() > 6
+        || !seconds
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let fraction = format!("{fraction:0<6}").parse::<i64>().ok()?;
+    seconds
+        .parse::<i64>()
+        .ok()?
+        .checked_mul(MICROS_PER_SECOND)?
+        .checked_add(fraction)
+}
+
+/// Format a non-negative number of microseconds as `HH:MM:SS[.ffffff]`,
+/// omitting trailing zeros of the fraction.
+fn format_clock(micros: i64) -> String {
+    let (hours, minutes, seconds, fraction) = (
+        micros / MICROS_PER_HOUR,
+        micros / MICROS_PER_MINUTE % 60,
+        micros / MICROS_PER_SECOND % 60,
+        micros % MICROS_PER_SECOND,
+    );
+    let clock = format!("{hours:02}:{minutes:02}:{seconds:02}");
+    if fraction == 0 {
+        clock
+    } else {
+        let fraction = format!("{fraction:06}");
+        format!("{clock}.{}", fraction.trim_end_matches('0'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> Date {
+        Date::parse(text).unwrap()
+    }
+
+    fn timestamp(text: &str) -> Timestamp {
+        Timestamp::parse(text).unwrap()
+    }
+
+    fn interval(text: &str) -> Interval {
+        Interval::parse(text).unwrap()
+    }
+
+    #[test]
+    fn dates_roundtrip_through_text() {
+        for text in [
+            "1970-01-01",
+            "2024-02-29",
+            "0001-01-01",
+            "9999-12-31",
+            "1969-12-31",
+        ] {
+            assert_eq!(date(text).to_string(), text);
+        }
+        assert_eq!(date("1970-01-02").days(), 1);
+        assert_eq!(date("1969-12-31").days(), -1);
+        assert_eq!(Date::parse("2023-02-29"), None);
+        assert_eq!(Date::parse("2024-13-01"), None);
+        assert_eq!(Date::parse("10000-01-01"), None);
+        assert_eq!(Date::parse("2024-1-x"), None);
+    }
+
+    #[test]
+    fn can_compute_calendar_fields() {
+        // 2024-01-31 was a Wednesday.
+        assert_eq!(date("2024-01-31").weekday(), 3);
+        assert_eq!(date("2024-12-31").ordinal(), 366);
+        assert_eq!(date("2021-01-03").iso_week(), 53);
+        assert_eq!(date("2024-12-30").iso_week(), 1);
+        assert_eq!(date("2024-06-15").iso_week(), 24);
+        assert_eq!(
+            date("2024-01-31").checked_add_months(1),
+            Some(date("2024-02-29"))
+        );
+        assert_eq!(
+            date("2024-03-31").checked_add_months(-13),
+            Some(date("2023-02-28"))
+        );
+        assert_eq!(date("9999-12-01").checked_add_months(1), None);
+    }
+
+    #[test]
+    fn times_roundtrip_through_text() {
+        assert_eq!(Time::parse("09:05").unwrap().to_string(), "09:05:00");
+        assert_eq!(Time::parse("23:59:59.5").unwrap().to_string(), "23:59:59.5");
+        assert_eq!(Time::parse("24:00"), None);
+        assert_eq!(Time::parse("12:60"), None);
+        assert_eq!(
+            timestamp("2024-01-31T08:30:00").to_string(),
+            "2024-01-31 08:30:00"
+        );
+        assert_eq!(timestamp("2024-01-31").to_string(), "2024-01-31 00:00:00");
+        assert_eq!(timestamp("1969-12-31 23:59:59.999999").micros(), -1);
+    }
+
+    #[test]
+    fn intervals_roundtrip_through_text() {
+        assert_eq!(interval("3 days").to_string(), "3 days");
+        assert_eq!(
+            interval("1 year 14 months 1 day 2 hours").to_string(),
+            "2 years 2 mons 1 day 02:00:00"
+        );
+        assert_eq!(interval("-90 minutes").to_string(), "-01:30:00");
+        assert_eq!(
+            interval("1 week -01:00:00.25").to_string(),
+            "7 days -01:00:00.25"
+        );
+        assert_eq!(interval("0 seconds").to_string(), "00:00:00");
+        assert_eq!(interval("36:00").to_string(), "36:00:00");
+        assert_eq!(Interval::parse(""), None);
+        assert_eq!(Interval::parse("3 fortnights"), None);
+        assert_eq!(Interval::parse("3"), None);
+        assert_eq!(interval("1 mon"), interval("30 days"));
+        assert!(interval("1 day") < interval("25 hours"));
+    }
+
+    #[test]
+    fn can_add_intervals() {
+        assert_eq!(
+            timestamp("2024-01-31 12:00").checked_add(&interval("1 month 1 day 12 hours")),
+            Some(timestamp("2024-03-02"))
+        );
+        assert_eq!(
+            timestamp("2024-02-29").checked_add(&interval("-1 year")),
+            Some(timestamp("2023-02-28"))
+        );
+        assert_eq!(
+            timestamp("2024-03-01").checked_sub(&timestamp("2024-01-31 12:00")),
+            Some(interval("29 days 12:00:00"))
+        );
+        assert_eq!(
+            Time::parse("23:00").unwrap().add(&interval("2 hours")),
+            Time::parse("01:00").unwrap()
+        );
+        assert_eq!(interval("1 mon").checked_div(2), Some(interval("15 days")));
+        assert_eq!(
+            interval("1 day 1 hour").checked_mul(3),
+            Some(interval("3 days 3 hours"))
+        );
+        assert_eq!(
+            timestamp("9999-12-31").checked_add(&interval("1 day")),
+            None
+        );
+    }
+
+    #[test]
+    fn can_extract_and_truncate_fields() {
+        let value = timestamp("2024-08-15 13:45:30.25");
+        let extract = |field| value.extract(field).unwrap().to_string();
+        assert_eq!(extract(DateTimeField::Year), "2024");
+        assert_eq!(extract(DateTimeField::Quarter), "3");
+        assert_eq!(extract(DateTimeField::DayOfWeek), "4");
+        assert_eq!(extract(DateTimeField::Minute), "45");
+        assert_eq!(extract(DateTimeField::Second), "30.250000");
+        assert_eq!(
+            timestamp("1970-01-02").extract(DateTimeField::Epoch),
+            Decimal::parse("86400.000000")
+        );
+        assert_eq!(
+            interval("1 year 2 months").extract(DateTimeField::Month),
+            Decimal::parse("2")
+        );
+        assert_eq!(interval("1 day").extract(DateTimeField::Week), None);
+
+        assert_eq!(
+            value.trunc(DateTimeField::Quarter),
+            Some(timestamp("2024-07-01"))
+        );
+        assert_eq!(
+            value.trunc(DateTimeField::Week),
+            Some(timestamp("2024-08-12"))
+        );
+        assert_eq!(
+            value.trunc(DateTimeField::Hour),
+            Some(timestamp("2024-08-15 13:00"))
+        );
+        assert_eq!(value.trunc(DateTimeField::Epoch), None);
+    }
+}
@@ -0,0 +1,860 @@
+//! Implementation of the type checking pass which resolves the types of
+//! expressions, inserts the casts implied by the implicit coercion rules and
+//! reports expressions mixing incompatible types.
+//!
+//! Besides the coercions of `DataType::coerces_to`, string literals compared
+//! to or passed where a value of another type is expected are cast to that
+//! type, so `salary = '10000'` compares integers.
+use std::error::Error;
+use std::fmt;
+
+use crate::decimal::MAX_PRECISION;
+use crate::functions::FunctionRegistry;
+use crate::schema::Schema;
+use crate::sql::Expr;
+use crate::temporal::DateTimeField;
+use crate::types::{DataType, Value};
+
+/// Errors reported by the type checker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeError {
+    UnknownColumn(String),
+    /// Column name matching columns of several `FROM` items, which are
+    /// listed qualified.
+    AmbiguousColumn {
+        column: String,
+        candidates: Vec<String>,
+    },
+    /// Column qualified by a name which is not one of the `FROM` items.
+    UnknownQualifier {
+        table: String,
+        column: String,
+    },
+    UnknownFunction(String),
+    /// No overload of the function accepts arguments of the given types.
+    InvalidArguments {
+        function: String,
+        arg_types: Vec<DataType>,
+    },
+    /// Comparison between values of types without a common type.
+    IncompatibleComparison {
+        left: DataType,
+        op: String,
+        right: DataType,
+    },
+    /// Arithmetic operation on values which are not numbers of a common type.
+    InvalidArithmetic {
+        left: DataType,
+        op: String,
+        right: DataType,
+    },
+    InvalidNegation(DataType),
+    /// Field which is not defined for values of the given type.
+    InvalidField {
+        field: DateTimeField,
+        data_type: DataType,
+    },
+    /// Elements of an array without a common type.
+    IncompatibleElements(DataType, DataType),
+    /// Values of a column of a `VALUES` list without a common type, columns
+    /// are numbered from 1.
+    IncompatibleValues {
+        column: usize,
+        left: DataType,
+        right: DataType,
+    },
+    InvalidCast {
+        from: DataType,
+        to: DataType,
+    },
+    /// String literal without a representation in the type it is cast to.
+    InvalidLiteral {
+        literal: String,
+        data_type: DataType,
+    },
+    /// Expression used as a predicate which does not evaluate to a boolean.
+    NotAPredicate(String),
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeError::UnknownColumn(column) => write!(f, "Unknown column {column}"),
+            TypeError::AmbiguousColumn { column, candidates } => write!(
+                f,
+                "Column reference {column} is ambiguous, it could refer to {}",
+                candidates.join(" or ")
+            ),
+            TypeError::UnknownQualifier { table, column } => write!(
+                f,
+                "Column {table}.{column} references {table} which is not in the FROM clause"
+            ),
+            TypeError::UnknownFunction(function) => write!(f, "Unknown function {function}"),
+            TypeError::InvalidArguments {
+                function,
+                arg_types,
+            } => {
+                let arg_types = arg_types.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+                write!(
+                    f,
+                    "Function {function} cannot be called with arguments of types ({})",
+                    arg_types.join(", ")
+                )
+            }
+            TypeError::IncompatibleComparison { left, op, right } => {
+                write!(f, "Cannot compare {left} {op} {right}")
+            }
+            TypeError::InvalidArithmetic { left, op, right } => {
+                write!(f, "Cannot apply {op} to {left} and {right}")
+            }
+            TypeError::InvalidNegation(data_type) => write!(f, "Cannot negate {data_type}"),
+            TypeError::InvalidField { field, data_type } => {
+                write!(f, "Cannot extract {field} from {data_type}")
+            }
+            TypeError::IncompatibleElements(left, right) => {
+                write!(
+                    f,
+                    "Array elements of types {left} and {right} are incompatible"
+                )
+            }
+            TypeError::IncompatibleValues {
+                column,
+                left,
+                right,
+            } => write!(
+                f,
+                "VALUES column {column} has incompatible types {left} and {right}"
+            ),
+            TypeError::InvalidCast { from, to } => write!(f, "Cannot cast {from} to {to}"),
+            TypeError::InvalidLiteral { literal, data_type } => {
+                write!(f, "Invalid {data_type} literal '{literal}'")
+            }
+            TypeError::NotAPredicate(expr) => write!(f, "Expression {expr} is not a predicate"),
+        }
+    }
+}
+
+impl Error for TypeError {}
+
+/// Type checker of the expressions evaluated on rows of a given schema.
+pub struct TypeChecker<'a> {
+    schema: &'a Schema,
+    functions: &'a FunctionRegistry,
+}
+
+impl<'a> TypeChecker<'a> {
+    pub fn new(schema: &'a Schema, functions: &'a FunctionRegistry) -> Self {
+        Self { schema, functions }
+    }
+
+    /// Returns the type of the values an expression evaluates to.
+    pub fn type_of(&self, expr: &Expr) -> Result<DataType, TypeError> {
+        self.check(expr.clone()).map(|(_, data_type)| data_type)
+    }
+
+    /// Type check an expression evaluating to a value, returns the expression
+    /// with implicit coercions made explicit along with its type.
+    pub fn check(&self, expr: Expr) -> Result<(Expr, DataType), TypeError> {
+        match expr {
+            Expr::Column(ref name) => {
+                let index = self.column(None, name, &expr)?;
+                let data_type = self.schema.field(index).data_type.clone();
+                Ok((expr, data_type))
+            }
+            Expr::QualifiedColumn(ref table, ref name) => {
+                let index = self.column(Some(table), name, &expr)?;
+                let data_type = self.schema.field(index).data_type.clone();
+                Ok((expr, data_type))
+            }
+            Expr::Bound(ref column) => {
+                let data_type = column.data_type.clone();
+                Ok((expr, data_type))
+            }
+            Expr::Null => Ok((expr, DataType::Null)),
+            Expr::Boolean(_) => Ok((expr, DataType::Boolean)),
+            Expr::Constant(ref value) => {
+                let data_type = value.data_type();
+                Ok((expr, data_type))
+            }
+            Expr::Value(value) => Ok((expr, Value::integer(value).data_type())),
+            Expr::Numeric(ref numeric) => match Value::numeric(numeric) {
+                Some(value) => Ok((expr, value.data_type())),
+                None => Err(TypeError::InvalidLiteral {
+                    literal: numeric.clone(),
+                    data_type: DataType::Float,
+                }),
+            },
+            Expr::Arithmetic(left, op, right) => {
+                let (left, left_type) = self.check(*left)?;
+                let (right, right_type) = self.check(*right)?;
+                if left_type.is_temporal() || right_type.is_temporal() {
+                    let [left_to, right_to, data_type] =
+                        Self::temporal_arithmetic(&left_type, &op, &right_type).ok_or_else(
+                            || TypeError::InvalidArithmetic {
+                                left: left_type.clone(),
+                                op: op.clone(),
+                                right: right_type.clone(),
+                            },
+                        )?;
+                    let left = self.coerce(left, &left_type, &left_to)?;
+                    let right = self.coerce(right, &right_type, &right_to)?;
+                    return Ok((
+                        Expr::Arithmetic(Box::new(left), op, Box::new(right)),
+                        data_type,
+                    ));
+                }
+                let data_type = Self::unify(&left, &left_type, &right, &right_type)
+                    .filter(|data_type| data_type.is_numeric() || *data_type == DataType::Null)
+                    .ok_or_else(|| TypeError::InvalidArithmetic {
+                        left: left_type.clone(),
+                        op: op.clone(),
+                        right: right_type.clone(),
+                    })?;
+                let left = self.coerce(left, &left_type, &data_type)?;
+                let right = self.coerce(right, &right_type, &data_type)?;
+                // Decimal operations can carry or add digits, their result type
+                // follows the scale of the results of `Value::arithmetic`.
+                let data_type = match (data_type, op.as_str()) {
+                    (DataType::Decimal(precision, scale), "+" | "-") => {
+                        DataType::Decimal((precision + 1).min(MAX_PRECISION), scale)
+                    }
+                    (DataType::Decimal(precision, scale), "*") => DataType::Decimal(
+                        (2 * precision).min(MAX_PRECISION),
+                        (2 * scale).min(MAX_PRECISION),
+                    ),
+                    (DataType::Decimal(_, scale), "/") => {
+                        DataType::Decimal(MAX_PRECISION, scale.max(6))
+                    }
+                    (data_type, _) => data_type,
+                };
+                Ok((
+                    Expr::Arithmetic(Box::new(left), op, Box::new(right)),
+                    data_type,
+                ))
+            }
+            Expr::Negate(expr) => {
+                let (expr, data_type) = self.check(*expr)?;
+                if !data_type.is_numeric()
+                    && data_type != DataType::Interval
+                    && data_type != DataType::Null
+                {
+                    return Err(TypeError::InvalidNegation(data_type));
+                }
+                Ok((Expr::Negate(Box::new(expr)), data_type))
+            }
+            Expr::Varchar(_) => Ok((expr, DataType::Varchar)),
+            Expr::Array(elements) => {
+                let elements = elements
+                    .into_iter()
+                    .map(|element| self.check(element))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mut element_type = DataType::Null;
+                for (_, data_type) in &elements {
+                    element_type = element_type.common_type(data_type).ok_or_else(|| {
+                        TypeError::IncompatibleElements(element_type.clone(), data_type.clone())
+                    })?;
+                }
+                let elements = elements
+                    .into_iter()
+                    .map(|(element, data_type)| self.coerce(element, &data_type, &element_type))
+                    .collect::<Result<_, _>>()?;
+                Ok((
+                    Expr::Array(elements),
+                    DataType::Array(Box::new(element_type)),
+                ))
+            }
+            Expr::Cast(expr, to) => {
+                let (expr, from) = self.check(*expr)?;
+                if let Expr::Varchar(literal) = &expr {
+                    Self::cast_literal(literal, &to)?;
+                } else if !from.casts_to(&to) {
+                    return Err(TypeError::InvalidCast { from, to });
+                }
+                Ok((Expr::Cast(Box::new(expr), to.clone()), to))
+            }
+            Expr::Function(name, args) => {
+                let args = args
+                    .into_iter()
+                    .map(|arg| self.check(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let arg_types = args.iter().map(|(_, t)| t.clone()).collect::<Vec<_>>();
+                let invalid_arguments = || TypeError::InvalidArguments {
+                    function: name.clone(),
+                    arg_types: arg_types.clone(),
+                };
+                if let Some(function) = self.functions.scalar(&name) {
+                    if function.arg_types.len() != args.len() {
+                        return Err(invalid_arguments());
+                    }
+                    let args = args
+                        .into_iter()
+                        .zip(&function.arg_types)
+                        .map(|((arg, from), to)| self.coerce(arg, &from, to))
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| invalid_arguments())?;
+                    Ok((Expr::Function(name, args), function.return_type.clone()))
+                } else if self.functions.is_aggregate(&name) {
+                    let function = self
+                        .functions
+                        .aggregate(&name, &arg_types)
+                        .ok_or_else(invalid_arguments)?;
+                    let args = args.into_iter().map(|(arg, _)| arg).collect();
+                    Ok((Expr::Function(name, args), function.return_type.clone()))
+                } else {
+                    Err(TypeError::UnknownFunction(name))
+                }
+            }
+            Expr::Extract(field, expr) => {
+                let (expr, from) = self.check(*expr)?;
+                let data_type = from.extract(field).ok_or(TypeError::InvalidField {
+                    field,
+                    data_type: from,
+                })?;
+                Ok((Expr::Extract(field, Box::new(expr)), data_type))
+            }
+            Expr::Comparison(left, op, right) => {
+                let (left, left_type) = self.check(*left)?;
+                let (right, right_type) = self.check(*right)?;
+                let incompatible = || TypeError::IncompatibleComparison {
+                    left: left_type.clone(),
+                    op: op.clone(),
+                    right: right_type.clone(),
+                };
+                let data_type =
+                    Self::unify(&left, &left_type, &right, &right_type).ok_or_else(incompatible)?;
+                let left = self.coerce(left, &left_type, &data_type)?;
+                let right = self.coerce(right, &right_type, &data_type)?;
+                Ok((
+                    Expr::Comparison(Box::new(left), op, Box::new(right)),
+                    DataType::Boolean,
+                ))
+            }
+            Expr::And(left, right) => Ok((
+                Expr::And(
+                    Box::new(self.check_predicate(*left)?),
+                    Box::new(self.check_predicate(*right)?),
+                ),
+                DataType::Boolean,
+            )),
+            Expr::Or(left, right) => Ok((
+                Expr::Or(
+                    Box::new(self.check_predicate(*left)?),
+                    Box::new(self.check_predicate(*right)?),
+                ),
+                DataType::Boolean,
+            )),
+            Expr::Not(expr) => Ok((
+                Expr::Not(Box::new(self.check_predicate(*expr)?)),
+                DataType::Boolean,
+            )),
+            Expr::IsNull(expr, negated) => Ok((
+                Expr::IsNull(Box::new(self.check(*expr)?.0), negated),
+                DataType::Boolean,
+            )),
+        }
+    }
+
+    /// Returns the index of the column a reference resolves to, which must be
+    /// a single column of the schema.
+    fn column(&self, table: Option<&str>, name: &str, expr: &Expr) -> Result<usize, TypeError> {
+        match self.schema.candidates(table, name)[..] {
+            [index] => Ok(index),
+            [] => match table {
+                Some(table)
+                    if !self
+                        .schema
+                        .fields
+                        .iter()
+                        .any(|field| field.table.as_deref() == Some(table)) =>
+                {
+                    Err(TypeError::UnknownQualifier {
+                        table: table.to_string(),
+                        column: name.to_string(),
+                    })
+                }
+                _ => Err(TypeError::UnknownColumn(expr.to_string())),
+            },
+            ref candidates => Err(TypeError::AmbiguousColumn {
+                column: expr.to_string(),
+                candidates: candidates
+                    .iter()
+                    .map(|&index| {
+                        let field = self.schema.field(index);
+                        match &field.table {
+                            Some(table) => format!("{table}.{}", field.name),
+                            None => field.name.clone(),
+                        }
+                    })
+                    .collect(),
+            }),
+        }
+    }
+
+    /// Type check a predicate, an expression evaluating to a boolean where
+    /// `NULL` is the unknown truth value, returns the predicate with implicit
+    /// coercions made explicit.
+    pub fn check_predicate(&self, expr: Expr) -> Result<Expr, TypeError> {
+        let text = expr.to_string();
+        let (expr, data_type) = self.check(expr)?;
+        self.coerce(expr, &data_type, &DataType::Boolean)
+            .map_err(|_| TypeError::NotAPredicate(text))
+    }
+
+    /// Type check the rows of a `VALUES` list, the values of each column are
+    /// coerced to their least common type. String literals take the type of
+    /// the other values of their column, columns of string literals and
+    /// `NULL` values only are `VARCHAR`. Returns the checked rows along with
+    /// the type of each column.
+    pub fn check_values(
+        &self,
+        rows: Vec<Vec<Expr>>,
+    ) -> Result<(Vec<Vec<Expr>>, Vec<DataType>), TypeError> {
+        let rows = rows
+            .into_iter()
+            .map(|row| row.into_iter().map(|expr| self.check(expr)).collect())
+            .collect::<Result<Vec<Vec<_>>, _>>()?;
+        let width = rows.first().map_or(0, Vec::len);
+        let mut types = vec![DataType::Null; width];
+        for row in &rows {
+            for (column, (expr, data_type)) in row.iter().enumerate() {
+                if let Expr::Varchar(_) = expr {
+                    continue;
+                }
+                types[column] = types[column].common_type(data_type).ok_or_else(|| {
+                    TypeError::IncompatibleValues {
+                        column: column + 1,
+                        left: types[column].clone(),
+                        right: data_type.clone(),
+                    }
+                })?;
+            }
+        }
+        for data_type in &mut types {
+            if *data_type == DataType::Null {
+                *data_type = DataType::Varchar;
+            }
+        }
+        let rows = rows
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .zip(&types)
+                    .map(|((expr, from), to)| self.coerce(expr, &from, to))
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok((rows, types))
+    }
+
+    /// Returns the type both operands of a binary operator are coerced to,
+    /// string literals take the type of the other operand.
+    fn unify(
+        left: &Expr,
+        left_type: &DataType,
+        right: &Expr,
+        right_type: &DataType,
+    ) -> Option<DataType> {
+        match (left, right) {
+            (Expr::Varchar(_), _) if *right_type != DataType::Null => Some(right_type.clone()),
+            (_, Expr::Varchar(_)) if *left_type != DataType::Null => Some(left_type.clone()),
+            _ => left_type.common_type(right_type),
+        }
+    }
+
+    /// Returns the types the operands of an arithmetic operation involving
+    /// temporal values are coerced to, followed by the type of its result,
+    /// see `Value::arithmetic`.
+    ///
+    /// Dates combined with intervals or timestamps are coerced to timestamps,
+    /// a `NULL` operand takes the type of the other operand, or `INTERVAL`
+    /// if the operation is not defined for two values of that type.
+    fn temporal_arithmetic(left: &DataType, op: &str, right: &DataType) -> Option<[DataType; 3]> {
+        use DataType::*;
+        match (left, op, right) {
+            (Null, _, other) => Self::temporal_arithmetic(other, op, other)
+                .or_else(|| Self::temporal_arithmetic(&Interval, op, other)),
+            (other, _, Null) => Self::temporal_arithmetic(other, op, other)
+                .or_else(|| Self::temporal_arithmetic(other, op, &Interval)),
+            (Date, "+" | "-", Integer) => Some([Date, Integer, Date]),
+            (Integer, "+", Date) => Some([Integer, Date, Date]),
+            (Date, "-", Date) => Some([Date, Date, Integer]),
+            (Date | Timestamp, "+" | "-", Interval) => Some([Timestamp, Interval, Timestamp]),
+            (Interval, "+", Date | Timestamp) => Some([Interval, Timestamp, Timestamp]),
+            (Date | Timestamp, "-", Date | Timestamp) => Some([Timestamp, Timestamp, Interval]),
+            (Time, "+" | "-", Interval) => Some([Time, Interval, Time]),
+            (Interval, "+", Time) => Some([Interval, Time, Time]),
+            (Time, "-", Time) => Some([Time, Time, Interval]),
+            (Interval, "+" | "-", Interval) => Some([Interval, Interval, Interval]),
+            (Interval, "*" | "/", Integer | BigInt) => Some([Interval, BigInt, Interval]),
+            (Integer | BigInt, "*", Interval) => Some([BigInt, Interval, Interval]),
+            _ => None,
+        }
+    }
+
+    /// Make the implicit coercion of an expression of type `from` to type
+    /// `to` explicit.
+    fn coerce(&self, expr: Expr, from: &DataType, to: &DataType) -> Result<Expr, TypeError> {
+        if from == to || *from == DataType::Null {
+            return Ok(expr);
+        }
+        if let Expr::Varchar(literal) = &expr {
+            Self::cast_literal(literal, to)?;
+            return Ok(Expr::Cast(Box::new(expr), to.clone()));
+        }
+        if from.coerces_to(to) {
+            Ok(Expr::Cast(Box::new(expr), to.clone()))
+        } else {
+            Err(TypeError::InvalidCast {
+                from: from.clone(),
+                to: to.clone(),
+            })
+        }
+    }
+
+    /// Check that a string literal has a representation in the given type.
+    fn cast_literal(literal: &str, data_type: &DataType) -> Result<Value, TypeError> {
+        Value::from(literal)
+            .cast(data_type)
+            .ok_or_else(|| TypeError::InvalidLiteral {
+                literal: literal.to_string(),
+                data_type: data_type.clone(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::Field;
+    use crate::sql::{Parser, Query, Tokenizer};
+
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("name", DataType::Varchar),
+            Field::new("salary", DataType::Integer),
+            Field::new("active", DataType::Boolean),
+        ])
+    }
+
+    fn predicate(sql: &str) -> Result<String, TypeError> {
+        let expr = Parser::new(Tokenizer::new(sql)).unwrap().expr().unwrap();
+        let (schema, functions) = (schema(), FunctionRegistry::new());
+        let checker = TypeChecker::new(&schema, &functions);
+        checker.check_predicate(expr).map(|expr| expr.to_string())
+    }
+
+    #[test]
+    fn string_literals_are_coerced() {
+        assert_eq!(
+            predicate("salary = '10000'"),
+            Ok("salary = CAST('10000' AS INTEGER)".to_string())
+        );
+        assert_eq!(predicate("'x' = name"), Ok("'x' = name".to_string()));
+        assert_eq!(
+            predicate("salary = 'ten'"),
+            Err(TypeError::InvalidLiteral {
+                literal: "ten".to_string(),
+                data_type: DataType::Integer
+            })
+        );
+    }
+
+    #[test]
+    fn incompatible_comparisons_are_rejected() {
+        assert_eq!(
+            predicate("salary > 9000 AND name < salary"),
+            Err(TypeError::IncompatibleComparison {
+                left: DataType::Varchar,
+                op: "<".to_string(),
+                right: DataType::Integer
+            })
+        );
+        assert_eq!(
+            predicate("name::integer < salary"),
+            Ok("CAST(name AS INTEGER) < salary".to_string())
+        );
+        assert_eq!(
+            predicate("name >= salary"),
+            Err(TypeError::IncompatibleComparison {
+                left: DataType::Varchar,
+                op: ">=".to_string(),
+                right: DataType::Integer
+            })
+        );
+    }
+
+    #[test]
+    fn every_comparison_coerces_its_operands() {
+        assert_eq!(
+            predicate("salary >= 1.5 AND salary <= '2' AND salary <> 3::bigint"),
+            Ok(
+                "CAST(salary AS DECIMAL(11, 1)) >= CAST(1.5 AS DECIMAL(11, 1)) AND salary <= CAST('2' AS INTEGER) \
+                 AND CAST(salary AS BIGINT) <> CAST(3 AS BIGINT)"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn invalid_casts_are_rejected() {
+        assert_eq!(
+            predicate("CAST(ARRAY[salary] AS INTEGER) = 1"),
+            Err(TypeError::InvalidCast {
+                from: DataType::Array(Box::new(DataType::Integer)),
+                to: DataType::Integer
+            })
+        );
+        assert_eq!(
+            predicate("salary = ARRAY[1]"),
+            Err(TypeError::IncompatibleComparison {
+                left: DataType::Integer,
+                op: "=".to_string(),
+                right: DataType::Array(Box::new(DataType::Integer))
+            })
+        );
+        assert_eq!(
+            predicate("salary"),
+            Err(TypeError::NotAPredicate("salary".to_string()))
+        );
+    }
+
+    #[test]
+    fn predicates_are_booleans() {
+        assert_eq!(
+            predicate("active AND NOT salary > 1"),
+            Ok("active AND NOT salary > 1".to_string())
+        );
+        assert_eq!(
+            predicate("active = 'yes' OR 'f'"),
+            Ok("active = CAST('yes' AS BOOLEAN) OR CAST('f' AS BOOLEAN)".to_string())
+        );
+        assert_eq!(
+            predicate("NOT salary"),
+            Err(TypeError::NotAPredicate("salary".to_string()))
+        );
+        assert_eq!(
+            predicate("active = 1"),
+            Err(TypeError::IncompatibleComparison {
+                left: DataType::Boolean,
+                op: "=".to_string(),
+                right: DataType::Integer
+            })
+        );
+
+        let functions = FunctionRegistry::new();
+        let schema = schema();
+        let checker = TypeChecker::new(&schema, &functions);
+        let check =
+            |sql: &str| checker.type_of(&Parser::new(Tokenizer::new(sql)).unwrap().expr().unwrap());
+        assert_eq!(check("salary > 1 OR NULL"), Ok(DataType::Boolean));
+        assert_eq!(check("max(active)"), Ok(DataType::Boolean));
+        assert_eq!(
+            check("active + 1"),
+            Err(TypeError::InvalidArithmetic {
+                left: DataType::Boolean,
+                op: "+".to_string(),
+                right: DataType::Integer
+            })
+        );
+    }
+
+    #[test]
+    fn arithmetic_is_typed() {
+        let functions = FunctionRegistry::new();
+        let schema = schema();
+        let checker = TypeChecker::new(&schema, &functions);
+        let check = |sql: &str| {
+            checker
+                .check(Parser::new(Tokenizer::new(sql)).unwrap().expr().unwrap())
+                .map(|(expr, data_type)| (expr.to_string(), data_type))
+        };
+
+        assert_eq!(
+            check("salary * 2 + 3000000000"),
+            Ok((
+                "CAST(salary * 2 AS BIGINT) + 3000000000".to_string(),
+                DataType::BigInt
+            ))
+        );
+        assert_eq!(
+            check("salary * 1.05"),
+            Ok((
+                "CAST(salary AS DECIMAL(12, 2)) * CAST(1.05 AS DECIMAL(12, 2))".to_string(),
+                DataType::Decimal(24, 4)
+            ))
+        );
+        assert_eq!(
+            check("-(salary / 1e3)"),
+            Ok((
+                "-(CAST(salary AS FLOAT) / 1e3)".to_string(),
+                DataType::Float
+            ))
+        );
+        assert_eq!(
+            check("salary + '10'"),
+            Ok((
+                "salary + CAST('10' AS INTEGER)".to_string(),
+                DataType::Integer
+            ))
+        );
+        assert_eq!(
+            check("name - 1"),
+            Err(TypeError::InvalidArithmetic {
+                left: DataType::Varchar,
+                op: "-".to_string(),
+                right: DataType::Integer
+            })
+        );
+        assert_eq!(
+            check("-name"),
+            Err(TypeError::InvalidNegation(DataType::Varchar))
+        );
+    }
+
+    #[test]
+    fn temporal_arithmetic_is_typed() {
+        let functions = FunctionRegistry::new();
+        let schema = Schema::new(vec![
+            Field::new("hired", DataType::Date),
+            Field::new("at", DataType::Timestamp),
+        ]);
+        let checker = TypeChecker::new(&schema, &functions);
+        let check = |sql: &str| {
+            checker
+                .check(Parser::new(Tokenizer::new(sql)).unwrap().expr().unwrap())
+                .map(|(expr, data_type)| (expr.to_string(), data_type))
+        };
+
+        assert_eq!(
+            check("hired + INTERVAL '1 day'"),
+            Ok((
+                "CAST(hired AS TIMESTAMP) + CAST('1 day' AS INTERVAL)".to_string(),
+                DataType::Timestamp
+            ))
+        );
+        assert_eq!(
+            check("at - hired"),
+            Ok((
+                "at - CAST(hired AS TIMESTAMP)".to_string(),
+                DataType::Interval
+            ))
+        );
+        assert_eq!(
+            check("hired - 7"),
+            Ok(("hired - 7".to_string(), DataType::Date))
+        );
+        assert_eq!(
+            check("-(INTERVAL '1 day' * 2)"),
+            Ok((
+                "-(CAST('1 day' AS INTERVAL) * CAST(2 AS BIGINT))".to_string(),
+                DataType::Interval
+            ))
+        );
+        assert_eq!(
+            check("EXTRACT(SECOND FROM at)"),
+            Ok((
+                "EXTRACT(SECOND FROM at)".to_string(),
+                DataType::Decimal(8, 6)
+            ))
+        );
+        assert_eq!(
+            check("hired + at"),
+            Err(TypeError::InvalidArithmetic {
+                left: DataType::Date,
+                op: "+".to_string(),
+                right: DataType::Timestamp
+            })
+        );
+        assert_eq!(
+            check("EXTRACT(HOUR FROM 1)"),
+            Err(TypeError::InvalidField {
+                field: DateTimeField::Hour,
+                data_type: DataType::Integer
+            })
+        );
+        assert_eq!(
+            check("DATE '2023-02-29'"),
+            Err(TypeError::InvalidLiteral {
+                literal: "2023-02-29".to_string(),
+                data_type: DataType::Date
+            })
+        );
+    }
+
+    #[test]
+    fn values_are_typed_across_rows() {
+        let functions = FunctionRegistry::new();
+        let schema = Schema::default();
+        let checker = TypeChecker::new(&schema, &functions);
+        let check = |sql: &str| {
+            let Query::Values(rows) = Parser::new(Tokenizer::new(sql)).unwrap().parse().unwrap()
+            else {
+                unreachable!()
+            };
+            checker.check_values(rows).map(|(rows, types)| {
+                let rows = rows
+                    .iter()
+                    .map(|row| row.iter().map(|expr| expr.to_string()).collect::<Vec<_>>())
+                    .collect::<Vec<_>>();
+                (rows, types)
+            })
+        };
+
+        assert_eq!(
+            check("VALUES (1, NULL, 'a'), ('2', NULL, 'b'), (NULL, NULL, NULL)"),
+            Ok((
+                vec![
+                    vec!["1".to_string(), "NULL".to_string(), "'a'".to_string()],
+                    vec![
+                        "CAST('2' AS INTEGER)".to_string(),
+                        "NULL".to_string(),
+                        "'b'".to_string()
+                    ],
+                    vec!["NULL".to_string(), "NULL".to_string(), "NULL".to_string()],
+                ],
+                vec![DataType::Integer, DataType::Varchar, DataType::Varchar]
+            ))
+        );
+        assert_eq!(
+            check("VALUES (1), (1.5)").map(|(_, types)| types),
+            Ok(vec![DataType::Decimal(11, 1)])
+        );
+        assert_eq!(
+            check("VALUES (1, 1), (2, TRUE)"),
+            Err(TypeError::IncompatibleValues {
+                column: 2,
+                left: DataType::Integer,
+                right: DataType::Boolean
+            })
+        );
+    }
+
+    #[test]
+    fn function_arguments_are_checked() {
+        let functions = FunctionRegistry::new();
+        let schema = schema();
+        let checker = TypeChecker::new(&schema, &functions);
+        let check =
+            |sql: &str| checker.type_of(&Parser::new(Tokenizer::new(sql)).unwrap().expr().unwrap());
+
+        assert_eq!(check("max(name)"), Ok(DataType::Varchar));
+        assert_eq!(
+            check("ARRAY[1, '2']"),
+            Err(TypeError::IncompatibleElements(
+                DataType::Integer,
+                DataType::Varchar
+            ))
+        );
+        assert_eq!(
+            check("sum(name)"),
+            Err(TypeError::InvalidArguments {
+                function: "sum".to_string(),
+                arg_types: vec![DataType::Varchar]
+            })
+        );
+        assert_eq!(
+            check("missing(1)"),
+            Err(TypeError::UnknownFunction("missing".to_string()))
+        );
+    }
+}
@@ -0,0 +1,1049 @@
+//! Implementation of the data types and typed values manipulated by expressions.
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::decimal::{Decimal, MAX_PRECISION};
+use crate::temporal::{Date, DateTimeField, Interval, Time, Timestamp};
+
+/// Data types supported by the engine.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DataType {
+    /// Type of the `NULL` literal, a `NULL` is a valid value of every type.
+    Null,
+    /// Truth values `TRUE` and `FALSE`, `NULL` is the unknown truth value.
+    Boolean,
+    /// 32-bit signed integers.
+    Integer,
+    /// 64-bit signed integers.
+    BigInt,
+    /// Exact numbers of `precision` digits, `scale` of which follow the
+    /// decimal point, see `Decimal`.
+    Decimal(u8, u8),
+    /// Double precision floating point numbers.
+    Float,
+    Date,
+    /// Time of the day without time zone.
+    Time,
+    /// Date and time of the day without time zone.
+    Timestamp,
+    /// Span of time made of months, days and microseconds.
+    Interval,
+    Varchar,
+    /// Arrays of values of the given element type.
+    Array(Box<DataType>),
+}
+
+impl DataType {
+    /// Returns true if values of this type are implicitly converted to values
+    /// of the given type where the latter is expected.
+    ///
+    /// Implicit coercions follow a lattice where a type coerces to the types
+    /// above it, `NULL` is at the bottom and coerces to every type:
+    ///
+    /// ```text
+    ///        FLOAT
+    ///          |
+    ///    DECIMAL(p, s)
+    ///          |
+    ///       BIGINT                                 TIMESTAMP
+    ///          |                                       |
+    ///       INTEGER   BOOLEAN   VARCHAR   T[]   TIME   DATE   INTERVAL
+    /// ```
+    ///
+    /// Integers coerce to decimals with enough digits before the decimal
+    /// point, and decimals to decimals with at least as many digits on both
+    /// sides of it. Arrays coerce element-wise, `NULL[]` coerces to every
+    /// array type. Conversions between unrelated types, say `INTEGER` and
+    /// `VARCHAR`, require an explicit `CAST`.
+    pub fn coerces_to(&self, to: &DataType) -> bool {
+        match (self, to) {
+            (DataType::Null, _) => true,
+            (DataType::Integer, DataType::BigInt | DataType::Float)
+            | (DataType::BigInt | DataType::Decimal(_, _), DataType::Float)
+            | (DataType::Date, DataType::Timestamp) => true,
+            (
+                DataType::Integer | DataType::BigInt | DataType::Decimal(_, _),
+                DataType::Decimal(_, _),
+            ) => {
+                let ((from_digits, from_scale), (to_digits, to_scale)) =
+                    (self.exact_digits().unwrap(), to.exact_digits().unwrap());
+                from_digits <= to_digits && from_scale <= to_scale
+            }
+            (DataType::Array(from), DataType::Array(to)) => from.coerces_to(to),
+            (from, to) => from == to,
+        }
+    }
+
+    /// Returns the least type both types coerce to, if any.
+    ///
+    /// The least type of exact numbers with more than `MAX_PRECISION` digits
+    /// is `FLOAT`.
+    pub fn common_type(&self, other: &DataType) -> Option<DataType> {
+        if other.coerces_to(self) {
+            Some(self.clone())
+        } else if self.coerces_to(other) {
+            Some(other.clone())
+        } else if let (Some(left), Some(right)) = (self.exact_digits(), other.exact_digits()) {
+            let (digits, scale) = (left.0.max(right.0), left.1.max(right.1));
+            if digits + scale <= MAX_PRECISION {
+                Some(DataType::Decimal(digits + scale, scale))
+            } else {
+                Some(DataType::Float)
+            }
+        } else if let (DataType::Array(left), DataType::Array(right)) = (self, other) {
+            Some(DataType::Array(Box::new(left.common_type(right)?)))
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if values of this type can be explicitly cast to values
+    /// of the given type, every type can be cast to `VARCHAR`, numbers,
+    /// booleans and temporal values can be cast from `VARCHAR`, numbers to
+    /// other numeric types, booleans to and from `INTEGER` and timestamps to
+    /// their date or time.
+    pub fn casts_to(&self, to: &DataType) -> bool {
+        match (self, to) {
+            (from, to) if from.coerces_to(to) => true,
+            (_, DataType::Varchar) => true,
+            (DataType::Varchar | DataType::Integer, DataType::Boolean) => true,
+            (DataType::Boolean, DataType::Integer) => true,
+            (DataType::Varchar, to) if to.is_temporal() => true,
+            (DataType::Timestamp, DataType::Date | DataType::Time) => true,
+            (from, to) if to.is_numeric() => from.is_numeric() || *from == DataType::Varchar,
+            (DataType::Array(from), DataType::Array(to)) => from.casts_to(to),
+            _ => false,
+        }
+    }
+
+    /// Returns true if values of this type can be stored in a column of the
+    /// given type, which besides coercions allows conversions between
+    /// numeric types, to `VARCHAR` and from timestamps to their date or time.
+    pub fn assigns_to(&self, to: &DataType) -> bool {
+        match (self, to) {
+            (from, to) if from.coerces_to(to) => true,
+            (_, DataType::Varchar) => true,
+            (DataType::Timestamp, DataType::Date | DataType::Time) => true,
+            (from, to) if to.is_numeric() => from.is_numeric(),
+            (DataType::Array(from), DataType::Array(to)) => from.assigns_to(to),
+            _ => false,
+        }
+    }
+
+    /// Returns true for the numeric types.
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            DataType::Integer | DataType::BigInt | DataType::Decimal(_, _) | DataType::Float
+        )
+    }
+
+    /// Returns true for the date and time types.
+    pub fn is_temporal(&self) -> bool {
+        matches!(
+            self,
+            DataType::Date | DataType::Time | DataType::Timestamp | DataType::Interval
+        )
+    }
+
+    /// Returns the type of the values of a field extracted from values of
+    /// this type, if the field is defined for it.
+    ///
+    /// Seconds and epochs are decimals with microseconds, other fields are
+    /// integers. Dates are read as timestamps at midnight, times only have
+    /// time fields and intervals no fields relative to a calendar.
+    pub fn extract(&self, field: DateTimeField) -> Option<DataType> {
+        let defined = match self {
+            DataType::Null | DataType::Date | DataType::Timestamp => true,
+            DataType::Time => matches!(
+                field,
+                DateTimeField::Hour
+                    | DateTimeField::Minute
+                    | DateTimeField::Second
+                    | DateTimeField::Epoch
+            ),
+            DataType::Interval => !matches!(
+                field,
+                DateTimeField::Week | DateTimeField::DayOfWeek | DateTimeField::DayOfYear
+            ),
+            _ => false,
+        };
+        defined.then_some(match field {
+            DateTimeField::Second => DataType::Decimal(8, 6),
+            DateTimeField::Epoch => DataType::Decimal(MAX_PRECISION, 6),
+            _ => DataType::BigInt,
+        })
+    }
+
+    /// Returns the number of digits before and after the decimal point of
+    /// exact numeric types.
+    fn exact_digits(&self) -> Option<(u8, u8)> {
+        match self {
+            DataType::Integer => Some((10, 0)),
+            DataType::BigInt => Some((19, 0)),
+            DataType::Decimal(precision, scale) => Some((precision - scale, *scale)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataType::Null => write!(f, "NULL"),
+            DataType::Boolean => write!(f, "BOOLEAN"),
+            DataType::Integer => write!(f, "INTEGER"),
+            DataType::BigInt => write!(f, "BIGINT"),
+            DataType::Decimal(precision, scale) => write!(f, "DECIMAL({precision}, {scale})"),
+            DataType::Float => write!(f, "FLOAT"),
+            DataType::Date => write!(f, "DATE"),
+            DataType::Time => write!(f, "TIME"),
+            DataType::Timestamp => write!(f, "TIMESTAMP"),
+            DataType::Interval => write!(f, "INTERVAL"),
+            DataType::Varchar => write!(f, "VARCHAR"),
+            DataType::Array(element) => write!(f, "{element}[]"),
+        }
+    }
+}
+
+/// Errors raised by arithmetic on values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArithmeticError {
+    /// The result does not fit in the type of the operands.
+    Overflow(DataType),
+    DivisionByZero,
+}
+
+impl fmt::Display for ArithmeticError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArithmeticError::Overflow(DataType::Decimal(_, _)) => write!(f, "DECIMAL out of range"),
+            ArithmeticError::Overflow(data_type) => write!(f, "{data_type} out of range"),
+            ArithmeticError::DivisionByZero => write!(f, "Division by zero"),
+        }
+    }
+}
+
+impl Error for ArithmeticError {}
+
+/// Typed value produced by evaluating an expression.
+///
+/// Values of the same type compare by value, floats are totally ordered with
+/// `NaN` above every other float. Values of different types compare by type.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Null,
+    Boolean(bool),
+    Integer(i32),
+    BigInt(i64),
+    Decimal(Decimal),
+    Float(f64),
+    Date(Date),
+    Time(Time),
+    Timestamp(Timestamp),
+    Interval(Interval),
+    Varchar(String),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    /// Returns the value of the narrowest integer type holding the given
+    /// integer, the type of integer literals.
+    pub fn integer(value: i64) -> Self {
+        match i32::try_from(value) {
+            Ok(value) => Value::Integer(value),
+            Err(_) => Value::BigInt(value),
+        }
+    }
+
+    /// Parse a numeric literal, literals in scientific notation or with more
+    /// than `MAX_PRECISION` digits are floats, other literals are integers if
+    /// they fit in a `BIGINT` and decimals otherwise.
+    pub fn numeric(text: &str) -> Option<Self> {
+        if let Ok(value) = text.parse() {
+            return Some(Value::integer(value));
+        }
+        match Decimal::parse(text) {
+            Some(value) if !text.contains(['e', 'E']) => Some(Value::Decimal(value)),
+            _ => text.parse().ok().map(Value::Float),
+        }
+    }
+
+    /// Returns the data type of the value.
+    pub fn data_type(&self) -> DataType {
+        match self {
+            Value::Null => DataType::Null,
+            Value::Boolean(_) => DataType::Boolean,
+            Value::Integer(_) => DataType::Integer,
+            Value::BigInt(_) => DataType::BigInt,
+            Value::Decimal(value) => DataType::Decimal(value.precision(), value.scale()),
+            Value::Float(_) => DataType::Float,
+            Value::Date(_) => DataType::Date,
+            Value::Time(_) => DataType::Time,
+            Value::Timestamp(_) => DataType::Timestamp,
+            Value::Interval(_) => DataType::Interval,
+            Value::Varchar(_) => DataType::Varchar,
+            Value::Array(values) => DataType::Array(Box::new(
+                values
+                    .iter()
+                    .map(Value::data_type)
+                    .find(|data_type| *data_type != DataType::Null)
+                    .unwrap_or(DataType::Null),
+            )),
+        }
+    }
+
+    /// Returns true if the value is `NULL`.
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// Returns true if the value is a value of the given type, `NULL` is a
+    /// value of every type.
+    pub fn has_type(&self, data_type: &DataType) -> bool {
+        match (self, data_type) {
+            (Value::Null, _) => true,
+            (Value::Decimal(value), DataType::Decimal(precision, scale)) => {
+                value.scale() == *scale && value.precision() <= *precision
+            }
+            (Value::Array(values), DataType::Array(element)) => {
+                values.iter().all(|value| value.has_type(element))
+            }
+            (value, data_type) => value.data_type() == *data_type,
+        }
+    }
+
+    /// Decode a value of the given type from its textual representation,
+    /// returns `None` if the text does not spell a value of that type.
+    /// Arrays have no textual representation to decode from.
+    pub fn decode(text: &str, data_type: DataType) -> Option<Self> {
+        match data_type {
+            DataType::Null => Some(Value::Null),
+            DataType::Varchar => Some(Value::Varchar(text.to_string())),
+            DataType::Array(_) => None,
+            data_type => Value::from(text).cast(&data_type),
+        }
+    }
+
+    /// Convert the value to the given type, returns `None` if the value has
+    /// no representation in that type, `NULL` casts to `NULL`.
+    ///
+    /// Conversions to integers and decimals round half away from zero.
+    /// Strings convert to booleans if they spell one of `true`, `t`, `yes`,
+    /// `y`, `on`, `1` or `false`, `f`, `no`, `n`, `off`, `0`, and integers
+    /// convert to `FALSE` when they are zero and `TRUE` otherwise.
+    pub fn cast(&self, data_type: &DataType) -> Option<Value> {
+        match (self, data_type) {
+            (Value::Null, _) => Some(Value::Null),
+            (Value::Varchar(_), DataType::Varchar) => Some(self.clone()),
+            (value, DataType::Varchar) => Some(Value::Varchar(value.to_string())),
+            (Value::Varchar(text), DataType::Float) => text.trim().parse().ok().map(Value::Float),
+            (Value::Varchar(text), DataType::Integer | DataType::BigInt) => {
+                Value::integer(text.trim().parse().ok()?).cast(data_type)
+            }
+            (Value::Varchar(text), DataType::Decimal(_, _)) => {
+                Value::Decimal(Decimal::parse(text.trim())?).cast(data_type)
+            }
+            (Value::Varchar(text), DataType::Boolean) => {
+                match text.trim().to_lowercase().as_str() {
+                    "true" | "t" | "yes" | "y" | "on" | "1" => Some(Value::Boolean(true)),
+                    "false" | "f" | "no" | "n" | "off" | "0" => Some(Value::Boolean(false)),
+                    _ => None,
+                }
+            }
+            (Value::Boolean(value), DataType::Boolean) => Some(Value::Boolean(*value)),
+            (Value::Boolean(value), DataType::Integer) => Some(Value::Integer(*value as i32)),
+            (Value::Integer(value), DataType::Boolean) => Some(Value::Boolean(*value != 0)),
+            (Value::Integer(value), _) => Value::BigInt(*value as i64).cast(data_type),
+            (Value::BigInt(value), DataType::Integer) => {
+                i32::try_from(*value).ok().map(Value::Integer)
+            }
+            (Value::BigInt(value), DataType::BigInt) => Some(Value::BigInt(*value)),
+            (Value::BigInt(value), DataType::Float) => Some(Value::Float(*value as f64)),
+            (Value::BigInt(value), DataType::Decimal(_, _)) => {
+                Value::Decimal(Decimal::new(*value as i128, 0)?).cast(data_type)
+            }
+            (Value::Decimal(value), DataType::Integer | DataType::BigInt) => {
+                Value::integer(i64::try_from(value.round()).ok()?).cast(data_type)
+            }
+            (Value::Decimal(value), DataType::Decimal(precision, scale)) => {
+                let value = value.rescale(*scale)?;
+                (value.precision() <= *precision).then_some(Value::Decimal(value))
+            }
+            (Value::Decimal(value), DataType::Float) => Some(Value::Float(value.to_f64())),
+            (Value::Float(value), DataType::Integer | DataType::BigInt) => {
+                let value = value.round();
+                // Floats at or above 2^63 do not fit in a BIGINT.
+                if value.is_finite() && value >= i64::MIN as f64 && value < i64::MAX as f64 {
+                    Value::BigInt(value as i64).cast(data_type)
+                } else {
+                    None
+                }
+            }
+            (Value::Float(value), DataType::Decimal(_, scale)) => {
+                Value::Decimal(Decimal::from_f64(*value, *scale)?).cast(data_type)
+            }
+            (Value::Float(value), DataType::Float) => Some(Value::Float(*value)),
+            (Value::Varchar(text), DataType::Date) => Date::parse(text.trim()).map(Value::Date),
+            (Value::Varchar(text), DataType::Time) => Time::parse(text.trim()).map(Value::Time),
+            (Value::Varchar(text), DataType::Timestamp) => {
+                Timestamp::parse(text.trim()).map(Value::Timestamp)
+            }
+            (Value::Varchar(text), DataType::Interval) => {
+                Interval::parse(text.trim()).map(Value::Interval)
+            }
+            (Value::Date(value), DataType::Date) => Some(Value::Date(*value)),
+            (Value::Date(value), DataType::Timestamp) => {
+                Some(Value::Timestamp(Timestamp::from(*value)))
+            }
+            (Value::Time(value), DataType::Time) => Some(Value::Time(*value)),
+            (Value::Timestamp(value), DataType::Date) => Some(Value::Date(value.date())),
+            (Value::Timestamp(value), DataType::Time) => Some(Value::Time(value.time())),
+            (Value::Timestamp(value), DataType::Timestamp) => Some(Value::Timestamp(*value)),
+            (Value::Interval(value), DataType::Interval) => Some(Value::Interval(*value)),
+            (Value::Array(values), DataType::Array(element)) => values
+                .iter()
+                .map(|value| value.cast(element))
+                .collect::<Option<_>>()
+                .map(Value::Array),
+            _ => None,
+        }
+    }
+
+    /// Apply the arithmetic operator `+`, `-`, `*` or `/` to values of the
+    /// same numeric type, or to temporal values and the values they combine
+    /// with, the result is `NULL` if either value is `NULL`.
+    ///
+    /// Integer division truncates toward zero, decimal division rounds the
+    /// quotient to at least 6 digits after the decimal point.
+    ///
+    /// Dates are shifted by integers, timestamps and times by intervals and
+    /// subtracting them yields the days or the interval between them.
+    /// Intervals are added together and scaled by `BIGINT`s.
+    pub fn arithmetic(&self, op: &str, other: &Value) -> Result<Value, ArithmeticError> {
+        let overflow = || ArithmeticError::Overflow(self.data_type());
+        let is_zero = match other {
+            Value::Integer(value) => *value == 0,
+            Value::BigInt(value) => *value == 0,
+            Value::Decimal(value) => value.is_zero(),
+            Value::Float(value) => *value == 0.0,
+            _ => false,
+        };
+        match (self, other) {
+            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+            _ if op == "/" && is_zero => Err(ArithmeticError::DivisionByZero),
+            (Value::Integer(left), Value::Integer(right)) => match op {
+                "+" => left.checked_add(*right),
+                "-" => left.checked_sub(*right),
+                "*" => left.checked_mul(*right),
+                "/" => left.checked_div(*right),
+                _ => panic!("Unknown arithmetic operator {op}"),
+            }
+            .map(Value::Integer)
+            .ok_or_else(overflow),
+            (Value::BigInt(left), Value::BigInt(right)) => match op {
+                "+" => left.checked_add(*right),
+                "-" => left.checked_sub(*right),
+                "*" => left.checked_mul(*right),
+                "/" => left.checked_div(*right),
+                _ => panic!("Unknown arithmetic operator {op}"),
+            }
+            .map(Value::BigInt)
+            .ok_or_else(overflow),
+            (Value::Decimal(left), Value::Decimal(right)) => match op {
+                "+" => left.checked_add(right),
+                "-" => left.checked_sub(right),
+                "*" => left.checked_mul(right),
+                "/" => left.checked_div(right, left.scale().max(right.scale()).max(6)),
+                _ => panic!("Unknown arithmetic operator {op}"),
+            }
+            .map(Value::Decimal)
+            .ok_or_else(overflow),
+            (Value::Float(left), Value::Float(right)) => {
+                let result = match op {
+                    "+" => left + right,
+                    "-" => left - right,
+                    "*" => left * right,
+                    "/" => left / right,
+                    _ => panic!("Unknown arithmetic operator {op}"),
+                };
+                if result.is_infinite() && left.is_finite() && right.is_finite() {
+                    Err(overflow())
+                } else {
+                    Ok(Value::Float(result))
+                }
+            }
+            (Value::Date(date), Value::Integer(days)) if op == "+" || op == "-" => {
+                let days = if op == "+" {
+                    *days as i64
+                } else {
+                    -(*days as i64)
+                };
+                date.checked_add_days(days)
+                    .map(Value::Date)
+                    .ok_or(ArithmeticError::Overflow(DataType::Date))
+            }
+            (Value::Integer(_), Value::Date(_)) if op == "+" => other.arithmetic(op, self),
+            (Value::Date(left), Value::Date(right)) if op == "-" => {
+                Ok(Value::Integer(left.days() - right.days()))
+            }
+            (Value::Timestamp(timestamp), Value::Interval(interval)) if op == "+" || op == "-" => {
+                let interval = if op == "+" {
+                    Some(*interval)
+                } else {
+                    interval.checked_neg()
+                };
+                interval
+                    .and_then(|interval| timestamp.checked_add(&interval))
+                    .map(Value::Timestamp)
+                    .ok_or(ArithmeticError::Overflow(DataType::Timestamp))
+            }
+            (Value::Timestamp(left), Value::Timestamp(right)) if op == "-" => left
+                .checked_sub(right)
+                .map(Value::Interval)
+                .ok_or(ArithmeticError::Overflow(DataType::Interval)),
+            (Value::Time(time), Value::Interval(interval)) if op == "+" || op == "-" => {
+                let interval = if op == "+" {
+                    Some(*interval)
+                } else {
+                    interval.checked_neg()
+                };
+                interval
+                    .map(|interval| Value::Time(time.add(&interval)))
+                    .ok_or(ArithmeticError::Overflow(DataType::Time))
+            }
+            (Value::Time(left), Value::Time(right)) if op == "-" => {
+                Ok(Value::Interval(left.sub(right)))
+            }
+            (Value::Interval(_), Value::Timestamp(_) | Value::Time(_)) if op == "+" => {
+                other.arithmetic(op, self)
+            }
+            (Value::Interval(left), Value::Interval(right)) if op == "+" || op == "-" => {
+                if op == "+" {
+                    left.checked_add(right)
+                } else {
+                    left.checked_sub(right)
+                }
+                .map(Value::Interval)
+                .ok_or_else(overflow)
+            }
+            (Value::Interval(interval), Value::BigInt(factor)) if op == "*" || op == "/" => {
+                if op == "*" {
+                    interval.checked_mul(*factor)
+                } else {
+                    interval.checked_div(*factor)
+                }
+                .map(Value::Interval)
+                .ok_or_else(overflow)
+            }
+            (Value::BigInt(_), Value::Interval(_)) if op == "*" => other.arithmetic(op, self),
+            (left, right) => panic!("Cannot apply {op} to {left} and {right}"),
+        }
+    }
+
+    /// Extract a field of a temporal value, see `DataType::extract`.
+    pub fn extract(&self, field: DateTimeField) -> Option<Value> {
+        let value = match self {
+            Value::Null => return Some(Value::Null),
+            Value::Date(date) => Timestamp::from(*date).extract(field),
+            Value::Time(time) => time.extract(field),
+            Value::Timestamp(timestamp) => timestamp.extract(field),
+            Value::Interval(interval) => interval.extract(field),
+            _ => None,
+        }?;
+        match self.data_type().extract(field)? {
+            DataType::BigInt => Some(Value::BigInt(value.mantissa() as i64)),
+            _ => Some(Value::Decimal(value)),
+        }
+    }
+
+    /// Apply the comparison operator `=`, `<>`, `<`, `<=`, `>` or `>=` to
+    /// values of the same type, returns `None` for any other operator.
+    pub fn compare(&self, op: &str, other: &Value) -> Option<bool> {
+        let ordering = self.cmp(other);
+        Some(match op {
+            "=" => ordering.is_eq(),
+            "<>" => ordering.is_ne(),
+            "<" => ordering.is_lt(),
+            "<=" => ordering.is_le(),
+            ">" => ordering.is_gt(),
+            ">=" => ordering.is_ge(),
+            _ => return None,
+        })
+    }
+
+    /// Negate a numeric value.
+    pub fn negate(&self) -> Result<Value, ArithmeticError> {
+        let overflow = || ArithmeticError::Overflow(self.data_type());
+        match self {
+            Value::Null => Ok(Value::Null),
+            Value::Integer(value) => value.checked_neg().map(Value::Integer).ok_or_else(overflow),
+            Value::BigInt(value) => value.checked_neg().map(Value::BigInt).ok_or_else(overflow),
+            Value::Decimal(value) => value.checked_neg().map(Value::Decimal).ok_or_else(overflow),
+            Value::Float(value) => Ok(Value::Float(-value)),
+            Value::Interval(value) => value
+                .checked_neg()
+                .map(Value::Interval)
+                .ok_or_else(overflow),
+            value => panic!("Cannot negate {value}"),
+        }
+    }
+
+    /// Rank of the type of the value when ordering values of different types.
+    fn rank(&self) -> u8 {
+        match self {
+            Value::Null => 0,
+            Value::Boolean(_) => 1,
+            Value::Integer(_) => 2,
+            Value::BigInt(_) => 3,
+            Value::Decimal(_) => 4,
+            Value::Float(_) => 5,
+            Value::Date(_) => 6,
+            Value::Time(_) => 7,
+            Value::Timestamp(_) => 8,
+            Value::Interval(_) => 9,
+            Value::Varchar(_) => 10,
+            Value::Array(_) => 11,
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Value {}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Value::Boolean(left), Value::Boolean(right)) => left.cmp(right),
+            (Value::Integer(left), Value::Integer(right)) => left.cmp(right),
+            (Value::BigInt(left), Value::BigInt(right)) => left.cmp(right),
+            (Value::Decimal(left), Value::Decimal(right)) => left.cmp(right),
+            // Zeros compare equal regardless of their sign.
+            (Value::Float(left), Value::Float(right)) if left == right => Ordering::Equal,
+            (Value::Float(left), Value::Float(right)) => left.total_cmp(right),
+            (Value::Date(left), Value::Date(right)) => left.cmp(right),
+            (Value::Time(left), Value::Time(right)) => left.cmp(right),
+            (Value::Timestamp(left), Value::Timestamp(right)) => left.cmp(right),
+            (Value::Interval(left), Value::Interval(right)) => left.cmp(right),
+            (Value::Varchar(left), Value::Varchar(right)) => left.cmp(right),
+            (Value::Array(left), Value::Array(right)) => left.cmp(right),
+            (left, right) => left.rank().cmp(&right.rank()),
+        }
+    }
+}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            Value::Null => {}
+            Value::Boolean(value) => value.hash(state),
+            Value::Integer(value) => value.hash(state),
+            Value::BigInt(value) => value.hash(state),
+            Value::Decimal(value) => value.hash(state),
+            Value::Float(value) if *value == 0.0 => 0.0f64.to_bits().hash(state),
+            Value::Float(value) => value.to_bits().hash(state),
+            Value::Date(value) => value.hash(state),
+            Value::Time(value) => value.hash(state),
+            Value::Timestamp(value) => value.hash(state),
+            Value::Interval(value) => value.hash(state),
+            Value::Varchar(value) => value.hash(state),
+            Value::Array(values) => values.hash(state),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "NULL"),
+            Value::Boolean(value) => write!(f, "{value}"),
+            Value::Integer(value) => write!(f, "{value}"),
+            Value::BigInt(value) => write!(f, "{value}"),
+            Value::Decimal(value) => write!(f, "{value}"),
+            Value::Float(value) => write!(f, "{value}"),
+            Value::Date(value) => write!(f, "{value}"),
+            Value::Time(value) => write!(f, "{value}"),
+            Value::Timestamp(value) => write!(f, "{value}"),
+            Value::Interval(value) => write!(f, "{value}"),
+            Value::Varchar(value) => write!(f, "{value}"),
+            Value::Array(values) => {
+                let values = values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+                write!(f, "[{}]", values.join(", "))
+            }
+        }
+    }
+}
+
+impl PartialEq<str> for Value {
+    fn eq(&self, other: &str) -> bool {
+        matches!(self, Value::Varchar(value) if value == other)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Boolean(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Value::Integer(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::BigInt(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<Decimal> for Value {
+    fn from(value: Decimal) -> Self {
+        Value::Decimal(value)
+    }
+}
+
+impl From<Date> for Value {
+    fn from(value: Date) -> Self {
+        Value::Date(value)
+    }
+}
+
+impl From<Time> for Value {
+    fn from(value: Time) -> Self {
+        Value::Time(value)
+    }
+}
+
+impl From<Timestamp> for Value {
+    fn from(value: Timestamp) -> Self {
+        Value::Timestamp(value)
+    }
+}
+
+impl From<Interval> for Value {
+    fn from(value: Interval) -> Self {
+        Value::Interval(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Varchar(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Varchar(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_decode_values() {
+        assert_eq!(
+            Value::decode("12000", DataType::Integer).unwrap(),
+            Value::Integer(12000)
+        );
+        assert_eq!(
+            Value::decode("Alice", DataType::Varchar).unwrap(),
+            Value::Varchar("Alice".to_string())
+        );
+        assert_eq!(Value::decode("twelve", DataType::Integer), None);
+        assert_eq!(
+            Value::decode("{1,2}", DataType::Array(Box::new(DataType::Integer))),
+            None
+        );
+    }
+
+    #[test]
+    fn values_roundtrip_through_text() {
+        let values = vec![Value::Integer(-42), Value::from("Developer")];
+        for value in values {
+            let text = value.to_string();
+            assert_eq!(Value::decode(&text, value.data_type()).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn can_cast_values() {
+        assert_eq!(
+            Value::from(" 42 ").cast(&DataType::Integer),
+            Some(Value::Integer(42))
+        );
+        assert_eq!(Value::from("4x").cast(&DataType::Integer), None);
+        assert_eq!(
+            Value::Array(vec![Value::Integer(1), Value::Null]).cast(&DataType::Varchar),
+            Some(Value::from("[1, NULL]"))
+        );
+        assert_eq!(
+            Value::Array(vec![Value::from("1")])
+                .cast(&DataType::Array(Box::new(DataType::Integer))),
+            Some(Value::Array(vec![Value::Integer(1)]))
+        );
+    }
+
+    #[test]
+    fn coercions_follow_the_lattice() {
+        let integers = DataType::Array(Box::new(DataType::Integer));
+        let nulls = DataType::Array(Box::new(DataType::Null));
+        assert!(DataType::Null.coerces_to(&DataType::Varchar));
+        assert!(!DataType::Integer.coerces_to(&DataType::Varchar));
+        assert!(DataType::Integer.casts_to(&DataType::Varchar));
+        assert!(!integers.casts_to(&DataType::Integer));
+        assert_eq!(
+            DataType::Null.common_type(&DataType::Integer),
+            Some(DataType::Integer)
+        );
+        assert_eq!(nulls.common_type(&integers), Some(integers.clone()));
+        assert_eq!(DataType::Varchar.common_type(&DataType::Integer), None);
+    }
+
+    #[test]
+    fn can_cast_numbers() {
+        let decimal = |text: &str| Value::Decimal(Decimal::parse(text).unwrap());
+        assert_eq!(
+            Value::from("12.345").cast(&DataType::Decimal(4, 2)),
+            Some(decimal("12.35"))
+        );
+        assert_eq!(Value::from("123.4").cast(&DataType::Decimal(4, 2)), None);
+        assert_eq!(
+            decimal("-2.5").cast(&DataType::Integer),
+            Some(Value::Integer(-3))
+        );
+        assert_eq!(
+            Value::Float(2.5).cast(&DataType::BigInt),
+            Some(Value::BigInt(3))
+        );
+        assert_eq!(Value::Float(f64::NAN).cast(&DataType::BigInt), None);
+        assert_eq!(Value::BigInt(1 << 40).cast(&DataType::Integer), None);
+        assert_eq!(
+            Value::Integer(7).cast(&DataType::Decimal(3, 1)),
+            Some(decimal("7.0"))
+        );
+        assert_eq!(
+            Value::Float(0.1).cast(&DataType::Decimal(10, 3)),
+            Some(decimal("0.100"))
+        );
+        assert_eq!(
+            decimal("0.125").cast(&DataType::Float),
+            Some(Value::Float(0.125))
+        );
+        assert_eq!(
+            Value::from("1e3").cast(&DataType::Float),
+            Some(Value::Float(1000.0))
+        );
+    }
+
+    #[test]
+    fn numbers_follow_the_lattice() {
+        assert!(DataType::Integer.coerces_to(&DataType::BigInt));
+        assert!(DataType::BigInt.coerces_to(&DataType::Decimal(21, 2)));
+        assert!(!DataType::BigInt.coerces_to(&DataType::Decimal(20, 2)));
+        assert!(!DataType::Float.coerces_to(&DataType::Decimal(38, 2)));
+        assert!(DataType::Float.casts_to(&DataType::Decimal(38, 2)));
+        assert_eq!(
+            DataType::Integer.common_type(&DataType::Decimal(5, 2)),
+            Some(DataType::Decimal(12, 2))
+        );
+        assert_eq!(
+            DataType::Decimal(38, 0).common_type(&DataType::Decimal(3, 2)),
+            Some(DataType::Float)
+        );
+        assert_eq!(
+            DataType::BigInt.common_type(&DataType::Float),
+            Some(DataType::Float)
+        );
+    }
+
+    #[test]
+    fn arithmetic_detects_errors() {
+        assert_eq!(
+            Value::Integer(i32::MAX).arithmetic("+", &Value::Integer(1)),
+            Err(ArithmeticError::Overflow(DataType::Integer))
+        );
+        assert_eq!(
+            Value::BigInt(i64::MIN).arithmetic("/", &Value::BigInt(-1)),
+            Err(ArithmeticError::Overflow(DataType::BigInt))
+        );
+        assert_eq!(
+            Value::Integer(i32::MIN).negate(),
+            Err(ArithmeticError::Overflow(DataType::Integer))
+        );
+        assert_eq!(
+            Value::Float(1.0).arithmetic("/", &Value::Float(-0.0)),
+            Err(ArithmeticError::DivisionByZero)
+        );
+        assert_eq!(
+            Value::Float(f64::MAX).arithmetic("*", &Value::Float(2.0)),
+            Err(ArithmeticError::Overflow(DataType::Float))
+        );
+        assert_eq!(
+            Value::Integer(-7).arithmetic("/", &Value::Integer(2)),
+            Ok(Value::Integer(-3))
+        );
+        assert_eq!(
+            Value::Null.arithmetic("/", &Value::Integer(0)),
+            Ok(Value::Null)
+        );
+        assert_eq!(
+            Value::Integer(1).arithmetic("-", &Value::Null),
+            Ok(Value::Null)
+        );
+    }
+
+    #[test]
+    fn can_parse_numeric_literals() {
+        assert_eq!(
+            Value::numeric("-9223372036854775808"),
+            Some(Value::BigInt(i64::MIN))
+        );
+        assert_eq!(
+            Value::numeric("12000.50").map(|value| value.data_type()),
+            Some(DataType::Decimal(7, 2))
+        );
+        assert_eq!(Value::numeric("1e-3"), Some(Value::Float(0.001)));
+        assert_eq!(Value::numeric(&"9".repeat(40)), Some(Value::Float(1e40)));
+    }
+
+    #[test]
+    fn can_assign_types() {
+        assert!(DataType::Float.assigns_to(&DataType::Integer));
+        assert!(DataType::Date.assigns_to(&DataType::Varchar));
+        assert!(DataType::Timestamp.assigns_to(&DataType::Date));
+        assert!(DataType::Null.assigns_to(&DataType::Boolean));
+        assert!(!DataType::Varchar.assigns_to(&DataType::Integer));
+        assert!(!DataType::Boolean.assigns_to(&DataType::Integer));
+        assert!(!DataType::Array(Box::new(DataType::Varchar))
+            .assigns_to(&DataType::Array(Box::new(DataType::Integer))));
+    }
+
+    #[test]
+    fn can_cast_booleans() {
+        assert_eq!(
+            Value::from(" Yes ").cast(&DataType::Boolean),
+            Some(Value::Boolean(true))
+        );
+        assert_eq!(Value::from("maybe").cast(&DataType::Boolean), None);
+        assert_eq!(
+            Value::Integer(-3).cast(&DataType::Boolean),
+            Some(Value::Boolean(true))
+        );
+        assert_eq!(
+            Value::Boolean(true).cast(&DataType::Integer),
+            Some(Value::Integer(1))
+        );
+        assert_eq!(
+            Value::Boolean(false).cast(&DataType::Varchar),
+            Some(Value::from("false"))
+        );
+        assert!(!DataType::BigInt.casts_to(&DataType::Boolean));
+        assert!(!DataType::Boolean.coerces_to(&DataType::Integer));
+        assert!(Value::Boolean(false) < Value::Boolean(true));
+    }
+
+    #[test]
+    fn can_cast_temporal_values() {
+        let timestamp = Value::from("2024-01-31 08:30:00")
+            .cast(&DataType::Timestamp)
+            .unwrap();
+        assert_eq!(
+            timestamp.cast(&DataType::Date),
+            Value::decode("2024-01-31", DataType::Date)
+        );
+        assert_eq!(
+            timestamp.cast(&DataType::Time),
+            Value::decode("08:30:00", DataType::Time)
+        );
+        assert_eq!(
+            timestamp.cast(&DataType::Varchar),
+            Some(Value::from("2024-01-31 08:30:00"))
+        );
+        assert_eq!(Value::from("2024-01-32").cast(&DataType::Date), None);
+        assert!(DataType::Date.coerces_to(&DataType::Timestamp));
+        assert!(!DataType::Timestamp.coerces_to(&DataType::Date));
+        assert!(DataType::Timestamp.casts_to(&DataType::Date));
+        assert!(!DataType::Interval.casts_to(&DataType::Integer));
+    }
+
+    #[test]
+    fn temporal_arithmetic_detects_errors() {
+        let date = Value::decode("9999-12-31", DataType::Date).unwrap();
+        let interval = Value::decode("1 day", DataType::Interval).unwrap();
+        assert_eq!(
+            date.arithmetic("+", &Value::Integer(1)),
+            Err(ArithmeticError::Overflow(DataType::Date))
+        );
+        assert_eq!(
+            Value::Integer(-1).arithmetic("+", &date),
+            Ok(Value::decode("9999-12-30", DataType::Date).unwrap())
+        );
+        assert_eq!(
+            date.cast(&DataType::Timestamp)
+                .unwrap()
+                .arithmetic("+", &interval),
+            Err(ArithmeticError::Overflow(DataType::Timestamp))
+        );
+        assert_eq!(
+            interval.arithmetic("/", &Value::BigInt(0)),
+            Err(ArithmeticError::DivisionByZero)
+        );
+        assert_eq!(
+            interval.negate(),
+            Ok(Value::decode("-1 days", DataType::Interval).unwrap())
+        );
+        assert_eq!(
+            interval.extract(DateTimeField::Epoch),
+            Some(Value::Decimal(Decimal::parse("86400.000000").unwrap()))
+        );
+    }
+
+    #[test]
+    fn floats_are_totally_ordered() {
+        let mut values = [
+            Value::Float(f64::NAN),
+            Value::Float(1.5),
+            Value::Float(-0.0),
+            Value::Float(f64::NEG_INFINITY),
+        ];
+        values.sort();
+        assert_eq!(values[0], Value::Float(f64::NEG_INFINITY));
+        assert_eq!(values[1], Value::Float(0.0));
+        assert_eq!(values[3], Value::Float(f64::NAN));
+    }
+
+    #[test]
+    fn nulls_sort_first() {
+        let mut values = vec![Value::from("Bob"), Value::Integer(1), Value::Null];
+        values.sort();
+        assert_eq!(
+            values,
+            vec![Value::Null, Value::Integer(1), Value::from("Bob")]
+        );
+    }
+}